@@ -566,6 +566,16 @@ pub fn initialize_collection<VM: VMBinding>(mmtk: &'static MMTK<VM>, tls: VMThre
     mmtk.initialize_collection(tls);
 }
 
+/// Wrapper for [`crate::mmtk::MMTK::initialize_collection_inline`].
+pub fn initialize_collection_inline<VM: VMBinding>(mmtk: &'static MMTK<VM>, tls: VMThread) {
+    mmtk.initialize_collection_inline(tls);
+}
+
+/// Wrapper for [`crate::mmtk::MMTK::run_gc_inline`].
+pub fn run_gc_inline<VM: VMBinding>(mmtk: &'static MMTK<VM>, tls: VMMutatorThread) {
+    mmtk.run_gc_inline(tls);
+}
+
 /// Process MMTk run-time options. Returns true if the option is processed successfully.
 ///
 /// Arguments:
@@ -603,6 +613,21 @@ pub fn free_bytes<VM: VMBinding>(mmtk: &MMTK<VM>) -> usize {
     mmtk.get_plan().get_free_pages() << LOG_BYTES_IN_PAGE
 }
 
+/// Return an estimate of the memory in bytes that the spaces keep resident: the committed memory,
+/// plus the free memory that has not been returned to the OS. Free memory is only returned to the
+/// OS if the option `heap_uncommit` is enabled. MMTk accounts for memory in pages, thus this method
+/// always returns a value in page granularity.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+pub fn resident_bytes<VM: VMBinding>(mmtk: &MMTK<VM>) -> usize {
+    let mut pages = 0;
+    mmtk.get_plan().for_each_space(&mut |space| {
+        pages += space.get_page_resource().resident_pages();
+    });
+    pages << LOG_BYTES_IN_PAGE
+}
+
 /// Return a hash map for live bytes statistics in the last GC for each space.
 ///
 /// MMTk usually accounts for memory in pages by each space.
@@ -637,6 +662,131 @@ pub fn total_bytes<VM: VMBinding>(mmtk: &MMTK<VM>) -> usize {
     mmtk.get_plan().get_total_pages() << LOG_BYTES_IN_PAGE
 }
 
+/// Change the bounds of the heap size while the VM is running. The new bounds take effect at the
+/// start of the next GC. A GC trigger with a fixed heap size (e.g. `FixedHeapSize`) uses `max` as
+/// its heap size. A GC trigger with a dynamic heap size (e.g. `DynamicHeapSize`) adjusts its heap
+/// size within the new bounds. The request is ignored (with a warning) if the GC trigger does not
+/// support changing its bounds, such as a trigger delegated to the binding.
+///
+/// The max heap size is clamped to the memory limit of the process (e.g. the memory limit of its
+/// cgroup). Returns false, and ignores the request, if the bounds are invalid, or if `min` is larger
+/// than the memory limit.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `min`: The lower bound of the heap size in bytes.
+/// * `max`: The upper bound of the heap size in bytes. It must be positive, and no smaller than `min`.
+pub fn set_heap_size_bounds<VM: VMBinding>(mmtk: &MMTK<VM>, min: usize, max: usize) -> bool {
+    if max == 0 || min > max {
+        warn!(
+            "Invalid heap size bounds: min = {}, max = {}. The request is ignored.",
+            min, max
+        );
+        return false;
+    }
+    mmtk.gc_trigger.request_heap_size_bounds(min, max)
+}
+
+/// Change the nursery size of generational plans while the VM is running. The new nursery size
+/// takes effect at the start of the next GC.
+///
+/// Returns false, and ignores the request, if the nursery size is invalid.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `nursery`: The new nursery size, with the same meaning as the `nursery` option.
+pub fn set_nursery_size<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    nursery: crate::util::options::NurserySize,
+) -> bool {
+    if !nursery.validate() {
+        warn!(
+            "Invalid nursery size: {:?}. The request is ignored.",
+            nursery
+        );
+        return false;
+    }
+    mmtk.gc_trigger.request_nursery_size(nursery);
+    true
+}
+
+/// Register a threshold of the memory used by the heap, or by a space, after a GC. At the end of
+/// each GC, MMTk calls [`crate::vm::Collection::heap_usage_threshold_crossed`] if the used memory
+/// has reached the limit of the threshold, or has fallen below the limit again, since the previous
+/// GC. The used memory is counted in pages reserved by each space, so it includes fragmentation.
+///
+/// Returns `None`, and ignores the request, if the threshold names a space that does not exist,
+/// or if its limit is invalid.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `threshold`: The threshold to register.
+pub fn add_heap_usage_threshold<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    threshold: crate::util::heap::HeapUsageThreshold,
+) -> Option<crate::util::heap::HeapUsageThresholdId> {
+    if let Some(name) = threshold.space {
+        let mut found = false;
+        mmtk.get_plan().for_each_space(&mut |space| {
+            found |= space.get_name() == name;
+        });
+        if !found {
+            warn!(
+                "No space named {}. The heap usage threshold is ignored.",
+                name
+            );
+            return None;
+        }
+    }
+    if !threshold.limit.validate() {
+        warn!(
+            "Invalid heap usage limit: {:?}. The heap usage threshold is ignored.",
+            threshold.limit
+        );
+        return None;
+    }
+    Some(mmtk.gc_trigger.add_usage_threshold(threshold))
+}
+
+/// Remove a heap usage threshold registered by [`add_heap_usage_threshold`].
+///
+/// Returns false if there is no such threshold.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `id`: The identifier returned when the threshold was registered.
+pub fn remove_heap_usage_threshold<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    id: crate::util::heap::HeapUsageThresholdId,
+) -> bool {
+    mmtk.gc_trigger.remove_usage_threshold(id)
+}
+
+/// Change the limits of allocation pacing for a mutator. If the option `allocation_pacing` is
+/// enabled, a mutator that allocates faster than its limits is delayed in the allocation slow
+/// path when the heap is close to full, or while concurrent marking is in progress. Pass `None` to
+/// use the limits set by the options again.
+///
+/// Returns false, and ignores the request, if the limits are invalid.
+///
+/// Arguments:
+/// * `mutator`: The mutator to change the limits for.
+/// * `limits`: The new limits, or `None` to use the options.
+pub fn set_allocation_pacing<VM: VMBinding>(
+    mutator: &mut Mutator<VM>,
+    limits: Option<crate::util::alloc::AllocationPacing>,
+) -> bool {
+    if limits.is_some_and(|limits| !limits.validate()) {
+        warn!(
+            "Invalid allocation pacing: {:?}. The request is ignored.",
+            limits
+        );
+        return false;
+    }
+    mutator.set_allocation_pacing(limits);
+    true
+}
+
 /// The application code has requested a collection. This is just a GC hint, and
 /// we may ignore it.
 ///
@@ -774,6 +924,43 @@ pub fn is_mapped_address(address: Address) -> bool {
     address.is_mapped()
 }
 
+/// Handle a memory protection fault, e.g. a `SIGSEGV` caused by accessing `addr`.
+///
+/// Some plans protect parts of the heap while they work concurrently with mutators, e.g. the
+/// Compressor with the option `compressor_concurrent_compaction`.  If such a plan is used, the
+/// binding must call this function from its fault handler, for faults in any thread, including GC
+/// workers.  If this function returns `true`, the fault is caused by MMTk.  The binding must then
+/// leave the signal handler (e.g. by redirecting the faulting thread to a stub, as for implicit
+/// null checks), call [`resolve_protection_fault`] on the faulting thread, and retry the faulting
+/// access.  Otherwise, the binding should handle the fault as usual.
+///
+/// This function is async-signal-safe.  It only checks whether `addr` is protected by MMTk.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `addr`: The faulting address.
+pub fn handle_protection_fault<VM: VMBinding>(mmtk: &MMTK<VM>, addr: Address) -> bool {
+    mmtk.get_plan().handle_protection_fault(addr)
+}
+
+/// Resolve a memory protection fault for which [`handle_protection_fault`] returned `true`.  This
+/// must be called on the faulting thread, outside the signal handler.  When this returns, the
+/// faulting access can be retried.
+///
+/// The faulting thread does the work to make `addr` accessible itself, e.g. the Compressor moves
+/// the objects into the faulting page, so it does not wait for GC workers.  The work calls into
+/// the VM with `tls` as a [`VMWorkerThread`], e.g. [`crate::vm::ObjectModel::copy_to`] and
+/// [`crate::vm::Scanning::scan_object`].  Those calls must not access other objects in the heap,
+/// as in a stop-the-world copying GC.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `tls`: The thread that caused the fault.
+/// * `addr`: The faulting address.
+pub fn resolve_protection_fault<VM: VMBinding>(mmtk: &MMTK<VM>, tls: VMThread, addr: Address) {
+    mmtk.get_plan().resolve_protection_fault(tls, addr)
+}
+
 /// Add a reference to the list of weak references. A binding may
 /// call this either when a weak reference is created, or when a weak reference is traced during GC.
 ///
@@ -815,7 +1002,8 @@ pub fn harness_begin<VM: VMBinding>(mmtk: &MMTK<VM>, tls: VMMutatorThread) {
 }
 
 /// Generic hook to allow benchmarks to be harnessed. We stop collecting
-/// statistics, and print stats values.
+/// statistics, and print stats values.  If the option `gc_trace_file` is set,
+/// the GC trace is written to the file.
 ///
 /// Arguments:
 /// * `mmtk`: A reference to an MMTk instance.
@@ -823,6 +1011,11 @@ pub fn harness_end<VM: VMBinding>(mmtk: &'static MMTK<VM>) {
     mmtk.harness_end();
 }
 
+/// Wrapper for [`crate::mmtk::MMTK::write_gc_trace`].
+pub fn write_gc_trace<VM: VMBinding>(mmtk: &MMTK<VM>) -> std::io::Result<()> {
+    mmtk.write_gc_trace()
+}
+
 /// Register a finalizable object. MMTk will retain the liveness of
 /// the object even if it is not reachable from the program.
 /// Note that finalization upon exit is not supported.
@@ -945,7 +1138,7 @@ pub fn get_finalizers_for<VM: VMBinding>(
 
 /// Get the number of workers. MMTk spawns worker threads for the 'threads' defined in the options.
 /// So the number of workers is derived from the threads option. Note the feature single_worker overwrites
-/// the threads option, and force one worker thread.
+/// the threads option, and force one worker thread.  So does the option deterministic_scheduler.
 ///
 /// Arguments:
 /// * `mmtk`: A reference to an MMTk instance.
@@ -953,6 +1146,16 @@ pub fn num_of_workers<VM: VMBinding>(mmtk: &'static MMTK<VM>) -> usize {
     mmtk.scheduler.num_workers()
 }
 
+/// Get the seed of the deterministic scheduler, or `None` if the option `deterministic_scheduler`
+/// is not enabled.  A binding may print it when it detects an error, so that the order of work
+/// packets can be replayed by setting the option `deterministic_scheduler_seed` to the seed.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+pub fn deterministic_scheduler_seed<VM: VMBinding>(mmtk: &MMTK<VM>) -> Option<u64> {
+    mmtk.scheduler.deterministic_seed()
+}
+
 /// Add a work packet to the given work bucket. Note that this simply adds the work packet to the given
 /// work bucket, and the scheduler will decide when to execute the work packet.
 ///
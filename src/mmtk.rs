@@ -144,13 +144,13 @@ impl<VM: VMBinding> MMTK<VM> {
         crate::policy::sft_map::SFTRefStorage::pre_use_check();
         SFT_MAP.initialize_once(&create_sft_map);
 
-        let num_workers = if cfg!(feature = "single_worker") {
+        let num_workers = if cfg!(feature = "single_worker") || *options.deterministic_scheduler {
             1
         } else {
             *options.threads
         };
 
-        let scheduler = GCWorkScheduler::new(num_workers, (*options.thread_affinity).clone());
+        let scheduler = GCWorkScheduler::new(num_workers, &options);
 
         let state = Arc::new(GlobalState::default());
 
@@ -252,6 +252,52 @@ impl<VM: VMBinding> MMTK<VM> {
         probe!(mmtk, collection_initialized);
     }
 
+    /// Initialize the collection without spawning GC threads, for VMs that cannot spawn dedicated
+    /// GC threads.  This can be called instead of [`MMTK::initialize_collection`].  GC is then run
+    /// by the mutator that needs it, on its own thread: the binding should call
+    /// [`MMTK::run_gc_inline`] in [`Collection::block_for_gc()`], which runs all the work
+    /// packets of the GC before returning.  Note that `stop_all_mutators()` and `resume_mutators()`
+    /// are called on the same mutator thread, with its `tls` as the GC worker thread.
+    ///
+    /// The option `threads` must be 1, as the mutator runs the work of a single GC worker.
+    ///
+    /// # Arguments
+    ///
+    /// *   `tls`: The thread that wants to enable the collection.
+    ///
+    /// [`Collection::block_for_gc()`]: crate::vm::Collection::block_for_gc()
+    pub fn initialize_collection_inline(&'static self, tls: VMThread) {
+        assert!(
+            !self.state.is_initialized(),
+            "MMTk collection has been initialized (was initialize_collection() already called before?)"
+        );
+        assert_eq!(
+            self.scheduler.num_workers(),
+            1,
+            "GC can only run inline with one GC worker (set the option threads=1)"
+        );
+        self.scheduler.initialize_inline_worker(self, tls);
+        self.state.initialized.store(true, Ordering::SeqCst);
+        probe!(mmtk, collection_initialized);
+    }
+
+    /// Run the GC that has been requested (if any) on the current mutator thread, and return when
+    /// it has finished.  This can only be used if the collection is initialized with
+    /// [`MMTK::initialize_collection_inline`].  If another mutator is running GC, this waits for it
+    /// to finish first.  This must not be called again by the mutator that is running GC, e.g.
+    /// from `stop_all_mutators()`.
+    ///
+    /// # Arguments
+    ///
+    /// *   `tls`: The current mutator thread.
+    pub fn run_gc_inline(&'static self, tls: VMMutatorThread) {
+        assert!(
+            self.state.is_initialized(),
+            "MMTk collection has not been initialized, yet (was initialize_collection_inline() called before?)"
+        );
+        self.scheduler.run_gc_inline(tls);
+    }
+
     /// Prepare an MMTk instance for calling the `fork()` system call.
     ///
     /// The `fork()` system call is available on Linux and some UNIX variants, and may be emulated
@@ -295,6 +341,10 @@ impl<VM: VMBinding> MMTK<VM> {
             "MMTk collection has not been initialized, yet (was initialize_collection() called before?)"
         );
         probe!(mmtk, prepare_to_fork);
+        if self.scheduler.is_inline() {
+            // There are no GC threads to stop.
+            return;
+        }
         self.scheduler.stop_gc_threads_for_forking();
     }
 
@@ -313,6 +363,9 @@ impl<VM: VMBinding> MMTK<VM> {
             "MMTk collection has not been initialized, yet (was initialize_collection() called before?)"
         );
         probe!(mmtk, after_fork);
+        if self.scheduler.is_inline() {
+            return;
+        }
         self.scheduler.respawn_gc_threads_after_forking(tls);
     }
 
@@ -330,10 +383,28 @@ impl<VM: VMBinding> MMTK<VM> {
     /// Generic hook to allow benchmarks to be harnessed. MMTk will stop collecting
     /// statistics, and print out the collected statistics in a defined format.
     /// This is usually called by the benchmark harness right after the actual benchmark.
+    ///
+    /// If the option `gc_trace_file` is set, the GC trace is written to the file, too.
     pub fn harness_end(&'static self) {
         self.stats.stop_all(self);
         self.state.inside_harness.store(false, Ordering::SeqCst);
         probe!(mmtk, harness_end);
+        if let Err(e) = self.write_gc_trace() {
+            warn!("Failed to write the GC trace: {}", e);
+        }
+    }
+
+    /// Write the GC events recorded so far to the file set by the option `gc_trace_file`, in the
+    /// Chrome trace event format.  The file is overwritten if it exists.  This does nothing if the
+    /// option is not set.  It should not be called during a GC, or the trace may include packets
+    /// that have started but not ended.
+    pub fn write_gc_trace(&self) -> std::io::Result<()> {
+        let Some(tracer) = self.scheduler.tracer() else {
+            return Ok(());
+        };
+        tracer.write_file()?;
+        info!("GC trace written to {}", tracer.path().display());
+        Ok(())
     }
 
     #[cfg(feature = "sanity")]
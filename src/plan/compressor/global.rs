@@ -18,6 +18,7 @@ use crate::util::heap::gc_trigger::SpaceStats;
 use crate::util::heap::VMRequest;
 use crate::util::metadata::side_metadata::SideMetadataContext;
 use crate::util::opaque_pointer::*;
+use crate::util::Address;
 use crate::vm::VMBinding;
 use enum_map::EnumMap;
 use mmtk_macros::{HasSpaces, PlanTraceObject};
@@ -25,6 +26,8 @@ use mmtk_macros::{HasSpaces, PlanTraceObject};
 /// [`Compressor`] implements a stop-the-world and parallel implementation of
 /// the Compressor, as described in Kermany and Petrank,
 /// [The Compressor: concurrent, incremental, and parallel compaction](https://dl.acm.org/doi/10.1145/1133255.1134023).
+/// With the option `compressor_concurrent_compaction`, objects are moved concurrently with
+/// mutators after the pause.
 #[derive(HasSpaces, PlanTraceObject)]
 pub struct Compressor<VM: VMBinding> {
     #[parent]
@@ -64,6 +67,7 @@ impl<VM: VMBinding> Plan for Compressor<VM> {
 
     fn prepare(&mut self, tls: VMWorkerThread) {
         self.common.prepare(tls, true);
+        self.compressor_space.finish_concurrent_compaction(tls);
         self.compressor_space.prepare();
     }
 
@@ -101,10 +105,20 @@ impl<VM: VMBinding> Plan for Compressor<VM> {
         // scan roots to update their references
         scheduler.work_buckets[WorkBucketStage::SecondRoots].add(UpdateReferences::<VM>::new());
 
-        scheduler.work_buckets[WorkBucketStage::Compact].add(GenerateWork::new(
-            &self.compressor_space,
-            CompressorSpace::<VM>::add_compact_tasks,
-        ));
+        if self.compressor_space.is_concurrent_compaction_enabled() {
+            // Objects are moved after mutators resume. Keep the concurrent work packets until
+            // then.
+            scheduler.work_buckets[WorkBucketStage::Concurrent].set_enabled(false);
+            scheduler.work_buckets[WorkBucketStage::Compact].add(GenerateWork::new(
+                &self.compressor_space,
+                CompressorSpace::<VM>::add_concurrent_compact_tasks,
+            ));
+        } else {
+            scheduler.work_buckets[WorkBucketStage::Compact].add(GenerateWork::new(
+                &self.compressor_space,
+                CompressorSpace::<VM>::add_compact_tasks,
+            ));
+        }
 
         scheduler.work_buckets[WorkBucketStage::Compact].set_sentinel(Box::new(
             AfterCompact::<VM>::new(&self.compressor_space, &self.common.los),
@@ -177,6 +191,15 @@ impl<VM: VMBinding> Plan for Compressor<VM> {
     fn get_used_pages(&self) -> usize {
         self.compressor_space.reserved_pages() + self.common.get_used_pages()
     }
+
+    fn handle_protection_fault(&self, addr: Address) -> bool {
+        self.compressor_space.handle_protection_fault(addr)
+    }
+
+    fn resolve_protection_fault(&self, tls: VMThread, addr: Address) {
+        self.compressor_space
+            .resolve_protection_fault(VMWorkerThread(tls), addr)
+    }
 }
 
 impl<VM: VMBinding> Compressor<VM> {
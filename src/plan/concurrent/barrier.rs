@@ -1,6 +1,8 @@
 use std::sync::atomic::Ordering;
 
 use super::{concurrent_marking_work::ProcessModBufSATB, Pause};
+use crate::plan::generational::barrier::GenObjectBarrierSemantics;
+use crate::plan::generational::global::GenerationalPlanExt;
 use crate::plan::global::PlanTraceObject;
 use crate::policy::gc_work::TraceKind;
 use crate::util::VMMutatorThread;
@@ -41,6 +43,9 @@ impl<VM: VMBinding, P: ConcurrentPlan<VM = VM> + PlanTraceObject<VM>, const KIND
     }
 
     fn slow(&mut self, _src: Option<ObjectReference>, _slot: VM::VMSlot, old: ObjectReference) {
+        if self.is_object_in_nursery(old) {
+            return;
+        }
         self.satb.push(old);
         if self.satb.is_full() {
             self.flush_satb();
@@ -99,6 +104,14 @@ impl<VM: VMBinding, P: ConcurrentPlan<VM = VM> + PlanTraceObject<VM>, const KIND
         }
     }
 
+    /// Nursery objects are allocated after the snapshot is taken, and are never traced by
+    /// concurrent marking.  They are evacuated (and traced) by the next pause.
+    fn is_object_in_nursery(&self, object: ObjectReference) -> bool {
+        self.plan
+            .generational()
+            .is_some_and(|gen| gen.is_object_in_nursery(object))
+    }
+
     fn should_create_satb_packets(&self) -> bool {
         self.plan.concurrent_work_in_progress()
             || self.plan.current_pause() == Some(Pause::FinalMark)
@@ -146,7 +159,7 @@ impl<VM: VMBinding, P: ConcurrentPlan<VM = VM> + PlanTraceObject<VM>, const KIND
     /// (and its children) may be treated as garbage if it happened to be weakly reachable at the
     /// time of `InitialMark`.
     fn load_weak_reference(&mut self, o: ObjectReference) {
-        if !self.plan.concurrent_work_in_progress() {
+        if !self.plan.concurrent_work_in_progress() || self.is_object_in_nursery(o) {
             return;
         }
         self.refs.push(o);
@@ -161,3 +174,94 @@ impl<VM: VMBinding, P: ConcurrentPlan<VM = VM> + PlanTraceObject<VM>, const KIND
         });
     }
 }
+
+/// The barrier semantics for a generational plan whose mature space is marked concurrently.
+///
+/// Both the object-remembering barrier of the nursery and the SATB barrier of concurrent marking
+/// are triggered by the unlog bit of the source object, and both clear it.  So they have to share
+/// a single slow path.  When the slow path is taken for an unlogged (mature) object, we first
+/// record the old values of its fields if concurrent marking is in progress, and then remember the
+/// object so that its pointers to the nursery are traced in the next pause.
+pub struct GenSATBBarrierSemantics<
+    VM: VMBinding,
+    P: ConcurrentPlan<VM = VM> + GenerationalPlanExt<VM> + PlanTraceObject<VM>,
+    const KIND: TraceKind,
+> {
+    plan: &'static P,
+    satb: SATBBarrierSemantics<VM, P, KIND>,
+    gen: GenObjectBarrierSemantics<VM, P>,
+}
+
+impl<
+        VM: VMBinding,
+        P: ConcurrentPlan<VM = VM> + GenerationalPlanExt<VM> + PlanTraceObject<VM>,
+        const KIND: TraceKind,
+    > GenSATBBarrierSemantics<VM, P, KIND>
+{
+    pub fn new(mmtk: &'static MMTK<VM>, tls: VMMutatorThread) -> Self {
+        let plan = mmtk.get_plan().downcast_ref::<P>().unwrap();
+        Self {
+            plan,
+            satb: SATBBarrierSemantics::new(mmtk, tls),
+            gen: GenObjectBarrierSemantics::new(mmtk, plan),
+        }
+    }
+
+    fn object_is_unlogged(&self, object: ObjectReference) -> bool {
+        Self::UNLOG_BIT_SPEC.load_atomic::<VM, u8>(object, None, Ordering::SeqCst) != 0
+    }
+}
+
+impl<
+        VM: VMBinding,
+        P: ConcurrentPlan<VM = VM> + GenerationalPlanExt<VM> + PlanTraceObject<VM>,
+        const KIND: TraceKind,
+    > BarrierSemantics for GenSATBBarrierSemantics<VM, P, KIND>
+{
+    type VM = VM;
+
+    #[cold]
+    fn flush(&mut self) {
+        self.satb.flush();
+        self.gen.flush();
+    }
+
+    fn object_reference_write_slow(
+        &mut self,
+        src: ObjectReference,
+        slot: <Self::VM as VMBinding>::VMSlot,
+        target: Option<ObjectReference>,
+    ) {
+        // Take the snapshot of the fields before the object is logged.
+        if self.plan.concurrent_work_in_progress() {
+            self.satb.object_probable_write_slow(src);
+        }
+        self.gen.object_reference_write_slow(src, slot, target);
+        Self::UNLOG_BIT_SPEC.store_atomic::<VM, u8>(src, 0, None, Ordering::SeqCst);
+    }
+
+    fn memory_region_copy_slow(
+        &mut self,
+        src: <Self::VM as VMBinding>::VMMemorySlice,
+        dst: <Self::VM as VMBinding>::VMMemorySlice,
+    ) {
+        if self.plan.concurrent_work_in_progress() {
+            self.satb.memory_region_copy_slow(src.clone(), dst.clone());
+        }
+        self.gen.memory_region_copy_slow(src, dst);
+    }
+
+    fn load_weak_reference(&mut self, o: ObjectReference) {
+        self.satb.load_weak_reference(o);
+    }
+
+    fn object_probable_write_slow(&mut self, obj: ObjectReference) {
+        if self.plan.concurrent_work_in_progress() {
+            self.satb.object_probable_write_slow(obj);
+        }
+        // Only mature objects need to be remembered.
+        if self.object_is_unlogged(obj) {
+            self.gen.object_probable_write_slow(obj);
+        }
+    }
+}
@@ -81,6 +81,14 @@ impl<VM: VMBinding, P: ConcurrentPlan<VM = VM> + PlanTraceObject<VM>, const KIND
                 let Some(t) = s.load() else {
                     return;
                 };
+                // Nursery objects are not part of the snapshot. They will be traced in the final mark pause.
+                if self
+                    .plan
+                    .generational()
+                    .is_some_and(|gen| gen.is_object_in_nursery(t))
+                {
+                    return;
+                }
 
                 self.next_objects.push(t);
                 if self.next_objects.len() > Self::SATB_BUFFER_SIZE {
@@ -89,6 +97,8 @@ impl<VM: VMBinding, P: ConcurrentPlan<VM = VM> + PlanTraceObject<VM>, const KIND
             },
         );
         self.plan.post_scan_object(object);
+        self.worker()
+            .consume_assist_budget(VM::VMObjectModel::get_current_size(object));
     }
 }
 
@@ -130,6 +140,11 @@ impl<VM: VMBinding, P: ConcurrentPlan<VM = VM> + PlanTraceObject<VM>, const KIND
                 if !(pause_opt == Some(Pause::FinalMark) || pause_opt.is_none()) {
                     break;
                 }
+                // A mutator that assists marking stops once it has done its share of work.
+                // The remaining objects are pushed to the bucket by `flush()`.
+                if self.worker().is_assist_budget_exhausted() {
+                    break;
+                }
                 let next_objects = self.next_objects.take();
                 self.trace_objects(&next_objects);
                 num_next_objects += next_objects.len();
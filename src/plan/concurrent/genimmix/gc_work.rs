@@ -0,0 +1,130 @@
+use super::global::ConcurrentGenImmix;
+use crate::plan::concurrent::concurrent_marking_work::ConcurrentTraceObjects;
+use crate::plan::generational::gc_work::GenNurseryProcessEdges;
+use crate::policy::gc_work::TraceKind;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::policy::immix::TRACE_KIND_FAST;
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesBase, SlotOf, UnsupportedProcessEdges,
+};
+use crate::scheduler::{ProcessEdgesWork, WorkBucketStage};
+use crate::util::ObjectReference;
+use crate::vm::slot::Slot;
+use crate::vm::VMBinding;
+use crate::MMTK;
+use std::ops::{Deref, DerefMut};
+
+type NurseryProcessEdges<VM> = GenNurseryProcessEdges<VM, ConcurrentGenImmix<VM>, DEFAULT_TRACE>;
+
+pub struct ConcurrentGenImmixNurseryGCWorkContext<VM: VMBinding>(std::marker::PhantomData<VM>);
+impl<VM: VMBinding> crate::scheduler::GCWorkContext for ConcurrentGenImmixNurseryGCWorkContext<VM> {
+    type VM = VM;
+    type PlanType = ConcurrentGenImmix<VM>;
+    type DefaultProcessEdges = NurseryProcessEdges<VM>;
+    type PinningProcessEdges = UnsupportedProcessEdges<VM>;
+}
+
+pub(super) struct ConcurrentGenImmixInitialMarkGCWorkContext<VM: VMBinding>(
+    std::marker::PhantomData<VM>,
+);
+impl<VM: VMBinding> crate::scheduler::GCWorkContext
+    for ConcurrentGenImmixInitialMarkGCWorkContext<VM>
+{
+    type VM = VM;
+    type PlanType = ConcurrentGenImmix<VM>;
+    type DefaultProcessEdges = InitialMarkProcessEdges<VM>;
+    type PinningProcessEdges = UnsupportedProcessEdges<VM>;
+}
+
+pub(super) struct ConcurrentGenImmixMatureGCWorkContext<VM: VMBinding, const KIND: TraceKind>(
+    std::marker::PhantomData<VM>,
+);
+impl<VM: VMBinding, const KIND: TraceKind> crate::scheduler::GCWorkContext
+    for ConcurrentGenImmixMatureGCWorkContext<VM, KIND>
+{
+    type VM = VM;
+    type PlanType = ConcurrentGenImmix<VM>;
+    type DefaultProcessEdges = PlanProcessEdges<VM, ConcurrentGenImmix<VM>, KIND>;
+    type PinningProcessEdges = UnsupportedProcessEdges<VM>;
+}
+
+/// Process edges for the initial mark pause.
+///
+/// The initial mark pause is a nursery GC.  It traces slots in the same way as
+/// [`GenNurseryProcessEdges`], but it also records the objects referenced by the roots after they
+/// have been evacuated from the nursery.  Those objects form the snapshot from which the mature
+/// space is marked concurrently after the pause.
+pub struct InitialMarkProcessEdges<VM: VMBinding> {
+    inner: NurseryProcessEdges<VM>,
+}
+
+impl<VM: VMBinding> InitialMarkProcessEdges<VM> {
+    fn schedule_concurrent_trace_objects_work(&self, objects: Vec<ObjectReference>) {
+        let w = ConcurrentTraceObjects::<VM, ConcurrentGenImmix<VM>, TRACE_KIND_FAST>::new(
+            objects,
+            self.mmtk(),
+        );
+        // The concurrent bucket is disabled during the pause, and will be enabled when the pause ends.
+        self.worker().scheduler().work_buckets[WorkBucketStage::Concurrent].add_no_notify(w);
+    }
+}
+
+impl<VM: VMBinding> ProcessEdgesWork for InitialMarkProcessEdges<VM> {
+    type VM = VM;
+    type ScanObjectsWorkType = <NurseryProcessEdges<VM> as ProcessEdgesWork>::ScanObjectsWorkType;
+
+    fn new(
+        slots: Vec<SlotOf<Self>>,
+        roots: bool,
+        mmtk: &'static MMTK<VM>,
+        bucket: WorkBucketStage,
+    ) -> Self {
+        Self {
+            inner: NurseryProcessEdges::new(slots, roots, mmtk, bucket),
+        }
+    }
+
+    fn trace_object(&mut self, object: ObjectReference) -> ObjectReference {
+        self.inner.trace_object(object)
+    }
+
+    fn process_slots(&mut self) {
+        if !self.roots {
+            self.inner.process_slots();
+            return;
+        }
+        probe!(mmtk, process_slots, self.slots.len(), self.is_roots());
+        let mut root_objects = Vec::with_capacity(self.slots.len());
+        for i in 0..self.slots.len() {
+            let slot = self.slots[i];
+            let Some(object) = slot.load() else {
+                continue;
+            };
+            let new_object = self.trace_object(object);
+            if new_object != object {
+                slot.store(new_object);
+            }
+            root_objects.push(new_object);
+        }
+        if !root_objects.is_empty() {
+            self.schedule_concurrent_trace_objects_work(root_objects);
+        }
+    }
+
+    fn create_scan_work(&self, nodes: Vec<ObjectReference>) -> Self::ScanObjectsWorkType {
+        self.inner.create_scan_work(nodes)
+    }
+}
+
+impl<VM: VMBinding> Deref for InitialMarkProcessEdges<VM> {
+    type Target = ProcessEdgesBase<VM>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<VM: VMBinding> DerefMut for InitialMarkProcessEdges<VM> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
@@ -0,0 +1,568 @@
+use super::gc_work::ConcurrentGenImmixInitialMarkGCWorkContext;
+use super::gc_work::ConcurrentGenImmixMatureGCWorkContext;
+use super::gc_work::ConcurrentGenImmixNurseryGCWorkContext;
+use crate::plan::concurrent::global::ConcurrentPlan;
+use crate::plan::concurrent::Pause;
+use crate::plan::generational::global::CommonGenPlan;
+use crate::plan::generational::global::GenerationalPlan;
+use crate::plan::generational::global::GenerationalPlanExt;
+use crate::plan::global::BasePlan;
+use crate::plan::global::CommonPlan;
+use crate::plan::global::CreateGeneralPlanArgs;
+use crate::plan::global::CreateSpecificPlanArgs;
+use crate::plan::AllocationSemantics;
+use crate::plan::Plan;
+use crate::plan::PlanConstraints;
+use crate::plan::PlanTraceObject;
+use crate::policy::gc_work::TraceKind;
+use crate::policy::immix::defrag::StatsForDefrag;
+use crate::policy::immix::ImmixSpace;
+use crate::policy::immix::ImmixSpaceArgs;
+use crate::policy::immix::{TRACE_KIND_DEFRAG, TRACE_KIND_FAST};
+use crate::policy::space::Space;
+use crate::scheduler::trace::{self, TraceArg};
+use crate::scheduler::GCWorkScheduler;
+use crate::scheduler::GCWorker;
+use crate::scheduler::WorkBucketStage;
+use crate::util::alloc::allocators::AllocatorSelector;
+use crate::util::copy::*;
+use crate::util::heap::gc_trigger::SpaceStats;
+use crate::util::heap::VMRequest;
+use crate::util::metadata::log_bit::UnlogBitsOperation;
+use crate::util::metadata::side_metadata::SideMetadataContext;
+use crate::util::Address;
+use crate::util::ObjectReference;
+use crate::util::VMWorkerThread;
+use crate::vm::*;
+use crate::ObjectQueue;
+
+use atomic::Atomic;
+use enum_map::EnumMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use mmtk_macros::{HasSpaces, PlanTraceObject};
+
+/// Concurrent generational immix. Young objects are allocated into a copying nursery, and
+/// surviving objects are promoted into a mature immix space, as in GenImmix. Instead of
+/// collecting the mature space in a full heap stop-the-world GC, the mature space is marked
+/// concurrently using a snapshot-at-the-beginning (SATB) barrier.
+///
+/// * A nursery GC and a full heap GC are done in a single pause ([`Pause::Full`]), like GenImmix.
+/// * When the mature space has grown enough, a nursery GC becomes an initial mark pause. After
+///   the nursery is evacuated, the mature spaces are prepared for marking, and the objects
+///   referenced by the roots form the snapshot.
+/// * Mutators keep allocating into the nursery while the mature space is marked. If the nursery
+///   is full before marking finishes, concurrent marking is suspended for a nursery GC
+///   ([`Pause::Full`] that is not full heap). Objects promoted by the nursery GC are marked, and
+///   the SATB barrier stays active. Concurrent marking resumes after the pause.
+/// * When marking has finished, or when the mature spaces need to be collected, the next GC
+///   becomes the final mark pause, which evacuates the nursery and completes the marking of the
+///   mature space.
+#[derive(HasSpaces, PlanTraceObject)]
+pub struct ConcurrentGenImmix<VM: VMBinding> {
+    /// Generational plan, which includes a nursery space and operations related with nursery.
+    #[parent]
+    pub gen: CommonGenPlan<VM>,
+    /// An immix space as the mature space.
+    #[post_scan]
+    #[space]
+    #[copy_semantics(CopySemantics::Mature)]
+    pub immix_space: ImmixSpace<VM>,
+    /// Whether the last GC was a defrag GC for the immix space.
+    pub last_gc_was_defrag: AtomicBool,
+    /// Whether the last GC was a full heap GC
+    pub last_gc_was_full_heap: AtomicBool,
+    current_pause: Atomic<Option<Pause>>,
+    previous_pause: Atomic<Option<Pause>>,
+    concurrent_marking_active: AtomicBool,
+    /// The number of pages used outside the nursery at the end of the last GC that collected the
+    /// mature space.
+    mature_pages_after_last_mature_gc: AtomicUsize,
+}
+
+/// The plan constraints for the concurrent generational immix plan.
+pub const CONCURRENT_GENIMMIX_CONSTRAINTS: PlanConstraints = PlanConstraints {
+    // See the comments in GENIMMIX_CONSTRAINTS.
+    max_non_los_default_alloc_bytes: crate::util::rust_util::min_of_usize(
+        crate::policy::immix::MAX_IMMIX_OBJECT_SIZE,
+        crate::plan::generational::GEN_CONSTRAINTS.max_non_los_default_alloc_bytes,
+    ),
+    needs_prepare_mutator: true,
+    // The SATB barrier is a pre-write barrier. It also remembers mature objects for nursery GCs.
+    // See `GenSATBBarrierSemantics`.
+    barrier: crate::BarrierSelector::SATBBarrier,
+    needs_log_bit: true,
+    may_trace_duplicate_edges: true,
+    ..crate::plan::generational::GEN_CONSTRAINTS
+};
+
+impl<VM: VMBinding> Plan for ConcurrentGenImmix<VM> {
+    fn constraints(&self) -> &'static PlanConstraints {
+        &CONCURRENT_GENIMMIX_CONSTRAINTS
+    }
+
+    fn create_copy_config(&'static self) -> CopyConfig<Self::VM> {
+        use enum_map::enum_map;
+        CopyConfig {
+            copy_mapping: enum_map! {
+                CopySemantics::PromoteToMature => CopySelector::ImmixHybrid(0),
+                CopySemantics::Mature => CopySelector::ImmixHybrid(0),
+                _ => CopySelector::Unused,
+            },
+            space_mapping: vec![(CopySelector::ImmixHybrid(0), &self.immix_space)],
+            constraints: &CONCURRENT_GENIMMIX_CONSTRAINTS,
+        }
+    }
+
+    fn last_collection_was_exhaustive(&self) -> bool {
+        self.last_gc_was_full_heap.load(Ordering::Relaxed)
+            && self
+                .immix_space
+                .is_last_gc_exhaustive(self.last_gc_was_defrag.load(Ordering::Relaxed))
+    }
+
+    fn collection_required(&self, space_full: bool, space: Option<SpaceStats<Self::VM>>) -> bool
+    where
+        Self: Sized,
+    {
+        let concurrent_bucket =
+            &self.gen.common.base.scheduler.work_buckets[WorkBucketStage::Concurrent];
+        if self.concurrent_marking_in_progress() && concurrent_bucket.is_drained() {
+            // After the Concurrent bucket is drained during concurrent marking,
+            // we trigger the FinalMark pause at the next poll() site (here).
+            return true;
+        }
+
+        let required = self.gen.collection_required(self, space_full, space);
+        if required && self.concurrent_marking_in_progress() {
+            // Suspend concurrent marking so that GC workers park and the GC can start without
+            // waiting for marking to finish.  The bucket is enabled again in `schedule_collection`
+            // if the GC is the final mark pause, or at the end of a nursery GC.
+            concurrent_bucket.set_enabled(false);
+        }
+        required
+    }
+
+    // ConcurrentGenImmixMatureGCWorkContext<VM, { TraceKind::Defrag }> and
+    // ConcurrentGenImmixMatureGCWorkContext<VM, { TraceKind::Fast }> are different types. However,
+    // it seems clippy does not recognize the constant type parameter and thinks we have identical
+    // blocks in different if branches.
+    #[allow(clippy::if_same_then_else)]
+    #[allow(clippy::branches_sharing_code)]
+    fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<Self::VM>) {
+        let concurrent_bucket = &scheduler.work_buckets[WorkBucketStage::Concurrent];
+        let pause = if self.concurrent_marking_in_progress() {
+            if !concurrent_bucket.is_empty() && !self.requires_full_heap_collection() {
+                // Marking has not finished, and only the nursery needs to be collected. The
+                // Concurrent bucket stays disabled during the nursery GC, and the packets added
+                // to it by this GC (e.g. flushed SATB buffers) wait in it until marking resumes.
+                Pause::Full
+            } else {
+                // Marking has finished, or the mature spaces are full. We finish marking in this
+                // pause, and collect the nursery at the same time. The remaining concurrent
+                // packets (if any) are executed in this pause.
+                self.gen.gc_full_heap.store(true, Ordering::SeqCst);
+                if !concurrent_bucket.is_empty() {
+                    concurrent_bucket.set_enabled(true);
+                    concurrent_bucket.open();
+                }
+                Pause::FinalMark
+            }
+        } else if self.requires_full_heap_collection() {
+            Pause::Full
+        } else if self.should_start_concurrent_marking() {
+            Pause::InitialMark
+        } else {
+            Pause::Full
+        };
+
+        self.current_pause.store(Some(pause), Ordering::SeqCst);
+
+        probe!(mmtk, concurrent_pause_determined, pause as usize);
+        trace::record_gc_decision(
+            scheduler.tracer(),
+            "concurrent_pause_determined",
+            "pause",
+            || TraceArg::Str(format!("{:?}", pause)),
+        );
+
+        match pause {
+            Pause::Full if self.is_current_gc_nursery() => {
+                info!("Nursery GC");
+                scheduler.schedule_common_work::<ConcurrentGenImmixNurseryGCWorkContext<VM>>(self);
+            }
+            Pause::Full => {
+                info!("Full heap GC");
+                crate::plan::immix::Immix::schedule_immix_full_heap_collection::<
+                    ConcurrentGenImmix<VM>,
+                    ConcurrentGenImmixMatureGCWorkContext<VM, TRACE_KIND_FAST>,
+                    ConcurrentGenImmixMatureGCWorkContext<VM, TRACE_KIND_DEFRAG>,
+                >(self, &self.immix_space, scheduler);
+            }
+            Pause::InitialMark => {
+                scheduler
+                    .schedule_common_work::<ConcurrentGenImmixInitialMarkGCWorkContext<VM>>(self);
+            }
+            Pause::FinalMark => {
+                // Roots are scanned again, and the closure is computed with a full heap trace.
+                // Objects already marked by concurrent marking will not be traced again.
+                scheduler.schedule_common_work::<ConcurrentGenImmixMatureGCWorkContext<
+                    VM,
+                    TRACE_KIND_FAST,
+                >>(self);
+            }
+        }
+    }
+
+    fn get_allocator_mapping(&self) -> &'static EnumMap<AllocationSemantics, AllocatorSelector> {
+        &super::mutator::ALLOCATOR_MAPPING
+    }
+
+    fn prepare(&mut self, tls: VMWorkerThread) {
+        let pause = self.current_pause().unwrap();
+        match pause {
+            Pause::Full if self.concurrent_marking_in_progress() => {
+                // A nursery GC during concurrent marking. The mature spaces are being marked, and
+                // must not be prepared. Objects promoted into the immix space are marked in the
+                // current mark state, and the LOS allocates objects as live instead of into its
+                // logical nursery.
+                self.gen.prepare_nursery();
+            }
+            Pause::Full => {
+                let full_heap = !self.gen.is_current_gc_nursery();
+                self.gen.prepare(tls);
+                if full_heap {
+                    self.immix_space.prepare(
+                        full_heap,
+                        Some(StatsForDefrag::new(self)),
+                        // Bulk clear unlog bits so that we will reconstruct them.
+                        UnlogBitsOperation::BulkClear,
+                    );
+                }
+            }
+            Pause::InitialMark => {
+                // The initial mark pause starts as a nursery GC. Only the nursery and the logical
+                // nursery of the LOS are prepared here. The mature spaces are prepared for marking
+                // in `release`, after the nursery has been evacuated.
+                self.gen.common.los.prepare(false);
+                self.gen.prepare_nursery();
+            }
+            Pause::FinalMark => {
+                // The mature spaces have been prepared in the initial mark pause.
+                self.gen.full_heap_gc_count.lock().unwrap().inc();
+                self.gen.prepare_nursery();
+            }
+        }
+    }
+
+    fn release(&mut self, tls: VMWorkerThread) {
+        let pause = self.current_pause().unwrap();
+        match pause {
+            Pause::Full if self.concurrent_marking_in_progress() => {
+                self.gen.release_nursery();
+                self.last_gc_was_full_heap.store(false, Ordering::Relaxed);
+            }
+            Pause::Full => {
+                let full_heap = !self.gen.is_current_gc_nursery();
+                self.gen.release(tls);
+                if full_heap {
+                    self.immix_space.release(
+                        full_heap,
+                        // We reconstructred unlog bits during tracing.  Keep them.
+                        UnlogBitsOperation::NoOp,
+                    );
+                }
+                self.last_gc_was_full_heap
+                    .store(full_heap, Ordering::Relaxed);
+            }
+            Pause::InitialMark => {
+                self.gen.release_nursery();
+                self.gen.common.los.release(false);
+
+                // Now that all the live nursery objects have been promoted, prepare the mature
+                // spaces for concurrent marking.  We do not prepare them earlier because reference
+                // processing and promotion in this pause still rely on the mark states of the last
+                // mature GC.
+                //
+                // Unlog bits are kept as is.  All mature objects that are not in the modbuf are
+                // already unlogged, so the SATB barrier will be triggered on them.
+                self.gen.common.prepare(tls, true);
+                self.immix_space.prepare(
+                    true,
+                    Some(StatsForDefrag::new(self)),
+                    UnlogBitsOperation::NoOp,
+                );
+                self.last_gc_was_full_heap.store(false, Ordering::Relaxed);
+            }
+            Pause::FinalMark => {
+                self.gen.release_nursery();
+                self.immix_space.release(
+                    true,
+                    // Traced objects have been unlogged by the tracing.  Keep them.
+                    UnlogBitsOperation::NoOp,
+                );
+                self.gen.common.release(tls, true);
+                self.last_gc_was_full_heap.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn end_of_gc(&mut self, tls: VMWorkerThread) {
+        let pause = self.current_pause().unwrap();
+
+        let next_gc_full_heap = CommonGenPlan::should_next_gc_be_full_heap(self);
+        self.gen.end_of_gc(tls, next_gc_full_heap);
+
+        let did_defrag = self.immix_space.end_of_gc();
+        self.last_gc_was_defrag.store(did_defrag, Ordering::Relaxed);
+
+        if pause == Pause::InitialMark {
+            self.set_concurrent_marking_state(true);
+        }
+        if !self.gen.is_current_gc_nursery() {
+            self.mature_pages_after_last_mature_gc
+                .store(self.get_used_pages(), Ordering::Relaxed);
+        }
+        self.previous_pause.store(Some(pause), Ordering::SeqCst);
+        self.current_pause.store(None, Ordering::SeqCst);
+        info!("{:?} end", pause);
+    }
+
+    fn current_gc_may_move_object(&self) -> bool {
+        // The nursery is evacuated in every pause.
+        true
+    }
+
+    fn get_collection_reserved_pages(&self) -> usize {
+        self.gen.get_collection_reserved_pages() + self.immix_space.defrag_headroom_pages()
+    }
+
+    fn get_used_pages(&self) -> usize {
+        self.gen.get_used_pages() + self.immix_space.reserved_pages()
+    }
+
+    /// Return the number of pages available for allocation. Assuming all future allocations goes to nursery.
+    fn get_available_pages(&self) -> usize {
+        // super.get_available_pages() / 2 to reserve pages for copying
+        (self
+            .get_total_pages()
+            .saturating_sub(self.get_reserved_pages()))
+            >> 1
+    }
+
+    fn base(&self) -> &BasePlan<VM> {
+        &self.gen.common.base
+    }
+
+    fn base_mut(&mut self) -> &mut BasePlan<Self::VM> {
+        &mut self.gen.common.base
+    }
+
+    fn common(&self) -> &CommonPlan<VM> {
+        &self.gen.common
+    }
+
+    fn notify_mutators_paused(&self, _scheduler: &GCWorkScheduler<VM>) {
+        use crate::vm::ActivePlan;
+        let pause = self.current_pause().unwrap();
+        match pause {
+            Pause::Full => {
+                debug_assert!(
+                    !self.concurrent_marking_in_progress() || self.is_current_gc_nursery(),
+                    "prev pause: {:?}",
+                    self.previous_pause()
+                );
+            }
+            Pause::InitialMark => {
+                debug_assert!(
+                    !self.concurrent_marking_in_progress(),
+                    "prev pause: {:?}",
+                    self.previous_pause()
+                );
+            }
+            Pause::FinalMark => {
+                debug_assert!(self.concurrent_marking_in_progress());
+                // Flush barrier buffers
+                for mutator in <VM as VMBinding>::VMActivePlan::mutators() {
+                    mutator.barrier.flush();
+                }
+                self.set_concurrent_marking_state(false);
+            }
+        }
+        info!("{:?} start", pause);
+    }
+
+    fn generational(&self) -> Option<&dyn GenerationalPlan<VM = VM>> {
+        Some(self)
+    }
+
+    fn concurrent(&self) -> Option<&dyn ConcurrentPlan<VM = VM>> {
+        Some(self)
+    }
+}
+
+impl<VM: VMBinding> GenerationalPlan for ConcurrentGenImmix<VM> {
+    fn is_current_gc_nursery(&self) -> bool {
+        self.gen.is_current_gc_nursery()
+    }
+
+    fn is_object_in_nursery(&self, object: ObjectReference) -> bool {
+        self.gen.nursery.in_space(object)
+    }
+
+    fn is_address_in_nursery(&self, addr: Address) -> bool {
+        self.gen.nursery.address_in_space(addr)
+    }
+
+    fn get_mature_physical_pages_available(&self) -> usize {
+        self.immix_space.available_physical_pages()
+    }
+
+    fn get_mature_reserved_pages(&self) -> usize {
+        self.immix_space.reserved_pages()
+    }
+
+    fn force_full_heap_collection(&self) {
+        self.gen.force_full_heap_collection()
+    }
+
+    fn last_collection_full_heap(&self) -> bool {
+        self.gen.last_collection_full_heap()
+    }
+
+    fn should_process_modbuf(&self) -> bool {
+        // The final mark pause does not trace mature objects that have already been marked, so
+        // their pointers to the nursery must be found through the modbuf.
+        self.is_current_gc_nursery() || self.current_pause() == Some(Pause::FinalMark)
+    }
+}
+
+impl<VM: VMBinding> GenerationalPlanExt<VM> for ConcurrentGenImmix<VM> {
+    fn trace_object_nursery<Q: ObjectQueue, const KIND: TraceKind>(
+        &self,
+        queue: &mut Q,
+        object: ObjectReference,
+        worker: &mut GCWorker<VM>,
+    ) -> ObjectReference {
+        if self.current_pause() == Some(Pause::FinalMark) {
+            // Slots from the modbuf may point to mature objects that have not been marked yet
+            // (e.g. objects stored into the slots during concurrent marking). Trace them as well.
+            <Self as PlanTraceObject<VM>>::trace_object::<Q, TRACE_KIND_FAST>(
+                self, queue, object, worker,
+            )
+        } else {
+            self.gen
+                .trace_object_nursery::<Q, KIND>(queue, object, worker)
+        }
+    }
+}
+
+impl<VM: VMBinding> ConcurrentPlan for ConcurrentGenImmix<VM> {
+    fn current_pause(&self) -> Option<Pause> {
+        self.current_pause.load(Ordering::SeqCst)
+    }
+
+    fn concurrent_work_in_progress(&self) -> bool {
+        self.concurrent_marking_in_progress()
+    }
+}
+
+impl<VM: VMBinding> ConcurrentGenImmix<VM> {
+    pub fn new(args: CreateGeneralPlanArgs<VM>) -> Self {
+        let spec = crate::util::metadata::extract_side_metadata(&[
+            *VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC,
+        ]);
+
+        let mut plan_args = CreateSpecificPlanArgs {
+            global_args: args,
+            constraints: &CONCURRENT_GENIMMIX_CONSTRAINTS,
+            global_side_metadata_specs: SideMetadataContext::new_global_specs(&spec),
+        };
+        let immix_space = ImmixSpace::new(
+            plan_args.get_mature_space_args(
+                "immix_mature",
+                true,
+                false,
+                VMRequest::discontiguous(),
+            ),
+            ImmixSpaceArgs {
+                // Young objects are not allocated in ImmixSpace directly.
+                mixed_age: false,
+                never_move_objects: false,
+                reference_counted: false,
+            },
+        );
+
+        let plan = ConcurrentGenImmix {
+            gen: CommonGenPlan::new(plan_args),
+            immix_space,
+            last_gc_was_defrag: AtomicBool::new(false),
+            last_gc_was_full_heap: AtomicBool::new(false),
+            current_pause: Atomic::new(None),
+            previous_pause: Atomic::new(None),
+            concurrent_marking_active: AtomicBool::new(false),
+            mature_pages_after_last_mature_gc: AtomicUsize::new(0),
+        };
+
+        plan.verify_side_metadata_sanity();
+
+        plan
+    }
+
+    fn requires_full_heap_collection(&self) -> bool {
+        self.gen.requires_full_heap_collection(self)
+    }
+
+    /// Should the nursery GC we are about to do start concurrent marking? We start marking when
+    /// the spaces outside the nursery have used more than half of the pages that were free after
+    /// the last GC that collected the mature space.
+    fn should_start_concurrent_marking(&self) -> bool {
+        let mature_pages_before = self
+            .mature_pages_after_last_mature_gc
+            .load(Ordering::Relaxed);
+        let mature_pages_now = self
+            .get_used_pages()
+            .saturating_sub(self.gen.nursery.reserved_pages());
+        let headroom = self.get_total_pages().saturating_sub(mature_pages_before);
+        let grown = mature_pages_now.saturating_sub(mature_pages_before);
+        if grown > headroom >> 1 {
+            info!("Mature spaces grew by {grown} pages since last mature GC (headroom: {headroom} pages): Do concurrent marking");
+            debug_assert!(
+                self.gen.common.base.scheduler.work_buckets[WorkBucketStage::Concurrent].is_empty()
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn concurrent_marking_in_progress(&self) -> bool {
+        self.concurrent_marking_active.load(Ordering::Acquire)
+    }
+
+    fn set_concurrent_marking_state(&self, active: bool) {
+        use crate::plan::global::HasSpaces;
+
+        // Tell the spaces to allocate new objects as live
+        let allocate_object_as_live = active;
+        self.for_each_space(&mut |space: &dyn Space<VM>| {
+            space.set_allocate_as_live(allocate_object_as_live);
+        });
+
+        // Store the state.
+        self.concurrent_marking_active
+            .store(active, Ordering::SeqCst);
+
+        // We also set SATB barrier as active -- this is done in Mutator prepare/release.
+    }
+
+    pub(super) fn is_concurrent_marking_active(&self) -> bool {
+        self.concurrent_marking_active.load(Ordering::SeqCst)
+    }
+
+    fn previous_pause(&self) -> Option<Pause> {
+        self.previous_pause.load(Ordering::SeqCst)
+    }
+}
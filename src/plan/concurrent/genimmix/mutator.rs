@@ -0,0 +1,107 @@
+use crate::plan::barriers::SATBBarrier;
+use crate::plan::concurrent::barrier::GenSATBBarrierSemantics;
+use crate::plan::concurrent::genimmix::ConcurrentGenImmix;
+use crate::plan::concurrent::Pause;
+use crate::plan::generational::create_gen_space_mapping;
+pub(super) use crate::plan::generational::ALLOCATOR_MAPPING;
+use crate::plan::mutator_context::common_prepare_func;
+use crate::plan::mutator_context::common_release_func;
+use crate::plan::mutator_context::Mutator;
+use crate::plan::mutator_context::MutatorBuilder;
+use crate::plan::mutator_context::MutatorConfig;
+use crate::plan::AllocationSemantics;
+use crate::policy::immix::TRACE_KIND_FAST;
+use crate::util::alloc::BumpAllocator;
+use crate::util::{VMMutatorThread, VMWorkerThread};
+use crate::vm::VMBinding;
+use crate::MMTK;
+
+type BarrierSemanticsType<VM> =
+    GenSATBBarrierSemantics<VM, ConcurrentGenImmix<VM>, TRACE_KIND_FAST>;
+
+type BarrierType<VM> = SATBBarrier<BarrierSemanticsType<VM>>;
+
+pub fn concurrent_genimmix_mutator_release<VM: VMBinding>(
+    mutator: &mut Mutator<VM>,
+    tls: VMWorkerThread,
+) {
+    // reset nursery allocator. The nursery is evacuated in every pause.
+    let bump_allocator = unsafe {
+        mutator
+            .allocators
+            .get_allocator_mut(mutator.config.allocator_mapping[AllocationSemantics::Default])
+    }
+    .downcast_mut::<BumpAllocator<VM>>()
+    .unwrap();
+    bump_allocator.reset();
+
+    common_release_func(mutator, tls);
+
+    // Deactivate SATB, unless this is a nursery GC during concurrent marking.
+    let concurrent = mutator.plan.concurrent().unwrap();
+    let current_pause = concurrent.current_pause().unwrap();
+    if (current_pause == Pause::Full || current_pause == Pause::FinalMark)
+        && !concurrent.concurrent_work_in_progress()
+    {
+        debug!("Deactivate SATB barrier active for {:?}", mutator as *mut _);
+        mutator
+            .barrier
+            .downcast_mut::<BarrierType<VM>>()
+            .unwrap()
+            .set_weak_ref_barrier_enabled(false);
+    }
+}
+
+pub fn concurrent_genimmix_mutator_prepare<VM: VMBinding>(
+    mutator: &mut Mutator<VM>,
+    tls: VMWorkerThread,
+) {
+    common_prepare_func(mutator, tls);
+
+    // Activate SATB
+    let current_pause = mutator.plan.concurrent().unwrap().current_pause().unwrap();
+    if current_pause == Pause::InitialMark {
+        debug!("Activate SATB barrier active for {:?}", mutator as *mut _);
+        mutator
+            .barrier
+            .downcast_mut::<BarrierType<VM>>()
+            .unwrap()
+            .set_weak_ref_barrier_enabled(true);
+    }
+}
+
+pub fn create_concurrent_genimmix_mutator<VM: VMBinding>(
+    mutator_tls: VMMutatorThread,
+    mmtk: &'static MMTK<VM>,
+) -> Mutator<VM> {
+    let genimmix = mmtk
+        .get_plan()
+        .downcast_ref::<ConcurrentGenImmix<VM>>()
+        .unwrap();
+    let config = MutatorConfig {
+        allocator_mapping: &ALLOCATOR_MAPPING,
+        space_mapping: Box::new(create_gen_space_mapping(
+            mmtk.get_plan(),
+            &genimmix.gen.nursery,
+        )),
+        prepare_func: &concurrent_genimmix_mutator_prepare,
+        release_func: &concurrent_genimmix_mutator_release,
+    };
+
+    let builder = MutatorBuilder::new(mutator_tls, mmtk, config);
+    let mut mutator = builder
+        .barrier(Box::new(SATBBarrier::new(BarrierSemanticsType::<VM>::new(
+            mmtk,
+            mutator_tls,
+        ))))
+        .build();
+
+    // Set barrier active, based on whether concurrent marking is in progress
+    mutator
+        .barrier
+        .downcast_mut::<BarrierType<VM>>()
+        .unwrap()
+        .set_weak_ref_barrier_enabled(genimmix.is_concurrent_marking_active());
+
+    mutator
+}
@@ -1,5 +1,13 @@
 use crate::plan::concurrent::Pause;
 use crate::plan::Plan;
+use crate::policy::space::Space;
+use crate::scheduler::gc_work::VMProcessWeakRefs;
+use crate::scheduler::{GCWorkScheduler, ProcessEdgesWork, WorkBucketStage};
+use crate::vm::VMBinding;
+use std::sync::atomic::AtomicBool;
+
+use atomic::Atomic;
+use atomic::Ordering;
 
 /// Trait for a concurrent plan.
 pub trait ConcurrentPlan: Plan {
@@ -8,3 +16,198 @@ pub trait ConcurrentPlan: Plan {
     /// Return the current pause kind.  `None` if not in a pause.
     fn current_pause(&self) -> Option<Pause>;
 }
+
+/// The pauses of a non-generational concurrent marking plan.  A GC is either a STW full GC, or an
+/// initial mark pause and a final mark pause with concurrent marking in between.  The plan
+/// decides the next pause and keeps the state of concurrent marking with this.
+pub(crate) struct ConcurrentMarkingPauses {
+    current_pause: Atomic<Option<Pause>>,
+    previous_pause: Atomic<Option<Pause>>,
+    should_do_full_gc: AtomicBool,
+    concurrent_marking_active: AtomicBool,
+}
+
+impl ConcurrentMarkingPauses {
+    pub fn new() -> Self {
+        Self {
+            current_pause: Atomic::new(None),
+            previous_pause: Atomic::new(None),
+            should_do_full_gc: AtomicBool::new(false),
+            concurrent_marking_active: AtomicBool::new(false),
+        }
+    }
+
+    /// Implement [`Plan::collection_required`] for `plan`.  A full GC is requested if the heap is
+    /// full, a final mark pause once the concurrent marking work is drained, and an initial mark
+    /// pause once half of the heap has been allocated since the last GC.
+    pub fn collection_required<P: Plan>(&self, plan: &P, space_full: bool) -> bool {
+        let base = plan.base();
+        if base.collection_required(plan, space_full) {
+            self.should_do_full_gc.store(true, Ordering::Release);
+            info!("Triggering full GC");
+            return true;
+        }
+
+        let concurrent_marking_in_progress = self.concurrent_marking_in_progress();
+
+        if concurrent_marking_in_progress
+            && base.scheduler.work_buckets[WorkBucketStage::Concurrent].is_drained()
+        {
+            // After the Concurrent bucket is drained during concurrent marking,
+            // we trigger the FinalMark pause at the next poll() site (here).
+            // FIXME: Immediately trigger FinalMark when the Concurrent bucket is drained.
+            return true;
+        }
+
+        let threshold = plan.get_total_pages() >> 1;
+        let used_pages_after_last_gc = base.global_state.get_used_pages_after_last_gc();
+        let used_pages_now = plan.get_used_pages();
+        let allocated = used_pages_now.saturating_sub(used_pages_after_last_gc);
+        if !concurrent_marking_in_progress && allocated > threshold {
+            info!("Allocated {allocated} pages since last GC ({used_pages_now} - {used_pages_after_last_gc} > {threshold}): Do concurrent marking");
+            debug_assert!(base.scheduler.work_buckets[WorkBucketStage::Concurrent].is_empty());
+            debug_assert_ne!(self.previous_pause(), Some(Pause::InitialMark));
+            return true;
+        }
+        false
+    }
+
+    /// Decide the kind of the pause that starts, and record it as the current pause.
+    pub fn start_pause(&self) -> Pause {
+        let pause = if self.concurrent_marking_in_progress() {
+            // An initial mark pause must be followed by a final mark pause.  If a full GC is
+            // requested during concurrent marking, it happens after the final mark.
+            // FIXME: Currently it is unsafe to bypass `FinalMark` and go directly from `InitialMark` to `Full`.
+            // It is related to defragmentation.  See https://github.com/mmtk/mmtk-core/issues/1357 for more details.
+            Pause::FinalMark
+        } else if self.should_do_full_gc.load(Ordering::SeqCst) {
+            Pause::Full
+        } else {
+            Pause::InitialMark
+        };
+        self.current_pause.store(Some(pause), Ordering::SeqCst);
+        pause
+    }
+
+    /// Implement [`Plan::notify_mutators_paused`] for `plan`.  Concurrent marking stops when the
+    /// mutators are paused for a final mark pause or a full GC.
+    pub fn notify_mutators_paused<P: Plan>(&self, plan: &P) {
+        use crate::vm::ActivePlan;
+        let pause = self.current_pause().unwrap();
+        match pause {
+            Pause::Full => {
+                self.set_concurrent_marking_state(plan, false);
+            }
+            Pause::InitialMark => {
+                debug_assert!(
+                    !self.concurrent_marking_in_progress(),
+                    "prev pause: {:?}",
+                    self.previous_pause().unwrap()
+                );
+            }
+            Pause::FinalMark => {
+                debug_assert!(self.concurrent_marking_in_progress());
+                // Flush barrier buffers
+                for mutator in <P::VM as VMBinding>::VMActivePlan::mutators() {
+                    mutator.barrier.flush();
+                }
+                self.set_concurrent_marking_state(plan, false);
+            }
+        }
+        info!("{:?} start", pause);
+    }
+
+    /// Finish the current pause at the end of a GC.  Concurrent marking starts after an initial
+    /// mark pause.
+    pub fn end_of_gc<P: Plan>(&self, plan: &P) {
+        let pause = self.current_pause().unwrap();
+        if pause == Pause::InitialMark {
+            self.set_concurrent_marking_state(plan, true);
+        }
+        self.previous_pause.store(Some(pause), Ordering::SeqCst);
+        self.current_pause.store(None, Ordering::SeqCst);
+        if pause != Pause::FinalMark {
+            self.should_do_full_gc.store(false, Ordering::SeqCst);
+        } else {
+            // We keep the value of `self.should_do_full_gc` so that if full GC is triggered
+            // during concurrent marking, the next GC will be full GC.
+        }
+        info!("{:?} end", pause);
+    }
+
+    pub fn current_pause(&self) -> Option<Pause> {
+        self.current_pause.load(Ordering::SeqCst)
+    }
+
+    pub fn previous_pause(&self) -> Option<Pause> {
+        self.previous_pause.load(Ordering::SeqCst)
+    }
+
+    pub fn concurrent_marking_in_progress(&self) -> bool {
+        self.concurrent_marking_active.load(Ordering::Acquire)
+    }
+
+    fn set_concurrent_marking_state<P: Plan>(&self, plan: &P, active: bool) {
+        // Tell the spaces to allocate new objects as live.  This also stops a mark sweep space
+        // from lazily sweeping blocks while the mark bits are incomplete.
+        let allocate_object_as_live = active;
+        plan.for_each_space(&mut |space: &dyn Space<P::VM>| {
+            space.set_allocate_as_live(allocate_object_as_live);
+        });
+
+        // Store the state.
+        self.concurrent_marking_active
+            .store(active, Ordering::SeqCst);
+
+        // We also set SATB barrier as active -- this is done in Mutator prepare/release.
+    }
+}
+
+/// Enable or disable the reference closure buckets.  They are disabled in initial mark pauses,
+/// and enabled in final mark pauses and full GCs.
+pub(crate) fn set_ref_closure_buckets_enabled<VM: VMBinding>(
+    scheduler: &GCWorkScheduler<VM>,
+    do_closure: bool,
+) {
+    scheduler.work_buckets[WorkBucketStage::VMRefClosure].set_enabled(do_closure);
+    scheduler.work_buckets[WorkBucketStage::WeakRefClosure].set_enabled(do_closure);
+    scheduler.work_buckets[WorkBucketStage::FinalRefClosure].set_enabled(do_closure);
+    scheduler.work_buckets[WorkBucketStage::SoftRefClosure].set_enabled(do_closure);
+    scheduler.work_buckets[WorkBucketStage::PhantomRefClosure].set_enabled(do_closure);
+}
+
+/// Schedule the processing of references, finalizers and VM-specific weak references of a final
+/// mark pause, which traces with `E`.
+pub(crate) fn schedule_final_mark_weak_ref_processing<E: ProcessEdgesWork>(
+    plan: &dyn Plan<VM = E::VM>,
+    scheduler: &GCWorkScheduler<E::VM>,
+) {
+    // TODO: Check against schedule_common_work and see if we are still missing any work packet
+    // Reference processing
+    if !*plan.base().options.no_reference_types {
+        use crate::util::reference_processor::{
+            PhantomRefProcessing, SoftRefProcessing, WeakRefProcessing,
+        };
+        scheduler.work_buckets[WorkBucketStage::SoftRefClosure].add(SoftRefProcessing::<E>::new());
+        scheduler.work_buckets[WorkBucketStage::WeakRefClosure]
+            .add(WeakRefProcessing::<E::VM>::new());
+        scheduler.work_buckets[WorkBucketStage::PhantomRefClosure]
+            .add(PhantomRefProcessing::<E::VM>::new());
+
+        use crate::util::reference_processor::RefEnqueue;
+        scheduler.work_buckets[WorkBucketStage::Release].add(RefEnqueue::<E::VM>::new());
+    }
+
+    // Finalization
+    if !*plan.base().options.no_finalizer {
+        use crate::util::finalizable_processor::Finalization;
+        // finalization
+        scheduler.work_buckets[WorkBucketStage::FinalRefClosure].add(Finalization::<E>::new());
+    }
+
+    // VM-specific weak ref processing
+    // Note that concurrent marking does not have a separate forwarding stage,
+    // so we don't schedule the `VMForwardWeakRefs` work packet.
+    scheduler.work_buckets[WorkBucketStage::VMRefClosure]
+        .set_sentinel(Box::new(VMProcessWeakRefs::<E>::new()));
+}
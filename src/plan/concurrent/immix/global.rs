@@ -1,5 +1,8 @@
 use crate::plan::concurrent::concurrent_marking_work::ProcessRootSlots;
-use crate::plan::concurrent::global::ConcurrentPlan;
+use crate::plan::concurrent::global::{
+    schedule_final_mark_weak_ref_processing, set_ref_closure_buckets_enabled,
+    ConcurrentMarkingPauses, ConcurrentPlan,
+};
 use crate::plan::concurrent::immix::gc_work::ConcurrentImmixGCWorkContext;
 use crate::plan::concurrent::immix::gc_work::ConcurrentImmixSTWGCWorkContext;
 use crate::plan::concurrent::Pause;
@@ -19,7 +22,7 @@ use crate::policy::space::Space;
 use crate::scheduler::gc_work::Release;
 use crate::scheduler::gc_work::StopMutators;
 use crate::scheduler::gc_work::UnsupportedProcessEdges;
-use crate::scheduler::gc_work::VMProcessWeakRefs;
+use crate::scheduler::trace::{self, TraceArg};
 use crate::scheduler::*;
 use crate::util::alloc::allocators::AllocatorSelector;
 use crate::util::copy::*;
@@ -32,7 +35,6 @@ use crate::vm::VMBinding;
 use crate::{policy::immix::ImmixSpace, util::opaque_pointer::VMWorkerThread};
 use std::sync::atomic::AtomicBool;
 
-use atomic::Atomic;
 use atomic::Ordering;
 use enum_map::EnumMap;
 
@@ -49,10 +51,7 @@ pub struct ConcurrentImmix<VM: VMBinding> {
     #[parent]
     pub common: CommonPlan<VM>,
     last_gc_was_defrag: AtomicBool,
-    current_pause: Atomic<Option<Pause>>,
-    previous_pause: Atomic<Option<Pause>>,
-    should_do_full_gc: AtomicBool,
-    concurrent_marking_active: AtomicBool,
+    pauses: ConcurrentMarkingPauses,
 }
 
 /// The plan constraints for the concurrent immix plan.
@@ -69,37 +68,7 @@ pub const CONCURRENT_IMMIX_CONSTRAINTS: PlanConstraints = PlanConstraints {
 
 impl<VM: VMBinding> Plan for ConcurrentImmix<VM> {
     fn collection_required(&self, space_full: bool, _space: Option<SpaceStats<Self::VM>>) -> bool {
-        if self.base().collection_required(self, space_full) {
-            self.should_do_full_gc.store(true, Ordering::Release);
-            info!("Triggering full GC");
-            return true;
-        }
-
-        let concurrent_marking_in_progress = self.concurrent_marking_in_progress();
-
-        if concurrent_marking_in_progress
-            && self.common.base.scheduler.work_buckets[WorkBucketStage::Concurrent].is_drained()
-        {
-            // After the Concurrent bucket is drained during concurrent marking,
-            // we trigger the FinalMark pause at the next poll() site (here).
-            // FIXME: Immediately trigger FinalMark when the Concurrent bucket is drained.
-            return true;
-        }
-
-        let threshold = self.get_total_pages() >> 1;
-        let used_pages_after_last_gc = self.common.base.global_state.get_used_pages_after_last_gc();
-        let used_pages_now = self.get_used_pages();
-        let allocated = used_pages_now.saturating_sub(used_pages_after_last_gc);
-        if !concurrent_marking_in_progress && allocated > threshold {
-            info!("Allocated {allocated} pages since last GC ({used_pages_now} - {used_pages_after_last_gc} > {threshold}): Do concurrent marking");
-            debug_assert!(
-                self.common.base.scheduler.work_buckets[WorkBucketStage::Concurrent].is_empty()
-            );
-            debug_assert!(!self.concurrent_marking_in_progress());
-            debug_assert_ne!(self.previous_pause(), Some(Pause::InitialMark));
-            return true;
-        }
-        false
+        self.pauses.collection_required(self, space_full)
     }
 
     fn last_collection_was_exhaustive(&self) -> bool {
@@ -124,26 +93,21 @@ impl<VM: VMBinding> Plan for ConcurrentImmix<VM> {
     }
 
     fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<VM>) {
-        let pause = if self.concurrent_marking_in_progress() {
-            // FIXME: Currently it is unsafe to bypass `FinalMark` and go directly from `InitialMark` to `Full`.
-            // It is related to defragmentation.  See https://github.com/mmtk/mmtk-core/issues/1357 for more details.
-            // We currently force `FinalMark` to happen if the last pause is `InitialMark`.
-            Pause::FinalMark
-        } else if self.should_do_full_gc.load(Ordering::SeqCst) {
-            Pause::Full
-        } else {
-            Pause::InitialMark
-        };
-
-        self.current_pause.store(Some(pause), Ordering::SeqCst);
+        let pause = self.pauses.start_pause();
 
         probe!(mmtk, concurrent_pause_determined, pause as usize);
+        trace::record_gc_decision(
+            scheduler.tracer(),
+            "concurrent_pause_determined",
+            "pause",
+            || TraceArg::Str(format!("{:?}", pause)),
+        );
 
         match pause {
             Pause::Full => {
                 // Ref closure buckets is disabled by initial mark, and needs to be re-enabled for full GC before
                 // we reuse the normal Immix scheduling.
-                self.set_ref_closure_buckets_enabled(true);
+                set_ref_closure_buckets_enabled(scheduler, true);
                 crate::plan::immix::global::Immix::schedule_immix_full_heap_collection::<
                     ConcurrentImmix<VM>,
                     ConcurrentImmixSTWGCWorkContext<VM, TRACE_KIND_FAST>,
@@ -220,21 +184,7 @@ impl<VM: VMBinding> Plan for ConcurrentImmix<VM> {
         self.last_gc_was_defrag
             .store(self.immix_space.end_of_gc(), Ordering::Relaxed);
 
-        let pause = self.current_pause().unwrap();
-        if pause == Pause::InitialMark {
-            self.set_concurrent_marking_state(true);
-        }
-        self.previous_pause.store(Some(pause), Ordering::SeqCst);
-        self.current_pause.store(None, Ordering::SeqCst);
-        if pause != Pause::FinalMark {
-            self.should_do_full_gc.store(false, Ordering::SeqCst);
-        } else {
-            // FIXME: Currently it is unsafe to trigger full GC during concurrent marking.
-            // See `Self::schedule_collection`.
-            // We keep the value of `self.should_do_full_gc` so that if full GC is triggered,
-            // the next GC will be full GC.
-        }
-        info!("{:?} end", pause);
+        self.pauses.end_of_gc(self);
     }
 
     fn current_gc_may_move_object(&self) -> bool {
@@ -262,29 +212,7 @@ impl<VM: VMBinding> Plan for ConcurrentImmix<VM> {
     }
 
     fn notify_mutators_paused(&self, _scheduler: &GCWorkScheduler<VM>) {
-        use crate::vm::ActivePlan;
-        let pause = self.current_pause().unwrap();
-        match pause {
-            Pause::Full => {
-                self.set_concurrent_marking_state(false);
-            }
-            Pause::InitialMark => {
-                debug_assert!(
-                    !self.concurrent_marking_in_progress(),
-                    "prev pause: {:?}",
-                    self.previous_pause().unwrap()
-                );
-            }
-            Pause::FinalMark => {
-                debug_assert!(self.concurrent_marking_in_progress());
-                // Flush barrier buffers
-                for mutator in <VM as VMBinding>::VMActivePlan::mutators() {
-                    mutator.barrier.flush();
-                }
-                self.set_concurrent_marking_state(false);
-            }
-        }
-        info!("{:?} start", pause);
+        self.pauses.notify_mutators_paused(self);
     }
 
     fn concurrent(&self) -> Option<&dyn ConcurrentPlan<VM = VM>> {
@@ -307,6 +235,7 @@ impl<VM: VMBinding> ConcurrentImmix<VM> {
         let immix_args = ImmixSpaceArgs {
             mixed_age: false,
             never_move_objects: false,
+            reference_counted: false,
         };
 
         // These buckets are not used in an Immix plan. We can simply disable them.
@@ -326,10 +255,7 @@ impl<VM: VMBinding> ConcurrentImmix<VM> {
             ),
             common: CommonPlan::new(plan_args),
             last_gc_was_defrag: AtomicBool::new(false),
-            current_pause: Atomic::new(None),
-            previous_pause: Atomic::new(None),
-            should_do_full_gc: AtomicBool::new(false),
-            concurrent_marking_active: AtomicBool::new(false),
+            pauses: ConcurrentMarkingPauses::new(),
         };
 
         immix.verify_side_metadata_sanity();
@@ -337,22 +263,13 @@ impl<VM: VMBinding> ConcurrentImmix<VM> {
         immix
     }
 
-    fn set_ref_closure_buckets_enabled(&self, do_closure: bool) {
-        let scheduler = &self.common.base.scheduler;
-        scheduler.work_buckets[WorkBucketStage::VMRefClosure].set_enabled(do_closure);
-        scheduler.work_buckets[WorkBucketStage::WeakRefClosure].set_enabled(do_closure);
-        scheduler.work_buckets[WorkBucketStage::FinalRefClosure].set_enabled(do_closure);
-        scheduler.work_buckets[WorkBucketStage::SoftRefClosure].set_enabled(do_closure);
-        scheduler.work_buckets[WorkBucketStage::PhantomRefClosure].set_enabled(do_closure);
-    }
-
     pub(crate) fn schedule_concurrent_marking_initial_pause(
         &'static self,
         scheduler: &GCWorkScheduler<VM>,
     ) {
         use crate::scheduler::gc_work::Prepare;
 
-        self.set_ref_closure_buckets_enabled(false);
+        set_ref_closure_buckets_enabled(scheduler, false);
 
         scheduler.work_buckets[WorkBucketStage::Unconstrained].add(StopMutators::<
             ConcurrentImmixGCWorkContext<ProcessRootSlots<VM, Self, TRACE_KIND_FAST>>,
@@ -363,7 +280,7 @@ impl<VM: VMBinding> ConcurrentImmix<VM> {
     }
 
     fn schedule_concurrent_marking_final_pause(&'static self, scheduler: &GCWorkScheduler<VM>) {
-        self.set_ref_closure_buckets_enabled(true);
+        set_ref_closure_buckets_enabled(scheduler, true);
 
         // Skip root scanning in the final mark
         scheduler.work_buckets[WorkBucketStage::Unconstrained].add(StopMutators::<
@@ -375,72 +292,19 @@ impl<VM: VMBinding> ConcurrentImmix<VM> {
         >::new(self));
 
         // Deal with weak ref and finalizers
-        // TODO: Check against schedule_common_work and see if we are still missing any work packet
-        type RefProcessingEdges<VM> =
-            crate::scheduler::gc_work::PlanProcessEdges<VM, ConcurrentImmix<VM>, TRACE_KIND_FAST>;
-        // Reference processing
-        if !*self.base().options.no_reference_types {
-            use crate::util::reference_processor::{
-                PhantomRefProcessing, SoftRefProcessing, WeakRefProcessing,
-            };
-            scheduler.work_buckets[WorkBucketStage::SoftRefClosure]
-                .add(SoftRefProcessing::<RefProcessingEdges<VM>>::new());
-            scheduler.work_buckets[WorkBucketStage::WeakRefClosure]
-                .add(WeakRefProcessing::<VM>::new());
-            scheduler.work_buckets[WorkBucketStage::PhantomRefClosure]
-                .add(PhantomRefProcessing::<VM>::new());
-
-            use crate::util::reference_processor::RefEnqueue;
-            scheduler.work_buckets[WorkBucketStage::Release].add(RefEnqueue::<VM>::new());
-        }
-
-        // Finalization
-        if !*self.base().options.no_finalizer {
-            use crate::util::finalizable_processor::Finalization;
-            // finalization
-            scheduler.work_buckets[WorkBucketStage::FinalRefClosure]
-                .add(Finalization::<RefProcessingEdges<VM>>::new());
-        }
-
-        // VM-specific weak ref processing
-        // Note that ConcurrentImmix does not have a separate forwarding stage,
-        // so we don't schedule the `VMForwardWeakRefs` work packet.
-        scheduler.work_buckets[WorkBucketStage::VMRefClosure]
-            .set_sentinel(Box::new(VMProcessWeakRefs::<RefProcessingEdges<VM>>::new()));
+        schedule_final_mark_weak_ref_processing::<
+            crate::scheduler::gc_work::PlanProcessEdges<VM, ConcurrentImmix<VM>, TRACE_KIND_FAST>,
+        >(self, scheduler);
     }
 
     pub fn concurrent_marking_in_progress(&self) -> bool {
-        self.concurrent_marking_active.load(Ordering::Acquire)
-    }
-
-    fn set_concurrent_marking_state(&self, active: bool) {
-        use crate::plan::global::HasSpaces;
-
-        // Tell the spaces to allocate new objects as live
-        let allocate_object_as_live = active;
-        self.for_each_space(&mut |space: &dyn Space<VM>| {
-            space.set_allocate_as_live(allocate_object_as_live);
-        });
-
-        // Store the state.
-        self.concurrent_marking_active
-            .store(active, Ordering::SeqCst);
-
-        // We also set SATB barrier as active -- this is done in Mutator prepare/release.
-    }
-
-    pub(super) fn is_concurrent_marking_active(&self) -> bool {
-        self.concurrent_marking_active.load(Ordering::SeqCst)
-    }
-
-    fn previous_pause(&self) -> Option<Pause> {
-        self.previous_pause.load(Ordering::SeqCst)
+        self.pauses.concurrent_marking_in_progress()
     }
 }
 
 impl<VM: VMBinding> ConcurrentPlan for ConcurrentImmix<VM> {
     fn current_pause(&self) -> Option<Pause> {
-        self.current_pause.load(Ordering::SeqCst)
+        self.pauses.current_pause()
     }
 
     fn concurrent_work_in_progress(&self) -> bool {
@@ -124,7 +124,7 @@ pub fn create_concurrent_immix_mutator<VM: VMBinding>(
         .barrier
         .downcast_mut::<BarrierType<VM>>()
         .unwrap()
-        .set_weak_ref_barrier_enabled(immix.is_concurrent_marking_active());
+        .set_weak_ref_barrier_enabled(immix.concurrent_marking_in_progress());
 
     mutator
 }
@@ -0,0 +1,236 @@
+use super::gc_work::{ProcessIncs, RCIncEdges};
+use super::LXR;
+use crate::plan::barriers::BarrierSemantics;
+use crate::plan::concurrent::concurrent_marking_work::ProcessModBufSATB;
+use crate::plan::concurrent::global::ConcurrentPlan;
+use crate::plan::concurrent::Pause;
+use crate::plan::VectorQueue;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::policy::immix::TRACE_KIND_FAST;
+use crate::scheduler::{ProcessEdgesWork, WorkBucketStage};
+use crate::util::{ObjectReference, VMMutatorThread};
+use crate::vm::slot::{MemorySlice, Slot};
+use crate::vm::VMBinding;
+use crate::MMTK;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+/// Locks that serialize the mutators that log the same object.  See [`LXRBarrierSemantics::log_object`].
+static LOG_LOCKS: [Mutex<()>; 256] = [const { Mutex::new(()) }; 256];
+
+/// The barrier semantics of [`LXR`].
+///
+/// The first time an object is modified after a GC, the barrier logs the object: It records the
+/// old values of the fields of the object, whose counts will be decremented after the next GC,
+/// and it remembers the object, whose fields will be scanned in the next GC so that the counts
+/// of the new values are incremented.  Objects are logged if their unlog bits are set.  Young
+/// objects are never logged, as they are scanned when they are promoted.
+///
+/// During concurrent marking, the old values are also recorded for the backup trace, like the
+/// SATB barrier of [`crate::plan::concurrent::barrier::SATBBarrierSemantics`].
+pub struct LXRBarrierSemantics<VM: VMBinding> {
+    mmtk: &'static MMTK<VM>,
+    tls: VMMutatorThread,
+    plan: &'static LXR<VM>,
+    incs: VectorQueue<ObjectReference>,
+    decs: VectorQueue<ObjectReference>,
+    satb: VectorQueue<ObjectReference>,
+    refs: VectorQueue<ObjectReference>,
+    /// The slots of memory slices outside known objects.  See [`Self::log_slots`].
+    slots: VectorQueue<VM::VMSlot>,
+}
+
+impl<VM: VMBinding> LXRBarrierSemantics<VM> {
+    pub fn new(mmtk: &'static MMTK<VM>, tls: VMMutatorThread) -> Self {
+        Self {
+            mmtk,
+            tls,
+            plan: mmtk.get_plan().downcast_ref::<LXR<VM>>().unwrap(),
+            incs: VectorQueue::default(),
+            decs: VectorQueue::default(),
+            satb: VectorQueue::default(),
+            refs: VectorQueue::default(),
+            slots: VectorQueue::default(),
+        }
+    }
+
+    fn object_is_unlogged(&self, object: ObjectReference) -> bool {
+        Self::UNLOG_BIT_SPEC.load_atomic::<VM, u8>(object, None, Ordering::SeqCst) != 0
+    }
+
+    /// Log an object if it is not logged yet.
+    ///
+    /// The old values must be recorded before any mutator modifies the object.  A mutator that
+    /// sees the unlog bit set may not write to the object until the unlog bit is cleared, so the
+    /// mutators that try to log the same object at the same time take a lock, and only the first
+    /// one records the old values.
+    fn log_object(&mut self, object: ObjectReference) {
+        let lock = &LOG_LOCKS[(object.to_raw_address().as_usize() >> 4) % LOG_LOCKS.len()];
+        let _guard = lock.lock().unwrap();
+        if !self.object_is_unlogged(object) {
+            return;
+        }
+        let marking = self.plan.concurrent_work_in_progress();
+        crate::plan::tracing::SlotIterator::<VM>::iterate_fields(object, self.tls.0, |s| {
+            let Some(old) = s.load() else {
+                return;
+            };
+            self.decs.push(old);
+            if self.decs.is_full() {
+                self.flush_decs();
+            }
+            if marking {
+                self.satb.push(old);
+                if self.satb.is_full() {
+                    self.flush_satb();
+                }
+            }
+        });
+        Self::UNLOG_BIT_SPEC.store_atomic::<VM, u8>(object, 0, None, Ordering::SeqCst);
+        self.incs.push(object);
+        if self.incs.is_full() {
+            self.flush_incs();
+        }
+    }
+
+    /// Log the slots of a memory slice whose object is unknown.
+    ///
+    /// Without the object, we cannot tell whether the old values are counted: The object may be
+    /// young, or the slots may have been logged since the last GC.  So the old values are not
+    /// decremented, and the objects they point to are kept alive until a backup trace finds them
+    /// dead.  The values stored to the slots are incremented in the next GC.
+    fn log_slots(&mut self, slice: VM::VMMemorySlice) {
+        let marking = self.plan.concurrent_work_in_progress();
+        for s in slice.iter_slots() {
+            if marking {
+                if let Some(old) = s.load() {
+                    self.satb.push(old);
+                    if self.satb.is_full() {
+                        self.flush_satb();
+                    }
+                }
+            }
+            self.slots.push(s);
+            if self.slots.is_full() {
+                self.flush_slots();
+            }
+        }
+    }
+
+    fn flush_incs(&mut self) {
+        if !self.incs.is_empty() {
+            self.mmtk.scheduler.work_buckets[WorkBucketStage::Closure]
+                .add(ProcessIncs::<VM>::new(self.incs.take()));
+        }
+    }
+
+    fn flush_slots(&mut self) {
+        if !self.slots.is_empty() {
+            let w = RCIncEdges::<VM, DEFAULT_TRACE>::new(
+                self.slots.take(),
+                false,
+                self.mmtk,
+                WorkBucketStage::Closure,
+            );
+            self.mmtk.scheduler.work_buckets[WorkBucketStage::Closure].add(w);
+        }
+    }
+
+    fn flush_decs(&mut self) {
+        if !self.decs.is_empty() {
+            self.plan.add_decs(self.decs.take());
+        }
+    }
+
+    fn flush_satb(&mut self) {
+        if !self.satb.is_empty() {
+            let satb = self.satb.take();
+            if self.should_create_satb_packets() {
+                self.add_satb_packet(satb);
+            }
+        }
+    }
+
+    #[cold]
+    fn flush_weak_refs(&mut self) {
+        if !self.refs.is_empty() {
+            let nodes = self.refs.take();
+            self.add_satb_packet(nodes);
+        }
+    }
+
+    fn add_satb_packet(&self, nodes: Vec<ObjectReference>) {
+        let bucket = if self.plan.concurrent_work_in_progress() {
+            WorkBucketStage::Concurrent
+        } else {
+            debug_assert_ne!(self.plan.current_pause(), Some(Pause::InitialMark));
+            WorkBucketStage::Closure
+        };
+        self.mmtk.scheduler.work_buckets[bucket].add(ProcessModBufSATB::<
+            VM,
+            LXR<VM>,
+            TRACE_KIND_FAST,
+        >::new(nodes));
+    }
+
+    fn should_create_satb_packets(&self) -> bool {
+        self.plan.concurrent_work_in_progress()
+            || self.plan.current_pause() == Some(Pause::FinalMark)
+    }
+}
+
+impl<VM: VMBinding> BarrierSemantics for LXRBarrierSemantics<VM> {
+    type VM = VM;
+
+    #[cold]
+    fn flush(&mut self) {
+        self.flush_incs();
+        self.flush_slots();
+        self.flush_decs();
+        self.flush_satb();
+        self.flush_weak_refs();
+    }
+
+    fn object_reference_write_slow(
+        &mut self,
+        src: ObjectReference,
+        _slot: <Self::VM as VMBinding>::VMSlot,
+        _target: Option<ObjectReference>,
+    ) {
+        self.log_object(src);
+    }
+
+    fn memory_region_copy_slow(
+        &mut self,
+        _src: <Self::VM as VMBinding>::VMMemorySlice,
+        dst: <Self::VM as VMBinding>::VMMemorySlice,
+    ) {
+        // We log the whole object that contains the destination slice, or every slot in the
+        // slice if the object is unknown.
+        let Some(object) = dst.object() else {
+            self.log_slots(dst);
+            return;
+        };
+        if self.object_is_unlogged(object) {
+            self.log_object(object);
+        }
+    }
+
+    /// Enqueue the referent during concurrent marking.  See
+    /// [`crate::plan::concurrent::barrier::SATBBarrierSemantics`] for why this is needed.
+    fn load_weak_reference(&mut self, o: ObjectReference) {
+        if !self.plan.concurrent_work_in_progress() {
+            return;
+        }
+        self.refs.push(o);
+        if self.refs.is_full() {
+            self.flush_weak_refs();
+        }
+    }
+
+    fn object_probable_write_slow(&mut self, obj: ObjectReference) {
+        if self.object_is_unlogged(obj) {
+            self.log_object(obj);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use super::global::LXR;
+use crate::plan::concurrent::concurrent_marking_work::ConcurrentTraceObjects;
+use crate::plan::concurrent::global::ConcurrentPlan;
+use crate::plan::concurrent::Pause;
+use crate::plan::VectorObjectQueue;
+use crate::policy::gc_work::{TraceKind, DEFAULT_TRACE, TRACE_KIND_TRANSITIVE_PIN};
+use crate::policy::immix::TRACE_KIND_FAST;
+use crate::policy::space::Space;
+use crate::scheduler::gc_work::{ProcessEdgesBase, ScanObjects, SlotOf, StopMutators};
+use crate::scheduler::{GCWork, GCWorker, ProcessEdgesWork, WorkBucketStage};
+use crate::util::metadata::ref_count;
+use crate::util::ObjectReference;
+use crate::vm::slot::Slot;
+use crate::vm::{ObjectModel, VMBinding};
+use crate::MMTK;
+use crossbeam::deque::Steal;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::Ordering;
+
+pub(super) struct LXRGCWorkContext<VM: VMBinding>(std::marker::PhantomData<VM>);
+impl<VM: VMBinding> crate::scheduler::GCWorkContext for LXRGCWorkContext<VM> {
+    type VM = VM;
+    type PlanType = LXR<VM>;
+    type DefaultProcessEdges = RCIncEdges<VM, DEFAULT_TRACE>;
+    type PinningProcessEdges = RCIncEdges<VM, TRACE_KIND_TRANSITIVE_PIN>;
+}
+
+/// Process edges by incrementing the reference counts of the objects they point to.
+///
+/// A young object is promoted when its count is incremented for the first time, and it is then
+/// scanned so that the counts of its children are incremented, too.  The counts of the objects
+/// pointed to by roots are only held until the next GC, when they are decremented again.  In the
+/// initial mark pause, those objects are also where the backup trace starts.
+pub struct RCIncEdges<VM: VMBinding, const KIND: TraceKind> {
+    plan: &'static LXR<VM>,
+    base: ProcessEdgesBase<VM>,
+    /// The objects pointed to by the roots in this packet.
+    root_objects: Vec<ObjectReference>,
+    /// Whether the backup trace starts from the objects pointed to by the roots.
+    mark_root_objects: bool,
+}
+
+impl<VM: VMBinding, const KIND: TraceKind> ProcessEdgesWork for RCIncEdges<VM, KIND> {
+    type VM = VM;
+    type ScanObjectsWorkType = ScanObjects<Self>;
+
+    fn new(
+        slots: Vec<SlotOf<Self>>,
+        roots: bool,
+        mmtk: &'static MMTK<VM>,
+        bucket: WorkBucketStage,
+    ) -> Self {
+        let base = ProcessEdgesBase::new(slots, roots, mmtk, bucket);
+        let plan = base.plan().downcast_ref::<LXR<VM>>().unwrap();
+        Self {
+            plan,
+            base,
+            root_objects: vec![],
+            mark_root_objects: roots,
+        }
+    }
+
+    fn create_scan_work(&self, nodes: Vec<ObjectReference>) -> Self::ScanObjectsWorkType {
+        ScanObjects::<Self>::new(nodes, false, self.bucket)
+    }
+
+    fn trace_object(&mut self, object: ObjectReference) -> ObjectReference {
+        // We cannot borrow `self` twice in a call, so we extract `worker` as a local variable.
+        let worker = self.worker();
+        let new_object =
+            self.plan
+                .increment::<VectorObjectQueue, KIND>(&mut self.base.nodes, object, worker);
+        if self.roots {
+            self.root_objects.push(new_object);
+        }
+        new_object
+    }
+}
+
+impl<VM: VMBinding, const KIND: TraceKind> Drop for RCIncEdges<VM, KIND> {
+    // Node roots are traced with `trace_object` directly, and the packet is dropped without
+    // being flushed.  So we hand over the root objects when the packet is dropped.
+    fn drop(&mut self) {
+        if self.root_objects.is_empty() {
+            return;
+        }
+        let root_objects = std::mem::take(&mut self.root_objects);
+        if self.mark_root_objects && self.plan.current_pause() == Some(Pause::InitialMark) {
+            let w = ConcurrentTraceObjects::<VM, LXR<VM>, TRACE_KIND_FAST>::new(
+                root_objects.clone(),
+                self.mmtk(),
+            );
+            // The concurrent bucket is disabled during the pause, and will be enabled when the pause ends.
+            self.mmtk().scheduler.work_buckets[WorkBucketStage::Concurrent].add_no_notify(w);
+        }
+        self.plan.add_root_objects(root_objects);
+    }
+}
+
+impl<VM: VMBinding, const KIND: TraceKind> Deref for RCIncEdges<VM, KIND> {
+    type Target = ProcessEdgesBase<VM>;
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl<VM: VMBinding, const KIND: TraceKind> DerefMut for RCIncEdges<VM, KIND> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+/// Process edges for the objects that are kept alive by weak references and finalizers.
+///
+/// Those objects are held in the same way as the objects pointed to by roots, i.e. until the
+/// next GC, so that reference counting does not reclaim them before the weak references are
+/// processed again.  Unlike roots, they do not start the backup trace, as it is the trace that
+/// decides whether they are still strongly reachable.
+pub struct RCRetainEdges<VM: VMBinding> {
+    inner: RCIncEdges<VM, DEFAULT_TRACE>,
+}
+
+impl<VM: VMBinding> ProcessEdgesWork for RCRetainEdges<VM> {
+    type VM = VM;
+    // The children of the retained objects are counted as usual.
+    type ScanObjectsWorkType = ScanObjects<RCIncEdges<VM, DEFAULT_TRACE>>;
+
+    fn new(
+        slots: Vec<SlotOf<Self>>,
+        _roots: bool,
+        mmtk: &'static MMTK<VM>,
+        bucket: WorkBucketStage,
+    ) -> Self {
+        let mut inner = RCIncEdges::new(slots, true, mmtk, bucket);
+        inner.mark_root_objects = false;
+        Self { inner }
+    }
+
+    fn create_scan_work(&self, nodes: Vec<ObjectReference>) -> Self::ScanObjectsWorkType {
+        ScanObjects::new(nodes, false, self.bucket)
+    }
+
+    fn trace_object(&mut self, object: ObjectReference) -> ObjectReference {
+        self.inner.trace_object(object)
+    }
+}
+
+impl<VM: VMBinding> Deref for RCRetainEdges<VM> {
+    type Target = ProcessEdgesBase<VM>;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<VM: VMBinding> DerefMut for RCRetainEdges<VM> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+/// Increment the counts of the children of the objects that are logged by the write barrier
+/// since the last GC, and set the unlog bits of those objects again.
+pub(super) struct ProcessIncs<VM: VMBinding> {
+    objects: Vec<ObjectReference>,
+    _p: std::marker::PhantomData<VM>,
+}
+
+impl<VM: VMBinding> ProcessIncs<VM> {
+    pub fn new(objects: Vec<ObjectReference>) -> Self {
+        Self {
+            objects,
+            _p: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for ProcessIncs<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let objects = std::mem::take(&mut self.objects);
+        for object in objects.iter() {
+            VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC
+                .mark_as_unlogged::<VM>(*object, atomic::Ordering::SeqCst);
+        }
+        let mut w = ScanObjects::<RCIncEdges<VM, DEFAULT_TRACE>>::new(
+            objects,
+            false,
+            WorkBucketStage::Closure,
+        );
+        GCWork::do_work(&mut w, worker, mmtk);
+    }
+}
+
+/// Decrement the counts of objects.  An object in the Immix space is dead once its count drops
+/// to zero, and the counts of its children are decremented recursively.  Its memory is reclaimed
+/// when the space is swept in the next GC.  Objects in other spaces are only reclaimed by the
+/// backup trace, and their children are not decremented.
+///
+/// Decrements are processed concurrently after a GC.  The next GC processes the remaining ones
+/// with [`FinishDecs`] before it stops the mutators, so all of them are processed before it
+/// increments any count.
+pub(super) struct ProcessDecs<VM: VMBinding> {
+    objects: Vec<ObjectReference>,
+    _p: std::marker::PhantomData<VM>,
+}
+
+impl<VM: VMBinding> ProcessDecs<VM> {
+    const CAPACITY: usize = 4096;
+
+    pub fn new(plan: &LXR<VM>, objects: Vec<ObjectReference>) -> Self {
+        plan.pending_decs.fetch_add(1, Ordering::SeqCst);
+        Self {
+            objects,
+            _p: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for ProcessDecs<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let plan = mmtk.get_plan().downcast_ref::<LXR<VM>>().unwrap();
+        let mut objects = std::mem::take(&mut self.objects);
+        // A mutator that assists concurrent work stops once it has done its share of work.
+        while !worker.is_assist_budget_exhausted() {
+            let Some(object) = objects.pop() else {
+                break;
+            };
+            if !plan.immix_space.in_space(object) {
+                if object.is_in_any_space() {
+                    ref_count::dec(object);
+                }
+                continue;
+            }
+            if !ref_count::dec(object) {
+                continue;
+            }
+            crate::plan::tracing::SlotIterator::<VM>::iterate_fields(object, worker.tls.0, |s| {
+                if let Some(child) = s.load() {
+                    objects.push(child);
+                }
+            });
+            worker.consume_assist_budget(VM::VMObjectModel::get_current_size(object));
+            if objects.len() > Self::CAPACITY {
+                let rest = objects.split_off(objects.len() / 2);
+                worker.add_work(WorkBucketStage::Concurrent, Self::new(plan, rest));
+            }
+        }
+        if !objects.is_empty() {
+            worker.add_work(WorkBucketStage::Concurrent, Self::new(plan, objects));
+        }
+        plan.pending_decs.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Process the decrements left from the last GC, and then stop the mutators.  This is the first
+/// packet of every pause.
+///
+/// The decrements may not have finished when a GC is requested, e.g. if a mutator that assists
+/// concurrent work still holds some of them, or if the `Concurrent` bucket is disabled.  They must
+/// be processed before this GC increments any count.  Otherwise, an object whose count drops to
+/// zero in the middle of the pause would be promoted again as a young object, and may be
+/// evacuated while its children are being decremented.
+pub(super) struct FinishDecs<VM: VMBinding>(std::marker::PhantomData<VM>);
+
+impl<VM: VMBinding> FinishDecs<VM> {
+    pub fn new() -> Self {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for FinishDecs<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let plan = mmtk.get_plan().downcast_ref::<LXR<VM>>().unwrap();
+        let bucket = &mmtk.scheduler.work_buckets[WorkBucketStage::Concurrent];
+        if plan.pending_decs.load(Ordering::SeqCst) != 0 {
+            bucket.set_enabled(true);
+            bucket.open();
+        }
+        while plan.pending_decs.load(Ordering::SeqCst) != 0 {
+            // Other GC workers and mutators may execute the decrements, too.  Other concurrent
+            // packets taken from the bucket (e.g. concurrent marking before a final mark pause)
+            // would be executed in this pause anyway.
+            let work = match worker.local_work_buffer.pop() {
+                Some(work) => Some(work),
+                None => match bucket.poll_one() {
+                    Steal::Success(work) => Some(work),
+                    Steal::Retry => continue,
+                    Steal::Empty => None,
+                },
+            };
+            match work {
+                Some(mut work) => work.do_work(worker, mmtk),
+                // Another thread is executing the last decrements.
+                None => std::thread::yield_now(),
+            }
+        }
+        GCWork::do_work(
+            &mut StopMutators::<LXRGCWorkContext<VM>>::new(),
+            worker,
+            mmtk,
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use super::gc_work::{FinishDecs, LXRGCWorkContext, ProcessDecs, RCRetainEdges};
+use crate::plan::concurrent::global::ConcurrentPlan;
+use crate::plan::concurrent::Pause;
+use crate::plan::global::BasePlan;
+use crate::plan::global::CommonPlan;
+use crate::plan::global::CreateGeneralPlanArgs;
+use crate::plan::global::CreateSpecificPlanArgs;
+use crate::plan::immix::mutator::ALLOCATOR_MAPPING;
+use crate::plan::AllocationSemantics;
+use crate::plan::ObjectQueue;
+use crate::plan::Plan;
+use crate::plan::PlanConstraints;
+use crate::plan::PlanTraceObject;
+use crate::plan::VectorObjectQueue;
+use crate::policy::gc_work::{TraceKind, TRACE_KIND_TRANSITIVE_PIN};
+use crate::policy::immix::ImmixSpaceArgs;
+use crate::policy::immix::TRACE_KIND_FAST;
+use crate::policy::space::Space;
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, Prepare, Release, VMForwardWeakRefs, VMPostForwarding, VMProcessWeakRefs,
+};
+use crate::scheduler::trace::{self, TraceArg};
+use crate::scheduler::*;
+use crate::util::alloc::allocators::AllocatorSelector;
+use crate::util::copy::*;
+use crate::util::heap::gc_trigger::SpaceStats;
+use crate::util::heap::VMRequest;
+use crate::util::metadata::log_bit::UnlogBitsOperation;
+use crate::util::metadata::ref_count;
+use crate::util::metadata::side_metadata::spec_defs::RC_COUNT;
+use crate::util::metadata::side_metadata::SideMetadataContext;
+use crate::util::metadata::MetadataSpec;
+use crate::util::ObjectReference;
+use crate::vm::ObjectModel;
+use crate::vm::VMBinding;
+use crate::{policy::immix::ImmixSpace, util::opaque_pointer::VMWorkerThread};
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::Mutex;
+
+use atomic::Atomic;
+use atomic::Ordering;
+use enum_map::EnumMap;
+
+use mmtk_macros::{HasSpaces, PlanTraceObject};
+
+/// A reference counting Immix plan in the style of LXR (Zhao, Blackburn and McKinley, PLDI 2022).
+///
+/// Objects in the Immix space are reclaimed by coalescing, deferred reference counting.  The
+/// write barrier logs an object the first time it is modified after a GC.  In the next GC, the
+/// counts of the objects that the logged objects and the roots point to are incremented, and the
+/// counts of the old values of the logged objects, and of the objects that the roots pointed to
+/// in the last GC, are decremented lazily in the `Concurrent` bucket after the GC.  Objects are
+/// young until their counts are incremented for the first time.  Young objects are promoted at
+/// that point, and they are evacuated if the GC is a [`Pause::Full`] pause.  The lines that hold
+/// no counted objects are reclaimed in every GC.
+///
+/// Cyclic garbage, and objects outside the Immix space, are reclaimed by a backup trace, which
+/// marks objects concurrently with an SATB barrier between an [`Pause::InitialMark`] pause and a
+/// [`Pause::FinalMark`] pause.  Both pauses are reference counting pauses, too.  Weak references
+/// and finalizable objects are held alive until the next GC, and they are only reclaimed in
+/// [`Pause::Full`] pauses if they are young, and otherwise by the backup trace.
+///
+/// Counts are stuck once they overflow, and objects with stuck counts can only be reclaimed by
+/// the backup trace.  There is no mature defragmentation.
+#[derive(HasSpaces, PlanTraceObject)]
+pub struct LXR<VM: VMBinding> {
+    #[space]
+    #[copy_semantics(CopySemantics::DefaultCopy)]
+    pub immix_space: ImmixSpace<VM>,
+    #[parent]
+    pub common: CommonPlan<VM>,
+    current_pause: Atomic<Option<Pause>>,
+    previous_pause: Atomic<Option<Pause>>,
+    should_do_backup_trace: AtomicBool,
+    concurrent_marking_active: AtomicBool,
+    /// The old values of the fields of the objects logged since the last GC.
+    decs: Mutex<Vec<Vec<ObjectReference>>>,
+    /// The objects pointed to by the roots (and kept alive by weak references) in this GC.
+    curr_roots: Mutex<Vec<Vec<ObjectReference>>>,
+    /// The objects pointed to by the roots (and kept alive by weak references) in the last GC.
+    prev_roots: Mutex<Vec<Vec<ObjectReference>>>,
+    /// The number of [`ProcessDecs`] packets that have not finished.
+    pub(super) pending_decs: AtomicUsize,
+}
+
+/// The plan constraints for the LXR plan.
+pub const LXR_CONSTRAINTS: PlanConstraints = PlanConstraints {
+    // Young objects are evacuated when they are promoted.
+    moves_objects: true,
+    // Max immix object size is half of a block.
+    max_non_los_default_alloc_bytes: crate::policy::immix::MAX_IMMIX_OBJECT_SIZE,
+    needs_prepare_mutator: true,
+    barrier: crate::BarrierSelector::SATBBarrier,
+    needs_log_bit: true,
+    needs_ref_count: true,
+    // The slots of memory slices are logged every time they are copied to, if their objects are
+    // unknown.  See `LXRBarrierSemantics::log_slots`.
+    may_trace_duplicate_edges: true,
+    ..PlanConstraints::default()
+};
+
+impl<VM: VMBinding> Plan for LXR<VM> {
+    fn collection_required(&self, space_full: bool, _space: Option<SpaceStats<Self::VM>>) -> bool {
+        if self.base().collection_required(self, space_full) {
+            self.should_do_backup_trace.store(true, Ordering::Release);
+            info!("Triggering backup trace");
+            return true;
+        }
+
+        let concurrent_marking_in_progress = self.concurrent_marking_in_progress();
+
+        if concurrent_marking_in_progress
+            && self.common.base.scheduler.work_buckets[WorkBucketStage::Concurrent].is_drained()
+        {
+            // After the Concurrent bucket is drained during concurrent marking,
+            // we trigger the FinalMark pause at the next poll() site (here).
+            return true;
+        }
+
+        // Young objects are only reclaimed in GCs, so we collect when the young objects would
+        // fill up a nursery.
+        let used_pages_after_last_gc = self.common.base.global_state.get_used_pages_after_last_gc();
+        let allocated = self
+            .get_used_pages()
+            .saturating_sub(used_pages_after_last_gc);
+        allocated > self.common.base.gc_trigger.get_max_nursery_pages()
+    }
+
+    fn last_collection_was_exhaustive(&self) -> bool {
+        self.previous_pause() == Some(Pause::FinalMark)
+    }
+
+    fn constraints(&self) -> &'static PlanConstraints {
+        &LXR_CONSTRAINTS
+    }
+
+    fn create_copy_config(&'static self) -> CopyConfig<Self::VM> {
+        use enum_map::enum_map;
+        CopyConfig {
+            copy_mapping: enum_map! {
+                CopySemantics::DefaultCopy => CopySelector::Immix(0),
+                _ => CopySelector::Unused,
+            },
+            space_mapping: vec![(CopySelector::Immix(0), &self.immix_space)],
+            constraints: &LXR_CONSTRAINTS,
+        }
+    }
+
+    fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<VM>) {
+        let pause = if self.concurrent_marking_in_progress() {
+            Pause::FinalMark
+        } else if self.should_do_backup_trace.load(Ordering::SeqCst)
+            || self.common.base.global_state.get_used_pages_after_last_gc()
+                > self.get_total_pages() >> 1
+        {
+            // Start a backup trace if the heap is full, or if reference counting cannot keep
+            // the heap below half full.
+            Pause::InitialMark
+        } else {
+            Pause::Full
+        };
+
+        self.current_pause.store(Some(pause), Ordering::SeqCst);
+
+        probe!(mmtk, concurrent_pause_determined, pause as usize);
+        trace::record_gc_decision(
+            scheduler.tracer(),
+            "concurrent_pause_determined",
+            "pause",
+            || TraceArg::Str(format!("{:?}", pause)),
+        );
+
+        self.schedule_rc_pause(pause, scheduler);
+    }
+
+    fn get_allocator_mapping(&self) -> &'static EnumMap<AllocationSemantics, AllocatorSelector> {
+        &ALLOCATOR_MAPPING
+    }
+
+    fn prepare(&mut self, tls: VMWorkerThread) {
+        let pause = self.current_pause().unwrap();
+        match pause {
+            Pause::Full => {
+                self.common.prepare(tls, false);
+                self.immix_space
+                    .prepare(false, None, UnlogBitsOperation::NoOp);
+            }
+            Pause::InitialMark => {
+                self.common.prepare(tls, true);
+                // Clear the marks for the backup trace.
+                self.immix_space
+                    .prepare(true, None, UnlogBitsOperation::NoOp);
+            }
+            Pause::FinalMark => {
+                // The other spaces have been prepared for the backup trace in the initial mark.
+                self.immix_space
+                    .prepare(false, None, UnlogBitsOperation::NoOp);
+            }
+        }
+    }
+
+    fn release(&mut self, tls: VMWorkerThread) {
+        let pause = self.current_pause().unwrap();
+        self.immix_space
+            .release(pause == Pause::FinalMark, UnlogBitsOperation::NoOp);
+        match pause {
+            Pause::Full => self.common.release(tls, false),
+            // The other spaces are swept when the backup trace finishes.
+            Pause::InitialMark => (),
+            Pause::FinalMark => self.common.release(tls, true),
+        }
+    }
+
+    fn end_of_gc(&mut self, _tls: VMWorkerThread) {
+        self.immix_space.end_of_gc();
+
+        let pause = self.current_pause().unwrap();
+        match pause {
+            Pause::Full => (),
+            Pause::InitialMark => self.set_concurrent_marking_state(true),
+            Pause::FinalMark => self.immix_space.set_cycle_collection(false),
+        }
+        self.schedule_decs();
+
+        self.previous_pause.store(Some(pause), Ordering::SeqCst);
+        self.current_pause.store(None, Ordering::SeqCst);
+        self.should_do_backup_trace.store(false, Ordering::SeqCst);
+        info!("{:?} end", pause);
+    }
+
+    fn current_gc_may_move_object(&self) -> bool {
+        self.current_pause() == Some(Pause::Full)
+    }
+
+    fn get_collection_reserved_pages(&self) -> usize {
+        self.immix_space.defrag_headroom_pages()
+    }
+
+    fn get_used_pages(&self) -> usize {
+        self.immix_space.reserved_pages() + self.common.get_used_pages()
+    }
+
+    fn base(&self) -> &BasePlan<VM> {
+        &self.common.base
+    }
+
+    fn base_mut(&mut self) -> &mut BasePlan<Self::VM> {
+        &mut self.common.base
+    }
+
+    fn common(&self) -> &CommonPlan<VM> {
+        &self.common
+    }
+
+    fn notify_mutators_paused(&self, _scheduler: &GCWorkScheduler<VM>) {
+        let pause = self.current_pause().unwrap();
+        match pause {
+            Pause::Full | Pause::InitialMark => {
+                debug_assert!(!self.concurrent_marking_in_progress());
+            }
+            Pause::FinalMark => {
+                debug_assert!(self.concurrent_marking_in_progress());
+                self.set_concurrent_marking_state(false);
+                // Objects that are not marked by the backup trace are dead in this GC.
+                self.immix_space.set_cycle_collection(true);
+            }
+        }
+        info!("{:?} start", pause);
+    }
+
+    fn concurrent(&self) -> Option<&dyn ConcurrentPlan<VM = VM>> {
+        Some(self)
+    }
+}
+
+impl<VM: VMBinding> LXR<VM> {
+    pub fn new(args: CreateGeneralPlanArgs<VM>) -> Self {
+        let spec = crate::util::metadata::extract_side_metadata(&[
+            *VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC,
+            MetadataSpec::OnSide(RC_COUNT),
+        ]);
+
+        let mut plan_args = CreateSpecificPlanArgs {
+            global_args: args,
+            constraints: &LXR_CONSTRAINTS,
+            global_side_metadata_specs: SideMetadataContext::new_global_specs(&spec),
+        };
+
+        let immix_args = ImmixSpaceArgs {
+            mixed_age: false,
+            never_move_objects: false,
+            reference_counted: true,
+        };
+
+        // These buckets are not used in this plan. We can simply disable them.
+        let scheduler = &plan_args.global_args.scheduler;
+        scheduler.work_buckets[WorkBucketStage::CalculateForwarding].set_enabled(false);
+        scheduler.work_buckets[WorkBucketStage::SecondRoots].set_enabled(false);
+        scheduler.work_buckets[WorkBucketStage::Compact].set_enabled(false);
+
+        let lxr = LXR {
+            immix_space: ImmixSpace::new(
+                plan_args.get_normal_space_args("immix", true, false, VMRequest::discontiguous()),
+                immix_args,
+            ),
+            common: CommonPlan::new(plan_args),
+            current_pause: Atomic::new(None),
+            previous_pause: Atomic::new(None),
+            should_do_backup_trace: AtomicBool::new(false),
+            concurrent_marking_active: AtomicBool::new(false),
+            decs: Mutex::new(vec![]),
+            curr_roots: Mutex::new(vec![]),
+            prev_roots: Mutex::new(vec![]),
+            pending_decs: AtomicUsize::new(0),
+        };
+
+        lxr.verify_side_metadata_sanity();
+
+        lxr
+    }
+
+    fn schedule_rc_pause(&'static self, pause: Pause, scheduler: &GCWorkScheduler<VM>) {
+        // Every pause scans the roots and increments the counts of the objects they point to,
+        // after the decrements of the last GC are processed.
+        scheduler.work_buckets[WorkBucketStage::Unconstrained].add(FinishDecs::<VM>::new());
+        scheduler.work_buckets[WorkBucketStage::Prepare]
+            .add(Prepare::<LXRGCWorkContext<VM>>::new(self));
+        scheduler.work_buckets[WorkBucketStage::Release]
+            .add(Release::<LXRGCWorkContext<VM>>::new(self));
+
+        // Weak references and finalizers.  Their liveness is decided by the counts in a
+        // `Pause::Full` pause, and by the backup trace in a `Pause::FinalMark` pause.  In the
+        // forwarding stages, the objects they keep alive are held until the next GC.  The marks
+        // are being cleared in a `Pause::InitialMark` pause, so we only hold the objects.
+        type MarkingEdges<VM> = PlanProcessEdges<VM, LXR<VM>, TRACE_KIND_FAST>;
+        if !*self.base().options.no_reference_types {
+            use crate::util::reference_processor::{
+                PhantomRefProcessing, RefEnqueue, RefForwarding, SoftRefProcessing,
+                WeakRefProcessing,
+            };
+            match pause {
+                Pause::Full => scheduler.work_buckets[WorkBucketStage::SoftRefClosure]
+                    .add(SoftRefProcessing::<RCRetainEdges<VM>>::new()),
+                Pause::FinalMark => scheduler.work_buckets[WorkBucketStage::SoftRefClosure]
+                    .add(SoftRefProcessing::<MarkingEdges<VM>>::new()),
+                Pause::InitialMark => (),
+            }
+            if pause != Pause::InitialMark {
+                scheduler.work_buckets[WorkBucketStage::WeakRefClosure]
+                    .add(WeakRefProcessing::<VM>::new());
+                scheduler.work_buckets[WorkBucketStage::PhantomRefClosure]
+                    .add(PhantomRefProcessing::<VM>::new());
+                scheduler.work_buckets[WorkBucketStage::Release].add(RefEnqueue::<VM>::new());
+            }
+            scheduler.work_buckets[WorkBucketStage::RefForwarding]
+                .add(RefForwarding::<RCRetainEdges<VM>>::new());
+        }
+
+        if !*self.base().options.no_finalizer {
+            use crate::util::finalizable_processor::{Finalization, ForwardFinalization};
+            match pause {
+                Pause::Full => scheduler.work_buckets[WorkBucketStage::FinalRefClosure]
+                    .add(Finalization::<RCRetainEdges<VM>>::new()),
+                Pause::FinalMark => scheduler.work_buckets[WorkBucketStage::FinalRefClosure]
+                    .add(Finalization::<MarkingEdges<VM>>::new()),
+                Pause::InitialMark => (),
+            }
+            scheduler.work_buckets[WorkBucketStage::FinalizableForwarding]
+                .add(ForwardFinalization::<RCRetainEdges<VM>>::new());
+        }
+
+        // VM-specific weak ref processing
+        match pause {
+            Pause::Full => scheduler.work_buckets[WorkBucketStage::VMRefClosure]
+                .set_sentinel(Box::new(VMProcessWeakRefs::<RCRetainEdges<VM>>::new())),
+            Pause::FinalMark => scheduler.work_buckets[WorkBucketStage::VMRefClosure]
+                .set_sentinel(Box::new(VMProcessWeakRefs::<MarkingEdges<VM>>::new())),
+            Pause::InitialMark => (),
+        }
+        scheduler.work_buckets[WorkBucketStage::VMRefForwarding]
+            .add(VMForwardWeakRefs::<RCRetainEdges<VM>>::new());
+
+        scheduler.work_buckets[WorkBucketStage::Release].add(VMPostForwarding::<VM>::default());
+    }
+
+    /// Increment the count of an object that is reached in a GC, and return its new location.
+    /// If the object is promoted, it is enqueued so that its children are counted, too.
+    pub(super) fn increment<Q: ObjectQueue, const KIND: TraceKind>(
+        &self,
+        queue: &mut Q,
+        object: ObjectReference,
+        worker: &mut GCWorker<VM>,
+    ) -> ObjectReference {
+        if self.immix_space.in_space(object) {
+            let may_copy =
+                KIND != TRACE_KIND_TRANSITIVE_PIN && self.current_pause() == Some(Pause::Full);
+            let (new_object, promoted) = self
+                .immix_space
+                .increment_and_promote(object, may_copy, worker);
+            if promoted {
+                queue.enqueue(new_object);
+            }
+            new_object
+        } else if object.is_in_any_space() {
+            if ref_count::inc(object) == 0 {
+                // The other spaces keep their own young objects alive by tracing.
+                if self.current_pause() == Some(Pause::Full) {
+                    self.common.trace_object::<_, KIND>(
+                        &mut VectorObjectQueue::new(),
+                        object,
+                        worker,
+                    );
+                }
+                VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC
+                    .mark_as_unlogged::<VM>(object, Ordering::SeqCst);
+                queue.enqueue(object);
+            }
+            object
+        } else {
+            object
+        }
+    }
+
+    /// Record the old values of fields recorded by the write barrier.
+    pub(super) fn add_decs(&self, decs: Vec<ObjectReference>) {
+        self.decs.lock().unwrap().push(decs);
+    }
+
+    /// Record the objects pointed to by the roots in this GC.
+    pub(super) fn add_root_objects(&self, objects: Vec<ObjectReference>) {
+        self.curr_roots.lock().unwrap().push(objects);
+    }
+
+    /// Schedule the decrements recorded since the last GC, and the decrements of the objects
+    /// pointed to by the roots in the last GC.  This is done after all the increments of this GC,
+    /// and after the spaces are swept, so that an object that is reclaimed lazily is not
+    /// reclaimed until the next GC.
+    fn schedule_decs(&self) {
+        let mut decs = std::mem::take(&mut *self.decs.lock().unwrap());
+        let curr_roots = std::mem::take(&mut *self.curr_roots.lock().unwrap());
+        decs.append(&mut std::mem::replace(
+            &mut *self.prev_roots.lock().unwrap(),
+            curr_roots,
+        ));
+        let bucket = &self.common.base.scheduler.work_buckets[WorkBucketStage::Concurrent];
+        for objects in decs.into_iter().filter(|objects| !objects.is_empty()) {
+            // The Concurrent bucket will be enabled when the GC ends.
+            bucket.add_no_notify(ProcessDecs::new(self, objects));
+        }
+    }
+
+    pub fn concurrent_marking_in_progress(&self) -> bool {
+        self.concurrent_marking_active.load(Ordering::Acquire)
+    }
+
+    fn set_concurrent_marking_state(&self, active: bool) {
+        use crate::plan::global::HasSpaces;
+
+        // Tell the spaces other than the Immix space to allocate new objects as live.  Young
+        // objects in the Immix space are kept alive by their counts.
+        let allocate_object_as_live = active;
+        self.common.for_each_space(&mut |space: &dyn Space<VM>| {
+            space.set_allocate_as_live(allocate_object_as_live);
+        });
+
+        // Store the state.
+        self.concurrent_marking_active
+            .store(active, Ordering::SeqCst);
+
+        // We also set SATB barrier as active -- this is done in Mutator prepare/release.
+    }
+
+    pub(super) fn is_concurrent_marking_active(&self) -> bool {
+        self.concurrent_marking_active.load(Ordering::SeqCst)
+    }
+
+    fn previous_pause(&self) -> Option<Pause> {
+        self.previous_pause.load(Ordering::SeqCst)
+    }
+}
+
+impl<VM: VMBinding> ConcurrentPlan for LXR<VM> {
+    fn current_pause(&self) -> Option<Pause> {
+        self.current_pause.load(Ordering::SeqCst)
+    }
+
+    fn concurrent_work_in_progress(&self) -> bool {
+        self.concurrent_marking_in_progress()
+    }
+}
@@ -0,0 +1,8 @@
+//! Plan: LXR-style reference counting immix
+
+pub(in crate::plan) mod barrier;
+pub(in crate::plan) mod gc_work;
+pub(in crate::plan) mod global;
+pub(in crate::plan) mod mutator;
+
+pub use global::LXR;
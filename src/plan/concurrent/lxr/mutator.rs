@@ -0,0 +1,94 @@
+use super::barrier::LXRBarrierSemantics;
+use crate::plan::barriers::SATBBarrier;
+use crate::plan::concurrent::lxr::LXR;
+use crate::plan::concurrent::Pause;
+pub(super) use crate::plan::immix::mutator::ALLOCATOR_MAPPING;
+use crate::plan::immix::mutator::RESERVED_ALLOCATORS;
+use crate::plan::mutator_context::create_space_mapping;
+use crate::plan::mutator_context::Mutator;
+use crate::plan::mutator_context::MutatorBuilder;
+use crate::plan::mutator_context::MutatorConfig;
+use crate::plan::AllocationSemantics;
+use crate::util::alloc::allocators::AllocatorSelector;
+use crate::util::alloc::ImmixAllocator;
+use crate::util::opaque_pointer::{VMMutatorThread, VMWorkerThread};
+use crate::vm::VMBinding;
+use crate::MMTK;
+
+type BarrierType<VM> = SATBBarrier<LXRBarrierSemantics<VM>>;
+
+fn reset_immix_allocator<VM: VMBinding>(mutator: &mut Mutator<VM>) {
+    let immix_allocator = unsafe {
+        mutator
+            .allocators
+            .get_allocator_mut(mutator.config.allocator_mapping[AllocationSemantics::Default])
+    }
+    .downcast_mut::<ImmixAllocator<VM>>()
+    .unwrap();
+    immix_allocator.reset();
+}
+
+pub fn lxr_mutator_prepare<VM: VMBinding>(mutator: &mut Mutator<VM>, _tls: VMWorkerThread) {
+    reset_immix_allocator(mutator);
+
+    // Activate SATB
+    let current_pause = mutator.plan.concurrent().unwrap().current_pause().unwrap();
+    if current_pause == Pause::InitialMark {
+        debug!("Activate SATB barrier active for {:?}", mutator as *mut _);
+        mutator
+            .barrier
+            .downcast_mut::<BarrierType<VM>>()
+            .unwrap()
+            .set_weak_ref_barrier_enabled(true);
+    }
+}
+
+pub fn lxr_mutator_release<VM: VMBinding>(mutator: &mut Mutator<VM>, _tls: VMWorkerThread) {
+    // Lines are reclaimed in every GC, so the allocator must find its holes again.
+    reset_immix_allocator(mutator);
+
+    // Deactivate SATB
+    let current_pause = mutator.plan.concurrent().unwrap().current_pause().unwrap();
+    if current_pause == Pause::FinalMark {
+        debug!("Deactivate SATB barrier active for {:?}", mutator as *mut _);
+        mutator
+            .barrier
+            .downcast_mut::<BarrierType<VM>>()
+            .unwrap()
+            .set_weak_ref_barrier_enabled(false);
+    }
+}
+
+pub fn create_lxr_mutator<VM: VMBinding>(
+    mutator_tls: VMMutatorThread,
+    mmtk: &'static MMTK<VM>,
+) -> Mutator<VM> {
+    let lxr = mmtk.get_plan().downcast_ref::<LXR<VM>>().unwrap();
+    let config = MutatorConfig {
+        allocator_mapping: &ALLOCATOR_MAPPING,
+        space_mapping: Box::new({
+            let mut vec = create_space_mapping(RESERVED_ALLOCATORS, true, lxr);
+            vec.push((AllocatorSelector::Immix(0), &lxr.immix_space));
+            vec
+        }),
+        prepare_func: &lxr_mutator_prepare,
+        release_func: &lxr_mutator_release,
+    };
+
+    let builder = MutatorBuilder::new(mutator_tls, mmtk, config);
+    let mut mutator = builder
+        .barrier(Box::new(SATBBarrier::new(LXRBarrierSemantics::<VM>::new(
+            mmtk,
+            mutator_tls,
+        ))))
+        .build();
+
+    // Set barrier active, based on whether concurrent marking is in progress
+    mutator
+        .barrier
+        .downcast_mut::<BarrierType<VM>>()
+        .unwrap()
+        .set_weak_ref_barrier_enabled(lxr.is_concurrent_marking_active());
+
+    mutator
+}
@@ -0,0 +1,23 @@
+use crate::plan::concurrent::marksweep::global::ConcurrentMarkSweep;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::scheduler::gc_work::{PlanProcessEdges, UnsupportedProcessEdges};
+use crate::scheduler::ProcessEdgesWork;
+use crate::vm::VMBinding;
+
+pub(super) struct ConcurrentMarkSweepSTWGCWorkContext<VM: VMBinding>(std::marker::PhantomData<VM>);
+impl<VM: VMBinding> crate::scheduler::GCWorkContext for ConcurrentMarkSweepSTWGCWorkContext<VM> {
+    type VM = VM;
+    type PlanType = ConcurrentMarkSweep<VM>;
+    type DefaultProcessEdges = PlanProcessEdges<VM, ConcurrentMarkSweep<VM>, DEFAULT_TRACE>;
+    type PinningProcessEdges = PlanProcessEdges<VM, ConcurrentMarkSweep<VM>, DEFAULT_TRACE>;
+}
+pub(super) struct ConcurrentMarkSweepGCWorkContext<E: ProcessEdgesWork>(
+    std::marker::PhantomData<E>,
+);
+
+impl<E: ProcessEdgesWork> crate::scheduler::GCWorkContext for ConcurrentMarkSweepGCWorkContext<E> {
+    type VM = E::VM;
+    type PlanType = ConcurrentMarkSweep<E::VM>;
+    type DefaultProcessEdges = E;
+    type PinningProcessEdges = UnsupportedProcessEdges<Self::VM>;
+}
@@ -0,0 +1,256 @@
+use crate::plan::concurrent::concurrent_marking_work::ProcessRootSlots;
+use crate::plan::concurrent::global::{
+    schedule_final_mark_weak_ref_processing, set_ref_closure_buckets_enabled,
+    ConcurrentMarkingPauses, ConcurrentPlan,
+};
+use crate::plan::concurrent::marksweep::gc_work::ConcurrentMarkSweepGCWorkContext;
+use crate::plan::concurrent::marksweep::gc_work::ConcurrentMarkSweepSTWGCWorkContext;
+use crate::plan::concurrent::marksweep::mutator::ALLOCATOR_MAPPING;
+use crate::plan::concurrent::Pause;
+use crate::plan::global::BasePlan;
+use crate::plan::global::CommonPlan;
+use crate::plan::global::CreateGeneralPlanArgs;
+use crate::plan::global::CreateSpecificPlanArgs;
+use crate::plan::AllocationSemantics;
+use crate::plan::Plan;
+use crate::plan::PlanConstraints;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::policy::marksweepspace::native_ms::MarkSweepSpace;
+use crate::policy::marksweepspace::native_ms::MAX_OBJECT_SIZE;
+use crate::policy::space::Space;
+use crate::scheduler::gc_work::Release;
+use crate::scheduler::gc_work::StopMutators;
+use crate::scheduler::gc_work::UnsupportedProcessEdges;
+use crate::scheduler::trace::{self, TraceArg};
+use crate::scheduler::*;
+use crate::util::alloc::allocators::AllocatorSelector;
+use crate::util::heap::gc_trigger::SpaceStats;
+use crate::util::heap::VMRequest;
+use crate::util::metadata::log_bit::UnlogBitsOperation;
+use crate::util::metadata::side_metadata::SideMetadataContext;
+use crate::util::opaque_pointer::VMWorkerThread;
+use crate::vm::ObjectModel;
+use crate::vm::VMBinding;
+
+use enum_map::EnumMap;
+
+use mmtk_macros::{HasSpaces, PlanTraceObject};
+
+/// A concurrent mark sweep plan. The plan uses the native mark sweep space with lazy sweeping,
+/// and supports both concurrent collection and STW full heap collection.  It never moves objects.
+/// The concurrent GC consists of two STW pauses (initial mark and final mark) with concurrent marking in between.
+#[derive(HasSpaces, PlanTraceObject)]
+pub struct ConcurrentMarkSweep<VM: VMBinding> {
+    #[parent]
+    pub common: CommonPlan<VM>,
+    #[space]
+    pub ms: MarkSweepSpace<VM>,
+    pauses: ConcurrentMarkingPauses,
+}
+
+/// The plan constraints for the concurrent mark sweep plan.
+pub const CONCURRENT_MS_CONSTRAINTS: PlanConstraints = PlanConstraints {
+    moves_objects: false,
+    max_non_los_default_alloc_bytes: MAX_OBJECT_SIZE,
+    may_trace_duplicate_edges: true,
+    needs_prepare_mutator: true,
+    barrier: crate::BarrierSelector::SATBBarrier,
+    needs_log_bit: true,
+    ..PlanConstraints::default()
+};
+
+impl<VM: VMBinding> Plan for ConcurrentMarkSweep<VM> {
+    fn collection_required(&self, space_full: bool, _space: Option<SpaceStats<Self::VM>>) -> bool {
+        self.pauses.collection_required(self, space_full)
+    }
+
+    fn constraints(&self) -> &'static PlanConstraints {
+        &CONCURRENT_MS_CONSTRAINTS
+    }
+
+    fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<VM>) {
+        let pause = self.pauses.start_pause();
+
+        probe!(mmtk, concurrent_pause_determined, pause as usize);
+        trace::record_gc_decision(
+            scheduler.tracer(),
+            "concurrent_pause_determined",
+            "pause",
+            || TraceArg::Str(format!("{:?}", pause)),
+        );
+
+        match pause {
+            Pause::Full => {
+                // Ref closure buckets is disabled by initial mark, and needs to be re-enabled for full GC.
+                set_ref_closure_buckets_enabled(scheduler, true);
+                scheduler.schedule_common_work::<ConcurrentMarkSweepSTWGCWorkContext<VM>>(self);
+            }
+            Pause::InitialMark => self.schedule_concurrent_marking_initial_pause(scheduler),
+            Pause::FinalMark => self.schedule_concurrent_marking_final_pause(scheduler),
+        }
+    }
+
+    fn get_allocator_mapping(&self) -> &'static EnumMap<AllocationSemantics, AllocatorSelector> {
+        &ALLOCATOR_MAPPING
+    }
+
+    fn prepare(&mut self, tls: VMWorkerThread) {
+        let pause = self.current_pause().unwrap();
+        match pause {
+            Pause::Full => {
+                self.common.prepare(tls, true);
+                // Ignore unlog bits in full GCs because unlog bits should be all 0.
+                self.ms.prepare(true, UnlogBitsOperation::NoOp);
+            }
+            Pause::InitialMark => {
+                // Bulk set log bits so SATB barrier will be triggered on the existing objects.
+                self.ms.prepare(true, UnlogBitsOperation::BulkSet);
+                self.common.prepare(tls, true);
+                self.common
+                    .schedule_unlog_bits_op(UnlogBitsOperation::BulkSet);
+            }
+            Pause::FinalMark => (),
+        }
+    }
+
+    fn release(&mut self, tls: VMWorkerThread) {
+        let pause = self.current_pause().unwrap();
+        match pause {
+            Pause::InitialMark => (),
+            Pause::Full => {
+                // Full pauses didn't set unlog bits in the first place,
+                // so there is no need to clear them.
+                self.ms.release(UnlogBitsOperation::NoOp);
+                self.common.release(tls, true);
+            }
+            Pause::FinalMark => {
+                // Bulk clear log bits so SATB barrier will not be triggered.
+                self.ms.release(UnlogBitsOperation::BulkClear);
+                self.common.release(tls, true);
+                self.common
+                    .schedule_unlog_bits_op(UnlogBitsOperation::BulkClear);
+            }
+        }
+    }
+
+    fn end_of_gc(&mut self, tls: VMWorkerThread) {
+        self.ms.end_of_gc();
+        self.common.end_of_gc(tls);
+        self.pauses.end_of_gc(self);
+    }
+
+    fn current_gc_may_move_object(&self) -> bool {
+        false
+    }
+
+    fn get_used_pages(&self) -> usize {
+        self.common.get_used_pages() + self.ms.reserved_pages()
+    }
+
+    fn base(&self) -> &BasePlan<VM> {
+        &self.common.base
+    }
+
+    fn base_mut(&mut self) -> &mut BasePlan<Self::VM> {
+        &mut self.common.base
+    }
+
+    fn common(&self) -> &CommonPlan<VM> {
+        &self.common
+    }
+
+    fn notify_mutators_paused(&self, _scheduler: &GCWorkScheduler<VM>) {
+        self.pauses.notify_mutators_paused(self);
+    }
+
+    fn concurrent(&self) -> Option<&dyn ConcurrentPlan<VM = VM>> {
+        Some(self)
+    }
+}
+
+impl<VM: VMBinding> ConcurrentMarkSweep<VM> {
+    pub fn new(args: CreateGeneralPlanArgs<VM>) -> Self {
+        let spec = crate::util::metadata::extract_side_metadata(&[
+            *VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC,
+        ]);
+
+        let mut plan_args = CreateSpecificPlanArgs {
+            global_args: args,
+            constraints: &CONCURRENT_MS_CONSTRAINTS,
+            global_side_metadata_specs: SideMetadataContext::new_global_specs(&spec),
+        };
+
+        // These buckets are not used in a non-moving plan. We can simply disable them.
+        let scheduler = &plan_args.global_args.scheduler;
+        scheduler.work_buckets[WorkBucketStage::VMRefForwarding].set_enabled(false);
+        scheduler.work_buckets[WorkBucketStage::CalculateForwarding].set_enabled(false);
+        scheduler.work_buckets[WorkBucketStage::SecondRoots].set_enabled(false);
+        scheduler.work_buckets[WorkBucketStage::RefForwarding].set_enabled(false);
+        scheduler.work_buckets[WorkBucketStage::FinalizableForwarding].set_enabled(false);
+        scheduler.work_buckets[WorkBucketStage::Compact].set_enabled(false);
+
+        let res = ConcurrentMarkSweep {
+            ms: MarkSweepSpace::new(plan_args.get_normal_space_args(
+                "ms",
+                true,
+                false,
+                VMRequest::discontiguous(),
+            )),
+            common: CommonPlan::new(plan_args),
+            pauses: ConcurrentMarkingPauses::new(),
+        };
+
+        res.verify_side_metadata_sanity();
+
+        res
+    }
+
+    pub fn ms_space(&self) -> &MarkSweepSpace<VM> {
+        &self.ms
+    }
+
+    fn schedule_concurrent_marking_initial_pause(&'static self, scheduler: &GCWorkScheduler<VM>) {
+        use crate::scheduler::gc_work::Prepare;
+
+        set_ref_closure_buckets_enabled(scheduler, false);
+
+        scheduler.work_buckets[WorkBucketStage::Unconstrained].add(StopMutators::<
+            ConcurrentMarkSweepGCWorkContext<ProcessRootSlots<VM, Self, DEFAULT_TRACE>>,
+        >::new());
+        scheduler.work_buckets[WorkBucketStage::Prepare].add(Prepare::<
+            ConcurrentMarkSweepGCWorkContext<UnsupportedProcessEdges<VM>>,
+        >::new(self));
+    }
+
+    fn schedule_concurrent_marking_final_pause(&'static self, scheduler: &GCWorkScheduler<VM>) {
+        set_ref_closure_buckets_enabled(scheduler, true);
+
+        // Skip root scanning in the final mark
+        scheduler.work_buckets[WorkBucketStage::Unconstrained].add(StopMutators::<
+            ConcurrentMarkSweepGCWorkContext<ProcessRootSlots<VM, Self, DEFAULT_TRACE>>,
+        >::new_no_scan_roots());
+
+        scheduler.work_buckets[WorkBucketStage::Release].add(Release::<
+            ConcurrentMarkSweepGCWorkContext<UnsupportedProcessEdges<VM>>,
+        >::new(self));
+
+        // Deal with weak ref and finalizers
+        schedule_final_mark_weak_ref_processing::<
+            crate::scheduler::gc_work::PlanProcessEdges<VM, ConcurrentMarkSweep<VM>, DEFAULT_TRACE>,
+        >(self, scheduler);
+    }
+
+    pub fn concurrent_marking_in_progress(&self) -> bool {
+        self.pauses.concurrent_marking_in_progress()
+    }
+}
+
+impl<VM: VMBinding> ConcurrentPlan for ConcurrentMarkSweep<VM> {
+    fn current_pause(&self) -> Option<Pause> {
+        self.pauses.current_pause()
+    }
+
+    fn concurrent_work_in_progress(&self) -> bool {
+        self.concurrent_marking_in_progress()
+    }
+}
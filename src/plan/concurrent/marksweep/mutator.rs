@@ -0,0 +1,131 @@
+use crate::plan::barriers::SATBBarrier;
+use crate::plan::concurrent::barrier::SATBBarrierSemantics;
+use crate::plan::concurrent::marksweep::ConcurrentMarkSweep;
+use crate::plan::concurrent::Pause;
+use crate::plan::mutator_context::common_prepare_func;
+use crate::plan::mutator_context::common_release_func;
+use crate::plan::mutator_context::create_allocator_mapping;
+use crate::plan::mutator_context::create_space_mapping;
+use crate::plan::mutator_context::Mutator;
+use crate::plan::mutator_context::MutatorBuilder;
+use crate::plan::mutator_context::MutatorConfig;
+use crate::plan::mutator_context::ReservedAllocators;
+use crate::plan::AllocationSemantics;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::util::alloc::allocators::AllocatorSelector;
+use crate::util::alloc::FreeListAllocator;
+use crate::util::opaque_pointer::{VMMutatorThread, VMWorkerThread};
+use crate::vm::VMBinding;
+use crate::MMTK;
+use enum_map::EnumMap;
+
+type BarrierSemanticsType<VM> = SATBBarrierSemantics<VM, ConcurrentMarkSweep<VM>, DEFAULT_TRACE>;
+
+type BarrierType<VM> = SATBBarrier<BarrierSemanticsType<VM>>;
+
+fn get_freelist_allocator_mut<VM: VMBinding>(
+    mutator: &mut Mutator<VM>,
+) -> &mut FreeListAllocator<VM> {
+    unsafe {
+        mutator
+            .allocators
+            .get_allocator_mut(mutator.config.allocator_mapping[AllocationSemantics::Default])
+    }
+    .downcast_mut::<FreeListAllocator<VM>>()
+    .unwrap()
+}
+
+pub fn concurrent_ms_mutator_release<VM: VMBinding>(
+    mutator: &mut Mutator<VM>,
+    tls: VMWorkerThread,
+) {
+    // Release is not scheduled for initial mark pause
+    let current_pause = mutator.plan.concurrent().unwrap().current_pause().unwrap();
+    debug_assert_ne!(current_pause, Pause::InitialMark);
+
+    get_freelist_allocator_mut::<VM>(mutator).release();
+    common_release_func(mutator, tls);
+
+    // Deactivate SATB
+    if current_pause == Pause::Full || current_pause == Pause::FinalMark {
+        debug!("Deactivate SATB barrier active for {:?}", mutator as *mut _);
+        mutator
+            .barrier
+            .downcast_mut::<BarrierType<VM>>()
+            .unwrap()
+            .set_weak_ref_barrier_enabled(false);
+    }
+}
+
+pub fn concurrent_ms_mutator_prepare<VM: VMBinding>(
+    mutator: &mut Mutator<VM>,
+    tls: VMWorkerThread,
+) {
+    // Prepare is not scheduled for final mark pause
+    let current_pause = mutator.plan.concurrent().unwrap().current_pause().unwrap();
+    debug_assert_ne!(current_pause, Pause::FinalMark);
+
+    get_freelist_allocator_mut::<VM>(mutator).prepare();
+    common_prepare_func(mutator, tls);
+
+    // Activate SATB
+    if current_pause == Pause::InitialMark {
+        debug!("Activate SATB barrier active for {:?}", mutator as *mut _);
+        mutator
+            .barrier
+            .downcast_mut::<BarrierType<VM>>()
+            .unwrap()
+            .set_weak_ref_barrier_enabled(true);
+    }
+}
+
+pub(in crate::plan) const RESERVED_ALLOCATORS: ReservedAllocators = ReservedAllocators {
+    n_free_list: 1,
+    ..ReservedAllocators::DEFAULT
+};
+
+lazy_static! {
+    pub static ref ALLOCATOR_MAPPING: EnumMap<AllocationSemantics, AllocatorSelector> = {
+        let mut map = create_allocator_mapping(RESERVED_ALLOCATORS, true);
+        map[AllocationSemantics::Default] = AllocatorSelector::FreeList(0);
+        map
+    };
+}
+
+pub fn create_concurrent_ms_mutator<VM: VMBinding>(
+    mutator_tls: VMMutatorThread,
+    mmtk: &'static MMTK<VM>,
+) -> Mutator<VM> {
+    let ms = mmtk
+        .get_plan()
+        .downcast_ref::<ConcurrentMarkSweep<VM>>()
+        .unwrap();
+    let config = MutatorConfig {
+        allocator_mapping: &ALLOCATOR_MAPPING,
+        space_mapping: Box::new({
+            let mut vec = create_space_mapping(RESERVED_ALLOCATORS, true, ms);
+            vec.push((AllocatorSelector::FreeList(0), ms.ms_space()));
+            vec
+        }),
+
+        prepare_func: &concurrent_ms_mutator_prepare,
+        release_func: &concurrent_ms_mutator_release,
+    };
+
+    let builder = MutatorBuilder::new(mutator_tls, mmtk, config);
+    let mut mutator = builder
+        .barrier(Box::new(SATBBarrier::new(BarrierSemanticsType::<VM>::new(
+            mmtk,
+            mutator_tls,
+        ))))
+        .build();
+
+    // Set barrier active, based on whether concurrent marking is in progress
+    mutator
+        .barrier
+        .downcast_mut::<BarrierType<VM>>()
+        .unwrap()
+        .set_weak_ref_barrier_enabled(ms.concurrent_marking_in_progress());
+
+    mutator
+}
@@ -2,7 +2,10 @@ pub mod barrier;
 pub(super) mod concurrent_marking_work;
 pub(super) mod global;
 
+pub mod genimmix;
 pub mod immix;
+pub mod lxr;
+pub mod marksweep;
 
 use bytemuck::NoUninit;
 
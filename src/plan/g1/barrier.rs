@@ -0,0 +1,129 @@
+use super::gc_work::{G1ProcessModBuf, G1ProcessSlots};
+use super::G1;
+use crate::plan::barriers::BarrierSemantics;
+use crate::plan::VectorQueue;
+use crate::policy::space::Space;
+use crate::scheduler::WorkBucketStage;
+use crate::util::ObjectReference;
+use crate::vm::slot::MemorySlice;
+use crate::vm::VMBinding;
+use crate::MMTK;
+use std::sync::atomic::Ordering;
+
+/// The barrier semantics of [`G1`].
+///
+/// The first time an object outside the eden is modified after a GC, the barrier logs the object
+/// in the modbuf.  A GC scans the logged objects as roots, so that the objects they point to in
+/// the collection set are evacuated and the pointers are updated.  The logged objects are then
+/// refined into the remembered sets of the regions they point into.  Objects are logged if their
+/// unlog bits are set.  Objects in the eden are never logged, as they are all evacuated and
+/// scanned in every GC.  When a memory slice that is not in an object is copied to, there is no
+/// object to log, and the barrier logs the slots of the slice instead.
+pub struct G1BarrierSemantics<VM: VMBinding> {
+    mmtk: &'static MMTK<VM>,
+    plan: &'static G1<VM>,
+    modbuf: VectorQueue<ObjectReference>,
+    slots: VectorQueue<VM::VMSlot>,
+}
+
+impl<VM: VMBinding> G1BarrierSemantics<VM> {
+    pub fn new(mmtk: &'static MMTK<VM>, plan: &'static G1<VM>) -> Self {
+        Self {
+            mmtk,
+            plan,
+            modbuf: VectorQueue::new(),
+            slots: VectorQueue::new(),
+        }
+    }
+
+    /// Attempt to atomically log an object.
+    /// Returns true if the object is not logged previously.
+    fn log_object(&self, object: ObjectReference) -> bool {
+        loop {
+            let old_value =
+                Self::UNLOG_BIT_SPEC.load_atomic::<VM, u8>(object, None, Ordering::SeqCst);
+            if old_value == 0 {
+                return false;
+            }
+            if Self::UNLOG_BIT_SPEC
+                .compare_exchange_metadata::<VM, u8>(
+                    object,
+                    1,
+                    0,
+                    None,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                )
+                .is_ok()
+            {
+                return true;
+            }
+        }
+    }
+
+    fn enqueue(&mut self, object: ObjectReference) {
+        debug_assert!(!self.plan.eden.in_space(object));
+        self.modbuf.push(object);
+        self.modbuf.is_full().then(|| self.flush_modbuf());
+    }
+
+    fn flush_modbuf(&mut self) {
+        let buf = self.modbuf.take();
+        if !buf.is_empty() {
+            self.mmtk.scheduler.work_buckets[WorkBucketStage::Closure]
+                .add(G1ProcessModBuf::<VM>::new(buf));
+        }
+    }
+
+    /// Log the slots of a memory slice that is not in an object.
+    fn log_slots(&mut self, slice: VM::VMMemorySlice) {
+        for slot in slice.iter_slots() {
+            self.slots.push(slot);
+            self.slots.is_full().then(|| self.flush_slots());
+        }
+    }
+
+    fn flush_slots(&mut self) {
+        let slots = self.slots.take();
+        if !slots.is_empty() {
+            self.mmtk.scheduler.work_buckets[WorkBucketStage::Closure]
+                .add(G1ProcessSlots::<VM>::new(slots));
+        }
+    }
+}
+
+impl<VM: VMBinding> BarrierSemantics for G1BarrierSemantics<VM> {
+    type VM = VM;
+
+    fn flush(&mut self) {
+        self.flush_modbuf();
+        self.flush_slots();
+    }
+
+    fn object_reference_write_slow(
+        &mut self,
+        src: ObjectReference,
+        _slot: VM::VMSlot,
+        _target: Option<ObjectReference>,
+    ) {
+        self.enqueue(src);
+    }
+
+    fn memory_region_copy_slow(&mut self, _src: VM::VMMemorySlice, dst: VM::VMMemorySlice) {
+        // We log the whole object that contains the destination slice, so the remembered sets
+        // record the object instead of each slot.
+        let Some(object) = dst.object() else {
+            self.log_slots(dst);
+            return;
+        };
+        if self.log_object(object) {
+            self.enqueue(object);
+        }
+    }
+
+    fn object_probable_write_slow(&mut self, obj: ObjectReference) {
+        if self.log_object(obj) {
+            self.enqueue(obj);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use super::global::{G1Pause, G1};
+use super::remset::RemSetEntry;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::policy::region::HeapRegion;
+use crate::policy::space::Space;
+use crate::scheduler::gc_work::{PlanProcessEdges, ScanObjects, UnsupportedProcessEdges};
+use crate::scheduler::{GCWork, GCWorker, ProcessEdgesWork, WorkBucketStage};
+use crate::util::linear_scan::Region;
+use crate::util::ObjectReference;
+use crate::vm::slot::Slot;
+use crate::vm::*;
+use crate::MMTK;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+pub struct G1GCWorkContext<VM: VMBinding>(std::marker::PhantomData<VM>);
+impl<VM: VMBinding> crate::scheduler::GCWorkContext for G1GCWorkContext<VM> {
+    type VM = VM;
+    type PlanType = G1<VM>;
+    type DefaultProcessEdges = PlanProcessEdges<VM, G1<VM>, DEFAULT_TRACE>;
+    type PinningProcessEdges = UnsupportedProcessEdges<VM>;
+}
+
+type G1ProcessEdges<VM> = PlanProcessEdges<VM, G1<VM>, DEFAULT_TRACE>;
+
+/// Process the objects logged by the write barrier.  Their unlog bits are set again, and they
+/// are recorded to be refined into the remembered sets.  In a young or mixed pause, they are also
+/// scanned as roots, as they may point to objects in the collection set.  Logged objects in the
+/// collection set itself are skipped, as they are scanned after they are evacuated if they are
+/// live.
+pub(super) struct G1ProcessModBuf<VM: VMBinding> {
+    modbuf: Vec<ObjectReference>,
+    _p: std::marker::PhantomData<VM>,
+}
+
+impl<VM: VMBinding> G1ProcessModBuf<VM> {
+    pub fn new(modbuf: Vec<ObjectReference>) -> Self {
+        debug_assert!(!modbuf.is_empty());
+        Self {
+            modbuf,
+            _p: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for G1ProcessModBuf<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let plan = mmtk.get_plan().downcast_ref::<G1<VM>>().unwrap();
+        let mut objects = std::mem::take(&mut self.modbuf);
+        for object in objects.iter() {
+            VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC
+                .mark_as_unlogged::<VM>(*object, Ordering::SeqCst);
+        }
+        objects.retain(|o| !plan.in_old_collection_set(*o));
+        plan.remsets.add_pending(&objects);
+        if plan.current_pause() != G1Pause::Full {
+            plan.add_scanned_objects(objects.len());
+            GCWork::do_work(
+                &mut ScanObjects::<G1ProcessEdges<VM>>::new(
+                    objects,
+                    false,
+                    WorkBucketStage::Closure,
+                ),
+                worker,
+                mmtk,
+            )
+        }
+    }
+}
+
+/// Process the slots outside objects that the write barrier saw being copied to.  They are
+/// recorded to be refined into the remembered sets.  In a young or mixed pause, they are also
+/// processed as roots, as they may point to objects in the collection set.  In a full pause, the
+/// objects they point to are found by tracing.
+pub(super) struct G1ProcessSlots<VM: VMBinding> {
+    slots: Vec<VM::VMSlot>,
+}
+
+impl<VM: VMBinding> G1ProcessSlots<VM> {
+    pub fn new(slots: Vec<VM::VMSlot>) -> Self {
+        debug_assert!(!slots.is_empty());
+        Self { slots }
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for G1ProcessSlots<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let plan = mmtk.get_plan().downcast_ref::<G1<VM>>().unwrap();
+        let slots = std::mem::take(&mut self.slots);
+        plan.remsets.add_pending_slots(&slots);
+        if plan.current_pause() != G1Pause::Full {
+            plan.add_scanned_objects(slots.len());
+            GCWork::do_work(
+                &mut G1ProcessEdges::<VM>::new(slots, false, mmtk, WorkBucketStage::Closure),
+                worker,
+                mmtk,
+            )
+        }
+    }
+}
+
+/// Refine remembered set entries into the remembered sets: Each object is added to the
+/// remembered sets of the old regions that its fields point into, except its own region, and
+/// each slot is added to the remembered set of the old region that it points into.
+pub(super) struct RefineEntries<VM: VMBinding> {
+    entries: Vec<RemSetEntry<VM::VMSlot>>,
+}
+
+impl<VM: VMBinding> RefineEntries<VM> {
+    pub const CAPACITY: usize = 4096;
+
+    pub fn new(entries: Vec<RemSetEntry<VM::VMSlot>>) -> Self {
+        Self { entries }
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for RefineEntries<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let plan = mmtk.get_plan().downcast_ref::<G1<VM>>().unwrap();
+        let old_region_of = |slot: VM::VMSlot| {
+            slot.load()
+                .filter(|target| plan.old.in_space(*target))
+                .map(|target| HeapRegion::from_unaligned_address(target.to_raw_address()))
+        };
+        let mut entries: HashMap<HeapRegion, Vec<RemSetEntry<VM::VMSlot>>> = HashMap::new();
+        for entry in self.entries.iter().copied() {
+            let object = match entry {
+                RemSetEntry::Object(object) => object,
+                RemSetEntry::Slot(slot) => {
+                    if let Some(region) = old_region_of(slot) {
+                        entries.entry(region).or_default().push(entry);
+                    }
+                    continue;
+                }
+            };
+            let source = HeapRegion::from_unaligned_address(object.to_raw_address());
+            let mut last = None;
+            crate::plan::tracing::SlotIterator::<VM>::iterate_fields(object, worker.tls.0, |s| {
+                let Some(region) = old_region_of(s) else {
+                    return;
+                };
+                // Consecutive fields often point into the same region.
+                if region != source && last != Some(region) {
+                    entries.entry(region).or_default().push(entry);
+                    last = Some(region);
+                }
+            });
+        }
+        plan.remsets.insert(entries);
+        plan.add_scanned_objects(self.entries.len());
+    }
+}
+
+/// Scan the objects and process the slots in the remembered sets of the old regions in the
+/// collection set as roots.  They are recorded to be refined again, as they will be updated to
+/// point to where the objects in the collection set are evacuated.
+pub(super) struct ScanRemSets<VM: VMBinding> {
+    regions: Vec<HeapRegion>,
+    _p: std::marker::PhantomData<VM>,
+}
+
+impl<VM: VMBinding> ScanRemSets<VM> {
+    pub fn new(regions: Vec<HeapRegion>) -> Self {
+        Self {
+            regions,
+            _p: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for ScanRemSets<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let plan = mmtk.get_plan().downcast_ref::<G1<VM>>().unwrap();
+        let mut sources = vec![];
+        let mut slots = vec![];
+        for entry in plan.remsets.sources(&self.regions) {
+            match entry {
+                RemSetEntry::Object(object) if !plan.in_old_collection_set(object) => {
+                    sources.push(object)
+                }
+                RemSetEntry::Object(_) => {}
+                RemSetEntry::Slot(slot) => slots.push(slot),
+            }
+        }
+        plan.remsets.add_pending(&sources);
+        plan.remsets.add_pending_slots(&slots);
+        plan.add_scanned_objects(sources.len() + slots.len());
+        let mut packets: Vec<Box<dyn GCWork<VM>>> = sources
+            .chunks(RefineEntries::<VM>::CAPACITY)
+            .map(|chunk| {
+                Box::new(ScanObjects::<G1ProcessEdges<VM>>::new(
+                    chunk.to_vec(),
+                    false,
+                    WorkBucketStage::Closure,
+                )) as Box<dyn GCWork<VM>>
+            })
+            .collect();
+        packets.extend(slots.chunks(RefineEntries::<VM>::CAPACITY).map(|chunk| {
+            Box::new(G1ProcessEdges::<VM>::new(
+                chunk.to_vec(),
+                false,
+                mmtk,
+                WorkBucketStage::Closure,
+            )) as Box<dyn GCWork<VM>>
+        }));
+        worker.scheduler().work_buckets[WorkBucketStage::Closure].bulk_add(packets);
+    }
+}
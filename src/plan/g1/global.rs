@@ -0,0 +1,535 @@
+use super::gc_work::{G1GCWorkContext, RefineEntries, ScanRemSets};
+use super::mutator::ALLOCATOR_MAPPING;
+use super::predictor::PausePredictor;
+use super::remset::{RemSetEntry, RememberedSets};
+use crate::plan::global::BasePlan;
+use crate::plan::global::CommonPlan;
+use crate::plan::global::CreateGeneralPlanArgs;
+use crate::plan::global::CreateSpecificPlanArgs;
+use crate::plan::AllocationSemantics;
+use crate::plan::ObjectQueue;
+use crate::plan::Plan;
+use crate::plan::PlanConstraints;
+use crate::plan::PlanTraceObject;
+use crate::policy::gc_work::TraceKind;
+use crate::policy::region::{HeapRegion, RegionSpace};
+use crate::policy::space::Space;
+use crate::scheduler::*;
+use crate::util::alloc::allocators::AllocatorSelector;
+use crate::util::constants::BYTES_IN_PAGE;
+use crate::util::copy::*;
+use crate::util::heap::gc_trigger::SpaceStats;
+use crate::util::heap::VMRequest;
+use crate::util::linear_scan::Region;
+use crate::util::metadata::side_metadata::SideMetadataContext;
+use crate::util::ObjectReference;
+use crate::util::VMWorkerThread;
+use crate::vm::VMBinding;
+use atomic::Atomic;
+use bytemuck::NoUninit;
+use enum_map::EnumMap;
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use mmtk_macros::HasSpaces;
+
+/// The kind of a pause of [`G1`].
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, NoUninit)]
+pub enum G1Pause {
+    /// Evacuate the eden.
+    Young,
+    /// Evacuate the eden and some old regions that are chosen by their liveness.
+    Mixed,
+    /// Evacuate the eden and mark the old regions.  Old regions are not evacuated.
+    Full,
+}
+
+/// A region-based collector that aims to keep evacuating pauses within a pause-time goal, in the
+/// style of G1 (Detlefs et al., ISMM 2004).
+///
+/// The heap is divided into [`HeapRegion`]s.  Mutators allocate into the eden regions, and every
+/// pause evacuates the eden into the old regions.  The old regions that point into each old
+/// region are recorded in the remembered set of the region (see [`RememberedSets`]), which is
+/// maintained with an object-remembering barrier, so that a region can be evacuated without
+/// tracing the rest of the heap.
+///
+/// A [`G1Pause::Full`] pause marks the old regions, counts their live bytes and frees the regions
+/// without live objects.  The regions with few live bytes become the candidates for evacuation,
+/// and the following [`G1Pause::Mixed`] pauses evacuate them, least live first, as many as the
+/// pause-time goal ([`crate::util::options::Options::g1_pause_time_goal_ms`]) allows.  The time
+/// of a pause is predicted from the bytes it copies and the objects it scans, using the pauses
+/// measured so far, and the size of the eden is adjusted after each pause so that a young pause
+/// meets the goal.  A full pause is done when the old regions fill up and no candidates are
+/// left.
+///
+/// Marking is done in full pauses and is not concurrent, so full pauses are not bounded by the
+/// goal.  Evacuation failure is not handled.  Instead, the plan reserves as many pages as the
+/// eden has for copying, and only chooses old regions whose live bytes fit in the free pages.
+/// When a memory slice that is not in an object is copied to, the barrier records the slots of
+/// the slice instead of an object.
+#[derive(HasSpaces)]
+pub struct G1<VM: VMBinding> {
+    #[parent]
+    pub common: CommonPlan<VM>,
+    #[space]
+    pub eden: RegionSpace<VM>,
+    #[space]
+    pub old: RegionSpace<VM>,
+    pub(super) remsets: RememberedSets<VM::VMSlot>,
+    pause: Atomic<G1Pause>,
+    /// Whether the next pause must be a full pause.
+    next_full: AtomicBool,
+    /// The eden is collected when it reserves this many pages.
+    eden_target_pages: AtomicUsize,
+    predictor: Mutex<PausePredictor>,
+    /// The old regions to be evacuated by mixed pauses, least live first.
+    candidates: Mutex<VecDeque<HeapRegion>>,
+    /// The number of candidates that a mixed pause evacuates even if it exceeds the goal, so
+    /// that the candidates are evacuated in a bounded number of pauses.
+    min_mixed_regions: AtomicUsize,
+    /// The start time of the current pause.
+    pause_start: Mutex<Option<Instant>>,
+    /// The work units of the current pause, excluding the bytes copied.
+    work_units: AtomicUsize,
+    /// The bytes allocated in the eden before the current pause.
+    eden_bytes: AtomicUsize,
+    /// The bytes copied by the current pause.
+    evacuated_bytes: AtomicUsize,
+    /// The used pages after the last full pause.
+    used_pages_after_full: AtomicUsize,
+}
+
+/// The plan constraints for the G1 plan.
+pub const G1_CONSTRAINTS: PlanConstraints = PlanConstraints {
+    // Objects larger than a sixteenth of a region are allocated in the LOS, so that regions
+    // are not wasted by their tails.
+    max_non_los_default_alloc_bytes: HeapRegion::BYTES >> 4,
+    ..crate::plan::generational::GEN_CONSTRAINTS
+};
+
+impl<VM: VMBinding> Plan for G1<VM> {
+    fn constraints(&self) -> &'static PlanConstraints {
+        &G1_CONSTRAINTS
+    }
+
+    fn create_copy_config(&'static self) -> CopyConfig<Self::VM> {
+        use enum_map::enum_map;
+        CopyConfig {
+            copy_mapping: enum_map! {
+                CopySemantics::PromoteToMature => CopySelector::Region(0),
+                CopySemantics::Mature => CopySelector::Region(0),
+                _ => CopySelector::Unused,
+            },
+            space_mapping: vec![(CopySelector::Region(0), &self.old)],
+            constraints: &G1_CONSTRAINTS,
+        }
+    }
+
+    fn collection_required(&self, space_full: bool, space: Option<SpaceStats<Self::VM>>) -> bool {
+        let is_triggered_by_eden =
+            space.is_some_and(|s| s.0.common().descriptor == self.eden.common().descriptor);
+        // If a space other than the eden is full, the old regions need to be marked.
+        if space_full && !is_triggered_by_eden {
+            self.next_full.store(true, Ordering::SeqCst);
+        }
+        self.common.base.collection_required(self, space_full)
+            || self.eden.reserved_pages() >= self.eden_target_pages()
+    }
+
+    fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<VM>) {
+        *self.pause_start.lock().unwrap() = Some(Instant::now());
+        let pause = self.select_pause();
+        self.pause.store(pause, Ordering::SeqCst);
+        info!("G1 {:?} pause", pause);
+        scheduler.schedule_common_work::<G1GCWorkContext<VM>>(self);
+    }
+
+    fn get_allocator_mapping(&self) -> &'static EnumMap<AllocationSemantics, AllocatorSelector> {
+        &ALLOCATOR_MAPPING
+    }
+
+    fn prepare(&mut self, tls: VMWorkerThread) {
+        let full = self.current_pause() == G1Pause::Full;
+        self.common.prepare(tls, full);
+        self.eden.prepare(false);
+        self.old.prepare(full);
+        self.work_units.store(0, Ordering::SeqCst);
+        self.evacuated_bytes.store(0, Ordering::SeqCst);
+
+        let mut eden_bytes = 0;
+        self.eden
+            .enumerate_regions(&mut |region, cursor| eden_bytes += cursor - region.start());
+        self.eden_bytes.store(eden_bytes, Ordering::SeqCst);
+        self.eden.select_collection_set(&mut |_| true);
+
+        let old_regions = if self.current_pause() == G1Pause::Mixed {
+            self.select_old_regions(eden_bytes)
+        } else {
+            vec![]
+        };
+        if old_regions.is_empty() && self.current_pause() == G1Pause::Mixed {
+            self.pause.store(G1Pause::Young, Ordering::SeqCst);
+        }
+        let selected: HashSet<HeapRegion> = old_regions.iter().copied().collect();
+        self.old
+            .select_collection_set(&mut |region| selected.contains(&region));
+
+        // The remembered sets must be up to date before the remembered sets of the collection
+        // set are scanned in the closure.
+        self.schedule_refinement();
+        if !old_regions.is_empty() {
+            self.common.base.scheduler.work_buckets[WorkBucketStage::Closure]
+                .add(ScanRemSets::<VM>::new(old_regions));
+        }
+    }
+
+    fn release(&mut self, tls: VMWorkerThread) {
+        let full = self.current_pause() == G1Pause::Full;
+        if full {
+            // The sources that died are removed before their memory is reclaimed.
+            self.remsets.retain(|object| object.is_live());
+        }
+        self.common.release(tls, full);
+
+        let eden = self.eden.release();
+        let old = self.old.release();
+        self.predictor
+            .lock()
+            .unwrap()
+            .record_survival(self.eden_bytes.load(Ordering::SeqCst), eden.evacuated_bytes);
+        self.evacuated_bytes
+            .store(eden.evacuated_bytes + old.evacuated_bytes, Ordering::SeqCst);
+
+        self.remsets.remove_regions(&old.regions);
+        if !full && !old.regions.is_empty() {
+            // The objects in the evacuated regions are dead.  Their memory may be reused.
+            let freed: HashSet<HeapRegion> = old.regions.iter().copied().collect();
+            self.remsets.retain(|object| {
+                !self.old.in_space(object)
+                    || !freed.contains(&HeapRegion::from_unaligned_address(object.to_raw_address()))
+            });
+        }
+        if full {
+            self.select_candidates();
+        }
+    }
+
+    fn end_of_gc(&mut self, tls: VMWorkerThread) {
+        self.remsets.add_pending(&self.old.take_copied_objects());
+        self.common.end_of_gc(tls);
+
+        let pause = self.current_pause();
+        let pause_ms = self
+            .pause_start
+            .lock()
+            .unwrap()
+            .take()
+            .map_or(0.0, |start| start.elapsed().as_secs_f64() * 1000.0);
+        if pause != G1Pause::Full {
+            let units = self.work_units.load(Ordering::SeqCst)
+                + self.evacuated_bytes.load(Ordering::SeqCst);
+            let mut predictor = self.predictor.lock().unwrap();
+            debug!(
+                "G1 {:?} pause: {} units predicted to take {:.3} ms",
+                pause,
+                units,
+                predictor.predict_ms(units)
+            );
+            predictor.record_pause(units, pause_ms);
+        }
+        info!("G1 {:?} pause took {:.3} ms", pause, pause_ms);
+
+        let total = self.get_total_pages();
+        let used = self.get_used_pages();
+        if pause == G1Pause::Full {
+            self.used_pages_after_full.store(used, Ordering::SeqCst);
+        }
+        // Mark the old regions when they fill up half of the memory that was free after the last
+        // full pause, and there are no candidates left to evacuate.
+        let used_after_full = self.used_pages_after_full.load(Ordering::SeqCst);
+        let threshold = usize::max(
+            total * 45 / 100,
+            used_after_full + total.saturating_sub(used_after_full) / 2,
+        );
+        let no_candidates = self.candidates.lock().unwrap().is_empty();
+        if (no_candidates && used > threshold) || self.get_available_pages() < HeapRegion::PAGES {
+            self.next_full.store(true, Ordering::SeqCst);
+        }
+        self.update_eden_target();
+    }
+
+    fn get_collection_reserved_pages(&self) -> usize {
+        // The survivors of the eden are copied into the old regions.
+        self.eden.reserved_pages()
+    }
+
+    fn get_used_pages(&self) -> usize {
+        self.eden.reserved_pages() + self.old.reserved_pages() + self.common.get_used_pages()
+    }
+
+    fn last_collection_was_exhaustive(&self) -> bool {
+        self.current_pause() == G1Pause::Full
+    }
+
+    fn current_gc_may_move_object(&self) -> bool {
+        true
+    }
+
+    fn notify_emergency_collection(&self) {
+        self.next_full.store(true, Ordering::SeqCst);
+    }
+
+    fn base(&self) -> &BasePlan<VM> {
+        &self.common.base
+    }
+
+    fn base_mut(&mut self) -> &mut BasePlan<Self::VM> {
+        &mut self.common.base
+    }
+
+    fn common(&self) -> &CommonPlan<VM> {
+        &self.common
+    }
+}
+
+impl<VM: VMBinding> PlanTraceObject<VM> for G1<VM> {
+    fn trace_object<Q: ObjectQueue, const KIND: TraceKind>(
+        &self,
+        queue: &mut Q,
+        object: ObjectReference,
+        worker: &mut GCWorker<VM>,
+    ) -> ObjectReference {
+        if self.eden.in_space(object) {
+            return self.eden.trace_object(
+                queue,
+                object,
+                Some(CopySemantics::PromoteToMature),
+                worker,
+            );
+        }
+        if self.old.in_space(object) {
+            // Objects outside the collection set are only traced if the old regions are marked.
+            return self
+                .old
+                .trace_object(queue, object, Some(CopySemantics::Mature), worker);
+        }
+        let los = self.common.get_los();
+        if los.in_space(object) {
+            // Large objects are not logged until they are traced for the first time, so they
+            // need to be refined into the remembered sets.
+            let new_object = los.is_in_nursery(object);
+            let result = los.trace_object(queue, object);
+            if new_object {
+                self.remsets.add_pending(&[object]);
+            }
+            return result;
+        }
+        if self.current_pause() == G1Pause::Full {
+            <CommonPlan<VM> as PlanTraceObject<VM>>::trace_object::<Q, KIND>(
+                &self.common,
+                queue,
+                object,
+                worker,
+            )
+        } else {
+            // The other spaces are only traced in full pauses.
+            object
+        }
+    }
+
+    fn post_scan_object(&self, _object: ObjectReference) {}
+
+    fn may_move_objects<const KIND: TraceKind>() -> bool {
+        true
+    }
+}
+
+impl<VM: VMBinding> G1<VM> {
+    pub fn new(args: CreateGeneralPlanArgs<VM>) -> Self {
+        let mut plan_args = CreateSpecificPlanArgs {
+            global_args: args,
+            constraints: &G1_CONSTRAINTS,
+            global_side_metadata_specs: SideMetadataContext::new_global_specs(
+                &crate::plan::generational::new_generational_global_metadata_specs::<VM>(),
+            ),
+        };
+
+        let res = G1 {
+            eden: RegionSpace::new(plan_args.get_nursery_space_args(
+                "eden",
+                true,
+                false,
+                VMRequest::discontiguous(),
+            )),
+            old: RegionSpace::new(plan_args.get_mature_space_args(
+                "old",
+                true,
+                false,
+                VMRequest::discontiguous(),
+            )),
+            common: CommonPlan::new(plan_args),
+            remsets: RememberedSets::new(),
+            pause: Atomic::new(G1Pause::Young),
+            next_full: AtomicBool::new(false),
+            eden_target_pages: AtomicUsize::new(0),
+            predictor: Mutex::new(PausePredictor::new()),
+            candidates: Mutex::new(VecDeque::new()),
+            min_mixed_regions: AtomicUsize::new(0),
+            pause_start: Mutex::new(None),
+            work_units: AtomicUsize::new(0),
+            eden_bytes: AtomicUsize::new(0),
+            evacuated_bytes: AtomicUsize::new(0),
+            used_pages_after_full: AtomicUsize::new(0),
+        };
+
+        res.verify_side_metadata_sanity();
+
+        res
+    }
+
+    pub fn current_pause(&self) -> G1Pause {
+        self.pause.load(Ordering::SeqCst)
+    }
+
+    /// Is the object in an old region in the collection set?
+    pub(super) fn in_old_collection_set(&self, object: ObjectReference) -> bool {
+        self.old.in_space(object) && self.old.in_collection_set(object)
+    }
+
+    /// Count the objects scanned for the remembered sets in the work of the current pause.
+    pub(super) fn add_scanned_objects(&self, objects: usize) {
+        self.work_units
+            .fetch_add(objects * PausePredictor::SCAN_UNITS, Ordering::Relaxed);
+    }
+
+    fn select_pause(&self) -> G1Pause {
+        let global_state = &self.common.base.global_state;
+        let next_full = self.next_full.swap(false, Ordering::SeqCst);
+        if next_full
+            || (global_state
+                .user_triggered_collection
+                .load(Ordering::SeqCst)
+                && *self.common.base.options.full_heap_system_gc)
+            || global_state.cur_collection_attempts.load(Ordering::SeqCst) > 1
+        {
+            G1Pause::Full
+        } else if !self.candidates.lock().unwrap().is_empty() {
+            G1Pause::Mixed
+        } else {
+            G1Pause::Young
+        }
+    }
+
+    /// The eden is collected when it reserves this many pages.
+    fn eden_target_pages(&self) -> usize {
+        match self.eden_target_pages.load(Ordering::Relaxed) {
+            0 => self.update_eden_target(),
+            pages => pages,
+        }
+    }
+
+    /// Size the eden so that a young pause is predicted to meet the pause-time goal.  The eden is
+    /// at most 60% of the heap, and half of the available pages, as it needs as many pages to be
+    /// reserved for copying.
+    fn update_eden_target(&self) -> usize {
+        let goal_ms = *self.common.base.options.g1_pause_time_goal_ms as f64;
+        let eden_bytes = {
+            let predictor = self.predictor.lock().unwrap();
+            predictor.units_within(goal_ms) as f64 / predictor.survival_rate().max(0.01)
+        };
+        let max_pages = usize::max(
+            HeapRegion::PAGES,
+            usize::min(
+                self.get_total_pages() * 60 / 100,
+                self.get_available_pages() / 2,
+            ),
+        );
+        let pages =
+            ((eden_bytes / BYTES_IN_PAGE as f64) as usize).clamp(HeapRegion::PAGES, max_pages);
+        self.eden_target_pages.store(pages, Ordering::Relaxed);
+        pages
+    }
+
+    /// Choose the candidates to evacuate in a mixed pause, within the pause-time goal that is
+    /// left after evacuating the eden, and within the free pages.
+    fn select_old_regions(&self, eden_bytes: usize) -> Vec<HeapRegion> {
+        let goal_ms = *self.common.base.options.g1_pause_time_goal_ms as f64;
+        let mut budget = {
+            let predictor = self.predictor.lock().unwrap();
+            predictor
+                .units_within(goal_ms)
+                .saturating_sub((eden_bytes as f64 * predictor.survival_rate()) as usize)
+        };
+        let mut free_bytes = self
+            .get_total_pages()
+            .saturating_sub(self.get_reserved_pages())
+            * BYTES_IN_PAGE;
+        let min_regions = self.min_mixed_regions.load(Ordering::Relaxed);
+        let mut candidates = self.candidates.lock().unwrap();
+        let mut selected = vec![];
+        while let Some(&region) = candidates.front() {
+            let live = RegionSpace::<VM>::live_bytes(region);
+            let cost = live + self.remsets.len(region) * PausePredictor::SCAN_UNITS;
+            if live > free_bytes || (selected.len() >= min_regions && cost > budget) {
+                break;
+            }
+            candidates.pop_front();
+            selected.push(region);
+            free_bytes -= live;
+            budget = budget.saturating_sub(cost);
+        }
+        debug!(
+            "G1 mixed pause: {} old regions selected, {} candidates left",
+            selected.len(),
+            candidates.len()
+        );
+        selected
+    }
+
+    /// Choose the candidates for mixed pauses after the old regions are marked.  The regions that
+    /// are mostly live are not worth evacuating, and no region is evacuated if little memory can
+    /// be reclaimed.
+    fn select_candidates(&self) {
+        let mut regions = vec![];
+        self.old.enumerate_regions(&mut |region, cursor| {
+            let live = RegionSpace::<VM>::live_bytes(region);
+            if cursor > region.start() && live < HeapRegion::BYTES * 85 / 100 {
+                regions.push((live, region));
+            }
+        });
+        regions.sort_unstable_by_key(|(live, _)| *live);
+        let reclaimable: usize = regions
+            .iter()
+            .map(|(live, _)| HeapRegion::BYTES - live)
+            .sum();
+        if reclaimable < self.get_total_pages() * BYTES_IN_PAGE / 20 {
+            regions.clear();
+        }
+        self.min_mixed_regions
+            .store(regions.len().div_ceil(8), Ordering::Relaxed);
+        debug!("G1 full pause: {} candidates", regions.len());
+        *self.candidates.lock().unwrap() = regions.into_iter().map(|(_, region)| region).collect();
+    }
+
+    /// Refine the pending entries into the remembered sets.  The objects in the collection set
+    /// are skipped.  They are refined after they are evacuated if they are live.
+    fn schedule_refinement(&self) {
+        let pending: Vec<RemSetEntry<VM::VMSlot>> = self
+            .remsets
+            .take_pending()
+            .into_iter()
+            .filter(|entry| match entry {
+                RemSetEntry::Object(object) => !self.in_old_collection_set(*object),
+                RemSetEntry::Slot(_) => true,
+            })
+            .collect();
+        let packets = pending
+            .chunks(RefineEntries::<VM>::CAPACITY)
+            .map(|chunk| Box::new(RefineEntries::<VM>::new(chunk.to_vec())) as Box<dyn GCWork<VM>>)
+            .collect();
+        self.common.base.scheduler.work_buckets[WorkBucketStage::Prepare].bulk_add(packets);
+    }
+}
@@ -0,0 +1,11 @@
+//! Plan: region-based pause-target collector (G1)
+
+pub(super) mod barrier;
+pub(super) mod gc_work;
+pub(super) mod global;
+pub(super) mod mutator;
+mod predictor;
+mod remset;
+
+pub use self::global::G1;
+pub use self::global::G1_CONSTRAINTS;
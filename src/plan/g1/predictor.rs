@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+
+/// Predict the time of an evacuating pause from the work it does.
+///
+/// The work of a pause is measured in units: Copying a byte is one unit, and scanning an object
+/// for a remembered set or a refinement is [`PausePredictor::SCAN_UNITS`] units.  The predictor
+/// fits `pause = a + b * units` to the recent pauses by least squares.  Until it can fit a line,
+/// it assumes that pauses take time in proportion to their work.
+pub(super) struct PausePredictor {
+    /// The work units and the time in milliseconds of the recent pauses.
+    samples: VecDeque<(f64, f64)>,
+    /// The fraction of the bytes allocated in the eden that survive a GC.
+    survival_rate: f64,
+}
+
+impl PausePredictor {
+    /// The work units of scanning an object.
+    pub const SCAN_UNITS: usize = 64;
+    /// The number of recent pauses that predictions are based on.
+    const MAX_SAMPLES: usize = 16;
+    /// The assumed cost of a unit before any pause is measured: 1 ms per MiB.
+    const DEFAULT_MS_PER_UNIT: f64 = 1.0 / (1 << 20) as f64;
+    /// The assumed survival rate before any GC.
+    const DEFAULT_SURVIVAL_RATE: f64 = 0.5;
+    /// The weight of the latest GC in the survival rate.
+    const SURVIVAL_WEIGHT: f64 = 0.3;
+
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(Self::MAX_SAMPLES),
+            survival_rate: Self::DEFAULT_SURVIVAL_RATE,
+        }
+    }
+
+    /// Record the work units and the time of a pause.
+    pub fn record_pause(&mut self, units: usize, pause_ms: f64) {
+        if self.samples.len() == Self::MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((units as f64, pause_ms));
+    }
+
+    /// Record how many of the bytes allocated in the eden survived a GC.
+    pub fn record_survival(&mut self, allocated_bytes: usize, survived_bytes: usize) {
+        if allocated_bytes == 0 {
+            return;
+        }
+        let rate = (survived_bytes as f64 / allocated_bytes as f64).min(1.0);
+        self.survival_rate += Self::SURVIVAL_WEIGHT * (rate - self.survival_rate);
+    }
+
+    pub fn survival_rate(&self) -> f64 {
+        self.survival_rate
+    }
+
+    /// The fixed cost in milliseconds and the cost of a unit of a pause.
+    fn model(&self) -> (f64, f64) {
+        let n = self.samples.len() as f64;
+        if self.samples.len() >= 2 {
+            let mean_units = self.samples.iter().map(|s| s.0).sum::<f64>() / n;
+            let mean_ms = self.samples.iter().map(|s| s.1).sum::<f64>() / n;
+            let var: f64 = self
+                .samples
+                .iter()
+                .map(|s| (s.0 - mean_units) * (s.0 - mean_units))
+                .sum();
+            let cov: f64 = self
+                .samples
+                .iter()
+                .map(|s| (s.0 - mean_units) * (s.1 - mean_ms))
+                .sum();
+            if var > 0.0 && cov > 0.0 {
+                let slope = cov / var;
+                return ((mean_ms - slope * mean_units).max(0.0), slope);
+            }
+        }
+        let total_units: f64 = self.samples.iter().map(|s| s.0).sum();
+        let total_ms: f64 = self.samples.iter().map(|s| s.1).sum();
+        if total_units > 0.0 && total_ms > 0.0 {
+            (0.0, total_ms / total_units)
+        } else {
+            (0.0, Self::DEFAULT_MS_PER_UNIT)
+        }
+    }
+
+    /// Predict the time in milliseconds of a pause that does the given units of work.
+    pub fn predict_ms(&self, units: usize) -> f64 {
+        let (fixed, per_unit) = self.model();
+        fixed + per_unit * units as f64
+    }
+
+    /// The units of work that a pause can do within the given time in milliseconds.
+    pub fn units_within(&self, ms: f64) -> usize {
+        let (fixed, per_unit) = self.model();
+        ((ms - fixed).max(0.0) / per_unit) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn default_model() {
+        let p = PausePredictor::new();
+        assert_eq!(p.predict_ms(1 << 20), 1.0);
+        assert_eq!(p.units_within(10.0), 10 << 20);
+    }
+
+    #[test]
+    fn proportional_model() {
+        let mut p = PausePredictor::new();
+        p.record_pause(1000, 2.0);
+        assert_close(p.predict_ms(500), 1.0);
+        // Pauses of the same work cannot be fitted to a line.
+        p.record_pause(1000, 4.0);
+        assert_close(p.predict_ms(500), 1.5);
+    }
+
+    #[test]
+    fn linear_model() {
+        let mut p = PausePredictor::new();
+        for units in [100, 200, 400, 800] {
+            p.record_pause(units, 5.0 + 0.01 * units as f64);
+        }
+        assert_close(p.predict_ms(1000), 15.0);
+        assert!(p.units_within(25.0).abs_diff(2000) <= 1);
+        // No units of work fit in a pause shorter than the fixed cost.
+        assert_eq!(p.units_within(4.0), 0);
+    }
+
+    #[test]
+    fn recent_samples() {
+        let mut p = PausePredictor::new();
+        for _ in 0..PausePredictor::MAX_SAMPLES {
+            p.record_pause(1000, 100.0);
+        }
+        for _ in 0..PausePredictor::MAX_SAMPLES {
+            p.record_pause(1000, 1.0);
+        }
+        assert_close(p.predict_ms(1000), 1.0);
+    }
+
+    #[test]
+    fn survival_rate() {
+        let mut p = PausePredictor::new();
+        p.record_survival(0, 0);
+        assert_eq!(p.survival_rate(), PausePredictor::DEFAULT_SURVIVAL_RATE);
+        for _ in 0..100 {
+            p.record_survival(1000, 100);
+        }
+        assert_close(p.survival_rate(), 0.1);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::policy::region::HeapRegion;
+use crate::util::ObjectReference;
+use crate::vm::slot::Slot;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, RwLock};
+
+/// The remembered sets of the old regions of [`super::G1`].
+///
+/// The remembered set of a region records the objects outside the region that may point into
+/// the region, and the slots outside objects that the write barrier saw being copied to (see
+/// [`RemSetEntry`]).  When the region is evacuated, those objects and slots are scanned as roots
+/// instead of tracing the rest of the heap.
+///
+/// The sets are maintained lazily.  Objects that may have gained pointers into old regions (the
+/// objects logged by the write barrier, and the objects copied or updated by a GC) are recorded
+/// as *pending*.  Pending objects are refined at the start of the next GC: Their fields are
+/// scanned, and each object is added to the sets of the regions that its fields point into.
+/// Entries are not removed when fields change.  A stale entry stays until its object dies in a
+/// full-heap GC, or the region of its object is freed.  A slot stays in a set until it no longer
+/// points into the region when it is refined, or its target dies in a full-heap GC.
+pub(super) struct RememberedSets<S: Slot> {
+    sets: RwLock<HashMap<HeapRegion, Mutex<HashSet<RemSetEntry<S>>>>>,
+    pending: Mutex<Vec<RemSetEntry<S>>>,
+}
+
+/// An entry of the remembered sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum RemSetEntry<S: Slot> {
+    /// An object whose fields may point into the region.
+    Object(ObjectReference),
+    /// A slot outside objects that may point into the region.  The VM must not free the memory
+    /// of the slot while it holds a reference.
+    Slot(S),
+}
+
+impl<S: Slot> RememberedSets<S> {
+    pub fn new() -> Self {
+        Self {
+            sets: RwLock::new(HashMap::new()),
+            pending: Mutex::new(vec![]),
+        }
+    }
+
+    /// Record objects to be refined in the next GC.
+    pub fn add_pending(&self, objects: &[ObjectReference]) {
+        self.pending
+            .lock()
+            .unwrap()
+            .extend(objects.iter().map(|object| RemSetEntry::Object(*object)));
+    }
+
+    /// Record slots to be refined in the next GC.
+    pub fn add_pending_slots(&self, slots: &[S]) {
+        self.pending
+            .lock()
+            .unwrap()
+            .extend(slots.iter().map(|slot| RemSetEntry::Slot(*slot)));
+    }
+
+    /// Take the entries to be refined.
+    pub fn take_pending(&self) -> Vec<RemSetEntry<S>> {
+        std::mem::take(&mut self.pending.lock().unwrap())
+    }
+
+    /// Add the entries to the sets of the regions they are mapped to.
+    pub fn insert(&self, entries: HashMap<HeapRegion, Vec<RemSetEntry<S>>>) {
+        let mut missing = vec![];
+        {
+            let sets = self.sets.read().unwrap();
+            for (region, entries) in entries {
+                match sets.get(&region) {
+                    Some(set) => set.lock().unwrap().extend(entries),
+                    None => missing.push((region, entries)),
+                }
+            }
+        }
+        if !missing.is_empty() {
+            let mut sets = self.sets.write().unwrap();
+            for (region, entries) in missing {
+                sets.entry(region)
+                    .or_default()
+                    .get_mut()
+                    .unwrap()
+                    .extend(entries);
+            }
+        }
+    }
+
+    /// The number of entries in the set of a region.
+    pub fn len(&self, region: HeapRegion) -> usize {
+        self.sets
+            .read()
+            .unwrap()
+            .get(&region)
+            .map_or(0, |set| set.lock().unwrap().len())
+    }
+
+    /// The entries in the sets of the given regions, without duplicates.
+    pub fn sources(&self, regions: &[HeapRegion]) -> HashSet<RemSetEntry<S>> {
+        let sets = self.sets.read().unwrap();
+        let mut sources = HashSet::new();
+        for region in regions {
+            if let Some(set) = sets.get(region) {
+                sources.extend(set.lock().unwrap().iter().copied());
+            }
+        }
+        sources
+    }
+
+    /// Remove the sets of the given regions.
+    pub fn remove_regions(&self, regions: &[HeapRegion]) {
+        let mut sets = self.sets.write().unwrap();
+        for region in regions {
+            sets.remove(region);
+        }
+    }
+
+    /// Remove the objects for which `keep` returns false from all the sets and the pending
+    /// entries.  A slot is removed if `keep` returns false for its target, or it holds no
+    /// reference.
+    pub fn retain(&self, keep: impl Fn(ObjectReference) -> bool) {
+        let keep_entry = |entry: &RemSetEntry<S>| match *entry {
+            RemSetEntry::Object(object) => keep(object),
+            RemSetEntry::Slot(slot) => slot.load().is_some_and(&keep),
+        };
+        let mut sets = self.sets.write().unwrap();
+        for set in sets.values_mut() {
+            set.get_mut().unwrap().retain(keep_entry);
+        }
+        sets.retain(|_, set| !set.get_mut().unwrap().is_empty());
+        self.pending.lock().unwrap().retain(keep_entry);
+    }
+}
@@ -108,9 +108,9 @@ impl<E: ProcessEdgesWork> ProcessModBuf<E> {
 
 impl<E: ProcessEdgesWork> GCWork<E::VM> for ProcessModBuf<E> {
     fn do_work(&mut self, worker: &mut GCWorker<E::VM>, mmtk: &'static MMTK<E::VM>) {
-        // Process and scan modbuf only if the current GC is a nursery GC
+        // Process and scan modbuf only if the current GC needs it (usually a nursery GC)
         let gen = mmtk.get_plan().generational().unwrap();
-        if gen.is_current_gc_nursery() {
+        if gen.should_process_modbuf() {
             // Flip the per-object unlogged bits to "unlogged" state.
             for obj in &self.modbuf {
                 debug_assert!(
@@ -126,6 +126,7 @@ impl<E: ProcessEdgesWork> GCWork<E::VM> for ProcessModBuf<E> {
                     Ordering::SeqCst,
                 );
             }
+            gen.on_modbuf_processed(&self.modbuf);
             // Scan objects in the modbuf and forward pointers
             let modbuf = std::mem::take(&mut self.modbuf);
             GCWork::do_work(
@@ -157,13 +158,10 @@ impl<E: ProcessEdgesWork> ProcessRegionModBuf<E> {
 
 impl<E: ProcessEdgesWork> GCWork<E::VM> for ProcessRegionModBuf<E> {
     fn do_work(&mut self, worker: &mut GCWorker<E::VM>, mmtk: &'static MMTK<E::VM>) {
-        // Scan modbuf only if the current GC is a nursery GC
-        if mmtk
-            .get_plan()
-            .generational()
-            .unwrap()
-            .is_current_gc_nursery()
-        {
+        // Scan modbuf only if the current GC needs it (usually a nursery GC)
+        let gen = mmtk.get_plan().generational().unwrap();
+        if gen.should_process_modbuf() {
+            gen.on_region_modbuf_processed(&self.modbuf);
             // Collect all the entries in all the slices
             let mut slots = vec![];
             for slice in &self.modbuf {
@@ -66,6 +66,11 @@ impl<VM: VMBinding> CommonGenPlan<VM> {
             self.full_heap_gc_count.lock().unwrap().inc();
         }
         self.common.prepare(tls, full_heap);
+        self.prepare_nursery();
+    }
+
+    /// Prepare the nursery space only. The spaces in the common plan are left untouched.
+    pub fn prepare_nursery(&mut self) {
         self.nursery.prepare(true);
         self.nursery
             .set_copy_for_sft_trace(Some(CopySemantics::PromoteToMature));
@@ -75,6 +80,11 @@ impl<VM: VMBinding> CommonGenPlan<VM> {
     pub fn release(&mut self, tls: VMWorkerThread) {
         let full_heap = !self.is_current_gc_nursery();
         self.common.release(tls, full_heap);
+        self.release_nursery();
+    }
+
+    /// Release the nursery space only. The spaces in the common plan are left untouched.
+    pub fn release_nursery(&mut self) {
         self.nursery.release();
     }
 
@@ -307,6 +317,24 @@ pub trait GenerationalPlan: Plan {
 
     /// Force the next collection to be full heap.
     fn force_full_heap_collection(&self);
+
+    /// Should the remembered set (modbuf) recorded by the barrier be processed in the current GC?
+    /// By default, it is only processed in nursery GCs, because a full heap GC traces all the
+    /// mature objects anyway. A plan that collects the nursery in a pause that does not trace
+    /// the mature space from the roots should override this.
+    fn should_process_modbuf(&self) -> bool {
+        self.is_current_gc_nursery()
+    }
+
+    /// Called with the objects in the modbuf when they are processed.  A plan in which objects
+    /// may survive a nursery GC without leaving the young generation can use this to find the
+    /// mature objects that still point to young objects after the GC.  By default, this does
+    /// nothing.
+    fn on_modbuf_processed(&self, _modbuf: &[ObjectReference]) {}
+
+    /// Called with the memory slices in the array-copy modbuf when they are processed.  See
+    /// [`GenerationalPlan::on_modbuf_processed`].  By default, this does nothing.
+    fn on_region_modbuf_processed(&self, _modbuf: &[<Self::VM as VMBinding>::VMMemorySlice]) {}
 }
 
 /// This trait is the extension trait for [`GenerationalPlan`] (see Rust's extension trait pattern).
@@ -15,6 +15,7 @@ use crate::policy::immix::ImmixSpace;
 use crate::policy::immix::ImmixSpaceArgs;
 use crate::policy::immix::{TRACE_KIND_DEFRAG, TRACE_KIND_FAST};
 use crate::policy::space::Space;
+use crate::scheduler::trace::{self, TraceArg};
 use crate::scheduler::GCWorkScheduler;
 use crate::scheduler::GCWorker;
 use crate::util::alloc::allocators::AllocatorSelector;
@@ -109,6 +110,9 @@ impl<VM: VMBinding> Plan for GenImmix<VM> {
     fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<Self::VM>) {
         let is_full_heap = self.requires_full_heap_collection();
         probe!(mmtk, gen_full_heap, is_full_heap);
+        trace::record_gc_decision(scheduler.tracer(), "gen_full_heap", "full_heap", || {
+            TraceArg::Bool(is_full_heap)
+        });
 
         if !is_full_heap {
             info!("Nursery GC");
@@ -272,6 +276,7 @@ impl<VM: VMBinding> GenImmix<VM> {
                 // In GenImmix, young objects are not allocated in ImmixSpace directly.
                 mixed_age: false,
                 never_move_objects: false,
+                reference_counted: false,
             },
         );
 
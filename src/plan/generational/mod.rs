@@ -25,6 +25,8 @@ pub mod barrier;
 pub mod copying;
 /// Generational immix (GenImmix)
 pub mod immix;
+/// Multi-generation copying (MultiGenCopy)
+pub mod multigen;
 
 // Common generational code
 
@@ -71,14 +73,14 @@ const RESERVED_ALLOCATORS: ReservedAllocators = ReservedAllocators {
 };
 
 lazy_static! {
-    static ref ALLOCATOR_MAPPING: EnumMap<AllocationSemantics, AllocatorSelector> = {
+    pub(in crate::plan) static ref ALLOCATOR_MAPPING: EnumMap<AllocationSemantics, AllocatorSelector> = {
         let mut map = create_allocator_mapping(RESERVED_ALLOCATORS, true);
         map[AllocationSemantics::Default] = AllocatorSelector::BumpPointer(0);
         map
     };
 }
 
-fn create_gen_space_mapping<VM: VMBinding>(
+pub(in crate::plan) fn create_gen_space_mapping<VM: VMBinding>(
     plan: &'static dyn Plan<VM = VM>,
     nursery: &'static CopySpace<VM>,
 ) -> Vec<(AllocatorSelector, &'static dyn Space<VM>)> {
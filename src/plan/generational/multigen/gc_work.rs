@@ -0,0 +1,72 @@
+use super::global::MultiGenCopy;
+use crate::plan::generational::gc_work::GenNurseryProcessEdges;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::policy::space::Space;
+use crate::scheduler::gc_work::{PlanProcessEdges, UnsupportedProcessEdges};
+use crate::scheduler::{GCWork, GCWorker};
+use crate::util::ObjectReference;
+use crate::vm::slot::{MemorySlice, Slot};
+use crate::vm::*;
+use crate::MMTK;
+use std::sync::atomic::Ordering;
+
+pub(super) type MultiGenCopyNurseryProcessEdges<VM> =
+    GenNurseryProcessEdges<VM, MultiGenCopy<VM>, DEFAULT_TRACE>;
+
+pub struct MultiGenCopyNurseryGCWorkContext<VM: VMBinding>(std::marker::PhantomData<VM>);
+impl<VM: VMBinding> crate::scheduler::GCWorkContext for MultiGenCopyNurseryGCWorkContext<VM> {
+    type VM = VM;
+    type PlanType = MultiGenCopy<VM>;
+    type DefaultProcessEdges = MultiGenCopyNurseryProcessEdges<VM>;
+    type PinningProcessEdges = UnsupportedProcessEdges<VM>;
+}
+
+pub struct MultiGenCopyGCWorkContext<VM: VMBinding>(std::marker::PhantomData<VM>);
+impl<VM: VMBinding> crate::scheduler::GCWorkContext for MultiGenCopyGCWorkContext<VM> {
+    type VM = VM;
+    type PlanType = MultiGenCopy<VM>;
+    type DefaultProcessEdges = PlanProcessEdges<Self::VM, MultiGenCopy<VM>, DEFAULT_TRACE>;
+    type PinningProcessEdges = UnsupportedProcessEdges<VM>;
+}
+
+/// Refine the candidates of the remembered set at the end of a nursery GC.  Only the objects and
+/// the memory slices that point into the survivor spaces are kept in the remembered set.  The
+/// kept objects are left logged, so the barrier does not log them again, and the others are
+/// unlogged.
+pub(super) struct RefineRememberedSet<VM: VMBinding> {
+    objects: Vec<ObjectReference>,
+    slices: Vec<VM::VMMemorySlice>,
+}
+
+impl<VM: VMBinding> RefineRememberedSet<VM> {
+    pub fn new(objects: Vec<ObjectReference>, slices: Vec<VM::VMMemorySlice>) -> Self {
+        Self { objects, slices }
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for RefineRememberedSet<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let plan = mmtk.get_plan().downcast_ref::<MultiGenCopy<VM>>().unwrap();
+        let survivors = plan.survivor_tospace();
+        let points_to_survivor =
+            |slot: VM::VMSlot| slot.load().is_some_and(|target| survivors.in_space(target));
+
+        let mut objects = std::mem::take(&mut self.objects);
+        objects.retain(|object| {
+            let mut young = false;
+            crate::plan::tracing::SlotIterator::<VM>::iterate_fields(*object, worker.tls.0, |s| {
+                young = young || points_to_survivor(s);
+            });
+            VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.store_atomic::<VM, u8>(
+                *object,
+                if young { 0 } else { 1 },
+                None,
+                Ordering::SeqCst,
+            );
+            young
+        });
+        let mut slices = std::mem::take(&mut self.slices);
+        slices.retain(|slice| slice.iter_slots().any(points_to_survivor));
+        plan.remset.insert(objects, slices);
+    }
+}
@@ -0,0 +1,504 @@
+use super::gc_work::MultiGenCopyGCWorkContext;
+use super::gc_work::MultiGenCopyNurseryGCWorkContext;
+use super::gc_work::MultiGenCopyNurseryProcessEdges;
+use super::gc_work::RefineRememberedSet;
+use super::mutator::ALLOCATOR_MAPPING;
+use super::remset::RememberedSet;
+use crate::plan::generational::gc_work::{ProcessModBuf, ProcessRegionModBuf};
+use crate::plan::generational::global::CommonGenPlan;
+use crate::plan::generational::global::GenerationalPlan;
+use crate::plan::generational::global::GenerationalPlanExt;
+use crate::plan::global::BasePlan;
+use crate::plan::global::CommonPlan;
+use crate::plan::global::CreateGeneralPlanArgs;
+use crate::plan::global::CreateSpecificPlanArgs;
+use crate::plan::AllocationSemantics;
+use crate::plan::Plan;
+use crate::plan::PlanConstraints;
+use crate::policy::copyspace::CopySpace;
+use crate::policy::gc_work::{TraceKind, TRACE_KIND_TRANSITIVE_PIN};
+use crate::policy::space::Space;
+use crate::scheduler::*;
+use crate::util::alloc::allocators::AllocatorSelector;
+use crate::util::copy::*;
+use crate::util::heap::gc_trigger::SpaceStats;
+use crate::util::heap::VMRequest;
+use crate::util::metadata::side_metadata::spec_defs::OBJECT_AGE;
+use crate::util::Address;
+use crate::util::ObjectReference;
+use crate::util::VMWorkerThread;
+use crate::vm::slot::MemorySlice;
+use crate::vm::*;
+use crate::ObjectQueue;
+use enum_map::EnumMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use mmtk_macros::{HasSpaces, PlanTraceObject};
+
+/// A generational copying plan with more than two generations.  Like [`GenCopy`], it allocates
+/// into a copying nursery, and uses a semi-space mature space.  In between, objects that survive
+/// a nursery GC are copied into a pair of survivor semi-spaces, and copied between the two in
+/// each nursery GC until they have survived [`multigen_generations`] - 1 GCs.  Only then are
+/// they promoted into the mature space.  The age of an object (the number of GCs it has
+/// survived) is stored in the side metadata [`OBJECT_AGE`].  A full heap GC promotes all the
+/// live young objects.
+///
+/// [`GenCopy`]: crate::plan::generational::copying::GenCopy
+/// [`multigen_generations`]: crate::util::options::Options::multigen_generations
+#[derive(HasSpaces, PlanTraceObject)]
+pub struct MultiGenCopy<VM: VMBinding> {
+    #[parent]
+    pub gen: CommonGenPlan<VM>,
+    pub hi: AtomicBool,
+    #[space]
+    #[copy_semantics(CopySemantics::Mature)]
+    pub copyspace0: CopySpace<VM>,
+    #[space]
+    #[copy_semantics(CopySemantics::Mature)]
+    pub copyspace1: CopySpace<VM>,
+    pub survivor_hi: AtomicBool,
+    #[space]
+    #[copy_semantics(CopySemantics::PromoteToMature)]
+    pub survivor0: CopySpace<VM>,
+    #[space]
+    #[copy_semantics(CopySemantics::PromoteToMature)]
+    pub survivor1: CopySpace<VM>,
+    /// The number of generations, including the nursery and the mature generation.
+    generations: usize,
+    pub(super) remset: RememberedSet<VM>,
+}
+
+/// The plan constraints for the multi-generation copying plan.
+pub const MULTIGENCOPY_CONSTRAINTS: PlanConstraints = crate::plan::generational::GEN_CONSTRAINTS;
+
+impl<VM: VMBinding> Plan for MultiGenCopy<VM> {
+    fn constraints(&self) -> &'static PlanConstraints {
+        &MULTIGENCOPY_CONSTRAINTS
+    }
+
+    fn create_copy_config(&'static self) -> CopyConfig<Self::VM> {
+        use enum_map::enum_map;
+        CopyConfig {
+            copy_mapping: enum_map! {
+                CopySemantics::Mature => CopySelector::CopySpace(0),
+                CopySemantics::PromoteToMature => CopySelector::CopySpace(0),
+                CopySemantics::Nursery => CopySelector::CopySpace(1),
+                _ => CopySelector::Unused,
+            },
+            space_mapping: vec![
+                // The tospace arguments don't matter, we will rebind before a GC anyway.
+                (CopySelector::CopySpace(0), self.tospace()),
+                (CopySelector::CopySpace(1), self.survivor_tospace()),
+            ],
+            constraints: &MULTIGENCOPY_CONSTRAINTS,
+        }
+    }
+
+    fn collection_required(&self, space_full: bool, space: Option<SpaceStats<Self::VM>>) -> bool
+    where
+        Self: Sized,
+    {
+        self.gen.collection_required(self, space_full, space)
+    }
+
+    fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<VM>) {
+        let is_full_heap = self.requires_full_heap_collection();
+        // A full heap GC promotes all the young objects, so the remembered set is discarded.
+        let (objects, slices) = self.remset.take();
+        if is_full_heap {
+            scheduler.schedule_common_work::<MultiGenCopyGCWorkContext<VM>>(self);
+        } else {
+            scheduler.schedule_common_work::<MultiGenCopyNurseryGCWorkContext<VM>>(self);
+            // Scan the remembered set like the modbuf.  The objects are recorded as candidates
+            // again when they are processed.
+            let mut packets = objects
+                .chunks(EDGES_WORK_BUFFER_SIZE)
+                .map(|chunk| {
+                    Box::new(ProcessModBuf::<MultiGenCopyNurseryProcessEdges<VM>>::new(
+                        chunk.to_vec(),
+                    )) as Box<dyn GCWork<VM>>
+                })
+                .collect::<Vec<_>>();
+            if !slices.is_empty() {
+                packets.push(Box::new(ProcessRegionModBuf::<
+                    MultiGenCopyNurseryProcessEdges<VM>,
+                >::new(slices)));
+            }
+            scheduler.work_buckets[WorkBucketStage::Closure].bulk_add(packets);
+        }
+    }
+
+    fn get_allocator_mapping(&self) -> &'static EnumMap<AllocationSemantics, AllocatorSelector> {
+        &ALLOCATOR_MAPPING
+    }
+
+    fn prepare(&mut self, tls: VMWorkerThread) {
+        let full_heap = !self.gen.is_current_gc_nursery();
+        self.gen.prepare(tls);
+        if full_heap {
+            self.hi
+                .store(!self.hi.load(Ordering::SeqCst), Ordering::SeqCst); // flip the semi-spaces
+        }
+        let hi = self.hi.load(Ordering::SeqCst);
+        self.copyspace0.prepare(hi);
+        self.copyspace1.prepare(!hi);
+
+        self.fromspace_mut()
+            .set_copy_for_sft_trace(Some(CopySemantics::Mature));
+        self.tospace_mut().set_copy_for_sft_trace(None);
+
+        // The survivor semi-spaces are flipped in every GC.
+        self.survivor_hi
+            .store(!self.survivor_hi.load(Ordering::SeqCst), Ordering::SeqCst);
+        let survivor_hi = self.survivor_hi.load(Ordering::SeqCst);
+        self.survivor0.prepare(survivor_hi);
+        self.survivor1.prepare(!survivor_hi);
+
+        self.survivor_fromspace_mut()
+            .set_copy_for_sft_trace(Some(CopySemantics::PromoteToMature));
+        self.survivor_tospace_mut().set_copy_for_sft_trace(None);
+    }
+
+    fn prepare_worker(&self, worker: &mut GCWorker<Self::VM>) {
+        let copy = &mut worker.get_copy_context_mut().copy;
+        unsafe { copy[0].assume_init_mut() }.rebind(self.tospace());
+        unsafe { copy[1].assume_init_mut() }.rebind(self.survivor_tospace());
+    }
+
+    fn release(&mut self, tls: VMWorkerThread) {
+        let full_heap = !self.gen.is_current_gc_nursery();
+        self.gen.release(tls);
+        if full_heap {
+            if VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.is_on_side() {
+                self.fromspace().clear_side_log_bits();
+            }
+            self.fromspace().release();
+        } else if self.has_survivors() {
+            self.schedule_refinement();
+        }
+        self.survivor_fromspace().release();
+    }
+
+    fn end_of_gc(&mut self, tls: VMWorkerThread) {
+        let next_gc_full_heap = CommonGenPlan::should_next_gc_be_full_heap(self);
+        self.gen.end_of_gc(tls, next_gc_full_heap);
+    }
+
+    fn get_collection_reserved_pages(&self) -> usize {
+        self.gen.get_collection_reserved_pages()
+            + self.tospace().reserved_pages()
+            + self.survivor_tospace().reserved_pages()
+    }
+
+    fn get_used_pages(&self) -> usize {
+        self.gen.get_used_pages()
+            + self.tospace().reserved_pages()
+            + self.survivor_tospace().reserved_pages()
+    }
+
+    fn current_gc_may_move_object(&self) -> bool {
+        true
+    }
+
+    /// Return the number of pages available for allocation. Assuming all future allocations goes to nursery.
+    fn get_available_pages(&self) -> usize {
+        // super.get_available_pages() / 2 to reserve pages for copying
+        (self
+            .get_total_pages()
+            .saturating_sub(self.get_reserved_pages()))
+            >> 1
+    }
+
+    fn base(&self) -> &BasePlan<VM> {
+        &self.gen.common.base
+    }
+
+    fn base_mut(&mut self) -> &mut BasePlan<Self::VM> {
+        &mut self.gen.common.base
+    }
+
+    fn common(&self) -> &CommonPlan<VM> {
+        &self.gen.common
+    }
+
+    fn generational(&self) -> Option<&dyn GenerationalPlan<VM = Self::VM>> {
+        Some(self)
+    }
+}
+
+impl<VM: VMBinding> GenerationalPlan for MultiGenCopy<VM> {
+    fn is_current_gc_nursery(&self) -> bool {
+        self.gen.is_current_gc_nursery()
+    }
+
+    // Only the nursery that mutators allocate into counts as the nursery.  The objects in the
+    // survivor spaces are not logged by the barrier, as they are never unlogged.
+    fn is_object_in_nursery(&self, object: ObjectReference) -> bool {
+        self.gen.nursery.in_space(object)
+    }
+
+    fn is_address_in_nursery(&self, addr: Address) -> bool {
+        self.gen.nursery.address_in_space(addr)
+    }
+
+    fn get_mature_physical_pages_available(&self) -> usize {
+        self.tospace().available_physical_pages()
+    }
+
+    fn get_mature_reserved_pages(&self) -> usize {
+        self.tospace().reserved_pages()
+    }
+
+    fn force_full_heap_collection(&self) {
+        self.gen.force_full_heap_collection()
+    }
+
+    fn last_collection_full_heap(&self) -> bool {
+        self.gen.last_collection_full_heap()
+    }
+
+    fn on_modbuf_processed(&self, modbuf: &[ObjectReference]) {
+        if self.has_survivors() {
+            for object in modbuf {
+                self.remset.add_candidate(*object);
+            }
+        }
+    }
+
+    fn on_region_modbuf_processed(&self, modbuf: &[VM::VMMemorySlice]) {
+        if self.has_survivors() {
+            // Slices in survivor objects are not remembered.  The objects are scanned anyway if
+            // they are live, and the slices will be stale once the objects are copied.
+            let slices = modbuf
+                .iter()
+                .filter(|slice| !self.is_in_survivor_spaces(slice))
+                .cloned()
+                .collect::<Vec<_>>();
+            self.remset.add_candidate_slices(&slices);
+        }
+    }
+}
+
+impl<VM: VMBinding> GenerationalPlanExt<VM> for MultiGenCopy<VM> {
+    fn trace_object_nursery<Q: ObjectQueue, const KIND: TraceKind>(
+        &self,
+        queue: &mut Q,
+        object: ObjectReference,
+        worker: &mut GCWorker<VM>,
+    ) -> ObjectReference {
+        assert!(
+            KIND != TRACE_KIND_TRANSITIVE_PIN,
+            "A copying nursery cannot pin objects"
+        );
+
+        let (space, age) = if self.gen.nursery.in_space(object) {
+            (&self.gen.nursery, 0)
+        } else if self.survivor_fromspace().in_space(object) {
+            let age = OBJECT_AGE.load_atomic::<u8>(object.to_raw_address(), Ordering::Relaxed);
+            (self.survivor_fromspace(), age as usize)
+        } else if self.gen.common.get_los().in_space(object) {
+            // A large object leaves the nursery once it is marked.  It is not copied, so it is
+            // remembered right away.
+            let los = self.gen.common.get_los();
+            let nursery_object = los.is_in_nursery(object);
+            let new_object = los.trace_object::<Q>(queue, object);
+            if nursery_object {
+                self.remember(new_object);
+            }
+            return new_object;
+        } else {
+            // Mature objects, and the objects that are already copied to the survivor tospace.
+            return object;
+        };
+
+        if age + 1 < self.generations - 1 {
+            let new_object =
+                space.trace_object::<Q>(queue, object, Some(CopySemantics::Nursery), worker);
+            // Every thread that reaches the object stores the same age.
+            OBJECT_AGE.store_atomic::<u8>(
+                new_object.to_raw_address(),
+                (age + 1) as u8,
+                Ordering::Relaxed,
+            );
+            new_object
+        } else {
+            let new_object = space.trace_object::<Q>(
+                queue,
+                object,
+                Some(CopySemantics::PromoteToMature),
+                worker,
+            );
+            self.remember(new_object);
+            new_object
+        }
+    }
+}
+
+impl<VM: VMBinding> MultiGenCopy<VM> {
+    pub fn new(args: CreateGeneralPlanArgs<VM>) -> Self {
+        let generations = *args.options.multigen_generations;
+        let mut global_side_metadata_specs =
+            crate::plan::generational::new_generational_global_metadata_specs::<VM>();
+        global_side_metadata_specs.push(OBJECT_AGE);
+        let mut plan_args = CreateSpecificPlanArgs {
+            global_args: args,
+            constraints: &MULTIGENCOPY_CONSTRAINTS,
+            global_side_metadata_specs,
+        };
+
+        let copyspace0 = CopySpace::new(
+            plan_args.get_mature_space_args("copyspace0", true, false, VMRequest::discontiguous()),
+            false,
+        );
+        let copyspace1 = CopySpace::new(
+            plan_args.get_mature_space_args("copyspace1", true, false, VMRequest::discontiguous()),
+            true,
+        );
+        // Survivors are young objects. They are not unlogged, so the barrier never logs them.
+        let survivor0 = CopySpace::new(
+            plan_args.get_nursery_space_args("survivor0", true, false, VMRequest::discontiguous()),
+            false,
+        );
+        let survivor1 = CopySpace::new(
+            plan_args.get_nursery_space_args("survivor1", true, false, VMRequest::discontiguous()),
+            true,
+        );
+
+        let res = MultiGenCopy {
+            gen: CommonGenPlan::new(plan_args),
+            hi: AtomicBool::new(false),
+            copyspace0,
+            copyspace1,
+            survivor_hi: AtomicBool::new(false),
+            survivor0,
+            survivor1,
+            generations,
+            remset: RememberedSet::new(),
+        };
+
+        res.verify_side_metadata_sanity();
+
+        res
+    }
+
+    fn requires_full_heap_collection(&self) -> bool {
+        self.gen.requires_full_heap_collection(self)
+    }
+
+    /// Are there any generations between the nursery and the mature generation?
+    fn has_survivors(&self) -> bool {
+        self.generations > 2
+    }
+
+    /// Record an object that has just become mature in a nursery GC as a candidate of the
+    /// remembered set, as it may point to survivors.  The object is logged so that it is only
+    /// recorded once, and the barrier does not log it until it is refined.
+    fn remember(&self, object: ObjectReference) {
+        if self.has_survivors()
+            && VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC
+                .compare_exchange_metadata::<VM, u8>(
+                    object,
+                    1,
+                    0,
+                    None,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                )
+                .is_ok()
+        {
+            self.remset.add_candidate(object);
+        }
+    }
+
+    fn is_in_survivor_spaces(&self, slice: &VM::VMMemorySlice) -> bool {
+        match slice.object() {
+            Some(object) => self.survivor0.in_space(object) || self.survivor1.in_space(object),
+            None => {
+                self.survivor0.address_in_space(slice.start())
+                    || self.survivor1.address_in_space(slice.start())
+            }
+        }
+    }
+
+    /// Refine the candidates of the remembered set recorded in this GC.
+    fn schedule_refinement(&self) {
+        let (objects, slices) = self.remset.take_candidates();
+        let mut packets = objects
+            .chunks(EDGES_WORK_BUFFER_SIZE)
+            .map(|chunk| {
+                Box::new(RefineRememberedSet::<VM>::new(chunk.to_vec(), vec![]))
+                    as Box<dyn GCWork<VM>>
+            })
+            .collect::<Vec<_>>();
+        if !slices.is_empty() {
+            packets.push(Box::new(RefineRememberedSet::<VM>::new(vec![], slices)));
+        }
+        self.gen.common.base.scheduler.work_buckets[WorkBucketStage::Release].bulk_add(packets);
+    }
+
+    pub fn tospace(&self) -> &CopySpace<VM> {
+        if self.hi.load(Ordering::SeqCst) {
+            &self.copyspace1
+        } else {
+            &self.copyspace0
+        }
+    }
+
+    pub fn tospace_mut(&mut self) -> &mut CopySpace<VM> {
+        if self.hi.load(Ordering::SeqCst) {
+            &mut self.copyspace1
+        } else {
+            &mut self.copyspace0
+        }
+    }
+
+    pub fn fromspace(&self) -> &CopySpace<VM> {
+        if self.hi.load(Ordering::SeqCst) {
+            &self.copyspace0
+        } else {
+            &self.copyspace1
+        }
+    }
+
+    pub fn fromspace_mut(&mut self) -> &mut CopySpace<VM> {
+        if self.hi.load(Ordering::SeqCst) {
+            &mut self.copyspace0
+        } else {
+            &mut self.copyspace1
+        }
+    }
+
+    /// The survivor space that survivors are copied into in the current GC, and that holds the
+    /// survivors between GCs.
+    pub fn survivor_tospace(&self) -> &CopySpace<VM> {
+        if self.survivor_hi.load(Ordering::SeqCst) {
+            &self.survivor1
+        } else {
+            &self.survivor0
+        }
+    }
+
+    pub fn survivor_tospace_mut(&mut self) -> &mut CopySpace<VM> {
+        if self.survivor_hi.load(Ordering::SeqCst) {
+            &mut self.survivor1
+        } else {
+            &mut self.survivor0
+        }
+    }
+
+    pub fn survivor_fromspace(&self) -> &CopySpace<VM> {
+        if self.survivor_hi.load(Ordering::SeqCst) {
+            &self.survivor0
+        } else {
+            &self.survivor1
+        }
+    }
+
+    pub fn survivor_fromspace_mut(&mut self) -> &mut CopySpace<VM> {
+        if self.survivor_hi.load(Ordering::SeqCst) {
+            &mut self.survivor0
+        } else {
+            &mut self.survivor1
+        }
+    }
+}
@@ -0,0 +1,10 @@
+//! Plan: multi-generation copying
+
+pub(in crate::plan) mod gc_work;
+pub(in crate::plan) mod global;
+pub(in crate::plan) mod mutator;
+mod remset;
+
+pub use self::global::MultiGenCopy;
+
+pub use self::global::MULTIGENCOPY_CONSTRAINTS;
@@ -0,0 +1,70 @@
+use crate::util::ObjectReference;
+use crate::vm::VMBinding;
+use crossbeam::queue::SegQueue;
+use std::sync::Mutex;
+
+/// The remembered set of [`super::MultiGenCopy`].
+///
+/// The modbuf of the barrier only records mature objects that are modified between two GCs.
+/// Objects in the survivor spaces stay young across nursery GCs, so mature objects may still
+/// point to them after a GC even though they are not modified again.  Those objects (and memory
+/// slices) are kept in this set, and scanned as roots in every nursery GC until they no longer
+/// point into the survivor spaces, or until the next full heap GC.
+///
+/// During a nursery GC, the objects that may point into the survivor spaces are recorded as
+/// *candidates*: The objects promoted or marked in the GC, and the objects in the modbuf
+/// (including the objects in this set).  The candidates are refined at the end of the GC, and
+/// only those that point into the survivor spaces are kept.
+pub(super) struct RememberedSet<VM: VMBinding> {
+    candidates: SegQueue<ObjectReference>,
+    candidate_slices: Mutex<Vec<VM::VMMemorySlice>>,
+    objects: Mutex<Vec<ObjectReference>>,
+    slices: Mutex<Vec<VM::VMMemorySlice>>,
+}
+
+impl<VM: VMBinding> RememberedSet<VM> {
+    pub fn new() -> Self {
+        Self {
+            candidates: SegQueue::new(),
+            candidate_slices: Mutex::new(vec![]),
+            objects: Mutex::new(vec![]),
+            slices: Mutex::new(vec![]),
+        }
+    }
+
+    /// Record an object to be refined at the end of the current GC.
+    pub fn add_candidate(&self, object: ObjectReference) {
+        self.candidates.push(object);
+    }
+
+    /// Record memory slices to be refined at the end of the current GC.
+    pub fn add_candidate_slices(&self, slices: &[VM::VMMemorySlice]) {
+        self.candidate_slices
+            .lock()
+            .unwrap()
+            .extend_from_slice(slices);
+    }
+
+    /// Take the objects and the memory slices to be refined.
+    pub fn take_candidates(&self) -> (Vec<ObjectReference>, Vec<VM::VMMemorySlice>) {
+        let mut objects = Vec::with_capacity(self.candidates.len());
+        while let Some(object) = self.candidates.pop() {
+            objects.push(object);
+        }
+        let slices = std::mem::take(&mut *self.candidate_slices.lock().unwrap());
+        (objects, slices)
+    }
+
+    /// Add refined objects and memory slices to the set.
+    pub fn insert(&self, objects: Vec<ObjectReference>, slices: Vec<VM::VMMemorySlice>) {
+        self.objects.lock().unwrap().extend(objects);
+        self.slices.lock().unwrap().extend(slices);
+    }
+
+    /// Take all the objects and the memory slices in the set, to be scanned in a nursery GC.
+    pub fn take(&self) -> (Vec<ObjectReference>, Vec<VM::VMMemorySlice>) {
+        let objects = std::mem::take(&mut *self.objects.lock().unwrap());
+        let slices = std::mem::take(&mut *self.slices.lock().unwrap());
+        (objects, slices)
+    }
+}
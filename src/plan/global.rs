@@ -26,8 +26,8 @@ use crate::util::metadata::side_metadata::SideMetadataSpec;
 use crate::util::options::Options;
 use crate::util::options::PlanSelector;
 use crate::util::statistics::stats::Stats;
-use crate::util::{conversions, ObjectReference};
-use crate::util::{VMMutatorThread, VMWorkerThread};
+use crate::util::{conversions, Address, ObjectReference};
+use crate::util::{VMMutatorThread, VMThread, VMWorkerThread};
 use crate::vm::*;
 use downcast_rs::Downcast;
 use enum_map::EnumMap;
@@ -60,12 +60,28 @@ pub fn create_mutator<VM: VMBinding>(
         PlanSelector::StickyImmix => {
             crate::plan::sticky::immix::mutator::create_stickyimmix_mutator(tls, mmtk)
         }
+        PlanSelector::StickyMarkSweep => {
+            crate::plan::sticky::marksweep::mutator::create_stickyms_mutator(tls, mmtk)
+        }
         PlanSelector::ConcurrentImmix => {
             crate::plan::concurrent::immix::mutator::create_concurrent_immix_mutator(tls, mmtk)
         }
+        PlanSelector::ConcurrentMarkSweep => {
+            crate::plan::concurrent::marksweep::mutator::create_concurrent_ms_mutator(tls, mmtk)
+        }
+        PlanSelector::ConcurrentGenImmix => {
+            crate::plan::concurrent::genimmix::mutator::create_concurrent_genimmix_mutator(
+                tls, mmtk,
+            )
+        }
         PlanSelector::Compressor => {
             crate::plan::compressor::mutator::create_compressor_mutator(tls, mmtk)
         }
+        PlanSelector::LXR => crate::plan::concurrent::lxr::mutator::create_lxr_mutator(tls, mmtk),
+        PlanSelector::G1 => crate::plan::g1::mutator::create_g1_mutator(tls, mmtk),
+        PlanSelector::MultiGenCopy => {
+            crate::plan::generational::multigen::mutator::create_multigencopy_mutator(tls, mmtk)
+        }
     })
 }
 
@@ -99,13 +115,32 @@ pub fn create_plan<VM: VMBinding>(
         PlanSelector::StickyImmix => {
             Box::new(crate::plan::sticky::immix::StickyImmix::new(args)) as Box<dyn Plan<VM = VM>>
         }
+        PlanSelector::StickyMarkSweep => {
+            Box::new(crate::plan::sticky::marksweep::StickyMarkSweep::new(args))
+                as Box<dyn Plan<VM = VM>>
+        }
         PlanSelector::ConcurrentImmix => {
             Box::new(crate::plan::concurrent::immix::ConcurrentImmix::new(args))
                 as Box<dyn Plan<VM = VM>>
         }
+        PlanSelector::ConcurrentMarkSweep => {
+            Box::new(crate::plan::concurrent::marksweep::ConcurrentMarkSweep::new(args))
+                as Box<dyn Plan<VM = VM>>
+        }
+        PlanSelector::ConcurrentGenImmix => Box::new(
+            crate::plan::concurrent::genimmix::ConcurrentGenImmix::new(args),
+        ) as Box<dyn Plan<VM = VM>>,
         PlanSelector::Compressor => {
             Box::new(crate::plan::compressor::Compressor::new(args)) as Box<dyn Plan<VM = VM>>
         }
+        PlanSelector::LXR => {
+            Box::new(crate::plan::concurrent::lxr::LXR::new(args)) as Box<dyn Plan<VM = VM>>
+        }
+        PlanSelector::G1 => Box::new(crate::plan::g1::G1::new(args)) as Box<dyn Plan<VM = VM>>,
+        PlanSelector::MultiGenCopy => {
+            Box::new(crate::plan::generational::multigen::MultiGenCopy::new(args))
+                as Box<dyn Plan<VM = VM>>
+        }
     };
 
     // We have created Plan in the heap, and we won't explicitly move it.
@@ -342,6 +377,22 @@ pub trait Plan: 'static + HasSpaces + Sync + Downcast {
     /// the current GC has just finished.
     fn current_gc_may_move_object(&self) -> bool;
 
+    /// Handle a memory protection fault at `addr`.  A plan that protects parts of the heap while
+    /// working concurrently with mutators should override this, and return `true` if the fault is
+    /// caused by the plan.  This is called from signal handlers, so it must be
+    /// async-signal-safe.  See [`crate::memory_manager::handle_protection_fault`].
+    fn handle_protection_fault(&self, _addr: Address) -> bool {
+        false
+    }
+
+    /// Resolve a memory protection fault at `addr` in the thread `tls`, for which
+    /// [`Plan::handle_protection_fault`] returned `true`.  This is called outside signal handlers,
+    /// and the plan should make the faulting access succeed on the current thread without waiting
+    /// for other threads.  See [`crate::memory_manager::resolve_protection_fault`].
+    fn resolve_protection_fault(&self, _tls: VMThread, addr: Address) {
+        unreachable!("{addr} is not protected by the plan")
+    }
+
     /// An object is firstly reached by a sanity GC. So the object is reachable
     /// in the current GC, and all the GC work has been done for the object (such as
     /// tracing and releasing). A plan can implement this to
@@ -777,12 +828,14 @@ impl<VM: VMBinding> CommonPlan<VM> {
     pub fn clear_side_log_bits(&self) {
         self.immortal.clear_side_log_bits();
         self.los.clear_side_log_bits();
+        self.nonmoving.clear_side_log_bits();
         self.base.clear_side_log_bits();
     }
 
     pub fn set_side_log_bits(&self) {
         self.immortal.set_side_log_bits();
         self.los.set_side_log_bits();
+        self.nonmoving.set_side_log_bits();
         self.base.set_side_log_bits();
     }
 
@@ -815,6 +868,7 @@ impl<VM: VMBinding> CommonPlan<VM> {
                     crate::policy::immix::ImmixSpaceArgs {
                         mixed_age: false,
                         never_move_objects: true,
+                        reference_counted: false,
                     },
                 )
             }
@@ -826,7 +880,7 @@ impl<VM: VMBinding> CommonPlan<VM> {
             if #[cfg(feature = "immortal_as_nonmoving")] {
                 self.nonmoving.prepare();
             } else if #[cfg(feature = "marksweep_as_nonmoving")] {
-                self.nonmoving.prepare(_full_heap);
+                self.nonmoving.prepare(_full_heap, UnlogBitsOperation::NoOp);
             } else {
                 self.nonmoving.prepare(_full_heap, None, UnlogBitsOperation::NoOp);
             }
@@ -838,7 +892,7 @@ impl<VM: VMBinding> CommonPlan<VM> {
             if #[cfg(feature = "immortal_as_nonmoving")] {
                 self.nonmoving.release();
             } else if #[cfg(feature = "marksweep_as_nonmoving")] {
-                self.nonmoving.prepare(_full_heap);
+                self.nonmoving.release(UnlogBitsOperation::NoOp);
             } else {
                 self.nonmoving.release(_full_heap, UnlogBitsOperation::NoOp);
             }
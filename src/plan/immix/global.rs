@@ -135,6 +135,7 @@ impl<VM: VMBinding> Immix<VM> {
             ImmixSpaceArgs {
                 mixed_age: false,
                 never_move_objects: false,
+                reference_counted: false,
             },
         )
     }
@@ -12,6 +12,7 @@ use crate::scheduler::GCWorkScheduler;
 use crate::util::alloc::allocators::AllocatorSelector;
 use crate::util::heap::gc_trigger::SpaceStats;
 use crate::util::heap::VMRequest;
+use crate::util::metadata::log_bit::UnlogBitsOperation;
 use crate::util::metadata::side_metadata::SideMetadataContext;
 use crate::util::VMWorkerThread;
 use crate::vm::VMBinding;
@@ -58,11 +59,11 @@ impl<VM: VMBinding> Plan for MarkSweep<VM> {
 
     fn prepare(&mut self, tls: VMWorkerThread) {
         self.common.prepare(tls, true);
-        self.ms.prepare(true);
+        self.ms.prepare(true, UnlogBitsOperation::NoOp);
     }
 
     fn release(&mut self, tls: VMWorkerThread) {
-        self.ms.release();
+        self.ms.release(UnlogBitsOperation::NoOp);
         self.common.release(tls, true);
     }
 
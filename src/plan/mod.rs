@@ -46,6 +46,7 @@ mod sticky;
 
 mod compressor;
 mod concurrent;
+mod g1;
 mod immix;
 mod markcompact;
 mod marksweep;
@@ -53,14 +54,36 @@ mod nogc;
 mod pageprotect;
 mod semispace;
 
+/// Used by mock tests that run a GC and need the work packet types of the plan.
+#[cfg(all(test, feature = "mock_test"))]
+pub(crate) use concurrent::{
+    concurrent_marking_work::ProcessRootSlots,
+    genimmix::{gc_work::InitialMarkProcessEdges, ConcurrentGenImmix},
+    lxr::gc_work::{RCIncEdges, RCRetainEdges},
+    marksweep::ConcurrentMarkSweep,
+};
+#[cfg(all(test, feature = "mock_test"))]
+pub(crate) use g1::G1;
+#[cfg(all(test, feature = "mock_test"))]
+pub(crate) use generational::gc_work::GenNurseryProcessEdges;
 pub(crate) use generational::global::is_nursery_gc;
 pub(crate) use generational::global::GenerationalPlan;
+#[cfg(all(test, feature = "mock_test", feature = "vo_bit"))]
+pub(crate) use immix::Immix;
+#[cfg(all(test, feature = "mock_test", feature = "object_pinning"))]
+pub(crate) use markcompact::MarkCompact;
+#[cfg(all(test, feature = "mock_test"))]
+pub(crate) use semispace::SemiSpace;
+#[cfg(all(test, feature = "mock_test", feature = "vo_bit"))]
+pub(crate) use sticky::marksweep::StickyMarkSweep;
 
 // Expose plan constraints as public. Though a binding can get them from plan.constraints(),
 // it is possible for performance reasons that they want the constraints as constants.
 
+pub use g1::G1_CONSTRAINTS;
 pub use generational::copying::GENCOPY_CONSTRAINTS;
 pub use generational::immix::GENIMMIX_CONSTRAINTS;
+pub use generational::multigen::MULTIGENCOPY_CONSTRAINTS;
 pub use immix::IMMIX_CONSTRAINTS;
 pub use markcompact::MARKCOMPACT_CONSTRAINTS;
 pub use marksweep::MS_CONSTRAINTS;
@@ -68,3 +91,4 @@ pub use nogc::NOGC_CONSTRAINTS;
 pub use pageprotect::PP_CONSTRAINTS;
 pub use semispace::SS_CONSTRAINTS;
 pub use sticky::immix::STICKY_IMMIX_CONSTRAINTS;
+pub use sticky::marksweep::STICKY_MS_CONSTRAINTS;
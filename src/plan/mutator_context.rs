@@ -6,6 +6,7 @@ use crate::plan::AllocationSemantics;
 use crate::policy::space::Space;
 use crate::util::alloc::allocator::AllocationOptions;
 use crate::util::alloc::allocators::{AllocatorSelector, Allocators};
+use crate::util::alloc::AllocationPacing;
 use crate::util::alloc::Allocator;
 use crate::util::{Address, ObjectReference};
 use crate::util::{VMMutatorThread, VMWorkerThread};
@@ -290,6 +291,17 @@ impl<VM: VMBinding> Mutator<VM> {
             .collect()
     }
 
+    /// Set the limits of allocation pacing for this mutator, or use the options if `limits` is
+    /// `None`.  All the allocators of a mutator share the same context, so the limits apply to all
+    /// of them.
+    pub fn set_allocation_pacing(&self, limits: Option<AllocationPacing>) {
+        if let Some(selector) = self.get_all_allocator_selectors().first() {
+            unsafe { self.allocators.get_allocator(*selector) }
+                .get_context()
+                .set_allocation_pacing(limits);
+        }
+    }
+
     /// Inform each allocator about destroying. Call allocator-specific on destroy methods.
     pub fn on_destroy(&mut self) {
         for selector in self.get_all_allocator_selectors() {
@@ -11,6 +11,7 @@ use crate::policy::immix::ImmixSpace;
 use crate::policy::immix::TRACE_KIND_FAST;
 use crate::policy::sft::SFT;
 use crate::policy::space::Space;
+use crate::scheduler::trace::{self, TraceArg};
 use crate::util::copy::CopyConfig;
 use crate::util::copy::CopySelector;
 use crate::util::copy::CopySemantics;
@@ -91,6 +92,9 @@ impl<VM: VMBinding> Plan for StickyImmix<VM> {
         let is_full_heap = self.requires_full_heap_collection();
         self.gc_full_heap.store(is_full_heap, Ordering::SeqCst);
         probe!(mmtk, gen_full_heap, is_full_heap);
+        trace::record_gc_decision(scheduler.tracer(), "gen_full_heap", "full_heap", || {
+            TraceArg::Bool(is_full_heap)
+        });
 
         if !is_full_heap {
             info!("Nursery GC");
@@ -347,6 +351,7 @@ impl<VM: VMBinding> StickyImmix<VM> {
                 // In StickyImmix, both young and old objects are allocated in the ImmixSpace.
                 mixed_age: true,
                 never_move_objects: false,
+                reference_counted: false,
             },
         );
         Self {
@@ -0,0 +1,23 @@
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::scheduler::gc_work::PlanProcessEdges;
+use crate::{plan::generational::gc_work::GenNurseryProcessEdges, vm::VMBinding};
+
+use super::global::StickyMarkSweep;
+
+pub struct StickyMSNurseryGCWorkContext<VM: VMBinding>(std::marker::PhantomData<VM>);
+
+impl<VM: VMBinding> crate::scheduler::GCWorkContext for StickyMSNurseryGCWorkContext<VM> {
+    type VM = VM;
+    type PlanType = StickyMarkSweep<VM>;
+    type DefaultProcessEdges = GenNurseryProcessEdges<VM, Self::PlanType, DEFAULT_TRACE>;
+    type PinningProcessEdges = GenNurseryProcessEdges<VM, Self::PlanType, DEFAULT_TRACE>;
+}
+
+pub struct StickyMSMatureGCWorkContext<VM: VMBinding>(std::marker::PhantomData<VM>);
+
+impl<VM: VMBinding> crate::scheduler::GCWorkContext for StickyMSMatureGCWorkContext<VM> {
+    type VM = VM;
+    type PlanType = StickyMarkSweep<VM>;
+    type DefaultProcessEdges = PlanProcessEdges<VM, Self::PlanType, DEFAULT_TRACE>;
+    type PinningProcessEdges = PlanProcessEdges<VM, Self::PlanType, DEFAULT_TRACE>;
+}
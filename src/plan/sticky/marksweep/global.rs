@@ -0,0 +1,317 @@
+use crate::plan::generational::global::GenerationalPlan;
+use crate::plan::global::BasePlan;
+use crate::plan::global::CommonPlan;
+use crate::plan::global::CreateGeneralPlanArgs;
+use crate::plan::global::CreateSpecificPlanArgs;
+use crate::plan::AllocationSemantics;
+use crate::plan::Plan;
+use crate::plan::PlanConstraints;
+use crate::policy::gc_work::TraceKind;
+use crate::policy::marksweepspace::native_ms::MarkSweepSpace;
+use crate::policy::marksweepspace::native_ms::MAX_OBJECT_SIZE;
+use crate::policy::sft::SFT;
+use crate::policy::space::Space;
+use crate::scheduler::trace::{self, TraceArg};
+use crate::scheduler::GCWorkScheduler;
+use crate::util::alloc::allocators::AllocatorSelector;
+use crate::util::heap::gc_trigger::SpaceStats;
+use crate::util::heap::VMRequest;
+use crate::util::metadata::log_bit::UnlogBitsOperation;
+use crate::util::metadata::side_metadata::SideMetadataContext;
+use crate::util::statistics::counter::EventCounter;
+use crate::util::VMWorkerThread;
+use crate::vm::ObjectModel;
+use crate::vm::VMBinding;
+use enum_map::EnumMap;
+
+use atomic::Ordering;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+use mmtk_macros::{HasSpaces, PlanTraceObject};
+
+use super::gc_work::StickyMSMatureGCWorkContext;
+use super::gc_work::StickyMSNurseryGCWorkContext;
+
+/// A non-moving generational plan that uses sticky mark bits in the native mark sweep space.
+/// Objects that are marked are mature, and a nursery GC only traces objects that are not marked
+/// yet.  The mark bits are only cleared in a full heap GC.
+#[derive(HasSpaces, PlanTraceObject)]
+pub struct StickyMarkSweep<VM: VMBinding> {
+    #[parent]
+    common: CommonPlan<VM>,
+    #[space]
+    ms: MarkSweepSpace<VM>,
+    gc_full_heap: AtomicBool,
+    next_gc_full_heap: AtomicBool,
+    full_heap_gc_count: Arc<Mutex<EventCounter>>,
+}
+
+/// The plan constraints for the sticky mark sweep plan.
+pub const STICKY_MS_CONSTRAINTS: PlanConstraints = PlanConstraints {
+    moves_objects: false,
+    max_non_los_default_alloc_bytes: MAX_OBJECT_SIZE,
+    // We may trace duplicate edges in sticky mark sweep (or any plan that uses object remembering barrier). See https://github.com/mmtk/mmtk-core/issues/743.
+    may_trace_duplicate_edges: true,
+    needs_prepare_mutator: !cfg!(feature = "eager_sweeping")
+        || PlanConstraints::default().needs_prepare_mutator,
+    needs_log_bit: true,
+    barrier: crate::plan::BarrierSelector::ObjectBarrier,
+    generational: true,
+    ..PlanConstraints::default()
+};
+
+impl<VM: VMBinding> Plan for StickyMarkSweep<VM> {
+    fn constraints(&self) -> &'static PlanConstraints {
+        &STICKY_MS_CONSTRAINTS
+    }
+
+    fn base(&self) -> &BasePlan<VM> {
+        &self.common.base
+    }
+
+    fn base_mut(&mut self) -> &mut BasePlan<Self::VM> {
+        &mut self.common.base
+    }
+
+    fn common(&self) -> &CommonPlan<VM> {
+        &self.common
+    }
+
+    fn generational(
+        &self,
+    ) -> Option<&dyn crate::plan::generational::global::GenerationalPlan<VM = Self::VM>> {
+        Some(self)
+    }
+
+    fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<VM>) {
+        let is_full_heap = self.requires_full_heap_collection();
+        self.gc_full_heap.store(is_full_heap, Ordering::SeqCst);
+        probe!(mmtk, gen_full_heap, is_full_heap);
+        trace::record_gc_decision(scheduler.tracer(), "gen_full_heap", "full_heap", || {
+            TraceArg::Bool(is_full_heap)
+        });
+
+        if !is_full_heap {
+            info!("Nursery GC");
+            scheduler.schedule_common_work::<StickyMSNurseryGCWorkContext<VM>>(self);
+        } else {
+            info!("Full heap GC");
+            scheduler.schedule_common_work::<StickyMSMatureGCWorkContext<VM>>(self);
+        }
+    }
+
+    fn get_allocator_mapping(&self) -> &'static EnumMap<AllocationSemantics, AllocatorSelector> {
+        &super::mutator::ALLOCATOR_MAPPING
+    }
+
+    fn prepare(&mut self, tls: VMWorkerThread) {
+        if self.is_current_gc_nursery() {
+            // Keep the mark bits and the block states. We don't do anything special to unlog
+            // bits during nursery GC because ProcessModBuf will set the unlog bits back.
+            self.ms.prepare(false, UnlogBitsOperation::NoOp);
+            self.common.los.prepare(false);
+        } else {
+            self.full_heap_gc_count.lock().unwrap().inc();
+            self.common.prepare(tls, true);
+            // We will reconstruct unlog bits during tracing.
+            self.ms.prepare(true, UnlogBitsOperation::BulkClear);
+        }
+    }
+
+    fn release(&mut self, tls: VMWorkerThread) {
+        if self.is_current_gc_nursery() {
+            self.ms.release(UnlogBitsOperation::NoOp);
+            self.common.los.release(false);
+        } else {
+            // We reconstructred unlog bits during tracing.  Keep them.
+            self.ms.release(UnlogBitsOperation::NoOp);
+            self.common.release(tls, true);
+        }
+    }
+
+    fn end_of_gc(&mut self, tls: VMWorkerThread) {
+        let next_gc_full_heap =
+            crate::plan::generational::global::CommonGenPlan::should_next_gc_be_full_heap(self);
+        self.next_gc_full_heap
+            .store(next_gc_full_heap, Ordering::Relaxed);
+
+        self.ms.end_of_gc();
+        self.common.end_of_gc(tls);
+    }
+
+    fn collection_required(&self, space_full: bool, space: Option<SpaceStats<Self::VM>>) -> bool {
+        let nursery_full =
+            self.ms.get_fresh_pages() > self.base().gc_trigger.get_max_nursery_pages();
+        if space_full && space.is_some() && space.as_ref().unwrap().0.name() != self.ms.name() {
+            self.next_gc_full_heap.store(true, Ordering::SeqCst);
+        }
+        self.base().collection_required(self, space_full) || nursery_full
+    }
+
+    fn last_collection_was_exhaustive(&self) -> bool {
+        self.gc_full_heap.load(Ordering::Relaxed)
+    }
+
+    fn current_gc_may_move_object(&self) -> bool {
+        false
+    }
+
+    fn get_used_pages(&self) -> usize {
+        self.common.get_used_pages() + self.ms.reserved_pages()
+    }
+
+    fn sanity_check_object(&self, object: crate::util::ObjectReference) -> bool {
+        if self.is_current_gc_nursery() {
+            // Every reachable object should be logged
+            if !VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.is_unlogged::<VM>(object, Ordering::SeqCst) {
+                error!("Object {} is not unlogged (all objects that have been traced should be unlogged/mature)", object);
+                return false;
+            }
+
+            // Every reachable object should be marked
+            if self.ms.in_space(object) && !self.ms.is_live(object) {
+                error!(
+                    "Object {} is not marked (all objects that have been traced should be marked)",
+                    object
+                );
+                return false;
+            } else if self.common.los.in_space(object) && !self.common.los.is_live(object) {
+                error!("LOS Object {} is not marked", object);
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl<VM: VMBinding> GenerationalPlan for StickyMarkSweep<VM> {
+    fn is_current_gc_nursery(&self) -> bool {
+        !self.gc_full_heap.load(Ordering::SeqCst)
+    }
+
+    fn is_object_in_nursery(&self, object: crate::util::ObjectReference) -> bool {
+        self.ms.in_space(object) && !self.ms.is_live(object)
+    }
+
+    // Like sticky immix, we cannot tell whether an address is in the nursery without an object.
+    // Returning false is conservative for the memory slice copying barrier: The object will be
+    // treated as a mature object, and pushed to the remembered set.
+    fn is_address_in_nursery(&self, _addr: crate::util::Address) -> bool {
+        false
+    }
+
+    fn get_mature_physical_pages_available(&self) -> usize {
+        self.ms.available_physical_pages()
+    }
+
+    fn get_mature_reserved_pages(&self) -> usize {
+        self.ms.reserved_pages()
+    }
+
+    fn force_full_heap_collection(&self) {
+        self.next_gc_full_heap.store(true, Ordering::SeqCst);
+    }
+
+    fn last_collection_full_heap(&self) -> bool {
+        self.gc_full_heap.load(Ordering::SeqCst)
+    }
+}
+
+impl<VM: VMBinding> crate::plan::generational::global::GenerationalPlanExt<VM>
+    for StickyMarkSweep<VM>
+{
+    fn trace_object_nursery<Q: crate::ObjectQueue, const KIND: TraceKind>(
+        &self,
+        queue: &mut Q,
+        object: crate::util::ObjectReference,
+        _worker: &mut crate::scheduler::GCWorker<VM>,
+    ) -> crate::util::ObjectReference {
+        if self.ms.in_space(object) {
+            if !self.is_object_in_nursery(object) {
+                trace!("MarkSweep mature object {}, skip", object);
+            } else {
+                trace!("MarkSweep nursery object {} is being traced", object);
+                self.ms.trace_object(queue, object);
+            }
+            return object;
+        }
+
+        if self.common.get_los().in_space(object) {
+            return self.common.get_los().trace_object::<Q>(queue, object);
+        }
+
+        object
+    }
+}
+
+impl<VM: VMBinding> StickyMarkSweep<VM> {
+    pub fn new(args: CreateGeneralPlanArgs<VM>) -> Self {
+        let full_heap_gc_count = args.stats.new_event_counter("majorGC", true, true);
+        let mut global_side_metadata_specs = SideMetadataContext::new_global_specs(
+            &crate::plan::generational::new_generational_global_metadata_specs::<VM>(),
+        );
+        MarkSweepSpace::<VM>::extend_global_side_metadata_specs(&mut global_side_metadata_specs);
+
+        let mut plan_args = CreateSpecificPlanArgs {
+            global_args: args,
+            constraints: &STICKY_MS_CONSTRAINTS,
+            global_side_metadata_specs,
+        };
+
+        let res = StickyMarkSweep {
+            // In StickyMarkSweep, both young and old objects are allocated in the MarkSweepSpace.
+            ms: MarkSweepSpace::new(plan_args.get_mixed_age_space_args(
+                "ms",
+                true,
+                false,
+                VMRequest::discontiguous(),
+            )),
+            common: CommonPlan::new(plan_args),
+            gc_full_heap: AtomicBool::new(false),
+            next_gc_full_heap: AtomicBool::new(false),
+            full_heap_gc_count,
+        };
+
+        res.verify_side_metadata_sanity();
+
+        res
+    }
+
+    fn requires_full_heap_collection(&self) -> bool {
+        // Separate each condition so the code is clear
+        #[allow(clippy::if_same_then_else, clippy::needless_bool)]
+        if crate::plan::generational::FULL_NURSERY_GC {
+            trace!("full heap: forced full heap");
+            // For barrier overhead measurements, we always do full gc in nursery collections.
+            true
+        } else if self
+            .common
+            .base
+            .global_state
+            .user_triggered_collection
+            .load(Ordering::SeqCst)
+            && *self.common.base.options.full_heap_system_gc
+        {
+            // User triggered collection, and we force full heap for user triggered collection
+            true
+        } else if self.next_gc_full_heap.load(Ordering::SeqCst)
+            || self
+                .common
+                .base
+                .global_state
+                .cur_collection_attempts
+                .load(Ordering::SeqCst)
+                > 1
+        {
+            // Forces full heap collection
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn ms_space(&self) -> &MarkSweepSpace<VM> {
+        &self.ms
+    }
+}
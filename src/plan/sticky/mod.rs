@@ -1 +1,2 @@
 pub mod immix;
+pub mod marksweep;
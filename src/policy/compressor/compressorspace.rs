@@ -1,10 +1,12 @@
 use crate::plan::VectorObjectQueue;
+use crate::policy::compressor::concurrent::{ConcurrentCompaction, RegionCompaction};
 use crate::policy::compressor::forwarding;
 use crate::policy::gc_work::{TraceKind, TRACE_KIND_TRANSITIVE_PIN};
 use crate::policy::largeobjectspace::LargeObjectSpace;
 use crate::policy::sft::{GCWorkerMutRef, SFT};
 use crate::policy::space::{CommonSpace, Space};
 use crate::scheduler::{GCWork, GCWorkScheduler, GCWorker, WorkBucketStage};
+use crate::util::constants::BYTES_IN_PAGE;
 use crate::util::copy::CopySemantics;
 use crate::util::heap::regionpageresource::AllocatedRegion;
 use crate::util::heap::{PageResource, RegionPageResource};
@@ -14,7 +16,7 @@ use crate::util::metadata::extract_side_metadata;
 use crate::util::metadata::vo_bit;
 use crate::util::metadata::MetadataSpec;
 use crate::util::object_enum::{self, ObjectEnumerator};
-use crate::util::{Address, ObjectReference};
+use crate::util::{Address, ObjectReference, VMWorkerThread};
 use crate::vm::slot::Slot;
 use crate::MMTK;
 use crate::{vm::*, ObjectQueue};
@@ -41,11 +43,20 @@ pub(crate) const TRACE_KIND_FORWARD_ROOT: TraceKind = 1;
 ///   pages of the from-virtual space after moving all objects out of said pages.)
 ///   We instead side-step this race by assigning only a single thread to each region, and
 ///   running multiple single-threaded Compressors at once.
+///
+/// Pinned objects are not moved.  The objects after a pinned object in its region are
+/// compacted towards the end of the pinned object instead.
+///
+/// With the option `compressor_concurrent_compaction`, [`CompressorSpace`] moves objects
+/// concurrently with mutators after the roots are updated, using page protection as in the
+/// paper.  See the [`super::concurrent`] module for details.
 pub struct CompressorSpace<VM: VMBinding> {
     common: CommonSpace<VM>,
     pr: RegionPageResource<VM, forwarding::CompressorRegion>,
     forwarding: forwarding::ForwardingMetadata<VM>,
     scheduler: Arc<GCWorkScheduler<VM>>,
+    /// The regions being compacted concurrently. `None` if concurrent compaction is disabled.
+    concurrent_compaction: Option<ConcurrentCompaction>,
 }
 
 pub(crate) const GC_MARK_BIT_MASK: u8 = 1;
@@ -70,18 +81,18 @@ impl<VM: VMBinding> SFT for CompressorSpace<VM> {
     }
 
     #[cfg(feature = "object_pinning")]
-    fn pin_object(&self, _object: ObjectReference) -> bool {
-        panic!("Cannot pin/unpin objects of CompressorSpace.")
+    fn pin_object(&self, object: ObjectReference) -> bool {
+        VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC.pin_object::<VM>(object)
     }
 
     #[cfg(feature = "object_pinning")]
-    fn unpin_object(&self, _object: ObjectReference) -> bool {
-        panic!("Cannot pin/unpin objects of CompressorSpace.")
+    fn unpin_object(&self, object: ObjectReference) -> bool {
+        VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC.unpin_object::<VM>(object)
     }
 
     #[cfg(feature = "object_pinning")]
-    fn is_object_pinned(&self, _object: ObjectReference) -> bool {
-        false
+    fn is_object_pinned(&self, object: ObjectReference) -> bool {
+        VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC.is_object_pinned::<VM>(object)
     }
 
     fn is_movable(&self) -> bool {
@@ -216,9 +227,15 @@ impl<VM: VMBinding> CompressorSpace<VM> {
         let local_specs = extract_side_metadata(&[
             MetadataSpec::OnSide(forwarding::MARK_SPEC),
             MetadataSpec::OnSide(forwarding::OFFSET_VECTOR_SPEC),
+            #[cfg(feature = "object_pinning")]
+            MetadataSpec::OnSide(forwarding::PINNED_SPEC),
+            #[cfg(feature = "object_pinning")]
+            *VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC,
         ]);
         let is_discontiguous = args.vmrequest.is_discontiguous();
         let scheduler = args.scheduler.clone();
+        let concurrent_compaction =
+            (*args.options.compressor_concurrent_compaction).then(ConcurrentCompaction::default);
         let common = CommonSpace::new(args.into_policy_args(true, false, local_specs));
         CompressorSpace {
             pr: if is_discontiguous {
@@ -229,6 +246,7 @@ impl<VM: VMBinding> CompressorSpace<VM> {
             forwarding: forwarding::ForwardingMetadata::new(),
             common,
             scheduler,
+            concurrent_compaction,
         }
     }
 
@@ -237,11 +255,17 @@ impl<VM: VMBinding> CompressorSpace<VM> {
             .enumerate_regions(&mut |r: &AllocatedRegion<forwarding::CompressorRegion>| {
                 forwarding::MARK_SPEC
                     .bzero_metadata(r.region.start(), r.region.end() - r.region.start());
+                #[cfg(feature = "object_pinning")]
+                forwarding::PINNED_SPEC
+                    .bzero_metadata(r.region.start(), r.region.end() - r.region.start());
             });
     }
 
     pub fn release(&self) {
-        self.forwarding.release();
+        // Concurrent compaction needs the forwarding addresses until it finishes.
+        if !self.is_compacting_concurrently() {
+            self.forwarding.release();
+        }
     }
 
     pub fn trace_mark_object<Q: ObjectQueue>(
@@ -258,6 +282,10 @@ impl<VM: VMBinding> CompressorSpace<VM> {
         if CompressorSpace::<VM>::test_and_mark(object) {
             queue.enqueue(object);
             self.forwarding.mark_last_word_of_object(object);
+            #[cfg(feature = "object_pinning")]
+            if self.is_object_pinned(object) {
+                self.forwarding.mark_pinned(object);
+            }
         }
         object
     }
@@ -334,15 +362,15 @@ impl<VM: VMBinding> CompressorSpace<VM> {
         ObjectReference::from_raw_address(self.forwarding.forward(object.to_raw_address())).unwrap()
     }
 
-    fn update_references(&self, worker: &mut GCWorker<VM>, object: ObjectReference) {
-        if VM::VMScanning::support_slot_enqueuing(worker.tls, object) {
-            VM::VMScanning::scan_object(worker.tls, object, &mut |s: VM::VMSlot| {
+    fn update_references(&self, tls: VMWorkerThread, object: ObjectReference) {
+        if VM::VMScanning::support_slot_enqueuing(tls, object) {
+            VM::VMScanning::scan_object(tls, object, &mut |s: VM::VMSlot| {
                 if let Some(o) = s.load() {
                     s.store(self.forward(o, false));
                 }
             });
         } else {
-            VM::VMScanning::scan_object_and_trace_edges(worker.tls, object, &mut |o| {
+            VM::VMScanning::scan_object_and_trace_edges(tls, object, &mut |o| {
                 self.forward(o, false)
             });
         }
@@ -372,6 +400,10 @@ impl<VM: VMBinding> CompressorSpace<VM> {
                     });
                 crate::util::metadata::vo_bit::bzero_vo_bit(start, end - start);
             }
+            // Dead objects may be pinned. Clear the pinning bits so that they do not pin the
+            // objects moved to their addresses. The live pinned objects are pinned again below.
+            #[cfg(feature = "object_pinning")]
+            Self::clear_side_pinning_bits(start, end);
             let mut to = start;
             self.forwarding
                 .scan_marked_objects(start, end, &mut |obj: ObjectReference| {
@@ -387,27 +419,233 @@ impl<VM: VMBinding> CompressorSpace<VM> {
                         "whilst forwarding {obj}, the new address {0} should be after the end of the last object {to}",
                         new_object.to_raw_address()
                     );
-                    // copy object
-                    trace!(" copy from {} to {}", obj, new_object);
-                    let end_of_new_object =
-                        VM::VMObjectModel::copy_to(obj, new_object, Address::ZERO);
+                    let end_of_new_object = if new_object == obj {
+                        trace!(" {} is pinned", obj);
+                        #[cfg(feature = "object_pinning")]
+                        self.pin_object(obj);
+                        obj.to_object_start::<VM>() + copied_size
+                    } else {
+                        // copy object
+                        trace!(" copy from {} to {}", obj, new_object);
+                        VM::VMObjectModel::copy_to(obj, new_object, Address::ZERO)
+                    };
                     // update VO bit
                     #[cfg(feature = "vo_bit")]
                     vo_bit::set_vo_bit(new_object);
                     to = new_object.to_object_start::<VM>() + copied_size;
                     debug_assert_eq!(end_of_new_object, to);
-                    self.update_references(worker, new_object);
+                    self.update_references(worker.tls, new_object);
                 });
             self.pr.reset_cursor(r, to);
         });
     }
 
+    /// Clear the pinning bits between `start` and `end` if they are on the side. In-header
+    /// pinning bits are overwritten when objects are moved or allocated.
+    #[cfg(feature = "object_pinning")]
+    fn clear_side_pinning_bits(start: Address, end: Address) {
+        if let MetadataSpec::OnSide(side) = *VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC {
+            side.bzero_metadata(start, end - start);
+        }
+    }
+
+    /// Does the region have pinned live objects?  Such regions are compacted in the pause even
+    /// with concurrent compaction, as native code may access pinned objects without going
+    /// through protection faults.
+    pub fn region_has_pinned_objects(&self, index: usize) -> bool {
+        self.pr.with_regions(&mut |regions| {
+            let r = &regions[index];
+            self.forwarding
+                .has_pinned_objects(r.region.start(), r.cursor())
+        })
+    }
+
+    pub fn is_concurrent_compaction_enabled(&self) -> bool {
+        self.concurrent_compaction.is_some()
+    }
+
+    /// Are any regions still being compacted concurrently?
+    pub fn is_compacting_concurrently(&self) -> bool {
+        self.concurrent_compaction
+            .as_ref()
+            .is_some_and(|concurrent| concurrent.is_in_progress())
+    }
+
+    pub fn add_concurrent_compact_tasks(&'static self) {
+        let start_packets: Vec<Box<dyn GCWork<VM>>> = self
+            .generate_tasks(&mut |_, i| Box::new(StartConcurrentCompaction::<VM>::new(self, i)));
+        self.scheduler.work_buckets[WorkBucketStage::Compact].bulk_add(start_packets);
+    }
+
+    /// Protect the to-space pages of a region, and schedule the region to be compacted when
+    /// mutators resume.
+    pub fn start_concurrent_compaction(&'static self, index: usize) {
+        self.pr.with_regions(&mut |regions| {
+            let r = &regions[index];
+            let start = r.region.start();
+            let old_cursor = r.cursor();
+            let new_cursor = self.forwarding.forward_end_of_region(r.region, old_cursor);
+            debug_assert!(!self.forwarding.has_pinned_objects(start, old_cursor));
+            // The region has no pinned live objects, so all pinning bits are stale.
+            #[cfg(feature = "object_pinning")]
+            Self::clear_side_pinning_bits(start, old_cursor);
+            // Objects are moved lazily, but the VO bits must be valid as soon as mutators resume.
+            #[cfg(feature = "vo_bit")]
+            {
+                vo_bit::bzero_vo_bit(start, old_cursor - start);
+                self.forwarding.scan_marked_objects(
+                    start,
+                    old_cursor,
+                    &mut |obj: ObjectReference| {
+                        vo_bit::set_vo_bit(self.forward(obj, false));
+                    },
+                );
+            }
+            if new_cursor > start {
+                let state =
+                    RegionCompaction::new(r.region, old_cursor, new_cursor).unwrap_or_else(|e| {
+                        panic!("Failed to start concurrent compaction for region {start}: {e}")
+                    });
+                self.concurrent_compaction
+                    .as_ref()
+                    .unwrap()
+                    .add_region(state);
+                self.scheduler.work_buckets[WorkBucketStage::Concurrent]
+                    .add(CompactConcurrently::<VM>::new(self, start));
+            }
+            self.pr.reset_cursor(r, new_cursor);
+        });
+    }
+
+    /// Compact the region starting at `start` page by page, so that threads which fault on the
+    /// region wait for one page rather than the whole region before they resolve the fault.
+    pub fn compact_region_concurrently(&self, tls: VMWorkerThread, start: Address) {
+        let concurrent = self.concurrent_compaction.as_ref().unwrap();
+        loop {
+            let finished = concurrent.with_region(start, |state| {
+                if !state.is_finished() {
+                    let target = state.published() + BYTES_IN_PAGE;
+                    self.compact_until(tls, state, target);
+                }
+                state.is_finished()
+            });
+            // The region may have been finished and forgotten by `finish_concurrent_compaction`.
+            if finished.unwrap_or(true) {
+                break;
+            }
+        }
+    }
+
+    /// Finish compacting all regions which are still being compacted concurrently, and forget
+    /// the forwarding addresses. This must be done before the next GC starts marking.
+    pub fn finish_concurrent_compaction(&self, tls: VMWorkerThread) {
+        let Some(concurrent) = self.concurrent_compaction.as_ref() else {
+            return;
+        };
+        if !concurrent.is_in_progress() {
+            return;
+        }
+        for start in concurrent.region_starts() {
+            self.compact_region_concurrently(tls, start);
+        }
+        concurrent.clear();
+        self.forwarding.release();
+    }
+
+    /// Handle a protection fault at `addr`.  Return `true` if `addr` is in a page which is
+    /// protected for concurrent compaction.  This is async-signal-safe.
+    pub fn handle_protection_fault(&self, addr: Address) -> bool {
+        let Some(concurrent) = self.concurrent_compaction.as_ref() else {
+            return false;
+        };
+        self.address_in_space(addr) && concurrent.is_protected(addr)
+    }
+
+    /// Move the objects into the page containing `addr` on the current thread `tls`, and publish
+    /// the page, if no other thread has published it.  The pages before it in its region are
+    /// published, too, as the objects in a region are moved in order.
+    pub fn resolve_protection_fault(&self, tls: VMWorkerThread, addr: Address) {
+        let Some(concurrent) = self.concurrent_compaction.as_ref() else {
+            return;
+        };
+        concurrent.resolve_fault(addr, |state, target| self.compact_until(tls, state, target));
+    }
+
+    /// Move objects to the staging buffer until all pages below `target` are complete, and
+    /// publish the complete pages.
+    fn compact_until(&self, tls: VMWorkerThread, state: &mut RegionCompaction, target: Address) {
+        let target = target.min(state.protected_end());
+        while state.staged < target && state.scanned < state.old_cursor {
+            let start = state.scanned;
+            let end = (start + BYTES_IN_PAGE)
+                .align_down(BYTES_IN_PAGE)
+                .min(state.old_cursor);
+            let mut in_object = state.in_object;
+            self.forwarding.scan_marked_objects_resumable(
+                start,
+                end,
+                &mut in_object,
+                &mut |obj: ObjectReference| {
+                    state.staged = self.stage_object(tls, state, obj);
+                },
+            );
+            state.in_object = in_object;
+            state.scanned = end;
+        }
+        let publish_end = if state.scanned >= state.old_cursor {
+            debug_assert_eq!(state.staged, state.new_cursor);
+            state.protected_end()
+        } else {
+            state.staged.align_down(BYTES_IN_PAGE)
+        };
+        state.publish(publish_end);
+    }
+
+    /// Copy an object from the from-space to the staging buffer, and update its references.
+    /// Return the end of the object in the to-space.
+    fn stage_object(
+        &self,
+        tls: VMWorkerThread,
+        state: &RegionCompaction,
+        object: ObjectReference,
+    ) -> Address {
+        let new_object = self.forward(object, false);
+        let from =
+            ObjectReference::from_raw_address(state.address_in_from_space(object.to_raw_address()))
+                .unwrap();
+        let to = ObjectReference::from_raw_address(
+            state.address_in_staging(new_object.to_raw_address()),
+        )
+        .unwrap();
+        // See `compact_region` for why the size must not change.
+        let copied_size = VM::VMObjectModel::get_size_when_copied(from);
+        debug_assert!(copied_size == VM::VMObjectModel::get_current_size(from));
+        trace!(
+            " stage {} (at {}) to {} (at {})",
+            object,
+            from,
+            new_object,
+            to
+        );
+        let end_of_staged_object = VM::VMObjectModel::copy_to(from, to, Address::ZERO);
+        debug_assert_eq!(
+            end_of_staged_object,
+            to.to_object_start::<VM>() + copied_size
+        );
+        self.update_references(tls, to);
+        new_object.to_object_start::<VM>() + copied_size
+    }
+
     pub fn after_compact(&self, worker: &mut GCWorker<VM>, los: &LargeObjectSpace<VM>) {
         self.pr.reset_allocator();
+        // All regions to be compacted concurrently have been protected.
+        if let Some(concurrent) = self.concurrent_compaction.as_ref() {
+            concurrent.protect_regions();
+        }
         // Update references from the LOS to Compressor too.
         los.enumerate_to_space_objects(&mut object_enum::ClosureObjectEnumerator::<_, VM>::new(
             &mut |o: ObjectReference| {
-                self.update_references(worker, o);
+                self.update_references(worker.tls, o);
             },
         ));
     }
@@ -461,3 +699,53 @@ impl<VM: VMBinding> Compact<VM> {
         }
     }
 }
+
+/// Start compacting a region concurrently.
+pub struct StartConcurrentCompaction<VM: VMBinding> {
+    compressor_space: &'static CompressorSpace<VM>,
+    index: usize,
+}
+
+impl<VM: VMBinding> GCWork<VM> for StartConcurrentCompaction<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
+        if self.compressor_space.region_has_pinned_objects(self.index) {
+            self.compressor_space.compact_region(worker, self.index);
+        } else {
+            self.compressor_space
+                .start_concurrent_compaction(self.index);
+        }
+    }
+}
+
+impl<VM: VMBinding> StartConcurrentCompaction<VM> {
+    pub fn new(compressor_space: &'static CompressorSpace<VM>, index: usize) -> Self {
+        Self {
+            compressor_space,
+            index,
+        }
+    }
+}
+
+/// Compact a region concurrently with mutators.
+pub struct CompactConcurrently<VM: VMBinding> {
+    compressor_space: &'static CompressorSpace<VM>,
+    start: Address,
+}
+
+impl<VM: VMBinding> GCWork<VM> for CompactConcurrently<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
+        self.compressor_space
+            .compact_region_concurrently(worker.tls, self.start);
+        // Count the region as the work done if a mutator is assisting.
+        worker.consume_assist_budget(forwarding::CompressorRegion::BYTES);
+    }
+}
+
+impl<VM: VMBinding> CompactConcurrently<VM> {
+    pub fn new(compressor_space: &'static CompressorSpace<VM>, start: Address) -> Self {
+        Self {
+            compressor_space,
+            start,
+        }
+    }
+}
@@ -0,0 +1,450 @@
+//! Concurrent compaction for [`super::CompressorSpace`].
+//!
+//! After the forwarding addresses are computed and the roots are updated in a pause, the
+//! Compressor moves the pages of each region to a from-space that is only visible to the
+//! collector, and protects the pages of the region which will hold live objects (the to-space).
+//! Mutators only ever see to-space addresses. Objects are then moved page by page into a private
+//! staging buffer, and a page is published with `mremap` once all the objects that overlap it
+//! have been moved and had their references updated.  This is done by GC workers in the
+//! background, by mutators that assist concurrent work, and by threads that fault on the page.
+//!
+//! A thread that accesses a protected page gets a fault, and the binding calls
+//! [`crate::memory_manager::handle_protection_fault`] from its signal handler.  The handler only
+//! looks up the page in a table that is only replaced when mutators are stopped, because moving
+//! objects calls into the VM and takes locks, which is not allowed in a signal handler.  If the
+//! page is protected by us, the binding leaves the signal handler and calls
+//! [`crate::memory_manager::resolve_protection_fault`], and the faulting thread moves the objects
+//! into the page itself.  It only waits for another thread to finish the page that thread is
+//! working on in the same region, so a GC worker that faults makes progress even if it is the only
+//! GC worker.
+//!
+//! Unlike Kermany and Petrank, we do not map the same physical pages at two virtual addresses.
+//! Moving pages with `mremap` gives us the two virtual spaces, and publishing a page atomically
+//! replaces the protected page, so a thread either faults or sees the page after compaction.
+
+use super::forwarding::CompressorRegion;
+use crate::util::constants::BYTES_IN_PAGE;
+use crate::util::conversions::raw_align_down;
+use crate::util::linear_scan::Region;
+use crate::util::memory;
+use crate::util::Address;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Mutex, RwLock};
+
+/// The state of a region which is being compacted concurrently.
+pub(crate) struct RegionCompaction {
+    pub region: CompressorRegion,
+    /// The address of the from-space pages of the region.
+    from_space: Address,
+    /// The address of the private buffer where to-space pages are built.
+    staging: Address,
+    /// The end of the objects in the region before compaction.
+    pub old_cursor: Address,
+    /// The end of the live objects in the region after compaction.
+    pub new_cursor: Address,
+    /// The mark bits for the from-space below this address have been scanned.
+    pub scanned: Address,
+    /// Whether `scanned` is in the middle of an object, i.e. whether the last mark bit scanned
+    /// was the first bit of an object.
+    pub in_object: bool,
+    /// All objects below this (to-space) address have been moved to the staging buffer.
+    pub staged: Address,
+    /// All pages below this (to-space) address have been published.
+    published: Address,
+}
+
+impl RegionCompaction {
+    /// Start compacting a region concurrently. This moves the pages of the region to the
+    /// from-space, and protects the to-space pages of the region.  This must be done when
+    /// mutators are stopped.
+    pub fn new(
+        region: CompressorRegion,
+        old_cursor: Address,
+        new_cursor: Address,
+    ) -> std::io::Result<Self> {
+        let start = region.start();
+        let old_bytes = old_cursor - start;
+        // Reserve the from-space, and move the pages of the region there. The region keeps its
+        // mapping with demand-zero pages.
+        let from_space = memory::dzmmap_anywhere(old_bytes)?;
+        Self::move_pages(start, old_bytes, from_space)?;
+        let state = Self {
+            region,
+            from_space,
+            staging: memory::dzmmap_anywhere(old_bytes)?,
+            old_cursor,
+            new_cursor,
+            scanned: start,
+            in_object: false,
+            staged: start,
+            published: start,
+        };
+        memory::mprotect(start, state.protected_end() - start)?;
+        Ok(state)
+    }
+
+    /// Move the pages in `[start, start + bytes)` to `to`, leaving demand-zero pages behind. A
+    /// region may consist of several mappings if it has been compacted concurrently before, and
+    /// we cannot move pages across mappings in one go.  In that case, we split the range until
+    /// each part is in a single mapping.
+    fn move_pages(start: Address, bytes: usize, to: Address) -> std::io::Result<()> {
+        match memory::mremap_dontunmap_fixed(start, bytes, to) {
+            Err(e) if e.raw_os_error() == Some(libc::EFAULT) && bytes > BYTES_IN_PAGE => {
+                let half = raw_align_down(bytes / 2, BYTES_IN_PAGE);
+                Self::move_pages(start, half, to)?;
+                Self::move_pages(start + half, bytes - half, to + half)
+            }
+            result => result,
+        }
+    }
+
+    /// The end of the pages that hold live objects after compaction. Pages in the region below
+    /// this address are protected until they are published.
+    pub fn protected_end(&self) -> Address {
+        self.new_cursor.align_up(BYTES_IN_PAGE)
+    }
+
+    /// All pages below this (to-space) address have been published.
+    pub fn published(&self) -> Address {
+        self.published
+    }
+
+    /// Have all the pages been published?
+    pub fn is_finished(&self) -> bool {
+        self.published() >= self.protected_end()
+    }
+
+    /// Get the address where the collector can access the from-space copy of `addr`.
+    pub fn address_in_from_space(&self, addr: Address) -> Address {
+        debug_assert!(addr >= self.region.start() && addr < self.old_cursor);
+        self.from_space + (addr - self.region.start())
+    }
+
+    /// Get the address where the collector can build the to-space page of `addr`.
+    pub fn address_in_staging(&self, addr: Address) -> Address {
+        debug_assert!(addr >= self.region.start() && addr < self.protected_end());
+        self.staging + (addr - self.region.start())
+    }
+
+    /// Publish the staged pages below `end`.  When all the pages are published, the from-space
+    /// and the staging buffer are unmapped.
+    pub fn publish(&mut self, end: Address) {
+        debug_assert!(end.is_aligned_to(BYTES_IN_PAGE));
+        debug_assert!(end <= self.protected_end());
+        let published = self.published();
+        if end > published {
+            memory::mremap_fixed(
+                self.address_in_staging(published),
+                end - published,
+                published,
+            )
+            .unwrap();
+            self.published = end;
+        }
+        if self.is_finished() {
+            let bytes = self.old_cursor - self.region.start();
+            memory::munmap(self.from_space, bytes).unwrap();
+            // Published pages have been moved out of the staging buffer, and munmap ignores them.
+            memory::munmap(self.staging, bytes).unwrap();
+        }
+    }
+}
+
+/// The to-space of a region being compacted concurrently, as seen by the fault handler.
+struct ProtectedRegion {
+    start: Address,
+    /// The end of the protected pages.
+    end: Address,
+}
+
+/// The regions being compacted concurrently, indexed by the start address of the region.
+#[derive(Default)]
+pub(crate) struct ConcurrentCompaction {
+    regions: RwLock<HashMap<Address, Mutex<RegionCompaction>>>,
+    /// The regions being compacted, sorted by their start addresses, for the fault handler which
+    /// cannot take locks.  It is null if no region is being compacted.  It is only replaced when
+    /// mutators are stopped, so no thread is in the fault handler.
+    protected_regions: AtomicPtr<Vec<ProtectedRegion>>,
+}
+
+impl ConcurrentCompaction {
+    pub fn add_region(&self, state: RegionCompaction) {
+        let mut regions = self.regions.write().unwrap();
+        let old = regions.insert(state.region.start(), Mutex::new(state));
+        debug_assert!(old.is_none());
+    }
+
+    /// Make the regions added so far visible to the fault handler.  This must be done when
+    /// mutators are stopped, after all the regions are added.
+    pub fn protect_regions(&self) {
+        let mut protected_regions: Vec<ProtectedRegion> = self
+            .regions
+            .read()
+            .unwrap()
+            .values()
+            .map(|state| {
+                let state = state.lock().unwrap();
+                ProtectedRegion {
+                    start: state.region.start(),
+                    end: state.protected_end(),
+                }
+            })
+            .collect();
+        protected_regions.sort_unstable_by_key(|r| r.start);
+        self.replace_protected_regions(Box::into_raw(Box::new(protected_regions)));
+    }
+
+    fn replace_protected_regions(&self, new: *mut Vec<ProtectedRegion>) {
+        let old = self.protected_regions.swap(new, Ordering::AcqRel);
+        if !old.is_null() {
+            drop(unsafe { Box::from_raw(old) });
+        }
+    }
+
+    /// Call `f` with the state of the region containing `addr`, if the region is being
+    /// compacted.
+    pub fn with_region<T>(
+        &self,
+        addr: Address,
+        f: impl FnOnce(&mut RegionCompaction) -> T,
+    ) -> Option<T> {
+        let start = CompressorRegion::from_unaligned_address(addr).start();
+        let regions = self.regions.read().unwrap();
+        regions
+            .get(&start)
+            .map(|state| f(&mut state.lock().unwrap()))
+    }
+
+    /// Is `addr` in a page which is protected for concurrent compaction?  The page may have been
+    /// published since the access that faulted, in which case the access can simply be retried.
+    ///
+    /// This is called from the signal handler of the binding, so it must be async-signal-safe:
+    /// It does not take locks, allocate memory, or call into the VM.  It only reads the table of
+    /// protected regions.
+    pub fn is_protected(&self, addr: Address) -> bool {
+        let protected_regions = self.protected_regions.load(Ordering::Acquire);
+        if protected_regions.is_null() {
+            return false;
+        }
+        let protected_regions = unsafe { &*protected_regions };
+        let start = CompressorRegion::from_unaligned_address(addr).start();
+        let Ok(index) = protected_regions.binary_search_by_key(&start, |r| r.start) else {
+            return false;
+        };
+        addr < protected_regions[index].end
+    }
+
+    /// Make the page containing `addr` accessible on the current thread, after the thread faulted
+    /// on it.  If the page is not published yet, `compact_until` is called with the state of its
+    /// region and the end of the page, and it must publish the pages below the end.  The region is
+    /// locked while `compact_until` runs, so the page is published by whichever thread gets the
+    /// lock first.
+    pub fn resolve_fault(
+        &self,
+        addr: Address,
+        compact_until: impl FnOnce(&mut RegionCompaction, Address),
+    ) {
+        let target = addr.align_down(BYTES_IN_PAGE) + BYTES_IN_PAGE;
+        self.with_region(addr, |state| {
+            if addr >= state.published() && addr < state.protected_end() {
+                compact_until(state, target);
+                debug_assert!(addr < state.published());
+            }
+        });
+    }
+
+    /// Is any region being compacted?
+    pub fn is_in_progress(&self) -> bool {
+        !self.regions.read().unwrap().is_empty()
+    }
+
+    /// Get the start addresses of all regions being compacted.
+    pub fn region_starts(&self) -> Vec<Address> {
+        self.regions.read().unwrap().keys().copied().collect()
+    }
+
+    /// Forget all regions.  All of them must have finished compaction.  This must be done when
+    /// mutators are stopped.
+    pub fn clear(&self) {
+        let mut regions = self.regions.write().unwrap();
+        debug_assert!(regions
+            .values()
+            .all(|state| state.lock().unwrap().is_finished()));
+        regions.clear();
+        self.replace_protected_regions(std::ptr::null_mut());
+    }
+}
+
+impl Drop for ConcurrentCompaction {
+    fn drop(&mut self) {
+        self.replace_protected_regions(std::ptr::null_mut());
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::util::test_util::serial_test;
+    use std::sync::atomic::AtomicUsize;
+
+    /// The compaction that the signal handler of the test handles faults for.
+    static COMPACTION: AtomicPtr<ConcurrentCompaction> = AtomicPtr::new(std::ptr::null_mut());
+    /// The number of faults caught by the signal handler.
+    static FAULTS: AtomicUsize = AtomicUsize::new(0);
+
+    /// A signal handler which calls `is_protected`, like the handler of a binding.  A binding
+    /// would then resolve the fault outside the handler.  This handler simply lets the access be
+    /// retried, so a reader keeps faulting until its page is published.
+    extern "C" fn handle_sigsegv(
+        _signal: libc::c_int,
+        info: *mut libc::siginfo_t,
+        _context: *mut libc::c_void,
+    ) {
+        let addr = Address::from_mut_ptr(unsafe { (*info).si_addr() });
+        FAULTS.fetch_add(1, Ordering::SeqCst);
+        let compaction = COMPACTION.load(Ordering::SeqCst);
+        if compaction.is_null() || !unsafe { &*compaction }.is_protected(addr) {
+            // Not our fault.  Crash when the access is retried.
+            unsafe { libc::signal(libc::SIGSEGV, libc::SIG_DFL) };
+        }
+    }
+
+    fn set_sigsegv_handler(action: &libc::sigaction) -> libc::sigaction {
+        let mut old: libc::sigaction = unsafe { std::mem::zeroed() };
+        assert_eq!(
+            unsafe { libc::sigaction(libc::SIGSEGV, action, &mut old) },
+            0
+        );
+        old
+    }
+
+    const OLD_PAGES: usize = 8;
+    const NEW_PAGES: usize = 4;
+
+    /// The word that the test writes at the start of each from-space page.
+    fn page_value(page: usize) -> usize {
+        0x1000 + page
+    }
+
+    /// Map a region, and start compacting it.  The to-space page `i` gets the content of the
+    /// from-space page `2 * i`, as if only the objects in the even pages were live.  Return the
+    /// mapping and the start of the region.
+    fn start_compaction(compaction: &ConcurrentCompaction) -> (Address, Address) {
+        let mapping = memory::dzmmap_anywhere(CompressorRegion::BYTES * 2).unwrap();
+        let start = mapping.align_up(CompressorRegion::BYTES);
+        for page in 0..OLD_PAGES {
+            unsafe { (start + page * BYTES_IN_PAGE).store(page_value(page)) };
+        }
+        let region = CompressorRegion::from_aligned_address(start);
+        let state = RegionCompaction::new(
+            region,
+            start + OLD_PAGES * BYTES_IN_PAGE,
+            start + NEW_PAGES * BYTES_IN_PAGE,
+        )
+        .unwrap();
+        compaction.add_region(state);
+        compaction.protect_regions();
+        (mapping, start)
+    }
+
+    /// Move the pages of the region below `target` one by one, and publish them, like
+    /// `CompressorSpace::compact_until`.
+    fn compact_until(state: &mut RegionCompaction, target: Address) {
+        let start = state.region.start();
+        while state.published() < target {
+            let to = state.published();
+            let page = (to - start) / BYTES_IN_PAGE;
+            let from = state.address_in_from_space(start + 2 * page * BYTES_IN_PAGE);
+            unsafe { state.address_in_staging(to).store(from.load::<usize>()) };
+            state.publish(to + BYTES_IN_PAGE);
+        }
+    }
+
+    fn finish_compaction(compaction: &ConcurrentCompaction, mapping: Address, start: Address) {
+        assert!(compaction
+            .with_region(start, |state| state.is_finished())
+            .unwrap());
+        compaction.clear();
+        assert!(!compaction.is_protected(start));
+        memory::munmap(mapping, CompressorRegion::BYTES * 2).unwrap();
+    }
+
+    /// Compact a region concurrently with threads that read the protected pages.  Each reader
+    /// faults until the page it reads is published, and then sees the content of the page after
+    /// compaction.
+    #[test]
+    fn compact_with_faulting_readers() {
+        serial_test(|| {
+            let compaction: &'static ConcurrentCompaction =
+                Box::leak(Box::new(ConcurrentCompaction::default()));
+            let (mapping, start) = start_compaction(compaction);
+
+            // Pages outside the protected to-space are not handled.
+            assert!(compaction.is_protected(start + (NEW_PAGES - 1) * BYTES_IN_PAGE));
+            assert!(!compaction.is_protected(start + NEW_PAGES * BYTES_IN_PAGE));
+            assert!(!compaction.is_protected(start + CompressorRegion::BYTES));
+
+            let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+            action.sa_sigaction = handle_sigsegv as usize;
+            action.sa_flags = libc::SA_SIGINFO;
+            COMPACTION.store(compaction as *const _ as *mut _, Ordering::SeqCst);
+            FAULTS.store(0, Ordering::SeqCst);
+            let old_action = set_sigsegv_handler(&action);
+
+            let readers: Vec<_> = (0..NEW_PAGES)
+                .map(|page| {
+                    let addr = start + page * BYTES_IN_PAGE;
+                    std::thread::spawn(move || unsafe { addr.load::<usize>() })
+                })
+                .collect();
+            // Wait until the readers have faulted.
+            while FAULTS.load(Ordering::SeqCst) < NEW_PAGES {
+                std::thread::yield_now();
+            }
+
+            // Resolve the faults, as the binding of each reader would do outside its handler.
+            for page in 0..NEW_PAGES {
+                compaction.resolve_fault(start + page * BYTES_IN_PAGE, compact_until);
+            }
+            let values: Vec<usize> = readers.into_iter().map(|r| r.join().unwrap()).collect();
+            assert_eq!(
+                values,
+                (0..NEW_PAGES)
+                    .map(|page| page_value(2 * page))
+                    .collect::<Vec<_>>()
+            );
+
+            set_sigsegv_handler(&old_action);
+            COMPACTION.store(std::ptr::null_mut(), Ordering::SeqCst);
+            finish_compaction(compaction, mapping, start);
+        })
+    }
+
+    /// A thread that faults publishes the page itself, even if no other thread compacts the
+    /// region, e.g. if it is the only GC worker.  It does not move the pages after the page.
+    #[test]
+    fn resolve_fault_on_faulting_thread() {
+        serial_test(|| {
+            let compaction = ConcurrentCompaction::default();
+            let (mapping, start) = start_compaction(&compaction);
+
+            let addr = start + BYTES_IN_PAGE + 8usize;
+            assert!(compaction.is_protected(addr));
+            compaction.resolve_fault(addr, compact_until);
+            assert_eq!(
+                unsafe { (start + BYTES_IN_PAGE).load::<usize>() },
+                page_value(2)
+            );
+            assert_eq!(
+                compaction.with_region(start, |state| state.published()),
+                Some(start + 2 * BYTES_IN_PAGE)
+            );
+            // Resolving a published page does nothing.
+            compaction.resolve_fault(start, |_, _| unreachable!());
+
+            compaction.resolve_fault(start + (NEW_PAGES - 1) * BYTES_IN_PAGE, compact_until);
+            finish_compaction(&compaction, mapping, start);
+        })
+    }
+}
@@ -1,7 +1,9 @@
 use crate::policy::compressor::GC_MARK_BIT_MASK;
 use crate::util::constants::BYTES_IN_WORD;
 use crate::util::linear_scan::{Region, RegionIterator};
-use crate::util::metadata::side_metadata::spec_defs::{COMPRESSOR_MARK, COMPRESSOR_OFFSET_VECTOR};
+use crate::util::metadata::side_metadata::spec_defs::{
+    COMPRESSOR_MARK, COMPRESSOR_OFFSET_VECTOR, COMPRESSOR_PINNED,
+};
 use crate::util::metadata::side_metadata::SideMetadataSpec;
 use crate::util::{Address, ObjectReference};
 use crate::vm::object_model::ObjectModel;
@@ -37,6 +39,10 @@ impl Region for CompressorRegion {
 /// each block by serialising the state using [`Transducer::encode`], and
 /// then deserialises the state whilst computing forwarding pointers
 /// using [`Transducer::decode`].
+///
+/// Pinned objects are not moved, so the transducer skips to the start of a
+/// pinned object when it visits the first bit of the object, and the objects
+/// after it are moved towards the end of the pinned object instead.
 #[derive(Debug)]
 struct Transducer {
     /// The address for the next object to be copied to, following preceding
@@ -56,7 +62,13 @@ impl Transducer {
             in_object: false,
         }
     }
-    pub fn visit_mark_bit(&mut self, address: Address) {
+    /// Visit a mark bit. `pinned` is whether the bit is the first bit of a
+    /// pinned object, and is ignored for last bits.
+    pub fn visit_mark_bit(&mut self, address: Address, pinned: bool) {
+        if !self.in_object && pinned {
+            debug_assert!(self.to <= address);
+            self.to = address;
+        }
         if self.in_object {
             // The size of an object is the distance between the end and
             // start of the object, and the last word of the object is one
@@ -115,6 +127,10 @@ impl Region for Block {
 
 pub(crate) const MARK_SPEC: SideMetadataSpec = COMPRESSOR_MARK;
 pub(crate) const OFFSET_VECTOR_SPEC: SideMetadataSpec = COMPRESSOR_OFFSET_VECTOR;
+/// Records the live objects which were pinned when they were marked.  We record
+/// the pinned objects when marking, as mutators may pin and unpin objects
+/// whilst objects are being forwarded during concurrent compaction.
+pub(crate) const PINNED_SPEC: SideMetadataSpec = COMPRESSOR_PINNED;
 
 impl<VM: VMBinding> ForwardingMetadata<VM> {
     pub fn new() -> ForwardingMetadata<VM> {
@@ -147,6 +163,33 @@ impl<VM: VMBinding> ForwardingMetadata<VM> {
         MARK_SPEC.fetch_or_atomic(last_word_of_object, GC_MARK_BIT_MASK, Ordering::SeqCst);
     }
 
+    /// Record that a live object is pinned, so that it is forwarded to itself.
+    #[cfg(feature = "object_pinning")]
+    pub fn mark_pinned(&self, object: ObjectReference) {
+        PINNED_SPEC.store_atomic::<u8>(object.to_raw_address(), 1, Ordering::SeqCst);
+    }
+
+    /// Is the address the start of a live object which is pinned?
+    #[cfg(feature = "object_pinning")]
+    fn is_pinned(&self, address: Address) -> bool {
+        PINNED_SPEC.load_atomic::<u8>(address, Ordering::Relaxed) == 1
+    }
+
+    #[cfg(not(feature = "object_pinning"))]
+    fn is_pinned(&self, _address: Address) -> bool {
+        false
+    }
+
+    /// Are there any pinned live objects between `start` and `end`?
+    pub fn has_pinned_objects(&self, start: Address, end: Address) -> bool {
+        if !cfg!(feature = "object_pinning") {
+            return false;
+        }
+        let mut found = false;
+        PINNED_SPEC.scan_non_zero_values::<u8>(start, end, &mut |_| found = true);
+        found
+    }
+
     pub fn calculate_offset_vector(&self, region: CompressorRegion, cursor: Address) {
         let mut state = Transducer::new(region.start());
         let first_block = Block::from_aligned_address(region.start());
@@ -161,7 +204,7 @@ impl<VM: VMBinding> ForwardingMetadata<VM> {
                 block.start(),
                 block.end(),
                 &mut |addr: Address| {
-                    state.visit_mark_bit(addr);
+                    state.visit_mark_bit(addr, self.is_pinned(addr));
                 },
             );
         }
@@ -173,11 +216,31 @@ impl<VM: VMBinding> ForwardingMetadata<VM> {
     }
 
     pub fn forward(&self, address: Address) -> Address {
+        if self.is_pinned(address) {
+            return address;
+        }
+        self.transduce(Block::from_unaligned_address(address), address)
+    }
+
+    /// Compute the end of the live data of a region after compaction, given the allocation
+    /// cursor of the region.
+    pub fn forward_end_of_region(&self, region: CompressorRegion, cursor: Address) -> Address {
+        if cursor == region.start() {
+            return cursor;
+        }
+        // The cursor may be the end of the region, so we start from the block of the last word
+        // before the cursor.
+        self.transduce(
+            Block::from_unaligned_address(cursor - BYTES_IN_WORD),
+            cursor,
+        )
+    }
+
+    fn transduce(&self, block: Block, address: Address) -> Address {
         debug_assert!(
             self.calculated.load(Ordering::Relaxed),
             "forward() should only be called when we have calculated an offset vector"
         );
-        let block = Block::from_unaligned_address(address);
         let mut state = Transducer::decode(
             OFFSET_VECTOR_SPEC.load_atomic::<usize>(block.start(), Ordering::Relaxed),
             block.start(),
@@ -186,7 +249,7 @@ impl<VM: VMBinding> ForwardingMetadata<VM> {
         // address of an object; whereas Total-Live-Data in the paper computes
         // the distance of the object from the start of the block.
         MARK_SPEC.scan_non_zero_values::<u8>(block.start(), address, &mut |addr: Address| {
-            state.visit_mark_bit(addr)
+            state.visit_mark_bit(addr, self.is_pinned(addr))
         });
         state.to
     }
@@ -198,12 +261,25 @@ impl<VM: VMBinding> ForwardingMetadata<VM> {
         f: &mut impl FnMut(ObjectReference),
     ) {
         let mut in_object = false;
+        self.scan_marked_objects_resumable(start, end, &mut in_object, f);
+    }
+
+    /// Like [`Self::scan_marked_objects`], but the scan can be resumed from `end` later.
+    /// `in_object` records whether the scan has seen the first bit of an object but not its last
+    /// bit yet, and should be `false` when scanning from the start of a region.
+    pub fn scan_marked_objects_resumable(
+        &self,
+        start: Address,
+        end: Address,
+        in_object: &mut bool,
+        f: &mut impl FnMut(ObjectReference),
+    ) {
         MARK_SPEC.scan_non_zero_values::<u8>(start, end, &mut |addr: Address| {
-            if !in_object {
+            if !*in_object {
                 let object = ObjectReference::from_raw_address(addr).unwrap();
                 f(object);
             }
-            in_object = !in_object;
+            *in_object = !*in_object;
         });
     }
 
@@ -211,3 +287,56 @@ impl<VM: VMBinding> ForwardingMetadata<VM> {
         self.calculated.load(Ordering::Relaxed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(offset: usize) -> Address {
+        unsafe { Address::from_usize(0x1000_0000 + offset) }
+    }
+
+    /// Visit the first and last bits of the objects of the given offsets and sizes, and return
+    /// the forwarding addresses of the objects.
+    fn forward_objects(objects: &[(usize, usize, bool)]) -> Vec<Address> {
+        let mut state = Transducer::new(addr(0));
+        let mut forwarded = vec![];
+        for (offset, size, pinned) in objects.iter().copied() {
+            // The forwarding address of an object is the state before its first bit, unless it
+            // is pinned.
+            forwarded.push(if pinned { addr(offset) } else { state.to });
+            state.visit_mark_bit(addr(offset), pinned);
+            state.visit_mark_bit(addr(offset + size - BYTES_IN_WORD), pinned);
+        }
+        forwarded.push(state.to);
+        forwarded
+    }
+
+    #[test]
+    fn forward_without_pinning() {
+        let forwarded = forward_objects(&[(0x10, 0x20, false), (0x80, 0x10, false)]);
+        assert_eq!(forwarded, vec![addr(0), addr(0x20), addr(0x30)]);
+    }
+
+    #[test]
+    fn forward_around_pinned_object() {
+        let forwarded = forward_objects(&[
+            (0x10, 0x20, false),
+            (0x80, 0x10, true),
+            (0x100, 0x10, false),
+        ]);
+        assert_eq!(forwarded, vec![addr(0), addr(0x80), addr(0x90), addr(0xa0)]);
+    }
+
+    #[test]
+    fn encode_inside_pinned_object() {
+        let mut state = Transducer::new(addr(0));
+        state.visit_mark_bit(addr(0x10), false);
+        state.visit_mark_bit(addr(0x28), false);
+        state.visit_mark_bit(addr(0x80), true);
+        // Resume from the middle of the pinned object.
+        let mut state = Transducer::decode(state.encode(addr(0x90)), addr(0x90));
+        state.visit_mark_bit(addr(0xa8), false);
+        assert_eq!(state.to, addr(0xb0));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod compressorspace;
+pub(crate) mod concurrent;
 pub mod forwarding;
 
 pub use compressorspace::*;
@@ -1,10 +1,12 @@
 use super::defrag::Histogram;
 use super::line::Line;
 use super::ImmixSpace;
+use crate::policy::space::Space;
 use crate::util::constants::*;
 use crate::util::heap::blockpageresource::BlockPool;
 use crate::util::heap::chunk_map::Chunk;
 use crate::util::linear_scan::{Region, RegionIterator};
+use crate::util::metadata::log_bit::UnlogBitsOperation;
 use crate::util::metadata::side_metadata::{MetadataByteArrayRef, SideMetadataSpec};
 #[cfg(feature = "vo_bit")]
 use crate::util::metadata::vo_bit;
@@ -177,6 +179,11 @@ impl Block {
 
     /// Deinitalize a block before releasing.
     pub fn deinit(&self) {
+        // The line mark states of the block would be stale when it is allocated again, and they
+        // would look marked when the line mark state wraps around to them.
+        if !super::BLOCK_ONLY {
+            Line::MARK_TABLE.bzero_metadata(self.start(), Self::BYTES);
+        }
         self.set_state(BlockState::Unallocated);
     }
 
@@ -255,6 +262,13 @@ impl Block {
                     #[cfg(feature = "immix_zero_on_release")]
                     crate::util::memory::zero(line.start(), Line::BYTES);
 
+                    // Objects reclaimed by tracing in a reference counting plan may still have
+                    // their counts and unlog bits set. Clear them as this line can be reused.
+                    if space.common().needs_ref_count {
+                        crate::util::metadata::ref_count::bzero(line.start(), Line::BYTES);
+                        UnlogBitsOperation::BulkClear.execute::<VM>(line.start(), Line::BYTES);
+                    }
+
                     // We need to clear the pin bit if it is on the side, as this line can be reused
                     #[cfg(feature = "object_pinning")]
                     if let MetadataSpec::OnSide(side) = *VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC {
@@ -289,8 +303,11 @@ impl Block {
                 // Record number of holes in block side metadata.
                 self.set_holes(holes);
 
+                // A reference counted space has rebuilt the VO bits from the counts.
                 #[cfg(feature = "vo_bit")]
-                vo_bit::helper::on_region_swept::<VM, _>(self, true);
+                if !space.common().needs_ref_count {
+                    vo_bit::helper::on_region_swept::<VM, _>(self, true);
+                }
 
                 false
             }
@@ -11,10 +11,12 @@ use crate::util::alloc::allocator::AllocationOptions;
 use crate::util::alloc::allocator::AllocatorContext;
 use crate::util::constants::LOG_BYTES_IN_PAGE;
 use crate::util::heap::chunk_map::*;
+use crate::util::heap::oom_report::SpaceFragmentation;
 use crate::util::heap::BlockPageResource;
 use crate::util::heap::PageResource;
 use crate::util::linear_scan::{Region, RegionIterator};
 use crate::util::metadata::log_bit::UnlogBitsOperation;
+use crate::util::metadata::ref_count;
 use crate::util::metadata::side_metadata::SideMetadataSpec;
 #[cfg(feature = "vo_bit")]
 use crate::util::metadata::vo_bit;
@@ -31,7 +33,7 @@ use crate::{
     MMTK,
 };
 use atomic::Ordering;
-use std::sync::{atomic::AtomicU8, atomic::AtomicUsize, Arc};
+use std::sync::{atomic::AtomicBool, atomic::AtomicU8, atomic::AtomicUsize, Arc};
 
 pub(crate) const TRACE_KIND_FAST: TraceKind = 0;
 pub(crate) const TRACE_KIND_DEFRAG: TraceKind = 1;
@@ -51,12 +53,17 @@ pub struct ImmixSpace<VM: VMBinding> {
     pub(super) defrag: Defrag,
     /// How many lines have been consumed since last GC?
     lines_consumed: AtomicUsize,
-    /// Object mark state
+    /// Object mark state.  If the mark bits are on the side, this is always
+    /// [`ImmixSpace::MARKED_STATE`].  If they are in the header, this cycles through the non-zero
+    /// values of the mark bits.  See [`ImmixSpace::next_mark_state`].
     mark_state: u8,
     /// Work packet scheduler
     scheduler: Arc<GCWorkScheduler<VM>>,
     /// Some settings for this space
     space_args: ImmixSpaceArgs,
+    /// Whether the marks of a backup trace decide which objects are live in this GC.
+    /// This is only used if the space is reference counted.
+    cycle_collection: AtomicBool,
 }
 
 /// Some arguments for Immix Space.
@@ -69,6 +76,11 @@ pub struct ImmixSpaceArgs {
     pub mixed_age: bool,
     /// Disable copying for this Immix space.
     pub never_move_objects: bool,
+    /// Whether objects in this space are reference counted.  The lines of a reference counted
+    /// space are marked in every GC according to the reference counts of the objects, rather
+    /// than during tracing, and defragmentation is disabled.  Young objects may still be moved
+    /// when they are promoted.  See [`crate::util::metadata::ref_count`].
+    pub reference_counted: bool,
 }
 
 unsafe impl<VM: VMBinding> Sync for ImmixSpace<VM> {}
@@ -92,8 +104,14 @@ impl<VM: VMBinding> SFT for ImmixSpace<VM> {
     }
 
     fn is_live(&self, object: ObjectReference) -> bool {
-        // If the mark bit is set, it is live.
-        if self.is_marked(object) {
+        if self.space_args.reference_counted && !self.in_cycle_collection() {
+            // An object is live if it has a reference count. Young objects get one when they
+            // are promoted.
+            if ref_count::count(object) > 0 {
+                return true;
+            }
+        } else if self.is_marked(object) {
+            // If the mark bit is set, it is live.
             return true;
         }
 
@@ -126,6 +144,22 @@ impl<VM: VMBinding> SFT for ImmixSpace<VM> {
         true
     }
     fn initialize_object_metadata(&self, _object: ObjectReference) {
+        // In-header mark bits are not cleared in GCs, so we clear them for new objects, unless the
+        // objects need to be considered live during concurrent marking.  Side mark bits are
+        // cleared in bulk, and they are set in bulk for lines allocated during concurrent marking.
+        if VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.is_in_header() {
+            let state = if self.should_allocate_as_live() {
+                self.mark_state
+            } else {
+                Self::UNMARKED_STATE
+            };
+            VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.store_atomic::<VM, u8>(
+                _object,
+                state,
+                None,
+                Ordering::SeqCst,
+            );
+        }
         #[cfg(feature = "vo_bit")]
         crate::util::metadata::vo_bit::set_vo_bit(_object);
     }
@@ -197,6 +231,31 @@ impl<VM: VMBinding> Space<VM> for ImmixSpace<VM> {
         object_enum::enumerate_blocks_from_chunk_map::<Block>(enumerator, &self.chunk_map);
     }
 
+    fn fragmentation(&self) -> Option<SpaceFragmentation> {
+        let mut blocks = 0;
+        let mut reusable_blocks = 0;
+        let mut free_lines = 0;
+        for chunk in self.chunk_map.all_chunks() {
+            for block in chunk.iter_region::<Block>() {
+                match block.get_state() {
+                    BlockState::Unallocated => {}
+                    BlockState::Reusable { unavailable_lines } => {
+                        blocks += 1;
+                        reusable_blocks += 1;
+                        free_lines += Block::LINES - unavailable_lines as usize;
+                    }
+                    BlockState::Unmarked | BlockState::Marked => blocks += 1,
+                }
+            }
+        }
+        Some(SpaceFragmentation::Immix {
+            blocks,
+            reusable_blocks,
+            free_lines,
+            line_bytes: Line::BYTES,
+        })
+    }
+
     fn clear_side_log_bits(&self) {
         // Remove the following warning if we have a legitimate use case.
         warn!("ImmixSpace::clear_side_log_bits is single-treaded.  Consider clearing side metadata in per-chunk work packets.");
@@ -280,10 +339,45 @@ impl<VM: VMBinding> crate::policy::gc_work::PolicyTraceObject<VM> for ImmixSpace
 }
 
 impl<VM: VMBinding> ImmixSpace<VM> {
-    #[allow(unused)]
     const UNMARKED_STATE: u8 = 0;
     const MARKED_STATE: u8 = 1;
 
+    /// The object mark state for the next full-heap GC if the mark bits are in the header.
+    ///
+    /// In-header mark bits cannot be cleared in bulk.  Instead, each full-heap GC marks objects
+    /// with a different non-zero value, so that the objects marked in the last full-heap GC are
+    /// considered unmarked.  New objects have the value [`ImmixSpace::UNMARKED_STATE`], which is
+    /// never a mark state.  The mark state wraps around to [`ImmixSpace::MARKED_STATE`] after all
+    /// the values of the mark bits are used.  Every object that is reachable in a full-heap GC
+    /// was either allocated after the last full-heap GC, or marked by it, so it does not have a
+    /// mark state that is used again.
+    ///
+    /// A dead object may stay in a live line with the mark state of the GC that last marked it,
+    /// and it would look marked when that mark state is used again.  So the mark bits of dead
+    /// objects are reset before their mark states are reused: With VO bits, the mark bits of
+    /// all objects are reset before they are traced, when their VO bits are cleared.  In a
+    /// reference counted space, the mark bits of garbage are reset when its counts are reset.
+    /// Otherwise, dead objects cannot be found, but they are never reached or counted again.
+    ///
+    /// At least two mark bits are needed, otherwise the objects marked in the last full-heap GC
+    /// would look marked, too.
+    fn next_mark_state(&self) -> u8 {
+        let MetadataSpec::InHeader(spec) = *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC else {
+            unreachable!("Mark bits on the side are cleared in bulk");
+        };
+        assert!(
+            spec.num_of_bits >= 2,
+            "ImmixSpace needs at least two in-header mark bits to cycle the mark state. \
+Declare the mark bits with `VMLocalMarkBitSpec::in_header_with_bits()`, or put them on the side."
+        );
+        let max_mark_state = ((1usize << spec.num_of_bits) - 1) as u8;
+        if self.mark_state >= max_mark_state {
+            Self::MARKED_STATE
+        } else {
+            self.mark_state + 1
+        }
+    }
+
     /// Get side metadata specs
     fn side_metadata_specs() -> Vec<SideMetadataSpec> {
         metadata::extract_side_metadata(&if super::BLOCK_ONLY {
@@ -338,6 +432,18 @@ impl<VM: VMBinding> ImmixSpace<VM> {
                 "Block-only immix must not move objects"
             );
         }
+        if space_args.reference_counted {
+            assert!(
+                args.constraints.needs_ref_count,
+                "Invalid args when the plan does not use reference counts"
+            );
+        }
+        if super::BLOCK_ONLY {
+            assert!(
+                !space_args.reference_counted,
+                "Reference counted immix needs line marks"
+            );
+        }
         assert!(
             Block::LINES / 2 <= u8::MAX as usize - 2,
             "Number of lines in a block should not exceed BlockState::MARK_MARKED"
@@ -377,6 +483,7 @@ impl<VM: VMBinding> ImmixSpace<VM> {
             mark_state: Self::MARKED_STATE,
             scheduler: scheduler.clone(),
             space_args,
+            cycle_collection: AtomicBool::new(false),
         }
     }
 
@@ -436,7 +543,7 @@ impl<VM: VMBinding> ImmixSpace<VM> {
                 self.mark_state = Self::MARKED_STATE;
             } else {
                 // For header metadata, we use cyclic mark bits.
-                unimplemented!("cyclic mark bits is not supported at the moment");
+                self.mark_state = self.next_mark_state();
             }
 
             // Prepare defrag info
@@ -461,18 +568,23 @@ impl<VM: VMBinding> ImmixSpace<VM> {
                 })
             });
             self.scheduler().work_buckets[WorkBucketStage::Prepare].bulk_add(work_packets);
+        }
 
-            if !super::BLOCK_ONLY {
-                self.line_mark_state.fetch_add(1, Ordering::AcqRel);
-                if self.line_mark_state.load(Ordering::Acquire) > Line::MAX_MARK_STATE {
-                    self.line_mark_state
-                        .store(Line::RESET_MARK_STATE, Ordering::Release);
-                }
+        // The lines of a reference counted space are marked in every GC.
+        if (major_gc || self.space_args.reference_counted) && !super::BLOCK_ONLY {
+            self.line_mark_state.fetch_add(1, Ordering::AcqRel);
+            if self.line_mark_state.load(Ordering::Acquire) > Line::MAX_MARK_STATE {
+                self.line_mark_state
+                    .store(Line::RESET_MARK_STATE, Ordering::Release);
             }
         }
 
+        // A reference counted space rebuilds its VO bits from the counts when it is swept.  See
+        // `mark_lines_for_counted_objects`.
         #[cfg(feature = "vo_bit")]
-        if vo_bit::helper::need_to_clear_vo_bits_before_tracing::<VM>() {
+        if vo_bit::helper::need_to_clear_vo_bits_before_tracing::<VM>()
+            && !self.space_args.reference_counted
+        {
             let maybe_scope = if major_gc {
                 // If it is major GC, we always clear all VO bits because we are doing full-heap
                 // tracing.
@@ -510,7 +622,7 @@ impl<VM: VMBinding> ImmixSpace<VM> {
 
     /// Release for the immix space.
     pub(crate) fn release(&mut self, major_gc: bool, unlog_bits_op: UnlogBitsOperation) {
-        if major_gc {
+        if major_gc || self.space_args.reference_counted {
             // Update line_unavail_state for hole searching after this GC.
             if !super::BLOCK_ONLY {
                 self.line_unavail_state.store(
@@ -912,7 +1024,113 @@ impl<VM: VMBinding> ImmixSpace<VM> {
     }
 
     pub(crate) fn is_defrag_enabled(&self) -> bool {
-        !self.space_args.never_move_objects
+        !self.space_args.never_move_objects && !self.space_args.reference_counted
+    }
+
+    /// Set whether the marks of a finished backup trace decide which objects are live in the
+    /// current GC.  If so, reference counted objects that are not marked are dead (e.g. they are
+    /// part of a garbage cycle), and their counts are reset when the space is released.
+    pub(crate) fn set_cycle_collection(&self, cycle_collection: bool) {
+        debug_assert!(self.space_args.reference_counted);
+        self.cycle_collection
+            .store(cycle_collection, Ordering::SeqCst);
+    }
+
+    fn in_cycle_collection(&self) -> bool {
+        self.cycle_collection.load(Ordering::SeqCst)
+    }
+
+    /// Increment the reference count of an object in a reference counted space.  If the count was
+    /// zero, the object is young, and it is promoted: It is evacuated if `may_copy` is true and it
+    /// is not pinned, its unlog bit is set, and it is marked if this GC collects cycles.
+    ///
+    /// Return the new location of the object, and whether this call promoted the object.  The
+    /// caller must scan a promoted object and increment the counts of its children.
+    pub(crate) fn increment_and_promote(
+        &self,
+        object: ObjectReference,
+        may_copy: bool,
+        worker: &mut GCWorker<VM>,
+    ) -> (ObjectReference, bool) {
+        debug_assert!(self.space_args.reference_counted);
+        // The old copy of an evacuated object never has a count. So if an object has a count, it
+        // is either mature or promoted in place, and it will not move.
+        if !may_copy || ref_count::count(object) > 0 {
+            debug_assert!(
+                !object_forwarding::is_forwarded::<VM>(object),
+                "{object} is reached after it is evacuated"
+            );
+            return (object, self.increment(object));
+        }
+
+        let forwarding_status = object_forwarding::attempt_to_forward::<VM>(object);
+        if object_forwarding::state_is_forwarded_or_being_forwarded(forwarding_status) {
+            let new_object =
+                object_forwarding::spin_and_get_forwarded_object::<VM>(object, forwarding_status);
+            (new_object, self.increment(new_object))
+        } else if ref_count::count(object) > 0 || self.is_pinned(object) {
+            // Increment the count before clearing the forwarding bits so that other threads
+            // that have seen the object being forwarded will find it promoted.
+            let promoted = self.increment(object);
+            object_forwarding::clear_forwarding_bits::<VM>(object);
+            (object, promoted)
+        } else {
+            let new_object = object_forwarding::forward_object::<VM>(
+                object,
+                CopySemantics::DefaultCopy,
+                worker.get_copy_context_mut(),
+                |_| {},
+            );
+            // The VO bit of the old copy is cleared when the space is swept.
+            #[cfg(feature = "vo_bit")]
+            vo_bit::set_vo_bit(new_object);
+            (new_object, self.increment(new_object))
+        }
+    }
+
+    /// Increment the count of an object that does not move. Return true if it is promoted.
+    fn increment(&self, object: ObjectReference) -> bool {
+        if ref_count::inc(object) != 0 {
+            return false;
+        }
+        if self.in_cycle_collection() {
+            self.attempt_mark(object, self.mark_state);
+        }
+        VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.mark_as_unlogged::<VM>(object, Ordering::SeqCst);
+        true
+    }
+
+    /// Mark the lines of the objects in a block that have reference counts.  In a cycle
+    /// collection, the counts and the mark bits of the objects that are not marked are reset
+    /// instead, as they are garbage.
+    ///
+    /// The objects that have counts are the live objects of the block, so their VO bits are the
+    /// only ones kept.  This also clears the VO bits of the objects whose counts dropped to zero
+    /// since the last GC, and of the young objects that were not promoted in this GC.
+    fn mark_lines_for_counted_objects(&self, block: Block, line_mark_state: u8) {
+        let cycle_collection = self.in_cycle_collection();
+        #[cfg(feature = "vo_bit")]
+        vo_bit::bzero_vo_bit(block.start(), Block::BYTES);
+        ref_count::for_each_counted_object(block.start(), block.end(), |object| {
+            if cycle_collection && !self.is_marked(object) {
+                ref_count::clear(object);
+                VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.clear::<VM>(object, Ordering::SeqCst);
+                // The garbage may stay in a live line.  Do not leave it with a mark state that
+                // will be used again.  See `ImmixSpace::next_mark_state`.
+                if VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.is_in_header() {
+                    VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.store_atomic::<VM, u8>(
+                        object,
+                        Self::UNMARKED_STATE,
+                        None,
+                        Ordering::SeqCst,
+                    );
+                }
+            } else {
+                Line::mark_lines_for_object::<VM>(object, line_mark_state);
+                #[cfg(feature = "vo_bit")]
+                vo_bit::set_vo_bit(object);
+            }
+        });
     }
 }
 
@@ -929,8 +1147,8 @@ pub struct PrepareBlockState<VM: VMBinding> {
 impl<VM: VMBinding> PrepareBlockState<VM> {
     /// Clear object mark table
     fn reset_object_mark(&self) {
-        // NOTE: We reset the mark bits because cyclic mark bit is currently not supported, yet.
-        // See `ImmixSpace::prepare`.
+        // NOTE: We only reset side mark bits.  In-header mark bits use cyclic mark states instead.
+        // See `ImmixSpace::next_mark_state`.
         if let MetadataSpec::OnSide(side) = *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC {
             side.bzero_metadata(self.chunk.start(), Chunk::BYTES);
         }
@@ -1028,6 +1246,11 @@ impl<VM: VMBinding> GCWork<VM> for SweepChunk<VM> {
                 }
             }
 
+            if self.space.space_args.reference_counted {
+                self.space
+                    .mark_lines_for_counted_objects(block, line_mark_state.unwrap());
+            }
+
             if !block.sweep(self.space, &mut histogram, line_mark_state) {
                 // Block is live. Increment the allocated block count.
                 allocated_blocks += 1;
@@ -1202,6 +1425,7 @@ impl<VM: VMBinding> GCWork<VM> for ClearVOBitsAfterPrepare {
     fn do_work(&mut self, _worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
         match self.scope {
             VOBitsClearingScope::FullGC => {
+                self.reset_in_header_mark_bits::<VM>();
                 vo_bit::bzero_vo_bit(self.chunk.start(), Chunk::BYTES);
             }
             VOBitsClearingScope::BlockOnly => {
@@ -1216,6 +1440,29 @@ impl<VM: VMBinding> GCWork<VM> for ClearVOBitsAfterPrepare {
 
 #[cfg(feature = "vo_bit")]
 impl ClearVOBitsAfterPrepare {
+    /// Reset the in-header mark bits of the objects in the chunk while they can still be found
+    /// with their VO bits.  The objects that are not reached in this GC are left unmarked, so
+    /// they do not look marked when the mark state of the last GC is used again.  See
+    /// [`ImmixSpace::next_mark_state`].
+    fn reset_in_header_mark_bits<VM: VMBinding>(&self) {
+        if VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.is_on_side() {
+            return;
+        }
+        vo_bit::VO_BIT_SIDE_METADATA_SPEC.scan_non_zero_values::<u8>(
+            self.chunk.start(),
+            self.chunk.end(),
+            &mut |address| {
+                let object = vo_bit::get_object_ref_for_vo_addr(address);
+                VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.store_atomic::<VM, u8>(
+                    object,
+                    ImmixSpace::<VM>::UNMARKED_STATE,
+                    None,
+                    Ordering::SeqCst,
+                );
+            },
+        );
+    }
+
     fn clear_blocks(&mut self, line_mark_state: Option<u8>) {
         for block in self
             .chunk
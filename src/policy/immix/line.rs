@@ -105,10 +105,13 @@ impl Line {
     }
 
     /// Eagerly mark all line mark states and all side mark bits in the gap.
+    /// In-header mark bits are set when the objects are initialized instead.
     ///
     /// Useful during concurrent marking.
     pub fn eager_mark_lines<VM: VMBinding>(line_mark_state: u8, lines: Range<Line>) {
         Self::bulk_set_line_mark_states(line_mark_state, lines.clone());
-        Self::initialize_mark_table_as_marked::<VM>(lines);
+        if VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.is_on_side() {
+            Self::initialize_mark_table_as_marked::<VM>(lines);
+        }
     }
 }
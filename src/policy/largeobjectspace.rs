@@ -7,6 +7,7 @@ use crate::policy::sft::SFT;
 use crate::policy::space::{CommonSpace, Space};
 use crate::util::alloc::allocator::AllocationOptions;
 use crate::util::constants::BYTES_IN_PAGE;
+use crate::util::heap::oom_report::SpaceFragmentation;
 use crate::util::heap::{FreeListPageResource, PageResource};
 use crate::util::metadata;
 use crate::util::object_enum::ClosureObjectEnumerator;
@@ -100,9 +101,13 @@ impl<VM: VMBinding> SFT for LargeObjectSpace<VM> {
             debug_assert!(self.common.needs_log_bit);
             debug_assert!(
                 !allocate_as_live,
-                "Currently only ConcurrentImmix can allocate as live, and it doesn't unlog allocated objects in LOS."
+                "Currently only concurrent plans can allocate as live, and they don't unlog allocated objects in LOS."
             );
 
+            VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.mark_as_unlogged::<VM>(object, Ordering::SeqCst);
+        } else if allocate_as_live && self.common.unlog_traced_object {
+            // An object allocated as live skips the logical nursery, as if it had been traced.
+            // Unlog it like a traced object so that the generational barrier remembers it.
             VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.mark_as_unlogged::<VM>(object, Ordering::SeqCst);
         } else {
             #[cfg(debug_assertions)]
@@ -218,6 +223,12 @@ impl<VM: VMBinding> Space<VM> for LargeObjectSpace<VM> {
         self.pr.release_pages(start);
     }
 
+    fn fragmentation(&self) -> Option<SpaceFragmentation> {
+        Some(SpaceFragmentation::LargeObject {
+            pages: self.pr.reserved_pages(),
+        })
+    }
+
     fn enumerate_objects(&self, enumerator: &mut dyn ObjectEnumerator) {
         // `MMTK::enumerate_objects` is not allowed during GC, so the collection nursery and the
         // from space must be empty.  In `ConcurrentImmix`, mutators may run during GC and call
@@ -380,6 +391,10 @@ impl<VM: VMBinding> LargeObjectSpace<VM> {
             if self.clear_log_bit_on_sweep {
                 VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.clear::<VM>(object, Ordering::SeqCst);
             }
+            // Dead objects may still have reference counts if they are reclaimed by tracing.
+            if self.common.needs_ref_count {
+                crate::util::metadata::ref_count::clear(object);
+            }
             self.pr
                 .release_pages(get_super_page(object.to_object_start::<VM>()));
         };
@@ -462,7 +477,7 @@ impl<VM: VMBinding> LargeObjectSpace<VM> {
     }
 
     /// Check if a given object is in nursery
-    fn is_in_nursery(&self, object: ObjectReference) -> bool {
+    pub(crate) fn is_in_nursery(&self, object: ObjectReference) -> bool {
         VM::VMObjectModel::LOCAL_LOS_MARK_NURSERY_SPEC.load_atomic::<VM, u8>(
             object,
             None,
@@ -46,18 +46,18 @@ impl<VM: VMBinding> SFT for MarkCompactSpace<VM> {
     }
 
     #[cfg(feature = "object_pinning")]
-    fn pin_object(&self, _object: ObjectReference) -> bool {
-        panic!("Cannot pin/unpin objects of MarkCompactSpace.")
+    fn pin_object(&self, object: ObjectReference) -> bool {
+        VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC.pin_object::<VM>(object)
     }
 
     #[cfg(feature = "object_pinning")]
-    fn unpin_object(&self, _object: ObjectReference) -> bool {
-        panic!("Cannot pin/unpin objects of MarkCompactSpace.")
+    fn unpin_object(&self, object: ObjectReference) -> bool {
+        VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC.unpin_object::<VM>(object)
     }
 
     #[cfg(feature = "object_pinning")]
-    fn is_object_pinned(&self, _object: ObjectReference) -> bool {
-        false
+    fn is_object_pinned(&self, object: ObjectReference) -> bool {
+        VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC.is_object_pinned::<VM>(object)
     }
 
     fn is_movable(&self) -> bool {
@@ -234,7 +234,11 @@ impl<VM: VMBinding> MarkCompactSpace<VM> {
     pub fn new(args: crate::policy::space::PlanCreateSpaceArgs<VM>) -> Self {
         let vm_map = args.vm_map;
         let is_discontiguous = args.vmrequest.is_discontiguous();
-        let local_specs = extract_side_metadata(&[*VM::VMObjectModel::LOCAL_MARK_BIT_SPEC]);
+        let local_specs = extract_side_metadata(&[
+            *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC,
+            #[cfg(feature = "object_pinning")]
+            *VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC,
+        ]);
         let common = CommonSpace::new(args.into_policy_args(true, false, local_specs));
         MarkCompactSpace {
             pr: if is_discontiguous {
@@ -357,6 +361,16 @@ impl<VM: VMBinding> MarkCompactSpace<VM> {
         Self::is_marked(*object)
     }
 
+    #[cfg(feature = "object_pinning")]
+    fn is_pinned(object: ObjectReference) -> bool {
+        VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC.is_object_pinned::<VM>(object)
+    }
+
+    #[cfg(not(feature = "object_pinning"))]
+    fn is_pinned(_object: ObjectReference) -> bool {
+        false
+    }
+
     /// Linear scan all the live objects in the given memory region
     fn linear_scan_objects(&self, range: Range<Address>) -> impl Iterator<Item = ObjectReference> {
         crate::util::linear_scan::ObjectIterator::<VM, MarkCompactObjectSize<VM>, true>::new(
@@ -365,6 +379,9 @@ impl<VM: VMBinding> MarkCompactSpace<VM> {
         )
     }
 
+    /// Calculate the forwarding pointers of live objects.  Live objects are slid towards the
+    /// start of the space, in address order.  Pinned objects are forwarded to themselves, and
+    /// the objects after them are slid towards the end of the pinned objects instead.
     pub fn calculate_forwarding_pointer(&self) {
         let mut to_iter = self.pr.iterate_allocated_regions();
         let Some((mut to_cursor, mut to_size)) = to_iter.next() else {
@@ -378,6 +395,20 @@ impl<VM: VMBinding> MarkCompactSpace<VM> {
                 .linear_scan_objects(from_start..from_end)
                 .filter(Self::to_be_compacted)
             {
+                if Self::is_pinned(obj) {
+                    // Objects never move to higher addresses, so the to-region is never after
+                    // the region of the pinned object.
+                    let obj_start = obj.to_object_start::<VM>();
+                    while !(to_cursor..to_end).contains(&obj_start) {
+                        (to_cursor, to_size) = to_iter.next().unwrap();
+                        to_end = to_cursor + to_size;
+                    }
+                    debug_assert!(to_cursor <= obj_start - Self::HEADER_RESERVED_IN_BYTES);
+                    Self::store_header_forwarding_pointer(obj, obj);
+                    trace!("Calculate forward: {} is pinned", obj);
+                    to_cursor = obj_start + VM::VMObjectModel::get_current_size(obj);
+                    continue;
+                }
                 let copied_size =
                     VM::VMObjectModel::get_size_when_copied(obj) + Self::HEADER_RESERVED_IN_BYTES;
                 let align = VM::VMObjectModel::get_align_when_copied(obj);
@@ -421,7 +452,12 @@ impl<VM: VMBinding> MarkCompactSpace<VM> {
                 vo_bit::unset_vo_bit(obj);
 
                 let maybe_forwarding_pointer = Self::get_header_forwarding_pointer(obj);
-                if let Some(forwarding_pointer) = maybe_forwarding_pointer {
+                if maybe_forwarding_pointer == Some(obj) {
+                    trace!("Skipping pinned object {}", obj);
+                    Self::clear_header_forwarding_pointer(obj);
+                    vo_bit::set_vo_bit(obj);
+                    to = obj.to_object_start::<VM>() + VM::VMObjectModel::get_current_size(obj);
+                } else if let Some(forwarding_pointer) = maybe_forwarding_pointer {
                     trace!("Compact {} to {}", obj, forwarding_pointer);
                     let new_object = forwarding_pointer;
                     Self::clear_header_forwarding_pointer(new_object);
@@ -436,6 +472,10 @@ impl<VM: VMBinding> MarkCompactSpace<VM> {
                     debug_assert_eq!(end_of_new_object, to);
                 } else {
                     trace!("Skipping dead object {}", obj);
+                    // Clear the pinning bit of a dead object, so that it does not pin another
+                    // object moved to its address.
+                    #[cfg(feature = "object_pinning")]
+                    VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC.unpin_object::<VM>(obj);
                 }
             }
         }
@@ -13,6 +13,7 @@ use crate::util::heap::PageResource;
 use crate::util::linear_scan::Region;
 use crate::util::malloc::library::{BYTES_IN_MALLOC_PAGE, LOG_BYTES_IN_MALLOC_PAGE};
 use crate::util::malloc::malloc_ms_util::*;
+use crate::util::metadata::log_bit::UnlogBitsOperation;
 use crate::util::metadata::side_metadata;
 use crate::util::metadata::side_metadata::{
     SideMetadataContext, SideMetadataSanity, SideMetadataSpec,
@@ -553,10 +554,15 @@ impl<VM: VMBinding> MallocSpace<VM> {
         crate::util::metadata::vo_bit::is_vo_bit_set_for_addr(addr)
     }
 
-    pub fn prepare(&mut self, _full_heap: bool) {}
+    // The unlog bits arguments exist so that `MallocSpace` can be used interchangeably with the
+    // native `MarkSweepSpace`.  No plan that uses `MallocSpace` needs to operate on unlog bits.
+    pub(crate) fn prepare(&mut self, _full_heap: bool, unlog_bits_op: UnlogBitsOperation) {
+        debug_assert!(unlog_bits_op == UnlogBitsOperation::NoOp);
+    }
 
-    pub fn release(&mut self) {
+    pub(crate) fn release(&mut self, unlog_bits_op: UnlogBitsOperation) {
         use crate::scheduler::WorkBucketStage;
+        debug_assert!(unlog_bits_op == UnlogBitsOperation::NoOp);
         let space = unsafe { &*(self as *const Self) };
         let work_packets = self.chunk_map.generate_tasks(|chunk| {
             Box::new(MSSweepChunk {
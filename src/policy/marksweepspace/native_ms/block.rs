@@ -59,8 +59,9 @@ impl Block {
     /// Log pages in block
     pub const LOG_PAGES: usize = Self::LOG_BYTES - LOG_BYTES_IN_PAGE as usize;
 
-    pub const METADATA_SPECS: [SideMetadataSpec; 7] = [
+    pub const METADATA_SPECS: [SideMetadataSpec; 8] = [
         Self::MARK_TABLE,
+        Self::FRESH_TABLE,
         Self::NEXT_BLOCK_TABLE,
         Self::PREV_BLOCK_TABLE,
         Self::FREE_LIST_TABLE,
@@ -73,6 +74,11 @@ impl Block {
     pub const MARK_TABLE: SideMetadataSpec =
         crate::util::metadata::side_metadata::spec_defs::MS_BLOCK_MARK;
 
+    /// Block fresh table (side). A block is fresh if it may have been allocated into since the
+    /// last GC.
+    pub const FRESH_TABLE: SideMetadataSpec =
+        crate::util::metadata::side_metadata::spec_defs::MS_BLOCK_FRESH;
+
     pub const NEXT_BLOCK_TABLE: SideMetadataSpec =
         crate::util::metadata::side_metadata::spec_defs::MS_BLOCK_NEXT;
 
@@ -231,6 +237,17 @@ impl Block {
         Self::MARK_TABLE.store_atomic::<u8>(self.start(), state, Ordering::SeqCst);
     }
 
+    /// Record that the block may have objects allocated since the last GC.
+    pub fn set_fresh(&self) {
+        Self::FRESH_TABLE.store_atomic::<u8>(self.start(), 1, Ordering::SeqCst);
+    }
+
+    /// Return true if the block may have objects allocated since the last GC.  A nursery GC only
+    /// needs to sweep fresh blocks, as other blocks contain no nursery objects.
+    pub fn is_fresh(&self) -> bool {
+        Self::FRESH_TABLE.load_atomic::<u8>(self.start(), Ordering::SeqCst) != 0
+    }
+
     /// Release this block if it is unmarked. Return true if the block is released.
     pub fn attempt_release<VM: VMBinding>(self, space: &MarkSweepSpace<VM>) -> bool {
         match self.get_state() {
@@ -251,6 +268,9 @@ impl Block {
 
     /// Sweep the block. This is done either lazily in the allocation phase, or eagerly at the end of a GC.
     pub fn sweep<VM: VMBinding>(&self) {
+        // A swept block has no nursery objects until it is allocated into again.
+        Self::FRESH_TABLE.store_atomic::<u8>(self.start(), 0, Ordering::SeqCst);
+
         // The important point here is that we need to distinguish cell address, allocation address, and object reference.
         // We only know cell addresses here. We do not know the allocation address, and we also do not know the object reference.
         // The mark bit is set for object references, and we need to use the mark bit to decide whether a cell is live or not.
@@ -183,6 +183,22 @@ impl BlockList {
             block.attempt_release(space);
         }
     }
+
+    /// Move blocks to `unswept`, so that allocators will sweep them before using them. Used by
+    /// lazy sweeping. If `fresh_only` is true, only fresh blocks are moved, and other blocks stay
+    /// in this list.
+    pub fn move_to_unswept(&mut self, unswept: &mut BlockList, fresh_only: bool) {
+        if !fresh_only {
+            unswept.append(self);
+            return;
+        }
+        for block in self.iter() {
+            if block.is_fresh() {
+                self.remove(block);
+                unswept.push(block);
+            }
+        }
+    }
 }
 
 pub struct BlockListIterator {
@@ -10,7 +10,9 @@ use crate::{
         copy::CopySemantics,
         epilogue,
         heap::{BlockPageResource, PageResource},
-        metadata::{self, side_metadata::SideMetadataSpec, MetadataSpec},
+        metadata::{
+            self, log_bit::UnlogBitsOperation, side_metadata::SideMetadataSpec, MetadataSpec,
+        },
         object_enum::{self, ObjectEnumerator},
         ObjectReference,
     },
@@ -27,6 +29,7 @@ use crate::policy::space::{CommonSpace, Space};
 use crate::util::alloc::allocator::AllocationOptions;
 use crate::util::constants::LOG_BYTES_IN_PAGE;
 use crate::util::heap::chunk_map::*;
+use crate::util::heap::oom_report::SpaceFragmentation;
 use crate::util::linear_scan::Region;
 use crate::util::VMThread;
 use crate::vm::ObjectModel;
@@ -60,11 +63,12 @@ pub enum BlockAcquireResult {
 /// |----------------|-------------------------------------------------|----------------------------------------------|-----------|
 /// | Allocation     | Alloc from local                                | Move blocks from global to local block lists | -         |
 /// |                | Lazy: sweep local blocks                        |                                              |           |
-/// | GC - Prepare   | -                                               | -                                            | Find used chunks, reset block mark, bzero mark bit |
+/// | GC - Prepare   | -                                               | -                                            | Find used chunks, reset block mark, bzero mark bit (full heap only) |
 /// | GC - Trace     | Trace object and mark blocks.                   | Trace object and mark blocks.                | -         |
 /// |                | No block list access.                           | No block list access.                        |           |
 /// | GC - Release   | Lazy: Move blocks to local unswept list         | Lazy: Move blocks to global unswept list     | _         |
 /// |                | Eager: Sweep local blocks                       | Eager: Sweep global blocks                   |           |
+/// |                | Nursery GC: Only move or sweep fresh blocks     | Nursery GC: Only move or sweep fresh blocks  |           |
 /// |                | Both: Return local blocks to a temp global list |                                              |           |
 /// | GC - End of GC | -                                               | Merge the temp global lists                  | -         |
 pub struct MarkSweepSpace<VM: VMBinding> {
@@ -86,6 +90,11 @@ pub struct MarkSweepSpace<VM: VMBinding> {
     /// Count the number of pending `ReleaseMarkSweepSpace` and `ReleaseMutator` work packets during
     /// the `Release` stage.
     pending_release_packets: AtomicUsize,
+    /// Is the current GC a nursery GC?  In a nursery GC, mark bits and block states are kept
+    /// from the last GC (sticky mark bits), and only fresh blocks are swept.
+    in_nursery_gc: bool,
+    /// The number of blocks that have become fresh since the last GC.
+    fresh_blocks: AtomicUsize,
 }
 
 unsafe impl<VM: VMBinding> Sync for MarkSweepSpace<VM> {}
@@ -106,6 +115,7 @@ impl AbandonedBlockLists {
     }
 
     fn sweep_later<VM: VMBinding>(&mut self, space: &MarkSweepSpace<VM>) {
+        let fresh_only = space.in_nursery_gc();
         for i in 0..MI_BIN_FULL {
             // Release free blocks
             self.available[i].release_blocks(space);
@@ -123,8 +133,8 @@ impl AbandonedBlockLists {
             // For lazy sweeping, we move blocks from available and consumed to unswept.  When an
             // allocator tries to use them, they will sweep the block.
             if cfg!(not(feature = "eager_sweeping")) {
-                self.unswept[i].append(&mut self.available[i]);
-                self.unswept[i].append(&mut self.consumed[i]);
+                self.available[i].move_to_unswept(&mut self.unswept[i], fresh_only);
+                self.consumed[i].move_to_unswept(&mut self.unswept[i], fresh_only);
             }
         }
     }
@@ -191,9 +201,16 @@ impl<VM: VMBinding> SFT for MarkSweepSpace<VM> {
         true
     }
 
-    fn initialize_object_metadata(&self, _object: crate::util::ObjectReference) {
+    fn initialize_object_metadata(&self, object: crate::util::ObjectReference) {
         #[cfg(feature = "vo_bit")]
-        crate::util::metadata::vo_bit::set_vo_bit(_object);
+        crate::util::metadata::vo_bit::set_vo_bit(object);
+
+        // Objects allocated during concurrent marking are considered live.  Mark the object and
+        // its block so that neither will be reclaimed at the end of the current GC.
+        if self.should_allocate_as_live() {
+            VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.mark::<VM>(object, Ordering::SeqCst);
+            Block::containing(object).set_state(BlockState::Marked);
+        }
     }
 
     #[cfg(feature = "is_mmtk_object")]
@@ -255,6 +272,36 @@ impl<VM: VMBinding> Space<VM> for MarkSweepSpace<VM> {
         object_enum::enumerate_blocks_from_chunk_map::<Block>(enumerator, &self.chunk_map);
     }
 
+    fn fragmentation(&self) -> Option<SpaceFragmentation> {
+        let blocks = self
+            .chunk_map
+            .all_chunks()
+            .flat_map(|chunk| chunk.iter_region::<Block>())
+            .filter(|block| block.get_state() != BlockState::Unallocated)
+            .count();
+        // Blocks owned by mutators may be allocated into at the same time, so we only count the
+        // free cells in abandoned blocks, which are protected by the lock.
+        let mut free_cells = 0;
+        let mut free_cell_bytes = 0;
+        let abandoned = self.abandoned.lock().unwrap();
+        for list in abandoned.available.iter() {
+            for block in list.iter() {
+                let cell_size = block.load_block_cell_size();
+                let mut cell = block.load_free_list();
+                while !cell.is_zero() {
+                    free_cells += 1;
+                    free_cell_bytes += cell_size;
+                    cell = unsafe { cell.load::<crate::util::Address>() };
+                }
+            }
+        }
+        Some(SpaceFragmentation::MarkSweep {
+            blocks,
+            free_cells,
+            free_cell_bytes,
+        })
+    }
+
     fn clear_side_log_bits(&self) {
         let log_bit = VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.extract_side_spec();
         for chunk in self.chunk_map.all_chunks() {
@@ -315,6 +362,7 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
                 MetadataSpec::OnSide(Block::BLOCK_LIST_TABLE),
                 MetadataSpec::OnSide(Block::TLS_TABLE),
                 MetadataSpec::OnSide(Block::MARK_TABLE),
+                MetadataSpec::OnSide(Block::FRESH_TABLE),
                 *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC,
             ])
         };
@@ -342,6 +390,8 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
             abandoned: Mutex::new(AbandonedBlockLists::new()),
             abandoned_in_gc: Mutex::new(AbandonedBlockLists::new()),
             pending_release_packets: AtomicUsize::new(0),
+            in_nursery_gc: false,
+            fresh_blocks: AtomicUsize::new(0),
         }
     }
 
@@ -397,7 +447,7 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
         }
     }
 
-    fn trace_object<Q: ObjectQueue>(
+    pub fn trace_object<Q: ObjectQueue>(
         &self,
         queue: &mut Q,
         object: ObjectReference,
@@ -410,6 +460,11 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
         if self.attempt_mark(object) {
             let block = Block::containing(object);
             block.set_state(BlockState::Marked);
+            // Mark the object as unlogged, as it is mature from now on.
+            if self.common.unlog_traced_object {
+                VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC
+                    .mark_as_unlogged::<VM>(object, Ordering::SeqCst);
+            }
             queue.enqueue(object);
         }
         object
@@ -420,20 +475,47 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
         self.chunk_map.set_allocated(block.chunk(), true);
     }
 
-    pub fn prepare(&mut self, _full_heap: bool) {
+    /// Record that an allocator is going to allocate into a block.
+    pub fn record_fresh_block(&self, block: Block) {
+        if !block.is_fresh() {
+            block.set_fresh();
+            self.fresh_blocks.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Return the number of pages in the blocks that have been allocated into since the last GC.
+    pub fn get_fresh_pages(&self) -> usize {
+        self.fresh_blocks.load(Ordering::Relaxed) << Block::LOG_PAGES
+    }
+
+    /// Prepare the space for a GC.  A nursery GC (`full_heap` is false) keeps the mark bits and the
+    /// block states from the last GC, so objects that survived a GC are treated as mature and
+    /// are not traced again.
+    pub(crate) fn prepare(&mut self, full_heap: bool, unlog_bits_op: UnlogBitsOperation) {
         #[cfg(debug_assertions)]
         self.abandoned_in_gc.lock().unwrap().assert_empty();
 
+        self.in_nursery_gc = !full_heap;
+        self.fresh_blocks.store(0, Ordering::Relaxed);
+        if self.in_nursery_gc {
+            debug_assert!(unlog_bits_op == UnlogBitsOperation::NoOp);
+            return;
+        }
+
         // # Safety: MarkSweepSpace reference is always valid within this collection cycle.
         let space = unsafe { &*(self as *const Self) };
-        let work_packets = self
-            .chunk_map
-            .generate_tasks(|chunk| Box::new(PrepareChunkMap { space, chunk }));
+        let work_packets = self.chunk_map.generate_tasks(|chunk| {
+            Box::new(PrepareChunkMap {
+                space,
+                chunk,
+                unlog_bits_op,
+            })
+        });
         self.scheduler.work_buckets[crate::scheduler::WorkBucketStage::Prepare]
             .bulk_add(work_packets);
     }
 
-    pub fn release(&mut self) {
+    pub(crate) fn release(&mut self, unlog_bits_op: UnlogBitsOperation) {
         let num_mutators = VM::VMActivePlan::number_of_mutators();
         // all ReleaseMutator work packets plus the ReleaseMarkSweepSpace packet
         self.pending_release_packets
@@ -444,6 +526,18 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
         let space = unsafe { &*(self as *const Self) };
         let work_packet = ReleaseMarkSweepSpace { space };
         self.scheduler.work_buckets[crate::scheduler::WorkBucketStage::Release].add(work_packet);
+
+        if unlog_bits_op != UnlogBitsOperation::NoOp {
+            let work_packets = self.chunk_map.generate_tasks(|chunk| {
+                Box::new(UnlogBitsChunk::<VM> {
+                    chunk,
+                    unlog_bits_op,
+                    _p: std::marker::PhantomData,
+                })
+            });
+            self.scheduler.work_buckets[crate::scheduler::WorkBucketStage::Release]
+                .bulk_add(work_packets);
+        }
     }
 
     pub fn end_of_gc(&mut self) {
@@ -453,6 +547,11 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
         );
     }
 
+    /// Return `true` if the current GC is a nursery GC.
+    pub fn in_nursery_gc(&self) -> bool {
+        self.in_nursery_gc
+    }
+
     /// Release a block.
     pub fn release_block(&self, block: Block) {
         self.block_clear_metadata(block);
@@ -469,6 +568,16 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
         crate::util::metadata::vo_bit::bzero_vo_bit(block.start(), Block::BYTES);
     }
 
+    /// Return `true` if mutators may sweep blocks lazily at this moment.
+    ///
+    /// Lazy sweeping relies on the mark bits computed by the last completed marking.  While
+    /// concurrent marking is in progress (i.e. when new objects are allocated as live), mark bits
+    /// have been reset and are not complete yet.  Sweeping a block at that time would free live
+    /// objects, so unswept blocks have to stay unswept until marking finishes.
+    pub fn can_sweep_lazily(&self) -> bool {
+        !self.should_allocate_as_live()
+    }
+
     pub fn acquire_block(
         &self,
         tls: VMThread,
@@ -488,7 +597,8 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
                 }
             }
 
-            {
+            // Unswept blocks cannot be used until concurrent marking finishes.
+            if self.can_sweep_lazily() {
                 let abandoned_unswept = &mut abandoned.unswept;
                 if !abandoned_unswept[bin].is_empty() {
                     let block = abandoned_unswept[bin].pop().unwrap();
@@ -576,6 +686,7 @@ use crate::MMTK;
 struct PrepareChunkMap<VM: VMBinding> {
     space: &'static MarkSweepSpace<VM>,
     chunk: Chunk,
+    unlog_bits_op: UnlogBitsOperation,
 }
 
 impl<VM: VMBinding> GCWork<VM> for PrepareChunkMap<VM> {
@@ -600,10 +711,26 @@ impl<VM: VMBinding> GCWork<VM> for PrepareChunkMap<VM> {
             if let MetadataSpec::OnSide(side) = *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC {
                 side.bzero_metadata(self.chunk.start(), Chunk::BYTES);
             }
+            self.unlog_bits_op
+                .execute::<VM>(self.chunk.start(), Chunk::BYTES);
         }
     }
 }
 
+/// Apply an operation to the side unlog bits of all objects in a chunk.
+struct UnlogBitsChunk<VM: VMBinding> {
+    chunk: Chunk,
+    unlog_bits_op: UnlogBitsOperation,
+    _p: std::marker::PhantomData<VM>,
+}
+
+impl<VM: VMBinding> GCWork<VM> for UnlogBitsChunk<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
+        self.unlog_bits_op
+            .execute::<VM>(self.chunk.start(), Chunk::BYTES);
+    }
+}
+
 struct ReleaseMarkSweepSpace<VM: VMBinding> {
     space: &'static MarkSweepSpace<VM>,
 }
@@ -643,7 +770,11 @@ impl<VM: VMBinding> GCWork<VM> for SweepChunk<VM> {
             // We have released unmarked blocks in `ReleaseMarkSweepSpace` and `ReleaseMutator`.
             // We shouldn't see any unmarked blocks now.
             debug_assert_eq!(block.get_state(), BlockState::Marked);
-            block.sweep::<VM>();
+            // Blocks that have not been allocated into since the last GC have no nursery objects,
+            // and there is nothing to sweep in a nursery GC.
+            if !self.space.in_nursery_gc() || block.is_fresh() {
+                block.sweep::<VM>();
+            }
             allocated_blocks += 1;
         }
         probe!(mmtk, sweep_chunk, allocated_blocks);
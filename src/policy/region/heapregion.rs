@@ -0,0 +1,26 @@
+use crate::util::constants::BYTES_IN_PAGE;
+use crate::util::linear_scan::Region;
+use crate::util::Address;
+
+/// A [`HeapRegion`] is the unit at which [`super::RegionSpace`] reclaims memory. A region is
+/// either evacuated as a whole, or not at all in a GC.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub struct HeapRegion(Address);
+
+impl Region for HeapRegion {
+    const LOG_BYTES: usize = 20; // 1 MiB
+
+    fn from_aligned_address(address: Address) -> Self {
+        debug_assert!(address.is_aligned_to(Self::BYTES));
+        HeapRegion(address)
+    }
+
+    fn start(&self) -> Address {
+        self.0
+    }
+}
+
+impl HeapRegion {
+    /// The number of pages in a region.
+    pub const PAGES: usize = Self::BYTES / BYTES_IN_PAGE;
+}
@@ -0,0 +1,5 @@
+pub mod heapregion;
+pub mod regionspace;
+
+pub use heapregion::HeapRegion;
+pub use regionspace::*;
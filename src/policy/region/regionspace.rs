@@ -0,0 +1,497 @@
+use super::HeapRegion;
+use crate::plan::{ObjectQueue, VectorObjectQueue};
+use crate::policy::copy_context::PolicyCopyContext;
+use crate::policy::gc_work::{TraceKind, TRACE_KIND_TRANSITIVE_PIN};
+use crate::policy::sft::{GCWorkerMutRef, SFT};
+use crate::policy::space::{CommonSpace, Space};
+use crate::scheduler::GCWorker;
+use crate::util::alloc::allocator::AllocatorContext;
+use crate::util::alloc::{Allocator, BumpAllocator};
+use crate::util::copy::CopySemantics;
+use crate::util::heap::regionpageresource::AllocatedRegion;
+use crate::util::heap::{PageResource, RegionPageResource};
+use crate::util::linear_scan::Region;
+use crate::util::metadata::side_metadata::spec_defs::{
+    REGION_IN_CSET, REGION_LIVE_BYTES, REGION_MARK,
+};
+use crate::util::metadata::{extract_side_metadata, MetadataSpec};
+use crate::util::object_enum::ObjectEnumerator;
+use crate::util::object_forwarding;
+use crate::util::opaque_pointer::VMWorkerThread;
+use crate::util::{Address, ObjectReference};
+use crate::vm::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A space made of fixed-size regions ([`HeapRegion`]), which are reclaimed by evacuation.
+///
+/// In each GC, the plan chooses a *collection set* of regions.  Live objects in the collection
+/// set are evacuated to wherever the copy semantics of the trace points, and the regions in the
+/// collection set are freed when the GC is released.  Objects in other regions are never moved.
+/// If the GC marks the space, objects outside the collection set are marked in place and the
+/// bytes of live objects are counted for each region, so that the plan can estimate the cost of
+/// evacuating a region in a later GC.  Regions without live objects are freed after marking.
+///
+/// The space does not handle evacuation failure.  The plan must make sure that there is enough
+/// free memory to copy the live objects of the collection set.
+pub struct RegionSpace<VM: VMBinding> {
+    common: CommonSpace<VM>,
+    pr: RegionPageResource<VM, HeapRegion>,
+    /// Whether the current GC marks the objects outside the collection set.
+    marking: AtomicBool,
+    /// The objects copied into this space during the current GC.
+    copied_objects: Mutex<Vec<ObjectReference>>,
+}
+
+/// The memory reclaimed by [`RegionSpace::release`].
+pub struct ReleasedRegions {
+    /// The regions that are freed.
+    pub regions: Vec<HeapRegion>,
+    /// The bytes of objects evacuated from the collection set.
+    pub evacuated_bytes: usize,
+}
+
+impl<VM: VMBinding> SFT for RegionSpace<VM> {
+    fn name(&self) -> &'static str {
+        self.get_name()
+    }
+
+    fn is_live(&self, object: ObjectReference) -> bool {
+        if self.in_collection_set(object) {
+            object_forwarding::is_forwarded::<VM>(object)
+        } else if self.is_marking() {
+            Self::is_marked(object)
+        } else {
+            true
+        }
+    }
+
+    #[cfg(feature = "object_pinning")]
+    fn pin_object(&self, _object: ObjectReference) -> bool {
+        panic!("Cannot pin/unpin objects of RegionSpace.")
+    }
+
+    #[cfg(feature = "object_pinning")]
+    fn unpin_object(&self, _object: ObjectReference) -> bool {
+        panic!("Cannot pin/unpin objects of RegionSpace.")
+    }
+
+    #[cfg(feature = "object_pinning")]
+    fn is_object_pinned(&self, _object: ObjectReference) -> bool {
+        false
+    }
+
+    fn is_movable(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "sanity")]
+    fn is_sane(&self) -> bool {
+        true
+    }
+
+    fn initialize_object_metadata(&self, _object: ObjectReference) {
+        #[cfg(feature = "vo_bit")]
+        crate::util::metadata::vo_bit::set_vo_bit(_object);
+    }
+
+    fn get_forwarded_object(&self, object: ObjectReference) -> Option<ObjectReference> {
+        if self.in_collection_set(object) && object_forwarding::is_forwarded::<VM>(object) {
+            Some(object_forwarding::read_forwarding_pointer::<VM>(object))
+        } else {
+            None
+        }
+    }
+
+    #[cfg(feature = "is_mmtk_object")]
+    fn is_mmtk_object(&self, addr: Address) -> Option<ObjectReference> {
+        crate::util::metadata::vo_bit::is_vo_bit_set_for_addr(addr)
+    }
+
+    #[cfg(feature = "is_mmtk_object")]
+    fn find_object_from_internal_pointer(
+        &self,
+        ptr: Address,
+        max_search_bytes: usize,
+    ) -> Option<ObjectReference> {
+        crate::util::metadata::vo_bit::find_object_from_internal_pointer::<VM>(
+            ptr,
+            max_search_bytes,
+        )
+    }
+
+    fn sft_trace_object(
+        &self,
+        queue: &mut VectorObjectQueue,
+        object: ObjectReference,
+        worker: GCWorkerMutRef,
+    ) -> ObjectReference {
+        let worker = worker.into_mut::<VM>();
+        self.trace_object(queue, object, self.common.copy, worker)
+    }
+
+    fn debug_print_object_info(&self, object: ObjectReference) {
+        println!("in collection set = {}", self.in_collection_set(object));
+        println!("marked = {}", Self::is_marked(object));
+        object_forwarding::debug_print_object_forwarding_info::<VM>(object);
+        self.common.debug_print_object_global_info(object);
+    }
+}
+
+impl<VM: VMBinding> Space<VM> for RegionSpace<VM> {
+    fn as_space(&self) -> &dyn Space<VM> {
+        self
+    }
+
+    fn as_sft(&self) -> &(dyn SFT + Sync + 'static) {
+        self
+    }
+
+    fn get_page_resource(&self) -> &dyn PageResource<VM> {
+        &self.pr
+    }
+
+    fn maybe_get_page_resource_mut(&mut self) -> Option<&mut dyn PageResource<VM>> {
+        Some(&mut self.pr)
+    }
+
+    fn common(&self) -> &CommonSpace<VM> {
+        &self.common
+    }
+
+    fn initialize_sft(&self, sft_map: &mut dyn crate::policy::sft_map::SFTMap) {
+        self.common().initialize_sft(self.as_sft(), sft_map)
+    }
+
+    fn release_multiple_pages(&mut self, _start: Address) {
+        panic!("regionspace only releases pages by regions")
+    }
+
+    fn set_copy_for_sft_trace(&mut self, semantics: Option<CopySemantics>) {
+        self.common.copy = semantics;
+    }
+
+    fn enumerate_objects(&self, enumerator: &mut dyn ObjectEnumerator) {
+        self.pr.enumerate(enumerator);
+    }
+
+    fn clear_side_log_bits(&self) {
+        let log_bit = VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.extract_side_spec();
+        self.pr.enumerate_regions(&mut |r| {
+            log_bit.bzero_metadata(r.region.start(), r.cursor() - r.region.start());
+        });
+    }
+
+    fn set_side_log_bits(&self) {
+        let log_bit = VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.extract_side_spec();
+        self.pr.enumerate_regions(&mut |r| {
+            log_bit.bset_metadata(r.region.start(), r.cursor() - r.region.start());
+        });
+    }
+}
+
+impl<VM: VMBinding> crate::policy::gc_work::PolicyTraceObject<VM> for RegionSpace<VM> {
+    fn trace_object<Q: ObjectQueue, const KIND: TraceKind>(
+        &self,
+        queue: &mut Q,
+        object: ObjectReference,
+        copy: Option<CopySemantics>,
+        worker: &mut GCWorker<VM>,
+    ) -> ObjectReference {
+        debug_assert!(
+            KIND != TRACE_KIND_TRANSITIVE_PIN,
+            "RegionSpace does not support transitive pin trace."
+        );
+        self.trace_object(queue, object, copy, worker)
+    }
+
+    fn may_move_objects<const KIND: TraceKind>() -> bool {
+        true
+    }
+}
+
+impl<VM: VMBinding> RegionSpace<VM> {
+    pub fn new(args: crate::policy::space::PlanCreateSpaceArgs<VM>) -> Self {
+        let vm_map = args.vm_map;
+        let is_discontiguous = args.vmrequest.is_discontiguous();
+        let common = CommonSpace::new(args.into_policy_args(
+            true,
+            false,
+            extract_side_metadata(&[
+                *VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC,
+                *VM::VMObjectModel::LOCAL_FORWARDING_POINTER_SPEC,
+                MetadataSpec::OnSide(REGION_MARK),
+                MetadataSpec::OnSide(REGION_LIVE_BYTES),
+                MetadataSpec::OnSide(REGION_IN_CSET),
+            ]),
+        ));
+        RegionSpace {
+            pr: if is_discontiguous {
+                RegionPageResource::new_discontiguous(vm_map)
+            } else {
+                RegionPageResource::new_contiguous(common.start, common.extent, vm_map)
+            },
+            common,
+            marking: AtomicBool::new(false),
+            copied_objects: Mutex::new(vec![]),
+        }
+    }
+
+    /// Prepare the space for a GC.  If `marking` is true, the GC marks the objects outside the
+    /// collection set, and counts the live bytes of every region again.
+    pub fn prepare(&self, marking: bool) {
+        self.marking.store(marking, Ordering::SeqCst);
+        if marking {
+            self.pr.enumerate_regions(&mut |r| {
+                REGION_MARK.bzero_metadata(r.region.start(), HeapRegion::BYTES);
+                Self::set_live_bytes(r.region, 0);
+            });
+        }
+    }
+
+    /// Add the non-empty regions for which `select` returns true to the collection set of the
+    /// current GC.  No objects are copied into those regions during the GC.  The live bytes of
+    /// those regions are counted again as their objects are evacuated.
+    pub fn select_collection_set(&self, select: &mut impl FnMut(HeapRegion) -> bool) {
+        self.pr.enumerate_regions(&mut |r| {
+            if r.cursor() > r.region.start() && select(r.region) {
+                REGION_IN_CSET.store_atomic::<u8>(r.region.start(), 1, Ordering::SeqCst);
+                Self::set_live_bytes(r.region, 0);
+                self.pr.retire_region(r);
+            }
+        });
+    }
+
+    /// Release the space after a GC.  The regions in the collection set are freed.  If the GC
+    /// marked the space, regions without live objects are freed as well.
+    pub fn release(&self) -> ReleasedRegions {
+        let marking = self.is_marking();
+        let mut released = ReleasedRegions {
+            regions: vec![],
+            evacuated_bytes: 0,
+        };
+        self.pr.enumerate_regions(&mut |r| {
+            if Self::region_in_collection_set(r.region) {
+                released.evacuated_bytes += Self::live_bytes(r.region);
+                self.free_region(r);
+                released.regions.push(r.region);
+            } else if marking && r.cursor() > r.region.start() {
+                if Self::live_bytes(r.region) == 0 {
+                    self.free_region(r);
+                    released.regions.push(r.region);
+                } else {
+                    // Dead objects must not be found by their VO bits, as we do not know whether
+                    // the objects they point to are still there.
+                    #[cfg(feature = "vo_bit")]
+                    crate::util::metadata::vo_bit::VO_BIT_SIDE_METADATA_SPEC
+                        .bcopy_metadata_contiguous(
+                            r.region.start(),
+                            HeapRegion::BYTES,
+                            &REGION_MARK,
+                        );
+                }
+            }
+        });
+        self.pr.reset_allocator();
+        self.marking.store(false, Ordering::SeqCst);
+        released
+    }
+
+    fn free_region(&self, r: &AllocatedRegion<HeapRegion>) {
+        let start = r.region.start();
+        let size = r.cursor() - start;
+        // Clear the forwarding bits if it is on the side.
+        if let MetadataSpec::OnSide(side_forwarding_status_table) =
+            *<VM::VMObjectModel as ObjectModel<VM>>::LOCAL_FORWARDING_BITS_SPEC
+        {
+            side_forwarding_status_table.bzero_metadata(start, size);
+        }
+        if self.common.needs_log_bit && VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.is_on_side() {
+            VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC
+                .extract_side_spec()
+                .bzero_metadata(start, size);
+        }
+        // Clear VO bits because all objects in the region are dead or evacuated.
+        #[cfg(feature = "vo_bit")]
+        crate::util::metadata::vo_bit::bzero_vo_bit(start, size);
+        REGION_MARK.bzero_metadata(start, size);
+        REGION_IN_CSET.store_atomic::<u8>(start, 0, Ordering::SeqCst);
+        Self::set_live_bytes(r.region, 0);
+        self.pr.reset_cursor(r, start);
+    }
+
+    /// Visit each region in the space, with the address up to which the region is allocated.
+    pub fn enumerate_regions(&self, f: &mut impl FnMut(HeapRegion, Address)) {
+        self.pr.enumerate_regions(&mut |r| f(r.region, r.cursor()));
+    }
+
+    /// Is the current GC marking the space?
+    pub fn is_marking(&self) -> bool {
+        self.marking.load(Ordering::Relaxed)
+    }
+
+    /// Is the region of the object in the collection set of the current GC?
+    pub fn in_collection_set(&self, object: ObjectReference) -> bool {
+        Self::region_in_collection_set(HeapRegion::from_unaligned_address(object.to_raw_address()))
+    }
+
+    /// Is the region in the collection set of the current GC?
+    pub fn region_in_collection_set(region: HeapRegion) -> bool {
+        REGION_IN_CSET.load_atomic::<u8>(region.start(), Ordering::Relaxed) != 0
+    }
+
+    /// The bytes of live objects in a region, as counted by the last GC that marked the space,
+    /// and the GCs that copied objects into the region since then.
+    pub fn live_bytes(region: HeapRegion) -> usize {
+        REGION_LIVE_BYTES.load_atomic::<usize>(region.start(), Ordering::Relaxed)
+    }
+
+    fn set_live_bytes(region: HeapRegion, bytes: usize) {
+        REGION_LIVE_BYTES.store_atomic::<usize>(region.start(), bytes, Ordering::Relaxed);
+    }
+
+    fn add_live_bytes(object: ObjectReference, bytes: usize) {
+        REGION_LIVE_BYTES.fetch_add_atomic::<usize>(
+            HeapRegion::from_unaligned_address(object.to_raw_address()).start(),
+            bytes,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Take the objects that are copied into this space since this method was last called.
+    pub fn take_copied_objects(&self) -> Vec<ObjectReference> {
+        std::mem::take(&mut self.copied_objects.lock().unwrap())
+    }
+
+    pub fn test_and_mark(object: ObjectReference) -> bool {
+        REGION_MARK.fetch_or_atomic::<u8>(object.to_raw_address(), 1, Ordering::SeqCst) == 0
+    }
+
+    pub fn is_marked(object: ObjectReference) -> bool {
+        REGION_MARK.load_atomic::<u8>(object.to_raw_address(), Ordering::SeqCst) == 1
+    }
+
+    pub fn trace_object<Q: ObjectQueue>(
+        &self,
+        queue: &mut Q,
+        object: ObjectReference,
+        semantics: Option<CopySemantics>,
+        worker: &mut GCWorker<VM>,
+    ) -> ObjectReference {
+        #[cfg(feature = "vo_bit")]
+        debug_assert!(
+            crate::util::metadata::vo_bit::is_vo_bit_set(object),
+            "{:x}: VO bit not set",
+            object
+        );
+        if self.in_collection_set(object) {
+            self.evacuate(queue, object, semantics, worker)
+        } else {
+            if self.is_marking() && Self::test_and_mark(object) {
+                Self::add_live_bytes(object, VM::VMObjectModel::get_current_size(object));
+                queue.enqueue(object);
+            }
+            object
+        }
+    }
+
+    fn evacuate<Q: ObjectQueue>(
+        &self,
+        queue: &mut Q,
+        object: ObjectReference,
+        semantics: Option<CopySemantics>,
+        worker: &mut GCWorker<VM>,
+    ) -> ObjectReference {
+        debug_assert!(semantics.is_some());
+        let forwarding_status = object_forwarding::attempt_to_forward::<VM>(object);
+        if object_forwarding::state_is_forwarded_or_being_forwarded(forwarding_status) {
+            object_forwarding::spin_and_get_forwarded_object::<VM>(object, forwarding_status)
+        } else {
+            // Count the evacuated bytes in the source region.
+            Self::add_live_bytes(object, VM::VMObjectModel::get_current_size(object));
+            let new_object = object_forwarding::forward_object::<VM>(
+                object,
+                semantics.unwrap(),
+                worker.get_copy_context_mut(),
+                |_new_object| {
+                    #[cfg(feature = "vo_bit")]
+                    crate::util::metadata::vo_bit::set_vo_bit(_new_object);
+                },
+            );
+            trace!("Evacuated [{:?} -> {:?}]", object, new_object);
+            queue.enqueue(new_object);
+            new_object
+        }
+    }
+}
+
+/// Copy allocator for RegionSpace
+pub struct RegionCopyContext<VM: VMBinding> {
+    copy_allocator: BumpAllocator<VM>,
+    space: &'static RegionSpace<VM>,
+    /// The objects copied by this context that are not yet handed to the space.
+    copied_objects: Vec<ObjectReference>,
+}
+
+impl<VM: VMBinding> PolicyCopyContext for RegionCopyContext<VM> {
+    type VM = VM;
+
+    fn prepare(&mut self) {}
+
+    fn release(&mut self) {
+        self.flush();
+        // The regions which the allocator was copying into may be evacuated in the next GC.
+        self.copy_allocator.reset();
+    }
+
+    fn alloc_copy(
+        &mut self,
+        _original: ObjectReference,
+        bytes: usize,
+        align: usize,
+        offset: usize,
+    ) -> Address {
+        self.copy_allocator.alloc(bytes, align, offset)
+    }
+
+    fn post_copy(&mut self, obj: ObjectReference, bytes: usize) {
+        if self.space.common().unlog_traced_object {
+            VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC
+                .mark_byte_as_unlogged::<VM>(obj, Ordering::Relaxed);
+        }
+        if self.space.is_marking() {
+            RegionSpace::<VM>::test_and_mark(obj);
+        }
+        RegionSpace::<VM>::add_live_bytes(obj, bytes);
+        self.copied_objects.push(obj);
+        if self.copied_objects.len() >= Self::CAPACITY {
+            self.flush();
+        }
+    }
+}
+
+impl<VM: VMBinding> RegionCopyContext<VM> {
+    const CAPACITY: usize = 4096;
+
+    pub(crate) fn new(
+        tls: VMWorkerThread,
+        context: Arc<AllocatorContext<VM>>,
+        space: &'static RegionSpace<VM>,
+    ) -> Self {
+        RegionCopyContext {
+            copy_allocator: BumpAllocator::new(tls.0, space, context),
+            space,
+            copied_objects: vec![],
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.copied_objects.is_empty() {
+            self.space
+                .copied_objects
+                .lock()
+                .unwrap()
+                .append(&mut self.copied_objects);
+        }
+    }
+}
@@ -28,6 +28,7 @@ use crate::util::heap::gc_trigger::GCTrigger;
 use crate::util::heap::layout::vm_layout::BYTES_IN_CHUNK;
 use crate::util::heap::layout::Mmapper;
 use crate::util::heap::layout::VMMap;
+use crate::util::heap::oom_report::SpaceFragmentation;
 use crate::util::heap::space_descriptor::SpaceDescriptor;
 use crate::util::heap::HeapMeta;
 use crate::util::memory::{self, HugePageSupport, MmapProtection, MmapStrategy};
@@ -86,6 +87,13 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
     ) -> bool {
         if self.will_oom_on_acquire(size) {
             if alloc_options.allow_oom_call {
+                self.get_gc_trigger().report_out_of_memory(
+                    tls,
+                    crate::util::alloc::AllocationError::HeapOutOfMemory,
+                    size,
+                    None,
+                    self.as_space(),
+                );
                 VM::VMCollection::out_of_memory(
                     tls,
                     crate::util::alloc::AllocationError::HeapOutOfMemory,
@@ -394,6 +402,13 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
         self.get_page_resource().get_available_physical_pages()
     }
 
+    /// Describe how free memory is scattered in the space.  This is used in out-of-memory reports,
+    /// and may be called while mutators are running.  Return `None` if the policy does not report
+    /// its fragmentation.
+    fn fragmentation(&self) -> Option<SpaceFragmentation> {
+        None
+    }
+
     fn get_name(&self) -> &'static str {
         self.common().name
     }
@@ -561,6 +576,8 @@ pub struct CommonSpace<VM: VMBinding> {
     /// This field equals to needs_log_bit in the plan constraints.
     // TODO: This should be a constant for performance.
     pub needs_log_bit: bool,
+    /// This field equals to needs_ref_count in the plan constraints.
+    pub needs_ref_count: bool,
     pub unlog_allocated_object: bool,
     pub unlog_traced_object: bool,
 
@@ -637,6 +654,7 @@ impl<VM: VMBinding> CommonSpace<VM> {
             vm_map: args.plan_args.vm_map,
             mmapper: args.plan_args.mmapper,
             needs_log_bit: args.plan_args.constraints.needs_log_bit,
+            needs_ref_count: args.plan_args.constraints.needs_ref_count,
             unlog_allocated_object: args.plan_args.unlog_allocated_object,
             unlog_traced_object: args.plan_args.unlog_traced_object,
             gc_trigger: args.plan_args.gc_trigger,
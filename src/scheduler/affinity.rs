@@ -1,3 +1,4 @@
+use super::numa::NumaTopology;
 use super::worker::ThreadId;
 use crate::util::options::AffinityKind;
 #[cfg(target_os = "linux")]
@@ -31,6 +32,21 @@ impl AffinityKind {
     /// thread affinity. Note that we assume that each GC thread is equivalent to an OS or hardware
     /// thread.
     pub fn resolve_affinity(&self, thread: ThreadId) {
+        let topology = match self {
+            AffinityKind::NumaAware => NumaTopology::discover(),
+            _ => None,
+        };
+        self.resolve_affinity_with_topology(thread, topology.as_ref());
+    }
+
+    /// Resolve affinity of GC thread like [`AffinityKind::resolve_affinity`], using the NUMA
+    /// topology that has been discovered.  `NumaAware` leaves the thread to the OS scheduler if
+    /// there is no topology.
+    pub(crate) fn resolve_affinity_with_topology(
+        &self,
+        thread: ThreadId,
+        topology: Option<&NumaTopology>,
+    ) {
         match self {
             AffinityKind::OsDefault => {}
             AffinityKind::AllInSet(cpuset) => {
@@ -43,6 +59,16 @@ impl AffinityKind {
                 debug!("Set affinity for thread {} to core {}", thread, cpu);
                 bind_current_thread_to_core(cpu);
             }
+            AffinityKind::NumaAware => {
+                if let Some(topology) = topology {
+                    let node = &topology.nodes()[topology.node_of_worker(thread)];
+                    debug!(
+                        "Set affinity for thread {} to NUMA node {} (cpuset {:?})",
+                        thread, node.id, node.cpus
+                    );
+                    bind_current_thread_to_cpuset(node.cpus.as_slice());
+                }
+            }
         }
     }
 }
@@ -0,0 +1,121 @@
+//! A deterministic scheduler for reproducing GC bugs.
+//!
+//! If the option `deterministic_scheduler` is enabled, MMTk creates only one GC worker.  Whenever
+//! the worker needs a work packet, it lists the sources that have packets (its designated work,
+//! its local queue, each open work bucket, and the queue of each NUMA node), and chooses one of
+//! them with a pseudo-random number generator.  With the same seed, the same program and the same
+//! GC triggers, the work packets are executed in the same order, so a bug that depends on the
+//! order of work packets can be replayed with the seed of a failing run.  Different seeds explore
+//! different orders.
+//!
+//! Concurrent work (e.g. concurrent marking) still runs alongside mutators, so its interleaving
+//! with mutators is not deterministic.
+
+use crate::util::options::Options;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Chooses the source of each work packet for the only GC worker.
+pub(crate) struct DeterministicScheduler {
+    seed: u64,
+    rng: Mutex<SplitMix64>,
+}
+
+impl DeterministicScheduler {
+    /// Create the scheduler if the option `deterministic_scheduler` is enabled.  A seed is chosen
+    /// at random if the option `deterministic_scheduler_seed` is 0.  The seed is printed so that the
+    /// run can be replayed.
+    pub fn from_options(options: &Options) -> Option<Self> {
+        if !*options.deterministic_scheduler {
+            return None;
+        }
+        let seed = match *options.deterministic_scheduler_seed {
+            0 => Self::random_seed(),
+            seed => seed,
+        };
+        info!(
+            "Deterministic scheduler seed: {} (replay with deterministic_scheduler_seed={})",
+            seed, seed
+        );
+        Some(Self::new(seed))
+    }
+
+    fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Mutex::new(SplitMix64 { state: seed }),
+        }
+    }
+
+    /// Choose a seed from the current time and the process ID.  The seed is never 0, which means
+    /// "choose at random" in the options.
+    fn random_seed() -> u64 {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        let mut rng = SplitMix64 {
+            state: nanos ^ ((std::process::id() as u64) << 32),
+        };
+        rng.next_u64().max(1)
+    }
+
+    /// The seed in use.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Choose one of `n` sources of work packets.  `n` must not be 0.
+    pub fn choose(&self, n: usize) -> usize {
+        debug_assert!(n > 0);
+        (self.rng.lock().unwrap().next_u64() % n as u64) as usize
+    }
+}
+
+/// The SplitMix64 generator.  It is small and fast, and good enough for choosing work packets.
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splitmix64() {
+        // The first outputs of SplitMix64 seeded with 0, from the reference implementation.
+        let mut rng = SplitMix64 { state: 0 };
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+    }
+
+    #[test]
+    fn test_same_seed_same_choices() {
+        let choices = |seed| {
+            let scheduler = DeterministicScheduler::new(seed);
+            (0..64)
+                .map(|i| scheduler.choose(i % 7 + 1))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(choices(42), choices(42));
+        assert_ne!(choices(42), choices(43));
+        assert!(choices(42)
+            .iter()
+            .enumerate()
+            .all(|(i, &choice)| choice < i % 7 + 1));
+    }
+
+    #[test]
+    fn test_random_seed() {
+        assert_ne!(DeterministicScheduler::random_seed(), 0);
+    }
+}
@@ -14,7 +14,9 @@ pub struct ScheduleCollection;
 
 impl<VM: VMBinding> GCWork<VM> for ScheduleCollection {
     fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
-        // Tell GC trigger that GC started.
+        // Apply the heap bounds changed at run time, and tell GC trigger that GC started.
+        mmtk.gc_trigger.apply_requested_bounds();
+        mmtk.gc_trigger.record_gc_start();
         mmtk.gc_trigger.policy.on_gc_start(mmtk);
 
         // Determine collection kind
@@ -71,7 +73,15 @@ impl<C: GCWorkContext> GCWork<C::VM> for Prepare<C> {
             mmtk.scheduler.work_buckets[WorkBucketStage::Prepare].bulk_add(prepare_mutator_packets);
         }
 
-        for w in &mmtk.scheduler.worker_group.workers_shared {
+        // Only active workers prepare their copy contexts.  Inactive workers do not copy objects.
+        let active_workers = mmtk.scheduler.activate_workers_for_gc(mmtk);
+        for w in mmtk
+            .scheduler
+            .worker_group
+            .workers_shared
+            .iter()
+            .take(active_workers)
+        {
             let result = w.designated_work.push(Box::new(PrepareCollector));
             debug_assert!(result.is_ok());
         }
@@ -149,7 +159,13 @@ impl<C: GCWorkContext + 'static> GCWork<C::VM> for Release<C> {
         );
         mmtk.scheduler.work_buckets[WorkBucketStage::Release].bulk_add(release_mutator_packets);
 
-        for w in &mmtk.scheduler.worker_group.workers_shared {
+        for w in mmtk
+            .scheduler
+            .worker_group
+            .workers_shared
+            .iter()
+            .take(mmtk.scheduler.active_workers())
+        {
             let result = w.designated_work.push(Box::new(ReleaseCollector));
             debug_assert!(result.is_ok());
         }
@@ -236,6 +252,9 @@ impl<C: GCWorkContext> GCWork<C::VM> for StopMutators<C> {
             }
         });
         trace!("stop_all_mutators end");
+        if let Some(tracer) = mmtk.scheduler.tracer() {
+            tracer.begin(trace::PAUSE_TID, "Pause");
+        }
         mmtk.get_plan().notify_mutators_paused(&mmtk.scheduler);
         mmtk.scheduler.notify_mutators_paused(mmtk);
         mmtk.scheduler.work_buckets[WorkBucketStage::Prepare].add(ScanVMSpecificRoots::<C>::new());
@@ -637,9 +656,23 @@ pub trait ProcessEdgesWork:
 
     /// Flush the nodes in ProcessEdgesBase, and create a ScanObjects work packet for it. If the node set is empty,
     /// this method will simply return with no work packet created.
+    ///
+    /// If the affinity is `NumaAware` and the objects are on another NUMA node, the packet is routed
+    /// to the workers on that node instead.
     fn flush(&mut self) {
         let nodes = self.pop_nodes();
         if !nodes.is_empty() {
+            if self.bucket.is_stw() {
+                if let Some(node) = self
+                    .mmtk
+                    .scheduler
+                    .remote_numa_node(self.worker(), nodes[0])
+                {
+                    let work_packet = self.create_scan_work(nodes);
+                    self.mmtk.scheduler.add_numa_work(node, work_packet);
+                    return;
+                }
+            }
             self.start_or_dispatch_scan_work(self.create_scan_work(nodes));
         }
     }
@@ -9,12 +9,15 @@
 pub const EDGES_WORK_BUFFER_SIZE: usize = 4096;
 
 pub(crate) mod affinity;
+mod deterministic;
+pub(crate) mod numa;
 
 #[allow(clippy::module_inception)]
 mod scheduler;
 pub(crate) use scheduler::GCWorkScheduler;
 
 mod stat;
+pub(crate) mod trace;
 mod work_counter;
 
 pub(crate) mod work;
@@ -0,0 +1,194 @@
+//! NUMA topology of the machine.
+//!
+//! Linux lists the NUMA nodes in `/sys/devices/system/node`.  Each node has a directory `nodeN`,
+//! and the file `nodeN/cpulist` lists the CPUs of the node, e.g. `0-15,32-47`.  With the affinity
+//! kind [`crate::util::options::AffinityKind::NumaAware`], GC workers are spread over the nodes in
+//! a round robin fashion, and each worker is bound to the CPUs of its node.  The scheduler then
+//! prefers stealing work from workers on the same node, and routes object scanning work to the
+//! node that holds the objects.
+
+use super::affinity::CoreId;
+use crate::util::Address;
+use std::path::Path;
+
+/// A NUMA node.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct NumaNode {
+    /// The node number used by the OS, i.e. `N` in `nodeN`.
+    pub id: usize,
+    /// The CPUs of the node.
+    pub cpus: Vec<CoreId>,
+}
+
+/// The NUMA nodes that have CPUs, sorted by their IDs.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct NumaTopology {
+    nodes: Vec<NumaNode>,
+}
+
+impl NumaTopology {
+    /// Read the topology of this machine.  Return `None` if it is not available (e.g. not on
+    /// Linux, or `sysfs` is not mounted).
+    pub fn discover() -> Option<Self> {
+        let topology = Self::read("/sys/devices/system/node");
+        if topology.is_none() {
+            warn!("Failed to discover the NUMA topology from /sys/devices/system/node");
+        }
+        topology
+    }
+
+    /// Read the topology from a directory laid out like `/sys/devices/system/node`.  Nodes without
+    /// CPUs (e.g. memory-only nodes) are left out, as no worker can run on them.
+    pub fn read(path: impl AsRef<Path>) -> Option<Self> {
+        let mut nodes = vec![];
+        for entry in std::fs::read_dir(path).ok()? {
+            let entry = entry.ok()?;
+            let name = entry.file_name();
+            let Some(id) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix("node"))
+                .and_then(|id| id.parse::<usize>().ok())
+            else {
+                continue;
+            };
+            let cpulist = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            let cpus = Self::parse_cpulist(cpulist.trim())?;
+            if !cpus.is_empty() {
+                nodes.push(NumaNode { id, cpus });
+            }
+        }
+        if nodes.is_empty() {
+            return None;
+        }
+        nodes.sort_by_key(|node| node.id);
+        Some(Self { nodes })
+    }
+
+    /// Parse a CPU list in the kernel format, e.g. `0-3,8,10-11`.  An empty list is allowed.
+    fn parse_cpulist(cpulist: &str) -> Option<Vec<CoreId>> {
+        let mut cpus = vec![];
+        if cpulist.is_empty() {
+            return Some(cpus);
+        }
+        for split in cpulist.split(',') {
+            match split.split_once('-') {
+                Some((start, end)) => {
+                    let start = start.parse::<CoreId>().ok()?;
+                    let end = end.parse::<CoreId>().ok()?;
+                    if start > end {
+                        return None;
+                    }
+                    cpus.extend(start..=end);
+                }
+                None => cpus.push(split.parse::<CoreId>().ok()?),
+            }
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+        Some(cpus)
+    }
+
+    /// The nodes, sorted by their IDs.  Nodes are referred to by their indices in this slice
+    /// elsewhere in the scheduler.
+    pub fn nodes(&self) -> &[NumaNode] {
+        &self.nodes
+    }
+
+    /// Return the index of the node that a GC worker is assigned to.
+    pub fn node_of_worker(&self, ordinal: usize) -> usize {
+        ordinal % self.nodes.len()
+    }
+
+    /// Return the index of the node with the given OS node ID.
+    pub fn index_of_node(&self, id: usize) -> Option<usize> {
+        self.nodes.iter().position(|node| node.id == id)
+    }
+
+    /// Return the index of the node that holds the memory at `addr`, or `None` if it cannot be
+    /// found out.
+    pub fn node_of_address(&self, addr: Address) -> Option<usize> {
+        self.index_of_node(os_node_of_address(addr)?)
+    }
+}
+
+#[cfg(target_os = "linux")]
+/// Ask the kernel for the node of the page that holds `addr`.  The page is faulted in if it is not
+/// yet.
+fn os_node_of_address(addr: Address) -> Option<usize> {
+    const MPOL_F_NODE: libc::c_ulong = 1;
+    const MPOL_F_ADDR: libc::c_ulong = 1 << 1;
+    let mut node: libc::c_int = 0;
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_get_mempolicy,
+            &mut node as *mut libc::c_int,
+            std::ptr::null_mut::<libc::c_ulong>(),
+            0 as libc::c_ulong,
+            addr.to_mut_ptr::<libc::c_void>(),
+            MPOL_F_NODE | MPOL_F_ADDR,
+        )
+    };
+    (ret == 0 && node >= 0).then_some(node as usize)
+}
+
+#[cfg(not(target_os = "linux"))]
+/// Ask the kernel for the node of the page that holds `addr`.
+fn os_node_of_address(_addr: Address) -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpulist() {
+        assert_eq!(
+            NumaTopology::parse_cpulist("0-3,8,10-11"),
+            Some(vec![0, 1, 2, 3, 8, 10, 11])
+        );
+        assert_eq!(NumaTopology::parse_cpulist("5"), Some(vec![5]));
+        assert_eq!(NumaTopology::parse_cpulist(""), Some(vec![]));
+        assert_eq!(NumaTopology::parse_cpulist("3-1"), None);
+        assert_eq!(NumaTopology::parse_cpulist("0,,1"), None);
+    }
+
+    #[test]
+    fn test_read_sysfs() {
+        let root = std::env::temp_dir().join(format!("mmtk-numa-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (node, cpulist) in [("node1", "4-7\n"), ("node0", "0-3\n"), ("node2", "\n")] {
+            std::fs::create_dir_all(root.join(node)).unwrap();
+            std::fs::write(root.join(node).join("cpulist"), cpulist).unwrap();
+        }
+        std::fs::write(root.join("possible"), "0-2\n").unwrap();
+
+        let topology = NumaTopology::read(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+        let topology = topology.unwrap();
+
+        // Node 2 has no CPUs and is left out.
+        assert_eq!(
+            topology.nodes(),
+            &[
+                NumaNode {
+                    id: 0,
+                    cpus: vec![0, 1, 2, 3]
+                },
+                NumaNode {
+                    id: 1,
+                    cpus: vec![4, 5, 6, 7]
+                },
+            ]
+        );
+        assert_eq!(topology.index_of_node(1), Some(1));
+        assert_eq!(topology.index_of_node(2), None);
+        assert_eq!(topology.node_of_worker(0), 0);
+        assert_eq!(topology.node_of_worker(3), 1);
+    }
+
+    #[test]
+    fn test_read_missing() {
+        assert_eq!(NumaTopology::read("/nonexistent/mmtk-numa"), None);
+    }
+}
@@ -1,23 +1,28 @@
 use self::worker::PollResult;
 
+use super::deterministic::DeterministicScheduler;
 use super::gc_work::ScheduleCollection;
+use super::numa::NumaTopology;
 use super::stat::SchedulerStat;
+use super::trace::{self, GCTracer, TraceArg};
 use super::work_bucket::*;
-use super::worker::{GCWorker, ThreadId, WorkerGroup};
+use super::worker::{GCWorker, GCWorkerShared, ThreadId, WorkerGroup};
 use super::worker_goals::{WorkerGoal, WorkerGoals};
 use super::worker_monitor::{LastParkedResult, WorkerMonitor};
 use super::*;
 use crate::global_state::GcStatus;
 use crate::mmtk::MMTK;
 use crate::util::opaque_pointer::*;
-use crate::util::options::AffinityKind;
+use crate::util::options::{AffinityKind, Options};
+use crate::util::ObjectReference;
 use crate::vm::Collection;
 use crate::vm::VMBinding;
 use crate::Plan;
-use crossbeam::deque::Steal;
+use crossbeam::deque::{self, Injector, Steal};
 use enum_map::{Enum, EnumMap};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 pub struct GCWorkScheduler<VM: VMBinding> {
@@ -29,6 +34,29 @@ pub struct GCWorkScheduler<VM: VMBinding> {
     pub(crate) worker_monitor: Arc<WorkerMonitor>,
     /// How to assign the affinity of each GC thread. Specified by the user.
     affinity: AffinityKind,
+    /// `GCWorker` instances lent to mutators that assist concurrent work.
+    /// See [`GCWorkScheduler::assist_concurrent_work`].
+    assist_workers: Mutex<Vec<GCWorker<VM>>>,
+    /// The `GCWorker` instance that mutators use to run GC on their own threads, if the collection
+    /// is initialized with [`crate::mmtk::MMTK::initialize_collection_inline`].  A mutator holds
+    /// the lock while running GC, so other mutators that want to run GC wait for it.
+    inline_worker: Mutex<Option<Box<GCWorker<VM>>>>,
+    /// The used pages at the end of the last GC.  It is used to estimate the work of a nursery GC
+    /// if the option `dynamic_gc_threads` is enabled.
+    used_pages_after_last_gc: AtomicUsize,
+    /// The NUMA topology if the affinity is `NumaAware`.
+    numa: Option<NumaTopology>,
+    /// For each worker, the other workers in the order to steal work from.  Workers on the same
+    /// NUMA node come first.
+    steal_order: Vec<Vec<usize>>,
+    /// Work packets routed to each NUMA node, if there are more than one node.  Workers take
+    /// packets from the queue of their own node before anything else, and from the queues of
+    /// other nodes after everything else.
+    node_queues: Vec<Injector<Box<dyn GCWork<VM>>>>,
+    /// Records GC events if the option `gc_trace_file` is set.
+    tracer: Option<GCTracer>,
+    /// Chooses the order of work packets if the option `deterministic_scheduler` is enabled.
+    deterministic: Option<DeterministicScheduler>,
 }
 
 // FIXME: GCWorkScheduler should be naturally Sync, but we cannot remove this `impl` yet.
@@ -37,7 +65,7 @@ pub struct GCWorkScheduler<VM: VMBinding> {
 unsafe impl<VM: VMBinding> Sync for GCWorkScheduler<VM> {}
 
 impl<VM: VMBinding> GCWorkScheduler<VM> {
-    pub fn new(num_workers: usize, affinity: AffinityKind) -> Arc<Self> {
+    pub(crate) fn new(num_workers: usize, options: &Options) -> Arc<Self> {
         let worker_monitor: Arc<WorkerMonitor> = Arc::new(WorkerMonitor::new(num_workers));
         let worker_group = WorkerGroup::new(num_workers);
 
@@ -69,24 +97,227 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
             }
         }
 
+        let affinity = (*options.thread_affinity).clone();
+        let numa = match affinity {
+            AffinityKind::NumaAware => NumaTopology::discover(),
+            _ => None,
+        };
+        let steal_order = Self::steal_order(num_workers, numa.as_ref());
+        let node_queues = match numa {
+            Some(ref numa) if numa.nodes().len() > 1 => {
+                numa.nodes().iter().map(|_| Injector::new()).collect()
+            }
+            _ => vec![],
+        };
+
         Arc::new(Self {
             work_buckets,
             worker_group,
             worker_monitor,
             affinity,
+            assist_workers: Mutex::new(vec![]),
+            inline_worker: Mutex::new(None),
+            used_pages_after_last_gc: AtomicUsize::new(0),
+            numa,
+            steal_order,
+            node_queues,
+            tracer: GCTracer::from_options(options, num_workers),
+            deterministic: DeterministicScheduler::from_options(options),
         })
     }
 
+    /// Return true if the option `deterministic_scheduler` is enabled.  Then there is only one
+    /// worker, and it gets all work packets with [`GCWorkScheduler::poll_deterministic`].
+    pub(crate) fn is_deterministic(&self) -> bool {
+        self.deterministic.is_some()
+    }
+
+    /// The seed of the deterministic scheduler, if the option `deterministic_scheduler` is
+    /// enabled.
+    pub(crate) fn deterministic_seed(&self) -> Option<u64> {
+        self.deterministic.as_ref().map(|d| d.seed())
+    }
+
+    /// The tracer of GC events, if the option `gc_trace_file` is set.
+    pub(crate) fn tracer(&self) -> Option<&GCTracer> {
+        self.tracer.as_ref()
+    }
+
+    /// Decide the order in which each worker steals work from the other workers.  Without NUMA
+    /// topology, the order is the ordinals of the workers.  Otherwise, the workers on the same node
+    /// come first.
+    fn steal_order(num_workers: usize, numa: Option<&NumaTopology>) -> Vec<Vec<usize>> {
+        (0..num_workers)
+            .map(|ordinal| {
+                let mut others: Vec<usize> = (0..num_workers).filter(|&i| i != ordinal).collect();
+                if let Some(numa) = numa {
+                    let node = numa.node_of_worker(ordinal);
+                    others.sort_by_key(|&i| numa.node_of_worker(i) != node);
+                }
+                others
+            })
+            .collect()
+    }
+
     pub fn num_workers(&self) -> usize {
         self.worker_group.as_ref().worker_count()
     }
 
+    /// Return the number of workers that are active in the current GC.  It is the same as
+    /// [`GCWorkScheduler::num_workers`] unless the option `dynamic_gc_threads` is enabled.
+    pub fn active_workers(&self) -> usize {
+        self.worker_monitor.active_workers()
+    }
+
+    /// Decide the number of active workers for the current GC if the option `dynamic_gc_threads`
+    /// is enabled.  This is called in the `Prepare` work packet, after the plan is prepared, so
+    /// the plan knows whether it is a nursery GC.  Return the number of active workers.
+    pub(crate) fn activate_workers_for_gc(&self, mmtk: &'static MMTK<VM>) -> usize {
+        if !*mmtk.options.dynamic_gc_threads {
+            return self.num_workers();
+        }
+
+        let plan = mmtk.get_plan();
+        let used_pages = plan.get_used_pages();
+        let work_pages = match plan.generational() {
+            Some(gen) if gen.is_current_gc_nursery() => {
+                used_pages.saturating_sub(self.used_pages_after_last_gc.load(Ordering::Relaxed))
+            }
+            _ => used_pages,
+        };
+        let work_bytes = work_pages << crate::util::constants::LOG_BYTES_IN_PAGE;
+        let active_workers = work_bytes
+            .div_ceil(*mmtk.options.heap_bytes_per_gc_thread)
+            .clamp(1, self.num_workers());
+        debug!(
+            "Activate {} of {} GC workers for {} bytes of work",
+            active_workers,
+            self.num_workers(),
+            work_bytes
+        );
+        self.worker_monitor.set_active_workers(active_workers);
+        active_workers
+    }
+
     /// Create GC threads for the first time.  It will also create the `GCWorker` instances.
     ///
     /// Currently GC threads only include worker threads, and we currently have only one worker
     /// group.  We may add more worker groups in the future.
     pub fn spawn_gc_threads(self: &Arc<Self>, mmtk: &'static MMTK<VM>, tls: VMThread) {
         self.worker_group.initial_spawn(tls, mmtk);
+        self.create_assist_workers(mmtk);
+    }
+
+    /// Create the `GCWorker` instance for mutators to run GC on their own threads, instead of
+    /// spawning GC threads.  See [`crate::mmtk::MMTK::initialize_collection_inline`].
+    pub(crate) fn initialize_inline_worker(
+        self: &Arc<Self>,
+        mmtk: &'static MMTK<VM>,
+        tls: VMThread,
+    ) {
+        let worker = self.worker_group.initial_create_inline(tls, mmtk);
+        *self.inline_worker.lock().unwrap() = Some(worker);
+        // Other mutators may assist while one mutator runs concurrent work inline.
+        self.create_assist_workers(mmtk);
+    }
+
+    /// Create the `GCWorker` instances for mutators that assist concurrent work.  At most as many
+    /// mutators as GC workers can assist at the same time.
+    fn create_assist_workers(self: &Arc<Self>, mmtk: &'static MMTK<VM>) {
+        // Assisting mutators would run packets out of the order chosen by the deterministic
+        // scheduler.
+        if *mmtk.options.concurrent_mark_assist_ratio <= 0.0 || self.is_deterministic() {
+            return;
+        }
+        let num_workers = self.num_workers();
+        let mut assist_workers = self.assist_workers.lock().unwrap();
+        for i in 0..num_workers {
+            assist_workers.push(GCWorker::new(
+                mmtk,
+                num_workers + i,
+                self.clone(),
+                Arc::new(GCWorkerShared::new(None)),
+                deque::Worker::new_fifo(),
+            ));
+        }
+    }
+
+    /// Return true if GC runs on mutator threads instead of GC threads.
+    pub(crate) fn is_inline(&self) -> bool {
+        self.worker_group.is_inline()
+    }
+
+    /// Run the GC requested by mutators (if any) on the current mutator thread until it finishes.
+    /// If another mutator is running GC, this waits for it first.
+    pub(crate) fn run_gc_inline(&self, tls: VMMutatorThread) {
+        let mut inline_worker = self.inline_worker.lock().unwrap();
+        let worker = inline_worker
+            .as_mut()
+            .expect("GC cannot run inline: collection is not initialized with initialize_collection_inline().");
+        let mmtk = worker.mmtk;
+        worker.run_inline(tls, mmtk);
+    }
+
+    /// Let a mutator execute concurrent work packets (such as concurrent marking) on its own
+    /// thread, until it has done about `budget` bytes of work.  The mutator borrows an idle
+    /// `GCWorker` instance to execute the packets.  This does nothing if there is no concurrent
+    /// work, or if no `GCWorker` instance is available.
+    ///
+    /// While the mutator executes a packet, the packets it creates are neither in the `Concurrent`
+    /// bucket nor visible to anyone else.  The bucket counts the assisting mutator until the
+    /// packets are returned to it, so that GC workers and mutators polling the bucket do not see
+    /// it drained and start the final mark pause too early.  The `tls` of the borrowed `GCWorker`
+    /// is set to `tls` while the mutator executes packets.
+    ///
+    /// Arguments:
+    /// * `tls`: The mutator thread that executes the packets.
+    /// * `budget`: The amount of work, in bytes, that the mutator does.
+    pub(crate) fn assist_concurrent_work(&self, tls: VMMutatorThread, budget: usize) {
+        let bucket = &self.work_buckets[WorkBucketStage::Concurrent];
+        if budget == 0 || !bucket.is_enabled() || !bucket.is_open() || bucket.is_empty() {
+            return;
+        }
+        let Some(mut worker) = self.assist_workers.lock().unwrap().pop() else {
+            return;
+        };
+        let mmtk = worker.mmtk;
+
+        // Count the mutator before it takes any packet, so that the bucket is never seen empty
+        // while a packet is taken out of it.
+        bucket.begin_assist();
+        worker.tls = VMWorkerThread(tls.0);
+        worker.assist_budget = Some(budget);
+        while !worker.is_assist_budget_exhausted() {
+            let mut work = match bucket.poll_one() {
+                Steal::Success(work) => work,
+                Steal::Retry => continue,
+                Steal::Empty => break,
+            };
+            let typename = work.get_type_name();
+            if let Some(tracer) = self.tracer() {
+                tracer.begin(trace::worker_tid(worker.ordinal), typename);
+            }
+            work.do_work(&mut worker, mmtk);
+            if let Some(tracer) = self.tracer() {
+                tracer.end(trace::worker_tid(worker.ordinal), typename);
+            }
+            // Give the new packets back to GC workers.
+            while let Some(work) = worker.local_work_buffer.pop() {
+                bucket.add_boxed(work);
+            }
+        }
+        worker.assist_budget = None;
+        worker.tls = VMWorkerThread(VMThread::UNINITIALIZED);
+        bucket.end_assist();
+
+        self.assist_workers.lock().unwrap().push(worker);
+    }
+
+    /// Return true if there are concurrent work packets (such as concurrent marking) waiting to be
+    /// executed.
+    pub(crate) fn has_pending_concurrent_work(&self) -> bool {
+        let bucket = &self.work_buckets[WorkBucketStage::Concurrent];
+        bucket.is_enabled() && bucket.is_open() && !bucket.is_empty()
     }
 
     /// Ask all GC workers to exit for forking.
@@ -119,7 +350,36 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
 
     /// Resolve the affinity of a thread.
     pub fn resolve_affinity(&self, thread: ThreadId) {
-        self.affinity.resolve_affinity(thread);
+        self.affinity
+            .resolve_affinity_with_topology(thread, self.numa.as_ref());
+    }
+
+    /// Return the NUMA node that holds `object` if it is not the node of `worker`, so the work of
+    /// scanning it should be routed to that node.  Return `None` if work is not routed, e.g. the
+    /// affinity is not `NumaAware`, or there is only one node.
+    ///
+    /// This asks the kernel about the page of the object, so it should be called once for many
+    /// objects, e.g. with the first object of a packet.
+    pub(crate) fn remote_numa_node(
+        &self,
+        worker: &GCWorker<VM>,
+        object: ObjectReference,
+    ) -> Option<usize> {
+        if self.node_queues.is_empty() || worker.ordinal >= self.num_workers() {
+            return None;
+        }
+        let numa = self.numa.as_ref().unwrap();
+        let node = numa.node_of_address(object.to_raw_address())?;
+        (node != numa.node_of_worker(worker.ordinal)).then_some(node)
+    }
+
+    /// Add a work packet to be executed by a worker on the given NUMA node (see
+    /// [`GCWorkScheduler::remote_numa_node`]).  Workers on other nodes will take the packet if
+    /// there is nothing else to do.  The packet must belong to the stop-the-world stage in
+    /// progress.
+    pub(crate) fn add_numa_work(&self, node: usize, work: impl GCWork<VM>) {
+        self.node_queues[node].push(Box::new(work));
+        self.worker_monitor.notify_work_available(false);
     }
 
     /// Request a GC to be scheduled.  Called by mutator via `GCTrigger`.
@@ -291,6 +551,13 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
             buckets_updated = buckets_updated || bucket_opened;
             if bucket_opened {
                 probe!(mmtk, bucket_opened, id);
+                if let Some(tracer) = self.tracer() {
+                    tracer.instant(
+                        trace::GC_TID,
+                        "BUCKET_OPEN",
+                        Some(("stage", TraceArg::Str(format!("{:?}", id)))),
+                    );
+                }
                 new_packets = new_packets || !bucket.is_drained();
                 if new_packets {
                     // Quit the loop. There are already new packets in the newly opened buckets.
@@ -334,6 +601,14 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         }
     }
 
+    /// Return true if any open bucket has work packets.
+    fn has_open_work(&self) -> bool {
+        self.work_buckets
+            .values()
+            .any(|bucket| bucket.is_enabled() && bucket.is_open() && !bucket.is_empty())
+            || self.node_queues.iter().any(|queue| !queue.is_empty())
+    }
+
     /// Check if all the work buckets are empty
     pub(crate) fn assert_all_open_buckets_are_empty(&self) {
         let mut error_example = None;
@@ -351,15 +626,38 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         if let Some(id) = error_example {
             panic!("Some open buckets (such as {:?}) are not empty.", id);
         }
+        for (node, queue) in self.node_queues.iter().enumerate() {
+            assert!(
+                queue.is_empty(),
+                "The queue of NUMA node {} is not empty.",
+                node
+            );
+        }
     }
 
     /// Get a schedulable work packet without retry.
     fn poll_schedulable_work_once(&self, worker: &GCWorker<VM>) -> Steal<Box<dyn GCWork<VM>>> {
+        if let Some(ref deterministic) = self.deterministic {
+            return self.poll_deterministic(deterministic, worker);
+        }
         let mut should_retry = false;
         // Try find a packet that can be processed only by this worker.
         if let Some(w) = worker.shared.designated_work.pop() {
             return Steal::Success(w);
         }
+        // Try get a packet routed to the NUMA node of this worker.
+        let node = self
+            .numa
+            .as_ref()
+            .filter(|_| !self.node_queues.is_empty())
+            .map(|numa| numa.node_of_worker(worker.ordinal));
+        if let Some(node) = node {
+            match self.node_queues[node].steal() {
+                Steal::Success(w) => return Steal::Success(w),
+                Steal::Retry => should_retry = true,
+                _ => {}
+            }
+        }
         // Try get a packet from a work bucket.
         for work_bucket in self.work_buckets.values() {
             match work_bucket.poll(&worker.local_work_buffer) {
@@ -368,12 +666,21 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
                 _ => {}
             }
         }
-        // Try steal some packets from any worker
-        for (id, worker_shared) in self.worker_group.workers_shared.iter().enumerate() {
-            if id == worker.ordinal {
+        // Try steal some packets from any worker, starting from the workers on the same node.
+        for &id in self.steal_order[worker.ordinal].iter() {
+            let worker_shared = &self.worker_group.workers_shared[id];
+            match worker_shared.stealer.as_ref().unwrap().steal() {
+                Steal::Success(w) => return Steal::Success(w),
+                Steal::Retry => should_retry = true,
+                _ => {}
+            }
+        }
+        // Try get a packet routed to other NUMA nodes, in case no worker on those nodes is active.
+        for (id, queue) in self.node_queues.iter().enumerate() {
+            if Some(id) == node {
                 continue;
             }
-            match worker_shared.stealer.as_ref().unwrap().steal() {
+            match queue.steal() {
                 Steal::Success(w) => return Steal::Success(w),
                 Steal::Retry => should_retry = true,
                 _ => {}
@@ -386,6 +693,49 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         }
     }
 
+    /// Get a work packet for the only worker of the deterministic scheduler.  Among the designated
+    /// work, the local queue of the worker, the open buckets and the NUMA node queues, choose one
+    /// that has packets, and take one packet from it.
+    fn poll_deterministic(
+        &self,
+        deterministic: &DeterministicScheduler,
+        worker: &GCWorker<VM>,
+    ) -> Steal<Box<dyn GCWork<VM>>> {
+        enum Source {
+            Designated,
+            Local,
+            Bucket(WorkBucketStage),
+            Node(usize),
+        }
+        let mut sources = vec![];
+        if !worker.shared.designated_work.is_empty() {
+            sources.push(Source::Designated);
+        }
+        if !worker.local_work_buffer.is_empty() {
+            sources.push(Source::Local);
+        }
+        for (stage, bucket) in self.work_buckets.iter() {
+            if bucket.is_enabled() && bucket.is_open() && !bucket.is_empty() {
+                sources.push(Source::Bucket(stage));
+            }
+        }
+        for (node, queue) in self.node_queues.iter().enumerate() {
+            if !queue.is_empty() {
+                sources.push(Source::Node(node));
+            }
+        }
+        if sources.is_empty() {
+            return Steal::Empty;
+        }
+        let work = match sources[deterministic.choose(sources.len())] {
+            Source::Designated => worker.shared.designated_work.pop(),
+            Source::Local => worker.local_work_buffer.pop(),
+            Source::Bucket(stage) => return self.work_buckets[stage].poll_one(),
+            Source::Node(node) => return self.node_queues[node].steal(),
+        };
+        work.map_or(Steal::Retry, Steal::Success)
+    }
+
     /// Get a schedulable work packet.
     fn poll_schedulable_work(&self, worker: &GCWorker<VM>) -> Option<Box<dyn GCWork<VM>>> {
         // Loop until we successfully get a packet.
@@ -405,11 +755,30 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         }
     }
 
+    /// Called by the worker that runs GC on a mutator thread to get a schedulable work packet.
+    /// Return `None` instead of parking if there're no available packets.
+    pub(crate) fn poll_inline(&self, worker: &GCWorker<VM>) -> Option<Box<dyn GCWork<VM>>> {
+        self.poll_schedulable_work(worker)
+    }
+
+    /// Called when the worker that runs GC on a mutator thread runs out of work packets.  As it is
+    /// the only worker, it does what the last parked worker does.  Return true if there are more
+    /// work packets to execute, or false if the worker should return to the mutator.
+    pub(crate) fn on_inline_worker_out_of_work(&self, worker: &GCWorker<VM>) -> bool {
+        let result = self
+            .worker_monitor
+            .act_as_last_parked(|goals| self.on_last_parked(worker, goals));
+        !matches!(result, LastParkedResult::ParkSelf)
+    }
+
     /// Called by workers to get a schedulable work packet.
     /// Park the worker if there're no available packets.
+    /// Inactive workers (see [`GCWorkScheduler::activate_workers_for_gc`]) park without polling.
     pub(crate) fn poll(&self, worker: &GCWorker<VM>) -> PollResult<VM> {
-        if let Some(work) = self.poll_schedulable_work(worker) {
-            return Ok(work);
+        if self.worker_monitor.is_worker_active(worker.ordinal) {
+            if let Some(work) = self.poll_schedulable_work(worker) {
+                return Ok(work);
+            }
         }
         self.poll_slow(worker)
     }
@@ -417,8 +786,10 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
     fn poll_slow(&self, worker: &GCWorker<VM>) -> PollResult<VM> {
         loop {
             // Retry polling
-            if let Some(work) = self.poll_schedulable_work(worker) {
-                return Ok(work);
+            if self.worker_monitor.is_worker_active(worker.ordinal) {
+                if let Some(work) = self.poll_schedulable_work(worker) {
+                    return Ok(work);
+                }
             }
 
             let ordinal = worker.ordinal;
@@ -449,6 +820,13 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
                 // We are in the middle of GC, and the last GC worker parked.
                 trace!("The last worker parked during GC.  Try to find more work to do...");
 
+                // An inactive worker parks without polling, so an active worker may have missed
+                // packets that were added while this worker was finishing its last packet.
+                if !self.worker_monitor.is_worker_active(worker.ordinal) && self.has_open_work() {
+                    trace!("The last parked worker is inactive.  Wake up active workers.");
+                    return LastParkedResult::WakeAll;
+                }
+
                 // During GC, if all workers parked, all open buckets must have been drained.
                 self.assert_all_open_buckets_are_empty();
 
@@ -504,6 +882,9 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
                 // We set the eBPF trace point here so that bpftrace scripts can start recording
                 // work packet events before the `ScheduleCollection` work packet starts.
                 probe!(mmtk, gc_start);
+                if let Some(tracer) = self.tracer() {
+                    tracer.begin(trace::GC_TID, "GC");
+                }
 
                 {
                     let mut gc_start_time = worker.mmtk.state.gc_start_time.borrow_mut();
@@ -563,9 +944,25 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
 
         // All other workers are parked, so it is safe to access the Plan instance mutably.
         probe!(mmtk, plan_end_of_gc_begin);
+        if let Some(tracer) = self.tracer() {
+            tracer.begin(trace::GC_TID, "end_of_gc");
+        }
         let plan_mut: &mut dyn Plan<VM = VM> = unsafe { mmtk.get_plan_mut() };
         plan_mut.end_of_gc(worker.tls);
         probe!(mmtk, plan_end_of_gc_end);
+        if let Some(tracer) = self.tracer() {
+            tracer.end(trace::GC_TID, "end_of_gc");
+        }
+
+        // Return free memory to the OS after the plan has released all the memory it frees.
+        mmtk.gc_trigger.uncommit_free_memory();
+
+        // Inform the binding of the heap usage thresholds crossed in this GC.
+        mmtk.gc_trigger.check_usage_thresholds(worker.tls);
+
+        // Remember the used pages to estimate the work of the next nursery GC.
+        self.used_pages_after_last_gc
+            .store(mmtk.get_plan().get_used_pages(), Ordering::Relaxed);
 
         // Compute the elapsed time of the GC.
         let start_time = {
@@ -573,6 +970,7 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
             gc_start_time.take().expect("GC not started yet?")
         };
         let elapsed = start_time.elapsed();
+        mmtk.gc_trigger.record_gc_end(elapsed);
 
         info!(
             "End of GC ({}/{} pages, took {} ms)",
@@ -583,6 +981,9 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
 
         // USDT tracepoint for the end of GC.
         probe!(mmtk, gc_end);
+        if let Some(tracer) = self.tracer() {
+            tracer.end(trace::GC_TID, "GC");
+        }
 
         if *mmtk.get_options().count_live_bytes_in_gc {
             // Aggregate the live bytes
@@ -620,6 +1021,9 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
 
         // Set to NotInGC after everything, and right before resuming mutators.
         mmtk.set_gc_status(GcStatus::NotInGC);
+        if let Some(tracer) = self.tracer() {
+            tracer.end(trace::PAUSE_TID, "Pause");
+        }
         <VM as VMBinding>::VMCollection::resume_mutators(worker.tls);
 
         concurrent_work_scheduled
@@ -0,0 +1,343 @@
+//! An in-process tracer of GC events.
+//!
+//! If the option `gc_trace_file` is set, MMTk records the execution of work packets, the opening
+//! of work buckets, GCs and stop-the-world pauses in memory, at the same points as the USDT
+//! probes used by `tools/tracing/timeline`.  The events are written to the file in the Chrome
+//! trace event format (a JSON file), which can be opened by Perfetto UI
+//! (<https://ui.perfetto.dev>) or `chrome://tracing`.  Unlike the bpftrace-based tools, this does
+//! not need root access.
+//!
+//! Each thread records events into its own ring buffer, so the latest events are kept if the
+//! buffers are full.
+
+use crate::util::options::Options;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The thread that GC begin and end events are recorded on.
+pub(crate) const GC_TID: usize = 0;
+/// The thread that pauses (from stopping to resuming mutators) are recorded on.
+pub(crate) const PAUSE_TID: usize = 1;
+/// The thread that events from mutators are recorded on.
+pub(crate) const MUTATOR_TID: usize = 2;
+
+/// Return the trace thread of a GC worker.
+pub(crate) fn worker_tid(ordinal: usize) -> usize {
+    ordinal + 3
+}
+
+/// Record how a plan decided to collect the current GC, as an instant event on [`GC_TID`].  Plans
+/// call it next to their `probe!` of the decision.  The argument is only computed if events are
+/// traced.
+pub(crate) fn record_gc_decision(
+    tracer: Option<&GCTracer>,
+    name: &'static str,
+    arg_name: &'static str,
+    arg: impl FnOnce() -> TraceArg,
+) {
+    if let Some(tracer) = tracer {
+        tracer.instant(GC_TID, name, Some((arg_name, arg())));
+    }
+}
+
+/// The kind of an event in the Chrome trace event format.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Phase {
+    /// The beginning of a duration.
+    Begin,
+    /// The end of a duration.
+    End,
+    /// An event without a duration.
+    Instant,
+}
+
+impl Phase {
+    fn as_str(self) -> &'static str {
+        match self {
+            Phase::Begin => "B",
+            Phase::End => "E",
+            Phase::Instant => "i",
+        }
+    }
+}
+
+/// The value of an argument of an event.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum TraceArg {
+    Bool(bool),
+    Str(String),
+}
+
+impl fmt::Display for TraceArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceArg::Bool(value) => write!(f, "{}", value),
+            TraceArg::Str(value) => write_json_str(f, value),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TraceEvent {
+    name: &'static str,
+    phase: Phase,
+    tid: usize,
+    /// The time since the tracer was created.
+    ts: Duration,
+    arg: Option<(&'static str, TraceArg)>,
+}
+
+/// Records GC events, and writes them as Chrome trace events.
+pub(crate) struct GCTracer {
+    path: PathBuf,
+    start: Instant,
+    /// The number of events kept for each thread.
+    capacity: usize,
+    /// The events of each thread, from the oldest to the latest.
+    buffers: Vec<Mutex<VecDeque<TraceEvent>>>,
+}
+
+impl GCTracer {
+    /// Create a tracer if the option `gc_trace_file` is set.  `num_workers` is the number of GC
+    /// workers.  Mutators that assist concurrent work use as many more workers.
+    pub fn from_options(options: &Options, num_workers: usize) -> Option<Self> {
+        if options.gc_trace_file.is_empty() {
+            return None;
+        }
+        Some(Self::new(
+            &*options.gc_trace_file,
+            *options.gc_trace_buffer_events,
+            worker_tid(num_workers * 2),
+        ))
+    }
+
+    fn new(path: impl Into<PathBuf>, capacity: usize, num_threads: usize) -> Self {
+        Self {
+            path: path.into(),
+            start: Instant::now(),
+            capacity,
+            buffers: (0..num_threads)
+                .map(|_| Mutex::new(VecDeque::new()))
+                .collect(),
+        }
+    }
+
+    /// The file that the trace is written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn record(
+        &self,
+        tid: usize,
+        name: &'static str,
+        phase: Phase,
+        arg: Option<(&'static str, TraceArg)>,
+    ) {
+        let Some(buffer) = self.buffers.get(tid) else {
+            return;
+        };
+        let event = TraceEvent {
+            name,
+            phase,
+            tid,
+            ts: self.start.elapsed(),
+            arg,
+        };
+        let mut buffer = buffer.lock().unwrap();
+        if buffer.len() == self.capacity {
+            buffer.pop_front();
+        }
+        buffer.push_back(event);
+    }
+
+    /// Record the beginning of a duration on the thread `tid`.
+    pub fn begin(&self, tid: usize, name: &'static str) {
+        self.record(tid, name, Phase::Begin, None);
+    }
+
+    /// Record the end of a duration on the thread `tid`.  It ends the last duration that has not
+    /// ended on the thread.
+    pub fn end(&self, tid: usize, name: &'static str) {
+        self.record(tid, name, Phase::End, None);
+    }
+
+    /// Record an event without a duration on the thread `tid`.
+    pub fn instant(&self, tid: usize, name: &'static str, arg: Option<(&'static str, TraceArg)>) {
+        self.record(tid, name, Phase::Instant, arg);
+    }
+
+    /// Write the recorded events to the file `path()`.
+    pub fn write_file(&self) -> io::Result<()> {
+        let mut file = io::BufWriter::new(std::fs::File::create(&self.path)?);
+        self.write_json(&mut file)?;
+        file.flush()
+    }
+
+    /// Write the recorded events as a Chrome trace event JSON object.
+    pub fn write_json(&self, w: &mut impl Write) -> io::Result<()> {
+        let mut events: Vec<TraceEvent> = vec![];
+        let mut used_tids = vec![];
+        for buffer in self.buffers.iter() {
+            let buffer = buffer.lock().unwrap();
+            if let Some(event) = buffer.front() {
+                used_tids.push(event.tid);
+            }
+            // The beginnings of the oldest durations may have been dropped from the ring buffer.
+            // Skip their ends, so the remaining durations still nest.
+            let mut depth = 0usize;
+            for event in buffer.iter() {
+                match event.phase {
+                    Phase::Begin => depth += 1,
+                    Phase::End if depth == 0 => continue,
+                    Phase::End => depth -= 1,
+                    Phase::Instant => {}
+                }
+                events.push(event.clone());
+            }
+        }
+        events.sort_by_key(|event| event.ts);
+
+        writeln!(w, "{{\"traceEvents\":[")?;
+        let mut first = true;
+        for tid in used_tids {
+            let name = match tid {
+                GC_TID => "GC".to_string(),
+                PAUSE_TID => "Pauses".to_string(),
+                MUTATOR_TID => "Mutators".to_string(),
+                _ => format!("GC worker {}", tid - worker_tid(0)),
+            };
+            if !first {
+                writeln!(w, ",")?;
+            }
+            first = false;
+            write!(
+                w,
+                "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":0,\"tid\":{},\"args\":{{\"name\":\"{}\"}}}}",
+                tid, name
+            )?;
+        }
+        for event in events.iter() {
+            if !first {
+                writeln!(w, ",")?;
+            }
+            first = false;
+            write!(
+                w,
+                "{{\"name\":{},\"ph\":\"{}\",\"pid\":0,\"tid\":{},\"ts\":{:.3}",
+                JsonStr(event.name),
+                event.phase.as_str(),
+                event.tid,
+                event.ts.as_nanos() as f64 / 1000.0
+            )?;
+            if event.phase == Phase::Instant {
+                // Show instant events on their threads rather than across the process.
+                write!(w, ",\"s\":\"t\"")?;
+            }
+            if let Some((key, ref value)) = event.arg {
+                write!(w, ",\"args\":{{{}:{}}}", JsonStr(key), value)?;
+            }
+            write!(w, "}}")?;
+        }
+        writeln!(w, "\n]}}")
+    }
+}
+
+/// Formats a string as a JSON string literal.
+struct JsonStr<'a>(&'a str);
+
+impl fmt::Display for JsonStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_json_str(f, self.0)
+    }
+}
+
+fn write_json_str(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(tracer: &GCTracer) -> String {
+        let mut out = vec![];
+        tracer.write_json(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_json() {
+        let tracer = GCTracer::new("unused", 16, worker_tid(1));
+        tracer.begin(GC_TID, "GC");
+        tracer.instant(
+            GC_TID,
+            "BUCKET_OPEN",
+            Some(("stage", TraceArg::Str("Closure".to_string()))),
+        );
+        tracer.begin(worker_tid(0), "mmtk::Packet<\"a\">");
+        tracer.end(worker_tid(0), "mmtk::Packet<\"a\">");
+        tracer.end(GC_TID, "GC");
+
+        let json = write(&tracer);
+        assert!(json.starts_with("{\"traceEvents\":[\n"));
+        assert!(json.ends_with("\n]}\n"));
+        assert!(json.contains(
+            "{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":0,\"tid\":3,\"args\":{\"name\":\"GC worker 0\"}}"
+        ));
+        assert!(json.contains("\"name\":\"GC\",\"ph\":\"B\",\"pid\":0,\"tid\":0,"));
+        assert!(json.contains("\"s\":\"t\",\"args\":{\"stage\":\"Closure\"}}"));
+        assert!(
+            json.contains("\"name\":\"mmtk::Packet<\\\"a\\\">\",\"ph\":\"E\",\"pid\":0,\"tid\":3,")
+        );
+        assert_eq!(json.matches("\"ph\":").count(), 7);
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let tracer = GCTracer::new("unused", 4, worker_tid(1));
+        let tid = worker_tid(0);
+        tracer.begin(tid, "A");
+        tracer.begin(tid, "B");
+        tracer.end(tid, "B");
+        tracer.end(tid, "A");
+        tracer.instant(tid, "C", Some(("full_heap", TraceArg::Bool(true))));
+
+        // Only the last four events are kept, and the end of "A" whose beginning was dropped is
+        // not written.
+        let json = write(&tracer);
+        assert!(!json.contains("\"name\":\"A\""));
+        assert!(json.contains("\"name\":\"B\",\"ph\":\"E\""));
+        assert!(json.contains("\"args\":{\"full_heap\":true}"));
+        // Events of unknown threads are ignored.
+        tracer.begin(worker_tid(5), "D");
+        assert!(!write(&tracer).contains("\"name\":\"D\""));
+    }
+
+    #[test]
+    fn test_record_gc_decision() {
+        let tracer = GCTracer::new("unused", 16, worker_tid(1));
+        record_gc_decision(Some(&tracer), "gen_full_heap", "full_heap", || {
+            TraceArg::Bool(false)
+        });
+        let json = write(&tracer);
+        assert!(json.contains("\"name\":\"gen_full_heap\",\"ph\":\"i\",\"pid\":0,\"tid\":0,"));
+        assert!(json.contains("\"args\":{\"full_heap\":false}"));
+
+        // The argument is not computed without a tracer.
+        record_gc_decision(None, "gen_full_heap", "full_heap", || unreachable!());
+    }
+}
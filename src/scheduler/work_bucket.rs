@@ -3,7 +3,7 @@ use super::*;
 use crate::vm::VMBinding;
 use crossbeam::deque::{Injector, Steal, Worker};
 use enum_map::Enum;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
 pub(super) struct BucketQueue<VM: VMBinding> {
@@ -21,6 +21,10 @@ impl<VM: VMBinding> BucketQueue<VM> {
         self.queue.is_empty()
     }
 
+    fn steal(&self) -> Steal<Box<dyn GCWork<VM>>> {
+        self.queue.steal()
+    }
+
     fn steal_batch_and_pop(
         &self,
         dest: &Worker<Box<dyn GCWork<VM>>>,
@@ -104,6 +108,10 @@ pub struct WorkBucket<VM: VMBinding> {
     /// recursively, such as ephemerons and Java-style SoftReference and finalizers.  Sentinels
     /// can be used repeatedly to discover and process more such objects.
     sentinel: Mutex<Option<Box<dyn GCWork<VM>>>>,
+    /// The number of mutators that are executing packets taken from this bucket.  The packets
+    /// they create are not in the bucket until the packets they execute are done, so the bucket
+    /// is not drained while this is not zero.  See [`GCWorkScheduler::assist_concurrent_work`].
+    assisting: AtomicUsize,
 }
 
 impl<VM: VMBinding> WorkBucket<VM> {
@@ -117,6 +125,7 @@ impl<VM: VMBinding> WorkBucket<VM> {
             monitor,
             can_open: None,
             sentinel: Mutex::new(None),
+            assisting: AtomicUsize::new(0),
         }
     }
 
@@ -170,7 +179,20 @@ impl<VM: VMBinding> WorkBucket<VM> {
     }
 
     pub fn is_drained(&self) -> bool {
-        !self.is_enabled() || (self.is_open() && self.is_empty())
+        !self.is_enabled()
+            || (self.is_open() && self.is_empty() && self.assisting.load(Ordering::SeqCst) == 0)
+    }
+
+    /// Record that a mutator starts taking packets from this bucket and executing them.
+    pub(crate) fn begin_assist(&self) {
+        self.assisting.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Record that a mutator has finished executing packets from this bucket, and has added the
+    /// packets they created to this bucket.
+    pub(crate) fn end_assist(&self) {
+        let old = self.assisting.fetch_sub(1, Ordering::SeqCst);
+        debug_assert!(old > 0);
     }
 
     /// Close the bucket
@@ -245,6 +267,20 @@ impl<VM: VMBinding> WorkBucket<VM> {
         }
     }
 
+    /// Get one work packet from this bucket, without moving other packets to the local queue of
+    /// the worker.  This is used by the deterministic scheduler, so that each packet is chosen
+    /// separately.
+    pub(crate) fn poll_one(&self) -> Steal<Box<dyn GCWork<VM>>> {
+        if !self.is_enabled() || !self.is_open() || self.is_empty() {
+            return Steal::Empty;
+        }
+        if let Some(prioritized_queue) = self.prioritized_queue.as_ref() {
+            prioritized_queue.steal().or_else(|| self.queue.steal())
+        } else {
+            self.queue.steal()
+        }
+    }
+
     pub fn set_open_condition(
         &mut self,
         pred: impl Fn(&GCWorkScheduler<VM>) -> bool + Send + 'static,
@@ -101,6 +101,10 @@ pub struct GCWorker<VM: VMBinding> {
     pub shared: Arc<GCWorkerShared<VM>>,
     /// Local work packet queue.
     pub local_work_buffer: deque::Worker<Box<dyn GCWork<VM>>>,
+    /// The amount of work (in bytes of scanned objects) this worker can still do if the worker is
+    /// lent to a mutator to assist concurrent work.  `None` if the worker is a GC thread.
+    /// See [`GCWorkScheduler::assist_concurrent_work`].
+    pub(crate) assist_budget: Option<usize>,
 }
 
 unsafe impl<VM: VMBinding> Sync for GCWorkerShared<VM> {}
@@ -148,16 +152,30 @@ impl<VM: VMBinding> GCWorker<VM> {
             mmtk,
             shared,
             local_work_buffer,
+            assist_budget: None,
+        }
+    }
+
+    /// Is this worker lent to a mutator, and has it done the amount of work it was asked to do?
+    /// Work packets that can be executed by mutators should check this and return early.
+    pub(crate) fn is_assist_budget_exhausted(&self) -> bool {
+        self.assist_budget == Some(0)
+    }
+
+    /// Consume the budget of a worker lent to a mutator.  It has no effect for GC threads.
+    pub(crate) fn consume_assist_budget(&mut self, bytes: usize) {
+        if let Some(budget) = self.assist_budget.as_mut() {
+            *budget = budget.saturating_sub(bytes);
         }
     }
 
     const LOCALLY_CACHED_WORK_PACKETS: usize = 16;
 
     /// Add a work packet to the work queue and mark it with a higher priority.
-    /// If the bucket is open, the packet will be pushed to the local queue, otherwise it will be
-    /// pushed to the global bucket with a higher priority.
+    /// If the bucket is open and enabled, the packet will be pushed to the local queue, otherwise
+    /// it will be pushed to the global bucket with a higher priority.
     pub fn add_work_prioritized(&mut self, bucket: WorkBucketStage, work: impl GCWork<VM>) {
-        if !self.scheduler().work_buckets[bucket].is_open()
+        if !self.is_bucket_schedulable(bucket)
             || self.local_work_buffer.len() >= Self::LOCALLY_CACHED_WORK_PACKETS
         {
             self.scheduler.work_buckets[bucket].add_prioritized(Box::new(work));
@@ -167,10 +185,10 @@ impl<VM: VMBinding> GCWorker<VM> {
     }
 
     /// Add a work packet to the work queue.
-    /// If the bucket is open, the packet will be pushed to the local queue, otherwise it will be
-    /// pushed to the global bucket.
+    /// If the bucket is open and enabled, the packet will be pushed to the local queue, otherwise
+    /// it will be pushed to the global bucket.
     pub fn add_work(&mut self, bucket: WorkBucketStage, work: impl GCWork<VM>) {
-        if !self.scheduler().work_buckets[bucket].is_open()
+        if !self.is_bucket_schedulable(bucket)
             || self.local_work_buffer.len() >= Self::LOCALLY_CACHED_WORK_PACKETS
         {
             self.scheduler.work_buckets[bucket].add(work);
@@ -179,6 +197,13 @@ impl<VM: VMBinding> GCWorker<VM> {
         self.local_work_buffer.push(Box::new(work));
     }
 
+    /// Can packets of `bucket` be executed now?  Packets of a disabled bucket (e.g. the
+    /// `Concurrent` bucket when concurrent work is suspended for a pause) must stay in the bucket.
+    fn is_bucket_schedulable(&self, bucket: WorkBucketStage) -> bool {
+        let bucket = &self.scheduler().work_buckets[bucket];
+        bucket.is_open() && bucket.is_enabled()
+    }
+
     /// Get the scheduler. There is only one scheduler per MMTk instance.
     pub fn scheduler(&self) -> &GCWorkScheduler<VM> {
         &self.scheduler
@@ -196,12 +221,15 @@ impl<VM: VMBinding> GCWorker<VM> {
     /// 3. Poll from open global work-buckets
     /// 4. Steal from other workers
     fn poll(&mut self) -> PollResult<VM> {
-        if let Some(work) = self.shared.designated_work.pop() {
-            return Ok(work);
-        }
+        // The deterministic scheduler chooses among the local queues, too.
+        if !self.scheduler.is_deterministic() {
+            if let Some(work) = self.shared.designated_work.pop() {
+                return Ok(work);
+            }
 
-        if let Some(work) = self.local_work_buffer.pop() {
-            return Ok(work);
+            if let Some(work) = self.local_work_buffer.pop() {
+                return Ok(work);
+            }
         }
 
         self.scheduler().poll(self)
@@ -243,8 +271,6 @@ impl<VM: VMBinding> GCWorker<VM> {
                 // The worker is asked to exit.  Break from the loop.
                 break;
             };
-            // probe! expands to an empty block on unsupported platforms
-            #[allow(unused_variables)]
             let typename = work.get_type_name();
 
             #[cfg(feature = "bpftrace_workaround")]
@@ -254,7 +280,14 @@ impl<VM: VMBinding> GCWorker<VM> {
             std::hint::black_box(unsafe { *(typename.as_ptr()) });
 
             probe!(mmtk, work, typename.as_ptr(), typename.len());
+            let tracer = mmtk.scheduler.tracer();
+            if let Some(tracer) = tracer {
+                tracer.begin(trace::worker_tid(self.ordinal), typename);
+            }
             work.do_work_with_stat(&mut self, mmtk);
+            if let Some(tracer) = tracer {
+                tracer.end(trace::worker_tid(self.ordinal), typename);
+            }
         }
         debug!(
             "Worker exiting. ordinal: {}, {}",
@@ -265,6 +298,54 @@ impl<VM: VMBinding> GCWorker<VM> {
 
         mmtk.scheduler.surrender_gc_worker(self);
     }
+
+    /// Run work packets on the current thread, which is a mutator thread, until the GC requested
+    /// by mutators (if any) has finished.  This is used instead of [`GCWorker::run`] if the
+    /// collection is initialized with [`crate::mmtk::MMTK::initialize_collection_inline`].
+    ///
+    /// Unlike [`GCWorker::run`], this never parks.  When there is no packet left, this worker does
+    /// what the last parked worker does, i.e. opens more buckets, finishes the GC, or starts a GC
+    /// that has been requested.
+    ///
+    /// Arguments:
+    /// * `tls`: The mutator thread that runs the GC.
+    /// * `mmtk`: A reference to an MMTk instance.
+    pub(crate) fn run_inline(&mut self, tls: VMMutatorThread, mmtk: &'static MMTK<VM>) {
+        WORKER_ORDINAL.with(|x| x.store(self.ordinal, Ordering::SeqCst));
+        self.tls = VMWorkerThread(tls.0);
+        loop {
+            probe!(mmtk, work_poll);
+            let work = if self.scheduler.is_deterministic() {
+                self.scheduler.poll_inline(self)
+            } else {
+                match self.shared.designated_work.pop() {
+                    Some(work) => Some(work),
+                    None => match self.local_work_buffer.pop() {
+                        Some(work) => Some(work),
+                        None => self.scheduler.poll_inline(self),
+                    },
+                }
+            };
+            let Some(mut work) = work else {
+                if self.scheduler.on_inline_worker_out_of_work(self) {
+                    continue;
+                }
+                break;
+            };
+            let typename = work.get_type_name();
+            probe!(mmtk, work, typename.as_ptr(), typename.len());
+            let tracer = mmtk.scheduler.tracer();
+            if let Some(tracer) = tracer {
+                tracer.begin(trace::worker_tid(self.ordinal), typename);
+            }
+            work.do_work_with_stat(self, mmtk);
+            if let Some(tracer) = tracer {
+                tracer.end(trace::worker_tid(self.ordinal), typename);
+            }
+        }
+        self.tls = VMWorkerThread(VMThread::UNINITIALIZED);
+        WORKER_ORDINAL.with(|x| x.store(ThreadId::MAX, Ordering::SeqCst));
+    }
 }
 
 /// Stateful part of [`WorkerGroup`].
@@ -289,6 +370,9 @@ enum WorkerCreationState<VM: VMBinding> {
         #[allow(clippy::vec_box)]
         workers: Vec<Box<GCWorker<VM>>>,
     },
+    /// No worker threads are spawn.  The only `GCWorker` struct is used by mutators to run GC on
+    /// their own threads.  See [`crate::mmtk::MMTK::initialize_collection_inline`].
+    Inline,
 }
 
 /// A worker group to manage all the GC workers.
@@ -339,6 +423,36 @@ impl<VM: VMBinding> WorkerGroup<VM> {
         *state = Some(WorkerCreationState::Spawned);
     }
 
+    /// Create the only `GCWorker` struct for running GC on mutator threads, instead of spawning
+    /// GC worker threads.  `tls` is the thread that initializes the collection.
+    pub fn initial_create_inline(
+        &self,
+        tls: VMThread,
+        mmtk: &'static MMTK<VM>,
+    ) -> Box<GCWorker<VM>> {
+        let mut state = self.state.lock().unwrap();
+
+        let WorkerCreationState::Initial { local_work_queues } = state.take().unwrap() else {
+            panic!("GCWorker structs have already been created");
+        };
+
+        let mut workers = self.create_workers(local_work_queues, mmtk);
+        assert_eq!(workers.len(), 1, "Only one GCWorker can run GC inline");
+        let mut worker = workers.pop().unwrap();
+        worker.copy = crate::plan::create_gc_worker_context(VMWorkerThread(tls), mmtk);
+
+        *state = Some(WorkerCreationState::Inline);
+        worker
+    }
+
+    /// Return true if GC runs on mutator threads instead of GC worker threads.
+    pub fn is_inline(&self) -> bool {
+        matches!(
+            *self.state.lock().unwrap(),
+            Some(WorkerCreationState::Inline)
+        )
+    }
+
     /// Respawn GC threads after stopping for forking.
     pub fn respawn(&self, tls: VMThread) {
         let mut state = self.state.lock().unwrap();
@@ -2,8 +2,10 @@
 //!
 //! -   allowing workers to park,
 //! -   letting the last parked worker take action, and
-//! -   letting workers and mutators notify workers when workers are given things to do.
+//! -   letting workers and mutators notify workers when workers are given things to do, and
+//! -   keeping some workers parked if not all workers are active in a GC.
 
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Condvar, Mutex};
 
 use super::{
@@ -28,6 +30,9 @@ pub(crate) enum LastParkedResult {
 ///
 /// -   It allows workers to park and unpark.
 /// -   It allows mutators to notify workers to schedule a GC.
+/// -   It keeps inactive workers parked.  Only the workers whose ordinals are smaller than the
+///     number of active workers poll work packets.  The others are counted as parked, and wait on
+///     a separate `CondVar` so that they are not woken up when work packets are available.
 pub(crate) struct WorkerMonitor {
     /// The synchronized part.
     sync: Mutex<WorkerMonitorSync>,
@@ -35,6 +40,12 @@ pub(crate) struct WorkerMonitor {
     /// -   any work packets available, and
     /// -   any field in `sync.goals.requests` set to true.
     workers_have_anything_to_do: Condvar,
+    /// The number of active workers.  It is only changed while holding the mutex `sync`, but it
+    /// can be read without the mutex.
+    active_workers: AtomicUsize,
+    /// Inactive workers wait on this.  Notified if more workers are activated, or if workers
+    /// should exit.
+    workers_activated: Condvar,
 }
 
 /// The synchronized part of `WorkerMonitor`.
@@ -92,6 +103,30 @@ impl WorkerMonitor {
                 goals: Default::default(),
             }),
             workers_have_anything_to_do: Default::default(),
+            active_workers: AtomicUsize::new(worker_count),
+            workers_activated: Default::default(),
+        }
+    }
+
+    /// Return the number of active workers.
+    pub fn active_workers(&self) -> usize {
+        self.active_workers.load(Ordering::Relaxed)
+    }
+
+    /// Is the worker with the given ordinal active?  Inactive workers should not poll work
+    /// packets.
+    pub fn is_worker_active(&self, ordinal: usize) -> bool {
+        ordinal < self.active_workers()
+    }
+
+    /// Set the number of active workers.  Newly activated workers are woken up.  Workers that are
+    /// no longer active will finish the work packets they are executing, and then stay parked.
+    pub fn set_active_workers(&self, active_workers: usize) {
+        let sync = self.sync.lock().unwrap();
+        debug_assert!(active_workers > 0 && active_workers <= sync.parker.worker_count);
+        let old = self.active_workers.swap(active_workers, Ordering::Relaxed);
+        if active_workers > old {
+            self.workers_activated.notify_all();
         }
     }
 
@@ -115,6 +150,16 @@ impl WorkerMonitor {
         }
     }
 
+    /// Call `on_last_parked` as if the last worker parked, but do not park or wait.  This is used
+    /// when the only worker runs GC on a mutator thread, and it has run out of work packets.
+    pub fn act_as_last_parked<F>(&self, on_last_parked: F) -> LastParkedResult
+    where
+        F: FnOnce(&mut WorkerGoals) -> LastParkedResult,
+    {
+        let mut sync = self.sync.lock().unwrap();
+        on_last_parked(&mut sync.goals)
+    }
+
     /// Park a worker and wait on the CondVar `workers_have_anything_to_do`.
     ///
     /// If it is the last worker parked, `on_last_parked` will be called.
@@ -145,6 +190,7 @@ impl WorkerMonitor {
         );
 
         let mut should_wait = false;
+        let active = self.is_worker_active(ordinal);
 
         if all_parked {
             trace!("Worker {} is the last worker parked.", ordinal);
@@ -154,17 +200,26 @@ impl WorkerMonitor {
                     should_wait = true;
                 }
                 LastParkedResult::WakeSelf => {
-                    // Continue without waiting.
+                    if !active {
+                        // This worker cannot do the work.  Let an active worker do it instead.
+                        self.notify_work_available(false);
+                        should_wait = true;
+                    }
+                    // Otherwise, continue without waiting.
                 }
                 LastParkedResult::WakeAll => {
                     self.notify_work_available(true);
+                    if matches!(sync.goals.current(), Some(WorkerGoal::StopForFork)) {
+                        // Inactive workers need to exit, too.
+                        self.workers_activated.notify_all();
+                    }
                 }
             }
         } else {
             should_wait = true;
         }
 
-        if should_wait {
+        if should_wait && active {
             // Notes on CondVar usage:
             //
             // Conditional variables are usually tested in a loop while holding a mutex
@@ -219,6 +274,19 @@ impl WorkerMonitor {
             //     conditions listed above are both false before blocking.  If either condition is
             //     true, the last parked worker will take action.
             sync = self.workers_have_anything_to_do.wait(sync).unwrap();
+
+            if !self.is_worker_active(ordinal) {
+                // This worker was deactivated while it was parked.  The notification may have been
+                // meant for an active worker, so pass it on.
+                self.notify_work_available(false);
+            }
+        }
+
+        // Inactive workers stay parked until they are activated, or asked to exit.
+        while !self.is_worker_active(ordinal)
+            && !matches!(sync.goals.current(), Some(WorkerGoal::StopForFork))
+        {
+            sync = self.workers_activated.wait(sync).unwrap();
         }
 
         // Unpark this worker.
@@ -340,4 +408,41 @@ mod tests {
         // `on_last_parked` should only be called once.
         assert_eq!(on_last_parked_called.load(Ordering::SeqCst), 1);
     }
+
+    /// Test that inactive workers stay parked when all workers are woken up, and unpark when they
+    /// are activated.
+    #[test]
+    fn test_inactive_workers_stay_parked() {
+        let number_threads = 4;
+        let worker_monitor = Arc::new(WorkerMonitor::new(number_threads));
+        worker_monitor.set_active_workers(1);
+        let threads_unparked = AtomicUsize::new(0);
+
+        std::thread::scope(|scope| {
+            for ordinal in 0..number_threads {
+                let worker_monitor = worker_monitor.clone();
+                let threads_unparked = &threads_unparked;
+                scope.spawn(move || {
+                    worker_monitor
+                        .park_and_wait(ordinal, |_goals| super::LastParkedResult::WakeAll)
+                        .unwrap();
+                    // Only the active worker unparks before the others are activated.
+                    let unparked = threads_unparked.fetch_add(1, Ordering::SeqCst);
+                    if unparked == 0 {
+                        assert_eq!(ordinal, 0);
+                    }
+                });
+            }
+
+            while threads_unparked.load(Ordering::SeqCst) == 0 {
+                std::thread::yield_now();
+            }
+            // Give the inactive workers a chance to unpark by mistake.
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            assert_eq!(threads_unparked.load(Ordering::SeqCst), 1);
+            worker_monitor.set_active_workers(number_threads);
+        });
+
+        assert_eq!(threads_unparked.load(Ordering::SeqCst), number_threads);
+    }
 }
@@ -1,5 +1,7 @@
 use crate::global_state::GlobalState;
+use crate::scheduler::GCWorkScheduler;
 use crate::util::address::Address;
+use crate::util::alloc::pacing::{AllocationPacer, AllocationPacing};
 #[cfg(feature = "analysis")]
 use crate::util::analysis::AnalysisManager;
 use crate::util::heap::gc_trigger::GCTrigger;
@@ -18,7 +20,7 @@ use crate::vm::{ActivePlan, Collection};
 use downcast_rs::Downcast;
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 /// A list of errors that MMTk can encounter during allocation.
 pub enum AllocationError {
     /// The specified heap size is too small for the given program to continue.
@@ -243,9 +245,11 @@ pub(crate) fn assert_allocation_args<VM: VMBinding>(size: usize, align: usize, o
 /// The context an allocator needs to access in order to perform allocation.
 pub struct AllocatorContext<VM: VMBinding> {
     alloc_options: AllocationOptionsHolder,
+    pacer: AllocationPacer,
     pub state: Arc<GlobalState>,
     pub options: Arc<Options>,
     pub gc_trigger: Arc<GCTrigger<VM>>,
+    pub(crate) scheduler: Arc<GCWorkScheduler<VM>>,
     #[cfg(feature = "analysis")]
     pub analysis_manager: Arc<AnalysisManager<VM>>,
 }
@@ -254,9 +258,11 @@ impl<VM: VMBinding> AllocatorContext<VM> {
     pub fn new(mmtk: &MMTK<VM>) -> Self {
         Self {
             alloc_options: AllocationOptionsHolder::new(AllocationOptions::default()),
+            pacer: AllocationPacer::new(),
             state: mmtk.state.clone(),
             options: mmtk.options.clone(),
             gc_trigger: mmtk.gc_trigger.clone(),
+            scheduler: mmtk.scheduler.clone(),
             #[cfg(feature = "analysis")]
             analysis_manager: mmtk.analysis_manager.clone(),
         }
@@ -273,6 +279,21 @@ impl<VM: VMBinding> AllocatorContext<VM> {
     pub fn get_alloc_options(&self) -> AllocationOptions {
         self.alloc_options.get_alloc_options()
     }
+
+    /// Set the limits of allocation pacing, or use the options if `limits` is `None`.
+    pub fn set_allocation_pacing(&self, limits: Option<AllocationPacing>) {
+        self.pacer.set_limits(limits);
+    }
+
+    /// Delay the current mutator if it allocates too fast under the current pressure.  This does
+    /// nothing unless the option `allocation_pacing` is enabled.
+    pub(crate) fn pace_allocation(&self, bytes: usize) {
+        if !*self.options.allocation_pacing {
+            return;
+        }
+        let pressure = self.gc_trigger.allocation_pressure();
+        self.pacer.pace(&self.options, bytes, pressure);
+    }
 }
 
 /// A trait which implements allocation routines. Every allocator needs to implements this trait.
@@ -403,6 +424,20 @@ pub trait Allocator<VM: VMBinding>: Downcast {
         let mut emergency_collection = false;
         let mut previous_result_zero = false;
 
+        // Slow down mutators that allocate fast under GC pressure.  Only do this at safepoints
+        // because the delay also delays GC.
+        if is_mutator && self.get_context().get_alloc_options().at_safepoint {
+            let allocated_size = if self.does_thread_local_allocation() {
+                crate::util::conversions::raw_align_up(
+                    size,
+                    self.get_thread_local_buffer_granularity(),
+                )
+            } else {
+                size
+            };
+            self.get_context().pace_allocation(allocated_size);
+        }
+
         loop {
             // Try to allocate using the slow path
             let result = if is_mutator && stress_test && *self.get_context().options.precise_stress
@@ -479,6 +514,24 @@ pub trait Allocator<VM: VMBinding>: Downcast {
                     }
                 }
 
+                // Pay for the allocation by doing some concurrent marking work, if concurrent
+                // marking is in progress.
+                let assist_ratio = *self.get_context().options.concurrent_mark_assist_ratio;
+                if assist_ratio > 0.0 {
+                    let allocated_size = if self.does_thread_local_allocation() {
+                        crate::util::conversions::raw_align_up(
+                            size,
+                            self.get_thread_local_buffer_granularity(),
+                        )
+                    } else {
+                        size
+                    };
+                    let budget = (allocated_size as f64 * assist_ratio) as usize;
+                    self.get_context()
+                        .scheduler
+                        .assist_concurrent_work(VMMutatorThread(self.get_tls()), budget);
+                }
+
                 return result;
             }
 
@@ -513,6 +566,13 @@ pub trait Allocator<VM: VMBinding>: Downcast {
                 if fail_with_oom {
                     // Note that we throw a `HeapOutOfMemory` error here and return a null ptr back to the VM
                     trace!("Throw HeapOutOfMemory!");
+                    self.get_context().gc_trigger.report_out_of_memory(
+                        tls,
+                        AllocationError::HeapOutOfMemory,
+                        size,
+                        Some(align),
+                        self.get_space(),
+                    );
                     VM::VMCollection::out_of_memory(tls, AllocationError::HeapOutOfMemory);
                     self.get_context()
                         .state
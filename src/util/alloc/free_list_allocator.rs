@@ -242,6 +242,8 @@ impl<VM: VMBinding> FreeListAllocator<VM> {
     /// Add a block to the given bin in the available block lists. Depending on which available block list we are using, this
     /// method may add the block to available_blocks, or available_blocks_stress.
     fn add_to_available_blocks(&mut self, bin: usize, block: Block, stress: bool) {
+        // We will allocate into the block.
+        self.space.record_fresh_block(block);
         if stress {
             debug_assert!(*self.context.options.precise_stress);
             self.available_blocks_stress[bin].push(block);
@@ -260,6 +262,9 @@ impl<VM: VMBinding> FreeListAllocator<VM> {
         if cfg!(feature = "eager_sweeping") {
             // We have swept blocks in the last GC. If we run out of available blocks, there is nothing we can do.
             None
+        } else if !self.space.can_sweep_lazily() {
+            // Concurrent marking is in progress. Mark bits are incomplete, and we cannot sweep blocks now.
+            None
         } else {
             // Get blocks from unswept_blocks and attempt to sweep
             loop {
@@ -418,6 +423,8 @@ impl<VM: VMBinding> FreeListAllocator<VM> {
     pub(crate) fn prepare(&mut self) {}
 
     pub(crate) fn release(&mut self) {
+        // In a nursery GC, blocks that have not been allocated into need no sweeping.
+        let fresh_only = self.space.in_nursery_gc();
         for bin in 0..MI_BIN_FULL {
             let unswept = self.unswept_blocks.get_mut(bin).unwrap();
 
@@ -433,7 +440,7 @@ impl<VM: VMBinding> FreeListAllocator<VM> {
                 // For lazy sweeping, we move blocks from available and consumed to unswept.  When
                 // an allocator tries to use them, they will sweep the block.
                 if cfg!(not(feature = "eager_sweeping")) {
-                    unswept.append(list);
+                    list.move_to_unswept(unswept, fresh_only);
                 }
             };
 
@@ -38,3 +38,7 @@ pub use markcompact_allocator::MarkCompactAllocator;
 
 /// Embedded metadata pages
 pub(crate) mod embedded_meta_data;
+
+/// Delaying mutators that allocate fast under GC pressure
+pub(crate) mod pacing;
+pub use pacing::AllocationPacing;
@@ -0,0 +1,160 @@
+//! Allocation pacing.
+//!
+//! If the option `allocation_pacing` is enabled, a mutator is delayed in the allocation slow path
+//! when the heap is close to its limit, or while concurrent work is in progress (see
+//! [`crate::util::heap::gc_trigger::GCTrigger::allocation_pressure`]).  Under the pressure `p`
+//! (from 0 to 1), each mutator is held to the rate `rate / p` bytes per second.  A mutator that
+//! allocates slower than that is never delayed, and one that allocates faster is delayed in
+//! proportion to how much it exceeds the rate.
+
+use crate::util::options::Options;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+/// The limits of allocation pacing for a mutator.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AllocationPacing {
+    /// The allocation rate in bytes per second that the mutator is held to when the heap is full.
+    pub rate: usize,
+    /// The longest delay of a single allocation.
+    pub max_delay: Duration,
+}
+
+impl AllocationPacing {
+    /// The limits set by the options.
+    pub(crate) fn from_options(options: &Options) -> Self {
+        Self {
+            rate: *options.allocation_pacing_rate,
+            max_delay: Duration::from_micros(*options.allocation_pacing_max_delay_us as u64),
+        }
+    }
+
+    /// Are the limits valid?  The rate must be positive.
+    pub(crate) fn validate(&self) -> bool {
+        self.rate > 0
+    }
+
+    /// Return how long a mutator should be delayed before allocating `bytes`, given the time it
+    /// was last paced, and the current allocation pressure.
+    fn delay(&self, last: Option<Instant>, now: Instant, bytes: usize, pressure: f64) -> Duration {
+        let Some(last) = last else {
+            return Duration::ZERO;
+        };
+        if pressure <= 0f64 {
+            return Duration::ZERO;
+        }
+        // The time it takes to allocate `bytes` at the allowed rate, minus the time since the last
+        // allocation.
+        let allowed_rate = self.rate as f64 / pressure;
+        let budget = Duration::from_secs_f64(bytes as f64 / allowed_rate);
+        (last + budget)
+            .saturating_duration_since(now)
+            .min(self.max_delay)
+    }
+}
+
+/// Paces the allocation of a mutator.
+///
+/// Like `AllocationOptionsHolder`, this is only accessed by the thread that owns the allocators,
+/// but it needs to be `Sync` because `AllocatorContext` is shared in an `Arc`.
+pub(crate) struct AllocationPacer {
+    state: RefCell<AllocationPacerState>,
+}
+
+unsafe impl Sync for AllocationPacer {}
+
+struct AllocationPacerState {
+    /// The limits set for this mutator, or `None` to use the options.
+    limits: Option<AllocationPacing>,
+    /// The time when the mutator was last paced, after the delay.
+    last: Option<Instant>,
+}
+
+impl AllocationPacer {
+    pub fn new() -> Self {
+        Self {
+            state: RefCell::new(AllocationPacerState {
+                limits: None,
+                last: None,
+            }),
+        }
+    }
+
+    pub fn set_limits(&self, limits: Option<AllocationPacing>) {
+        self.state.borrow_mut().limits = limits;
+    }
+
+    /// Delay the current thread if needed before it allocates `bytes` under the given pressure.
+    pub fn pace(&self, options: &Options, bytes: usize, pressure: f64) {
+        let mut state = self.state.borrow_mut();
+        let limits = state
+            .limits
+            .unwrap_or_else(|| AllocationPacing::from_options(options));
+        let now = Instant::now();
+        let delay = limits.delay(state.last, now, bytes, pressure);
+        if !delay.is_zero() {
+            trace!(
+                "Pace allocation of {} bytes under pressure {:.2}: delay {:?}",
+                bytes,
+                pressure,
+                delay
+            );
+            std::thread::sleep(delay);
+        }
+        state.last = Some(now + delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACING: AllocationPacing = AllocationPacing {
+        rate: 1024 * 1024,
+        max_delay: Duration::from_millis(100),
+    };
+
+    #[test]
+    fn test_no_delay() {
+        let now = Instant::now();
+        // The first allocation is not delayed.
+        assert_eq!(PACING.delay(None, now, 1024 * 1024, 1.0), Duration::ZERO);
+        // No delay without pressure.
+        assert_eq!(
+            PACING.delay(Some(now), now, 1024 * 1024, 0.0),
+            Duration::ZERO
+        );
+        // No delay if the mutator allocates slower than the allowed rate.
+        let last = now - Duration::from_secs(1);
+        assert_eq!(PACING.delay(Some(last), now, 1024, 1.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_delay_in_proportion() {
+        let now = Instant::now();
+        // 64KB takes 62.5ms at 1MB/s under full pressure, and half as long under half pressure.
+        assert_eq!(
+            PACING.delay(Some(now), now, 64 * 1024, 1.0),
+            Duration::from_micros(62500)
+        );
+        assert_eq!(
+            PACING.delay(Some(now), now, 64 * 1024, 0.5),
+            Duration::from_micros(31250)
+        );
+        // The time since the last allocation counts towards the budget.
+        let last = now - Duration::from_micros(12500);
+        assert_eq!(
+            PACING.delay(Some(last), now, 64 * 1024, 1.0),
+            Duration::from_millis(50)
+        );
+    }
+
+    #[test]
+    fn test_max_delay() {
+        let now = Instant::now();
+        assert_eq!(
+            PACING.delay(Some(now), now, 1024 * 1024, 1.0),
+            PACING.max_delay
+        );
+    }
+}
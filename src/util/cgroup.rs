@@ -0,0 +1,163 @@
+//! Memory limits of control groups (cgroups).
+//!
+//! A process in a container is usually limited by the memory limit of its cgroup, which may be
+//! much smaller than the physical memory of the host.  We read the limit from the cgroup file
+//! system, for both cgroup v1 (`memory.limit_in_bytes`) and cgroup v2 (`memory.max`).  The limits
+//! of the ancestor cgroups also apply, so the smallest limit from the cgroup of the process up to
+//! the root is used.
+
+use std::path::{Path, PathBuf};
+
+/// The cgroup file system that we read the memory limit from.  The paths can be changed for
+/// testing.
+pub(crate) struct CgroupFs {
+    /// The file that lists the cgroups of the process, usually `/proc/self/cgroup`.
+    proc_self_cgroup: PathBuf,
+    /// The directory where the cgroup file systems are mounted, usually `/sys/fs/cgroup`.
+    mount_root: PathBuf,
+}
+
+impl Default for CgroupFs {
+    fn default() -> Self {
+        Self::new("/proc/self/cgroup", "/sys/fs/cgroup")
+    }
+}
+
+impl CgroupFs {
+    pub fn new(proc_self_cgroup: impl Into<PathBuf>, mount_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_self_cgroup: proc_self_cgroup.into(),
+            mount_root: mount_root.into(),
+        }
+    }
+
+    /// Return the memory limit of the process in bytes, or `None` if the process is not in a
+    /// cgroup, or no cgroup along its path has a memory limit.
+    pub fn memory_limit(&self) -> Option<u64> {
+        let cgroups = std::fs::read_to_string(&self.proc_self_cgroup).ok()?;
+        // Each line is `hierarchy-ID:controller-list:cgroup-path`.  A v1 memory controller takes
+        // precedence, as the memory controller cannot be enabled in both versions.
+        let mut v2_path = None;
+        for line in cgroups.lines() {
+            let mut fields = line.splitn(3, ':');
+            let (Some(id), Some(controllers), Some(path)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            if controllers.split(',').any(|c| c == "memory") {
+                return Self::min_limit(
+                    &self.mount_root.join("memory"),
+                    path,
+                    "memory.limit_in_bytes",
+                );
+            }
+            if id == "0" && controllers.is_empty() {
+                v2_path = Some(path.to_string());
+            }
+        }
+        v2_path.and_then(|path| Self::min_limit(&self.mount_root, &path, "memory.max"))
+    }
+
+    /// Return the smallest limit in the given file of the cgroup at `path` and its ancestors.
+    /// Cgroups that are not visible under the mount point (e.g. outside the cgroup namespace of
+    /// the process) are skipped.
+    fn min_limit(mount: &Path, path: &str, file: &str) -> Option<u64> {
+        let mut dir = mount.join(path.trim_start_matches('/'));
+        let mut limit: Option<u64> = None;
+        loop {
+            if let Some(l) = Self::read_limit(&dir.join(file)) {
+                limit = Some(limit.map_or(l, |min| min.min(l)));
+            }
+            if dir.as_path() == mount || !dir.pop() {
+                break;
+            }
+        }
+        limit
+    }
+
+    /// Read a limit file.  `max` means no limit.
+    fn read_limit(file: &Path) -> Option<u64> {
+        let content = std::fs::read_to_string(file).ok()?;
+        let content = content.trim();
+        if content == "max" {
+            None
+        } else {
+            content.parse().ok()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a fake cgroup file system in a temporary directory.  `files` are paths relative to
+    /// the mount root, and their contents.
+    fn fake_cgroup_fs(name: &str, proc_self_cgroup: &str, files: &[(&str, &str)]) -> CgroupFs {
+        let root =
+            std::env::temp_dir().join(format!("mmtk-cgroup-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let mount_root = root.join("sys/fs/cgroup");
+        for (path, content) in files {
+            let file = mount_root.join(path);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, content).unwrap();
+        }
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("cgroup"), proc_self_cgroup).unwrap();
+        CgroupFs::new(root.join("cgroup"), mount_root)
+    }
+
+    #[test]
+    fn test_v2_limit() {
+        let fs = fake_cgroup_fs(
+            "v2",
+            "0::/pod/container\n",
+            &[
+                ("memory.max", "max\n"),
+                ("pod/memory.max", "2147483648\n"),
+                ("pod/container/memory.max", "max\n"),
+            ],
+        );
+        assert_eq!(fs.memory_limit(), Some(2147483648));
+    }
+
+    #[test]
+    fn test_v2_no_limit() {
+        let fs = fake_cgroup_fs("v2-none", "0::/\n", &[("memory.max", "max\n")]);
+        assert_eq!(fs.memory_limit(), None);
+    }
+
+    #[test]
+    fn test_v1_limit() {
+        let fs = fake_cgroup_fs(
+            "v1",
+            "5:cpu,cpuacct:/\n4:memory:/docker/abc\n0::/\n",
+            &[
+                ("memory/memory.limit_in_bytes", "9223372036854771712\n"),
+                ("memory/docker/abc/memory.limit_in_bytes", "536870912\n"),
+                ("memory.max", "1024\n"),
+            ],
+        );
+        // The v2 limit is ignored, as the memory controller is in v1.
+        assert_eq!(fs.memory_limit(), Some(536870912));
+    }
+
+    #[test]
+    fn test_namespaced_path() {
+        // The cgroup of the process is not visible under the mount point, but the root is.
+        let fs = fake_cgroup_fs(
+            "namespaced",
+            "0::/host/path\n",
+            &[("memory.max", "1073741824\n")],
+        );
+        assert_eq!(fs.memory_limit(), Some(1073741824));
+    }
+
+    #[test]
+    fn test_no_cgroup() {
+        let fs = CgroupFs::new("/nonexistent/cgroup", "/nonexistent/sys/fs/cgroup");
+        assert_eq!(fs.memory_limit(), None);
+    }
+}
@@ -7,6 +7,7 @@ use crate::policy::copyspace::CopySpace;
 use crate::policy::copyspace::CopySpaceCopyContext;
 use crate::policy::immix::ImmixSpace;
 use crate::policy::immix::{ImmixCopyContext, ImmixHybridCopyContext};
+use crate::policy::region::{RegionCopyContext, RegionSpace};
 use crate::policy::space::Space;
 use crate::util::object_forwarding;
 use crate::util::opaque_pointer::VMWorkerThread;
@@ -20,9 +21,10 @@ use enum_map::EnumMap;
 
 use super::alloc::allocator::AllocatorContext;
 
-const MAX_COPYSPACE_COPY_ALLOCATORS: usize = 1;
+const MAX_COPYSPACE_COPY_ALLOCATORS: usize = 2;
 const MAX_IMMIX_COPY_ALLOCATORS: usize = 1;
 const MAX_IMMIX_HYBRID_COPY_ALLOCATORS: usize = 1;
+const MAX_REGION_COPY_ALLOCATORS: usize = 1;
 
 type CopySpaceMapping<VM> = Vec<(CopySelector, &'static dyn Space<VM>)>;
 
@@ -58,6 +60,8 @@ pub struct GCWorkerCopyContext<VM: VMBinding> {
     pub immix: [MaybeUninit<ImmixCopyContext<VM>>; MAX_IMMIX_COPY_ALLOCATORS],
     /// Copy allocators for ImmixSpace
     pub immix_hybrid: [MaybeUninit<ImmixHybridCopyContext<VM>>; MAX_IMMIX_HYBRID_COPY_ALLOCATORS],
+    /// Copy allocators for RegionSpace
+    pub region: [MaybeUninit<RegionCopyContext<VM>>; MAX_REGION_COPY_ALLOCATORS],
     /// The config for the plan
     config: CopyConfig<VM>,
 }
@@ -98,6 +102,8 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
                 unsafe { self.immix_hybrid[index as usize].assume_init_mut() }
                     .alloc_copy(original, bytes, align, offset)
             }
+            CopySelector::Region(index) => unsafe { self.region[index as usize].assume_init_mut() }
+                .alloc_copy(original, bytes, align, offset),
             CopySelector::Unused => unreachable!(),
         }
     }
@@ -130,6 +136,9 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
                 unsafe { self.immix_hybrid[index as usize].assume_init_mut() }
                     .post_copy(object, bytes)
             }
+            CopySelector::Region(index) => {
+                unsafe { self.region[index as usize].assume_init_mut() }.post_copy(object, bytes)
+            }
             CopySelector::Unused => unreachable!(),
         }
     }
@@ -148,6 +157,9 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
                 CopySelector::ImmixHybrid(index) => {
                     unsafe { self.immix_hybrid[*index as usize].assume_init_mut() }.prepare()
                 }
+                CopySelector::Region(index) => {
+                    unsafe { self.region[*index as usize].assume_init_mut() }.prepare()
+                }
                 CopySelector::Unused => {}
             }
         }
@@ -167,6 +179,9 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
                 CopySelector::ImmixHybrid(index) => {
                     unsafe { self.immix_hybrid[*index as usize].assume_init_mut() }.release()
                 }
+                CopySelector::Region(index) => {
+                    unsafe { self.region[*index as usize].assume_init_mut() }.release()
+                }
                 CopySelector::Unused => {}
             }
         }
@@ -183,6 +198,7 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
             copy: unsafe { MaybeUninit::uninit().assume_init() },
             immix: unsafe { MaybeUninit::uninit().assume_init() },
             immix_hybrid: unsafe { MaybeUninit::uninit().assume_init() },
+            region: unsafe { MaybeUninit::uninit().assume_init() },
             config,
         };
         let context = Arc::new(AllocatorContext::new(mmtk));
@@ -211,6 +227,13 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
                         space.downcast_ref::<ImmixSpace<VM>>().unwrap(),
                     ));
                 }
+                CopySelector::Region(index) => {
+                    ret.region[index as usize].write(RegionCopyContext::new(
+                        worker_tls,
+                        context.clone(),
+                        space.downcast_ref::<RegionSpace<VM>>().unwrap(),
+                    ));
+                }
                 CopySelector::Unused => unreachable!(),
             }
         }
@@ -224,6 +247,7 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
             copy: unsafe { MaybeUninit::uninit().assume_init() },
             immix: unsafe { MaybeUninit::uninit().assume_init() },
             immix_hybrid: unsafe { MaybeUninit::uninit().assume_init() },
+            region: unsafe { MaybeUninit::uninit().assume_init() },
             config: CopyConfig::default(),
         }
     }
@@ -259,6 +283,7 @@ pub(crate) enum CopySelector {
     CopySpace(u8),
     Immix(u8),
     ImmixHybrid(u8),
+    Region(u8),
     #[default]
     Unused,
 }
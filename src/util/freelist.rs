@@ -94,6 +94,15 @@ pub trait FreeList: Sync + Downcast {
         self.get_size(unit)
     }
 
+    /// Call `f` with the first unit and the number of units of each free lump.
+    fn iterate_free(&self, f: &mut dyn FnMut(i32, i32)) {
+        let mut unit = self.get_next(self.head());
+        while unit != self.head() {
+            f(unit, self.get_size(unit));
+            unit = self.get_next(unit);
+        }
+    }
+
     fn initialize_heap(&mut self, units: i32, grain: i32) {
         // Initialize the sentinels
         // Set top sentinels per heads
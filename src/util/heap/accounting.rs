@@ -10,6 +10,10 @@ pub struct PageAccounting {
     reserved: AtomicUsize,
     /// The committed pages. This should be incremented when we successfully allocate pages from the OS.
     committed: AtomicUsize,
+    /// The free pages that may still be resident in memory, i.e. the pages released since free
+    /// pages were last returned to the OS. This is an estimate: We assume pages are reused from
+    /// them first when pages are committed.
+    free_resident: AtomicUsize,
 }
 
 impl PageAccounting {
@@ -17,13 +21,14 @@ impl PageAccounting {
         Self {
             reserved: AtomicUsize::new(0),
             committed: AtomicUsize::new(0),
+            free_resident: AtomicUsize::new(0),
         }
     }
 
     /// Inform of both reserving and committing a certain number of pages.
     pub fn reserve_and_commit(&self, pages: usize) {
         self.reserved.fetch_add(pages, Ordering::Relaxed);
-        self.committed.fetch_add(pages, Ordering::Relaxed);
+        self.commit(pages);
     }
 
     /// Inform of reserving a certain number of pages. Usually this is called before attempting
@@ -44,6 +49,11 @@ impl PageAccounting {
     /// pages and successfully allocated those memory.
     pub fn commit(&self, pages: usize) {
         self.committed.fetch_add(pages, Ordering::Relaxed);
+        let _ = self
+            .free_resident
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |free| {
+                Some(free.saturating_sub(pages))
+            });
     }
 
     /// Inform of releasing a certain number of pages. The number of pages will be deducted from
@@ -54,12 +64,20 @@ impl PageAccounting {
 
         let _prev_committed = self.committed.fetch_sub(pages, Ordering::Relaxed);
         debug_assert!(_prev_committed >= pages);
+
+        self.free_resident.fetch_add(pages, Ordering::Relaxed);
     }
 
     /// Set both reserved and committed pages to zero. This is only used when we completely clear a space.
     pub fn reset(&self) {
         self.reserved.store(0, Ordering::Relaxed);
-        self.committed.store(0, Ordering::Relaxed);
+        let committed = self.committed.swap(0, Ordering::Relaxed);
+        self.free_resident.fetch_add(committed, Ordering::Relaxed);
+    }
+
+    /// Inform of returning all the free pages to the OS.
+    pub fn uncommit_free(&self) {
+        self.free_resident.store(0, Ordering::Relaxed);
     }
 
     pub fn get_reserved_pages(&self) -> usize {
@@ -69,6 +87,12 @@ impl PageAccounting {
     pub fn get_committed_pages(&self) -> usize {
         self.committed.load(Ordering::Relaxed)
     }
+
+    /// Get the pages that may be resident in memory, including the committed pages, and the free
+    /// pages that have not been returned to the OS.
+    pub fn get_resident_pages(&self) -> usize {
+        self.get_committed_pages() + self.free_resident.load(Ordering::Relaxed)
+    }
 }
 
 impl Default for PageAccounting {
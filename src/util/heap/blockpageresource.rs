@@ -4,7 +4,7 @@ use crate::util::address::Address;
 use crate::util::constants::*;
 use crate::util::heap::layout::vm_layout::*;
 use crate::util::heap::layout::VMMap;
-use crate::util::heap::pageresource::CommonPageResource;
+use crate::util::heap::pageresource::{uncommit_pages, CommonPageResource};
 use crate::util::heap::space_descriptor::SpaceDescriptor;
 use crate::util::linear_scan::Region;
 use crate::util::opaque_pointer::*;
@@ -56,6 +56,37 @@ impl<VM: VMBinding, B: Region> PageResource<VM> for BlockPageResource<VM, B> {
         let _sync = self.sync.lock().unwrap();
         self.flpr.get_available_physical_pages()
     }
+
+    fn uncommit_free_pages(&self, lazy: bool) -> usize {
+        let _sync = self.sync.lock().unwrap();
+        let mut blocks = Vec::with_capacity(self.block_queue.len());
+        self.block_queue
+            .iterate_blocks(&mut |block: B| blocks.push(block.start()));
+        blocks.sort_unstable();
+        // Coalesce adjacent free blocks, so that the free blocks in a free chunk are returned
+        // with one call.
+        let mut uncommitted = 0;
+        let mut run: Option<(Address, Address)> = None;
+        for start in blocks {
+            match run {
+                Some((run_start, run_end)) if run_end == start => {
+                    run = Some((run_start, start + B::BYTES));
+                }
+                _ => {
+                    if let Some((run_start, run_end)) = run {
+                        uncommitted += uncommit_pages(run_start, run_end, lazy);
+                    }
+                    run = Some((start, start + B::BYTES));
+                }
+            }
+        }
+        if let Some((run_start, run_end)) = run {
+            uncommitted += uncommit_pages(run_start, run_end, lazy);
+        }
+        // Chunks that have not been split into blocks yet. This also clears the free resident
+        // pages of the shared accounting.
+        uncommitted + self.flpr.uncommit_free_pages(lazy)
+    }
 }
 
 impl<VM: VMBinding, B: Region> BlockPageResource<VM, B> {
@@ -77,6 +77,23 @@ impl<VM: VMBinding> PageResource<VM> for FreeListPageResource<VM> {
         rtn
     }
 
+    fn uncommit_free_pages(&self, lazy: bool) -> usize {
+        let sync = self.sync.lock().unwrap();
+        if sync.highwater_mark == UNINITIALIZED_WATER_MARK {
+            // Nothing has been allocated.
+            return 0;
+        }
+        let limit = sync.start + conversions::pages_to_bytes(sync.highwater_mark as _);
+        let mut uncommitted = 0;
+        sync.free_list.iterate_free(&mut |unit, units| {
+            let start = sync.start + conversions::pages_to_bytes(unit as _);
+            let end = start + conversions::pages_to_bytes(units as _);
+            uncommitted += super::pageresource::uncommit_mapped_pages(start, end, limit, lazy);
+        });
+        self.common.accounting.uncommit_free();
+        uncommitted
+    }
+
     fn alloc_pages(
         &self,
         space_descriptor: SpaceDescriptor,
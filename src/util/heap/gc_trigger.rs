@@ -3,16 +3,30 @@ use atomic::Ordering;
 use crate::global_state::GlobalState;
 use crate::plan::Plan;
 use crate::policy::space::Space;
+use crate::scheduler::trace;
 use crate::scheduler::GCWorkScheduler;
+use crate::util::alloc::AllocationError;
 use crate::util::constants::BYTES_IN_PAGE;
 use crate::util::conversions;
-use crate::util::options::{GCTriggerSelector, Options, DEFAULT_MAX_NURSERY, DEFAULT_MIN_NURSERY};
+use crate::util::heap::nursery_pause::PauseTargetNursery;
+use crate::util::heap::oom_report::{GCHistory, OutOfMemoryReport};
+use crate::util::heap::trigger_replay::{RecordingTrigger, ReplayTrigger};
+use crate::util::heap::uncommit::HeapUncommitter;
+use crate::util::heap::usage_threshold::{
+    HeapUsageThreshold, HeapUsageThresholdId, HeapUsageThresholds,
+};
+use crate::util::options::{
+    GCTriggerSelector, NurserySize, Options, DEFAULT_MAX_NURSERY, DEFAULT_MIN_NURSERY,
+};
+use crate::util::psi::PressureFile;
+use crate::util::{VMThread, VMWorkerThread};
 use crate::vm::Collection;
 use crate::vm::VMBinding;
 use crate::MMTK;
 use std::mem::MaybeUninit;
 use std::sync::atomic::{AtomicBool, AtomicUsize};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// GCTrigger is responsible for triggering GCs based on the given policy.
 /// All the decisions about heap limit and GC triggering should be resolved here.
@@ -30,6 +44,22 @@ pub struct GCTrigger<VM: VMBinding> {
     scheduler: Arc<GCWorkScheduler<VM>>,
     options: Arc<Options>,
     state: Arc<GlobalState>,
+    /// The nursery size in use.  It starts with the `nursery` option, and may be changed at run
+    /// time.
+    nursery: spin::RwLock<NurserySize>,
+    /// The heap size bounds (min and max, in pages) requested at run time, to be applied at the
+    /// start of the next GC.
+    requested_heap_size_bounds: Mutex<Option<(usize, usize)>>,
+    /// The nursery size requested at run time, to be applied at the start of the next GC.
+    requested_nursery: Mutex<Option<NurserySize>>,
+    /// Returns free memory in the heap to the OS after GCs.
+    uncommitter: HeapUncommitter,
+    /// The heap usage thresholds registered by the binding.
+    usage_thresholds: HeapUsageThresholds,
+    /// The recent GCs, for out-of-memory reports.
+    gc_history: GCHistory,
+    /// Adjusts the nursery size if the nursery size is `PauseTarget`.
+    pause_target_nursery: PauseTargetNursery,
 }
 
 impl<VM: VMBinding> GCTrigger<VM> {
@@ -38,36 +68,261 @@ impl<VM: VMBinding> GCTrigger<VM> {
         scheduler: Arc<GCWorkScheduler<VM>>,
         state: Arc<GlobalState>,
     ) -> Self {
+        let gc_trigger = match *options.gc_trigger {
+            GCTriggerSelector::DynamicHeapSize(min, max)
+            | GCTriggerSelector::GCTimeRatio(min, max, _)
+            | GCTriggerSelector::MemoryPressure(min, max)
+                if *options.plan == crate::util::options::PlanSelector::NoGC =>
+            {
+                warn!(
+                    "Cannot use {:?} with NoGC.  Using fixed heap size trigger instead.",
+                    *options.gc_trigger
+                );
+                let (_, max) = Self::clamp_heap_bounds(min, max);
+                GCTriggerSelector::FixedHeapSize(max)
+            }
+            gc_trigger => gc_trigger,
+        };
+        let policy: Box<dyn GCTriggerPolicy<VM>> = match gc_trigger {
+            GCTriggerSelector::FixedHeapSize(size) => Box::new(FixedHeapSizeTrigger {
+                total_pages: AtomicUsize::new(conversions::bytes_to_pages_up(size)),
+            }),
+            GCTriggerSelector::DynamicHeapSize(min, max) => {
+                let (min, max) = Self::clamp_heap_bounds(min, max);
+                let min_pages = conversions::bytes_to_pages_up(min);
+                let max_pages = conversions::bytes_to_pages_up(max);
+                Box::new(MemBalancerTrigger::new(min_pages, max_pages))
+            }
+            GCTriggerSelector::GCTimeRatio(min, max, ratio) => {
+                let (min, max) = Self::clamp_heap_bounds(min, max);
+                let min_pages = conversions::bytes_to_pages_up(min);
+                let max_pages = conversions::bytes_to_pages_up(max);
+                Box::new(GCTimeRatioTrigger::new(min_pages, max_pages, ratio))
+            }
+            GCTriggerSelector::MemoryPressure(min, max) => {
+                let (min, max) = Self::clamp_heap_bounds(min, max);
+                let min_pages = conversions::bytes_to_pages_up(min);
+                let max_pages = conversions::bytes_to_pages_up(max);
+                Box::new(MemoryPressureTrigger::new(
+                    min_pages,
+                    max_pages,
+                    *options.memory_pressure_shrink_threshold,
+                    *options.memory_pressure_gc_threshold,
+                    PressureFile::default(),
+                ))
+            }
+            GCTriggerSelector::Delegated => {
+                <VM::VMCollection as crate::vm::Collection<VM>>::create_gc_trigger()
+            }
+        };
+        let policy = Self::record_or_replay(policy, &options);
         GCTrigger {
             plan: MaybeUninit::uninit(),
-            policy: match *options.gc_trigger {
-                GCTriggerSelector::FixedHeapSize(size) => Box::new(FixedHeapSizeTrigger {
-                    total_pages: conversions::bytes_to_pages_up(size),
-                }),
-                GCTriggerSelector::DynamicHeapSize(min, max) => 'dynamic_heap_size: {
-                    let min_pages = conversions::bytes_to_pages_up(min);
-                    let max_pages = conversions::bytes_to_pages_up(max);
-
-                    if *options.plan == crate::util::options::PlanSelector::NoGC {
-                        warn!("Cannot use dynamic heap size with NoGC.  Using fixed heap size trigger instead.");
-                        break 'dynamic_heap_size Box::new(FixedHeapSizeTrigger {
-                            total_pages: max_pages,
-                        });
-                    }
-
-                    Box::new(MemBalancerTrigger::new(min_pages, max_pages))
-                }
-                GCTriggerSelector::Delegated => {
-                    <VM::VMCollection as crate::vm::Collection<VM>>::create_gc_trigger()
-                }
-            },
+            policy,
+            nursery: spin::RwLock::new(*options.nursery),
+            uncommitter: HeapUncommitter::new(&options),
+            usage_thresholds: HeapUsageThresholds::new(),
+            gc_history: GCHistory::new(),
+            pause_target_nursery: PauseTargetNursery::new(),
             options,
             request_flag: AtomicBool::new(false),
             scheduler,
             state,
+            requested_heap_size_bounds: Mutex::new(None),
+            requested_nursery: Mutex::new(None),
+        }
+    }
+
+    /// Wrap the policy to record the GCs if `gc_trigger_record` is set, or replace it to replay
+    /// recorded GCs if `gc_trigger_replay` is set.
+    fn record_or_replay(
+        policy: Box<dyn GCTriggerPolicy<VM>>,
+        options: &Options,
+    ) -> Box<dyn GCTriggerPolicy<VM>> {
+        let record = options.gc_trigger_record.as_str();
+        let replay = options.gc_trigger_replay.as_str();
+        if !replay.is_empty() {
+            if !record.is_empty() {
+                warn!("Cannot record GCs while replaying GCs.  gc_trigger_record is ignored.");
+            }
+            let trigger = ReplayTrigger::from_file(replay).unwrap_or_else(|e| {
+                panic!("Failed to read the GCs to replay from {}: {}", replay, e)
+            });
+            info!(
+                "Replaying {} GCs from {}",
+                trigger.schedule().gcs.len(),
+                replay
+            );
+            return Box::new(trigger);
+        }
+        if !record.is_empty() {
+            let trigger = RecordingTrigger::new(policy, record)
+                .unwrap_or_else(|e| panic!("Failed to create {} to record GCs: {}", record, e));
+            return Box::new(trigger);
+        }
+        policy
+    }
+
+    /// Return the memory limit of the process in bytes.
+    fn memory_limit() -> usize {
+        usize::try_from(crate::util::memory::get_memory_limit()).unwrap_or(usize::MAX)
+    }
+
+    /// Clamp the bounds of a dynamic heap size to the memory limit of the process, so that a heap
+    /// in a container does not grow beyond the memory limit of its cgroup.
+    fn clamp_heap_bounds(min: usize, max: usize) -> (usize, usize) {
+        let limit = Self::memory_limit();
+        if max > limit {
+            warn!(
+                "The max heap size ({} bytes) is larger than the memory limit ({} bytes).  Use the memory limit instead.",
+                max, limit
+            );
+        }
+        let max = max.min(limit);
+        if min > max {
+            warn!(
+                "The min heap size ({} bytes) is larger than the memory limit ({} bytes).  Use the memory limit instead.",
+                min, max
+            );
+        }
+        (min.min(max), max)
+    }
+
+    /// Request new bounds of the heap size (in bytes).  The bounds take effect at the start of the
+    /// next GC.  A later request overrides an earlier one that has not taken effect yet.  The max
+    /// is clamped to the memory limit of the process.  Return false, and ignore the request, if
+    /// the min is larger than the memory limit.
+    pub fn request_heap_size_bounds(&self, min: usize, max: usize) -> bool {
+        let limit = Self::memory_limit();
+        if min > limit {
+            warn!(
+                "The min heap size ({} bytes) is larger than the memory limit ({} bytes).  The request is ignored.",
+                min, limit
+            );
+            return false;
+        }
+        let (min, max) = Self::clamp_heap_bounds(min, max);
+        let bounds = (
+            conversions::bytes_to_pages_up(min),
+            conversions::bytes_to_pages_up(max),
+        );
+        *self.requested_heap_size_bounds.lock().unwrap() = Some(bounds);
+        true
+    }
+
+    /// Request a new nursery size.  The nursery size takes effect at the start of the next GC.  A
+    /// later request overrides an earlier one that has not taken effect yet.
+    pub fn request_nursery_size(&self, nursery: NurserySize) {
+        *self.requested_nursery.lock().unwrap() = Some(nursery);
+    }
+
+    /// Apply the heap size bounds and the nursery size requested at run time.  This is called at
+    /// the start of a GC, before the policy is informed of the GC.
+    pub(crate) fn apply_requested_bounds(&self) {
+        if let Some((min_pages, max_pages)) = self.requested_heap_size_bounds.lock().unwrap().take()
+        {
+            if self.policy.set_heap_size_bounds(min_pages, max_pages) {
+                info!(
+                    "Heap size bounds changed to [{}, {}] pages",
+                    min_pages, max_pages
+                );
+            } else {
+                warn!("The GC trigger does not support changing the heap size bounds.  The request is ignored.");
+            }
+        }
+        if let Some(nursery) = self.requested_nursery.lock().unwrap().take() {
+            info!("Nursery size changed to {:?}", nursery);
+            *self.nursery.write() = nursery;
+            self.pause_target_nursery.reset();
+        }
+    }
+
+    /// Return free memory in the heap to the OS if needed.  This is called at the end of a GC,
+    /// after the policy is informed of the end of the GC, and before mutators are resumed.
+    pub(crate) fn uncommit_free_memory(&self) {
+        self.uncommitter
+            .on_gc_end(self.plan(), self.policy.get_current_heap_size_in_pages());
+    }
+
+    /// Register a heap usage threshold, which is checked at the end of each GC.
+    pub fn add_usage_threshold(&self, threshold: HeapUsageThreshold) -> HeapUsageThresholdId {
+        self.usage_thresholds.add(threshold)
+    }
+
+    /// Remove a heap usage threshold.  Return false if there is no such threshold.
+    pub fn remove_usage_threshold(&self, id: HeapUsageThresholdId) -> bool {
+        self.usage_thresholds.remove(id)
+    }
+
+    /// Inform the binding of the heap usage thresholds crossed in this GC.  This is called at the
+    /// end of a GC, after free memory is returned to the OS, and before mutators are resumed.
+    pub(crate) fn check_usage_thresholds(&self, tls: VMWorkerThread) {
+        self.usage_thresholds
+            .on_gc_end(tls, self.plan(), self.policy.get_max_heap_size_in_pages());
+    }
+
+    /// Return the allocation pressure from 0 to 1, which decides how much mutators that allocate
+    /// fast are delayed (see [`crate::util::alloc::AllocationPacing`]).  The pressure grows from 0
+    /// when the used fraction of the heap reaches `allocation_pacing_threshold` to 1 when the heap
+    /// is full.  While concurrent work is pending, the pressure is at least the used fraction of
+    /// the heap, so mutators do not outpace concurrent marking.
+    pub(crate) fn allocation_pressure(&self) -> f64 {
+        let heap_pages = self.policy.get_current_heap_size_in_pages().max(1);
+        let usage = (self.plan().get_reserved_pages() as f64 / heap_pages as f64).min(1.0);
+        let threshold = *self.options.allocation_pacing_threshold;
+        let pressure = ((usage - threshold) / (1.0 - threshold)).max(0.0);
+        if self.scheduler.has_pending_concurrent_work() {
+            pressure.max(usage)
+        } else {
+            pressure
+        }
+    }
+
+    /// Record the start of a GC in the GC history, and for sizing the nursery.
+    pub(crate) fn record_gc_start(&self) {
+        self.gc_history.on_gc_start(self.plan());
+        self.pause_target_nursery.on_gc_start(self.plan());
+    }
+
+    /// Record the end of a GC in the GC history.  If the nursery size is `PauseTarget`, adjust the
+    /// nursery size for the next GC.  This is called before the collection state is reset.
+    pub(crate) fn record_gc_end(&self, elapsed: Duration) {
+        self.gc_history.on_gc_end(
+            self.plan(),
+            &self.state,
+            self.policy.get_current_heap_size_in_pages(),
+            elapsed,
+        );
+        if let NurserySize::PauseTarget { min, max, pause_ms } = *self.nursery.read() {
+            self.pause_target_nursery
+                .on_gc_end(self.plan(), elapsed, min, max, pause_ms);
         }
     }
 
+    /// Describe the state of the heap when an allocation of `size` bytes in `space` runs out of
+    /// memory, and pass the report to the binding.  This is called right before
+    /// `Collection::out_of_memory`.
+    pub(crate) fn report_out_of_memory(
+        &self,
+        tls: VMThread,
+        err_kind: AllocationError,
+        size: usize,
+        align: Option<usize>,
+        space: &dyn Space<VM>,
+    ) {
+        let report = OutOfMemoryReport::new(
+            err_kind,
+            size,
+            align,
+            space,
+            self.plan(),
+            self.policy.as_ref(),
+            &self.state,
+            &self.gc_history,
+        );
+        VM::VMCollection::out_of_memory_report(tls, &report);
+    }
+
     /// Set the plan. This is called in `create_plan()` after we created a boxed plan.
     pub fn set_plan(&mut self, plan: &'static dyn Plan<VM = VM>) {
         self.plan.write(plan);
@@ -89,6 +344,9 @@ impl<VM: VMBinding> GCTrigger<VM> {
             // with GC workers, which is expensive for functions like `poll`.  We use the atomic
             // flag `request_flag` to elide the need to acquire the mutex in subsequent calls.
             probe!(mmtk, gc_requested);
+            if let Some(tracer) = self.scheduler.tracer() {
+                tracer.instant(trace::MUTATOR_TID, "gc_requested", None);
+            }
             self.scheduler.request_schedule_collection();
         }
     }
@@ -201,9 +459,11 @@ impl<VM: VMBinding> GCTrigger<VM> {
 
     /// Return upper bound of the nursery size (in number of bytes)
     pub fn get_max_nursery_bytes(&self) -> usize {
-        use crate::util::options::NurserySize;
-        debug_assert!(self.plan().generational().is_some());
-        match *self.options.nursery {
+        // Reference counting plans also use the nursery size to bound the young objects.
+        debug_assert!(
+            self.plan().generational().is_some() || self.plan().constraints().needs_ref_count
+        );
+        match *self.nursery.read() {
             NurserySize::Bounded { min: _, max } => max,
             NurserySize::ProportionalBounded { min: _, max } => {
                 let heap_size_bytes =
@@ -218,14 +478,19 @@ impl<VM: VMBinding> GCTrigger<VM> {
                 }
             }
             NurserySize::Fixed(sz) => sz,
+            NurserySize::PauseTarget { min, max, .. } => {
+                self.pause_target_nursery.nursery_bytes(min, max)
+            }
         }
     }
 
     /// Return lower bound of the nursery size (in number of bytes)
     pub fn get_min_nursery_bytes(&self) -> usize {
-        use crate::util::options::NurserySize;
-        debug_assert!(self.plan().generational().is_some());
-        match *self.options.nursery {
+        // Reference counting plans also use the nursery size to bound the young objects.
+        debug_assert!(
+            self.plan().generational().is_some() || self.plan().constraints().needs_ref_count
+        );
+        match *self.nursery.read() {
             NurserySize::Bounded { min, max: _ } => min,
             NurserySize::ProportionalBounded { min, max: _ } => {
                 let min_bytes =
@@ -241,6 +506,7 @@ impl<VM: VMBinding> GCTrigger<VM> {
                 }
             }
             NurserySize::Fixed(sz) => sz,
+            NurserySize::PauseTarget { min, .. } => min,
         }
     }
 
@@ -316,11 +582,18 @@ pub trait GCTriggerPolicy<VM: VMBinding>: Sync + Send {
     fn get_max_heap_size_in_pages(&self) -> usize;
     /// Can the heap size grow?
     fn can_heap_size_grow(&self) -> bool;
+    /// Change the bounds of the heap size (in pages).  This is called at the start of a GC, before
+    /// [`GCTriggerPolicy::on_gc_start`], if new bounds were requested at run time.  A policy with
+    /// a fixed heap size should use `max_pages` as its heap size.  Return false if the policy does
+    /// not support changing the bounds.
+    fn set_heap_size_bounds(&self, _min_pages: usize, _max_pages: usize) -> bool {
+        false
+    }
 }
 
 /// A simple GC trigger that uses a fixed heap size.
 pub struct FixedHeapSizeTrigger {
-    total_pages: usize,
+    total_pages: AtomicUsize,
 }
 impl<VM: VMBinding> GCTriggerPolicy<VM> for FixedHeapSizeTrigger {
     fn is_gc_required(
@@ -335,20 +608,25 @@ impl<VM: VMBinding> GCTriggerPolicy<VM> for FixedHeapSizeTrigger {
 
     fn is_heap_full(&self, plan: &dyn Plan<VM = VM>) -> bool {
         // If reserved pages is larger than the total pages, the heap is full.
-        plan.get_reserved_pages() > self.total_pages
+        plan.get_reserved_pages() > self.total_pages.load(Ordering::Relaxed)
     }
 
     fn get_current_heap_size_in_pages(&self) -> usize {
-        self.total_pages
+        self.total_pages.load(Ordering::Relaxed)
     }
 
     fn get_max_heap_size_in_pages(&self) -> usize {
-        self.total_pages
+        self.total_pages.load(Ordering::Relaxed)
     }
 
     fn can_heap_size_grow(&self) -> bool {
         false
     }
+
+    fn set_heap_size_bounds(&self, _min_pages: usize, max_pages: usize) -> bool {
+        self.total_pages.store(max_pages, Ordering::Relaxed);
+        true
+    }
 }
 
 use atomic_refcell::AtomicRefCell;
@@ -361,9 +639,9 @@ use std::time::Instant;
 // TODO: implement a complete mem balancer.
 pub struct MemBalancerTrigger {
     /// The min heap size
-    min_heap_pages: usize,
+    min_heap_pages: AtomicUsize,
     /// The max heap size
-    max_heap_pages: usize,
+    max_heap_pages: AtomicUsize,
     /// The current heap size
     current_heap_pages: AtomicUsize,
     /// The number of pending allocation pages. The allocation requests for them have failed, and a GC is triggered.
@@ -609,18 +887,29 @@ impl<VM: VMBinding> GCTriggerPolicy<VM> for MemBalancerTrigger {
     }
 
     fn get_max_heap_size_in_pages(&self) -> usize {
-        self.max_heap_pages
+        self.max_heap_pages.load(Ordering::Relaxed)
     }
 
     fn can_heap_size_grow(&self) -> bool {
-        self.current_heap_pages.load(Ordering::Relaxed) < self.max_heap_pages
+        self.current_heap_pages.load(Ordering::Relaxed)
+            < self.max_heap_pages.load(Ordering::Relaxed)
+    }
+
+    fn set_heap_size_bounds(&self, min_pages: usize, max_pages: usize) -> bool {
+        self.min_heap_pages.store(min_pages, Ordering::Relaxed);
+        self.max_heap_pages.store(max_pages, Ordering::Relaxed);
+        // Keep the current heap size within the new bounds until the next heap limit is computed.
+        let current = self.current_heap_pages.load(Ordering::Relaxed);
+        self.current_heap_pages
+            .store(current.clamp(min_pages, max_pages), Ordering::Relaxed);
+        true
     }
 }
 impl MemBalancerTrigger {
     fn new(min_heap_pages: usize, max_heap_pages: usize) -> Self {
         Self {
-            min_heap_pages,
-            max_heap_pages,
+            min_heap_pages: AtomicUsize::new(min_heap_pages),
+            max_heap_pages: AtomicUsize::new(max_heap_pages),
             pending_pages: AtomicUsize::new(0),
             // start with min heap
             current_heap_pages: AtomicUsize::new(min_heap_pages),
@@ -721,11 +1010,385 @@ impl MemBalancerTrigger {
         );
 
         // The new heap size must be within min/max.
-        let new_heap = optimal_heap.clamp(self.min_heap_pages, self.max_heap_pages);
+        let min_heap_pages = self.min_heap_pages.load(Ordering::Relaxed);
+        let max_heap_pages = self.max_heap_pages.load(Ordering::Relaxed);
+        let new_heap = optimal_heap.clamp(min_heap_pages, max_heap_pages);
         debug!(
             "MemBalander: new heap limit = {} pages (optimal = {}, clamped to [{}, {}])",
-            new_heap, optimal_heap, self.min_heap_pages, self.max_heap_pages
+            new_heap, optimal_heap, min_heap_pages, max_heap_pages
         );
         self.current_heap_pages.store(new_heap, Ordering::Relaxed);
     }
 }
+
+/// A GC trigger that adjusts the heap size between the min heap and the max heap so that the
+/// fraction of time spent in GC stays near `1 / (1 + ratio)`, in the style of `-XX:GCTimeRatio`
+/// in HotSpot.
+///
+/// How often GCs happen is roughly inversely proportional to the headroom of the heap (the free
+/// pages above the live pages).  At the end of each GC, the headroom is scaled by the ratio of
+/// the measured GC time fraction to the target, so the heap grows if too much time is spent in
+/// GC, and shrinks otherwise.  The heap shrinks more slowly than it grows to avoid oscillation.
+pub struct GCTimeRatioTrigger {
+    /// The min heap size
+    min_heap_pages: AtomicUsize,
+    /// The max heap size
+    max_heap_pages: AtomicUsize,
+    /// The target fraction of time spent in GC
+    target_gc_fraction: f64,
+    /// The current heap size
+    current_heap_pages: AtomicUsize,
+    /// The number of pending allocation pages. The allocation requests for them have failed, and a GC is triggered.
+    /// We will need to take them into consideration so that the new heap size can accomodate those allocations.
+    pending_pages: AtomicUsize,
+    /// Statistics
+    stats: AtomicRefCell<GCTimeRatioStats>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct GCTimeRatioStats {
+    /// The time when this GC starts
+    gc_start_time: Instant,
+    /// The time when the last GC ends
+    gc_end_time: Instant,
+    /// The smoothed fraction of time spent in GC. `None` before the first GC ends.
+    gc_fraction: Option<f64>,
+}
+
+impl<VM: VMBinding> GCTriggerPolicy<VM> for GCTimeRatioTrigger {
+    fn is_gc_required(
+        &self,
+        space_full: bool,
+        space: Option<SpaceStats<VM>>,
+        plan: &dyn Plan<VM = VM>,
+    ) -> bool {
+        // Let the plan decide
+        plan.collection_required(space_full, space)
+    }
+
+    fn on_pending_allocation(&self, pages: usize) {
+        self.pending_pages.fetch_add(pages, Ordering::SeqCst);
+    }
+
+    fn on_gc_start(&self, _mmtk: &'static MMTK<VM>) {
+        self.stats.borrow_mut().gc_start_time = Instant::now();
+    }
+
+    fn on_gc_end(&self, mmtk: &'static MMTK<VM>) {
+        let gc_fraction = {
+            let mut stats = self.stats.borrow_mut();
+            let now = Instant::now();
+            let gc_time = (now - stats.gc_start_time).as_secs_f64();
+            let mutator_time = (stats.gc_start_time - stats.gc_end_time).as_secs_f64();
+            stats.gc_end_time = now;
+            if gc_time + mutator_time > 0f64 {
+                let current = gc_time / (gc_time + mutator_time);
+                stats.gc_fraction = Some(Self::smooth(stats.gc_fraction, current));
+            }
+            trace!(
+                "gc_time = {}, mutator_time = {}, gc_fraction = {:?}",
+                gc_time,
+                mutator_time,
+                stats.gc_fraction
+            );
+            stats.gc_fraction
+        };
+
+        if let Some(gc_fraction) = gc_fraction {
+            let plan = mmtk.get_plan();
+            let extra_reserve = if plan.generational().is_some() {
+                // Like MemBalancer, reserve an extra of min nursery, so the next GC is not forced
+                // to be a full heap GC.
+                plan.get_collection_reserved_pages() + mmtk.gc_trigger.get_min_nursery_pages()
+            } else {
+                plan.get_collection_reserved_pages()
+            };
+            self.compute_new_heap_limit(plan.get_reserved_pages(), extra_reserve, gc_fraction);
+        }
+        // Clear pending allocation pages at the end of GC, no matter we used it or not.
+        self.pending_pages.store(0, Ordering::SeqCst);
+    }
+
+    fn is_heap_full(&self, plan: &dyn Plan<VM = VM>) -> bool {
+        // If reserved pages is larger than the current heap size, the heap is full.
+        plan.get_reserved_pages() > self.current_heap_pages.load(Ordering::Relaxed)
+    }
+
+    fn get_current_heap_size_in_pages(&self) -> usize {
+        self.current_heap_pages.load(Ordering::Relaxed)
+    }
+
+    fn get_max_heap_size_in_pages(&self) -> usize {
+        self.max_heap_pages.load(Ordering::Relaxed)
+    }
+
+    fn can_heap_size_grow(&self) -> bool {
+        self.current_heap_pages.load(Ordering::Relaxed)
+            < self.max_heap_pages.load(Ordering::Relaxed)
+    }
+
+    fn set_heap_size_bounds(&self, min_pages: usize, max_pages: usize) -> bool {
+        self.min_heap_pages.store(min_pages, Ordering::Relaxed);
+        self.max_heap_pages.store(max_pages, Ordering::Relaxed);
+        // Keep the current heap size within the new bounds until the next heap limit is computed.
+        let current = self.current_heap_pages.load(Ordering::Relaxed);
+        self.current_heap_pages
+            .store(current.clamp(min_pages, max_pages), Ordering::Relaxed);
+        true
+    }
+}
+
+impl GCTimeRatioTrigger {
+    /// The weight of the previous GC time fraction when smoothing.
+    const SMOOTH_FACTOR: f64 = 0.5;
+    /// The headroom grows at most by this factor in one GC.
+    const MAX_GROWTH: f64 = 2.0;
+    /// The headroom shrinks at most by this factor in one GC.
+    const MAX_SHRINK: f64 = 0.9;
+    /// The headroom is at least this fraction of the live pages, so a heap without any headroom
+    /// can still grow.
+    const MIN_HEADROOM: f64 = 0.05;
+
+    fn new(min_heap_pages: usize, max_heap_pages: usize, ratio: usize) -> Self {
+        let now = Instant::now();
+        Self {
+            min_heap_pages: AtomicUsize::new(min_heap_pages),
+            max_heap_pages: AtomicUsize::new(max_heap_pages),
+            target_gc_fraction: 1f64 / (1 + ratio) as f64,
+            // start with min heap
+            current_heap_pages: AtomicUsize::new(min_heap_pages),
+            pending_pages: AtomicUsize::new(0),
+            stats: AtomicRefCell::new(GCTimeRatioStats {
+                gc_start_time: now,
+                gc_end_time: now,
+                gc_fraction: None,
+            }),
+        }
+    }
+
+    fn smooth(prev: Option<f64>, cur: f64) -> f64 {
+        prev.map(|p| p * Self::SMOOTH_FACTOR + cur * (1f64 - Self::SMOOTH_FACTOR))
+            .unwrap_or(cur)
+    }
+
+    fn compute_new_heap_limit(&self, live: usize, extra_reserve: usize, gc_fraction: f64) {
+        let current_heap = self.current_heap_pages.load(Ordering::Relaxed);
+        let headroom = (current_heap.saturating_sub(live + extra_reserve) as f64)
+            .max(live as f64 * Self::MIN_HEADROOM)
+            .max(1f64);
+        let scale =
+            (gc_fraction / self.target_gc_fraction).clamp(Self::MAX_SHRINK, Self::MAX_GROWTH);
+        let new_headroom = (headroom * scale) as usize;
+
+        let pending_pages = self.pending_pages.load(Ordering::SeqCst);
+        let optimal_heap = live + extra_reserve + new_headroom + pending_pages;
+        let min_heap_pages = self.min_heap_pages.load(Ordering::Relaxed);
+        let max_heap_pages = self.max_heap_pages.load(Ordering::Relaxed);
+        let new_heap = optimal_heap.clamp(min_heap_pages, max_heap_pages);
+        debug!(
+            "GCTimeRatio: new heap limit = {} pages (gc fraction = {:.4}, target = {:.4}, optimal = live {} + extra {} + headroom {} + pending {}, clamped to [{}, {}])",
+            new_heap,
+            gc_fraction,
+            self.target_gc_fraction,
+            live,
+            extra_reserve,
+            new_headroom,
+            pending_pages,
+            min_heap_pages,
+            max_heap_pages
+        );
+        self.current_heap_pages.store(new_heap, Ordering::Relaxed);
+    }
+}
+
+/// A GC trigger that sizes the heap like [`MemBalancerTrigger`], but also gives memory back when
+/// the system is under memory pressure, according to the pressure stall information of Linux (see
+/// [`crate::util::psi`]).
+///
+/// The pressure is read when the trigger is polled, at most once every [`Self::CHECK_INTERVAL`].
+/// While the `some` pressure is above the shrink threshold, the heap limit is lowered by
+/// [`Self::SHRINK_FACTOR`] at each check (but not below the min heap size, or the pages in use
+/// after the last GC), and the heap limit computed at the end of a GC does not grow.  If the
+/// `full` pressure is above the GC threshold, a GC is triggered, at most once every
+/// [`Self::GC_INTERVAL`].  If the pressure cannot be read, this behaves like
+/// [`MemBalancerTrigger`].
+pub struct MemoryPressureTrigger {
+    /// Decides the heap limit when there is no memory pressure.
+    balancer: MemBalancerTrigger,
+    /// Where the memory pressure is read from.
+    pressure_file: PressureFile,
+    /// The `some` pressure above which the heap limit is lowered.
+    shrink_threshold: f64,
+    /// The `full` pressure above which a GC is triggered.
+    gc_threshold: f64,
+    state: Mutex<MemoryPressureState>,
+}
+
+struct MemoryPressureState {
+    /// The time when the pressure was last read.
+    last_check: Option<Instant>,
+    /// The time when a GC was last triggered by the pressure.
+    last_gc: Option<Instant>,
+    /// Was the `some` pressure above the shrink threshold when it was last read?
+    under_pressure: bool,
+}
+
+impl<VM: VMBinding> GCTriggerPolicy<VM> for MemoryPressureTrigger {
+    fn is_gc_required(
+        &self,
+        space_full: bool,
+        space: Option<SpaceStats<VM>>,
+        plan: &dyn Plan<VM = VM>,
+    ) -> bool {
+        if self.check_pressure(plan) {
+            return true;
+        }
+        // Let the plan decide
+        plan.collection_required(space_full, space)
+    }
+
+    fn on_pending_allocation(&self, pages: usize) {
+        GCTriggerPolicy::<VM>::on_pending_allocation(&self.balancer, pages)
+    }
+
+    fn on_gc_start(&self, mmtk: &'static MMTK<VM>) {
+        self.balancer.on_gc_start(mmtk)
+    }
+
+    fn on_gc_release(&self, mmtk: &'static MMTK<VM>) {
+        self.balancer.on_gc_release(mmtk)
+    }
+
+    fn on_gc_end(&self, mmtk: &'static MMTK<VM>) {
+        let heap_pages = self.balancer.current_heap_pages.load(Ordering::Relaxed);
+        self.balancer.on_gc_end(mmtk);
+        if self.state.lock().unwrap().under_pressure {
+            // Do not grow the heap while the system is short of memory.
+            let new_heap_pages = self.balancer.current_heap_pages.load(Ordering::Relaxed);
+            if new_heap_pages > heap_pages {
+                debug!(
+                    "MemoryPressure: keep heap limit at {} pages (MemBalancer wants {} pages)",
+                    heap_pages, new_heap_pages
+                );
+                self.balancer
+                    .current_heap_pages
+                    .store(heap_pages, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn is_heap_full(&self, plan: &dyn Plan<VM = VM>) -> bool {
+        self.balancer.is_heap_full(plan)
+    }
+
+    fn get_current_heap_size_in_pages(&self) -> usize {
+        GCTriggerPolicy::<VM>::get_current_heap_size_in_pages(&self.balancer)
+    }
+
+    fn get_max_heap_size_in_pages(&self) -> usize {
+        GCTriggerPolicy::<VM>::get_max_heap_size_in_pages(&self.balancer)
+    }
+
+    fn can_heap_size_grow(&self) -> bool {
+        GCTriggerPolicy::<VM>::can_heap_size_grow(&self.balancer)
+    }
+
+    fn set_heap_size_bounds(&self, min_pages: usize, max_pages: usize) -> bool {
+        GCTriggerPolicy::<VM>::set_heap_size_bounds(&self.balancer, min_pages, max_pages)
+    }
+}
+
+impl MemoryPressureTrigger {
+    /// How often the pressure is read.
+    const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+    /// The minimum time between two GCs triggered by the pressure.  This is the window of the
+    /// pressure averages, so a GC is not triggered again by the stalls that it was meant to
+    /// relieve.
+    const GC_INTERVAL: Duration = Duration::from_secs(10);
+    /// The heap limit is multiplied by this factor at each check under pressure.
+    const SHRINK_FACTOR: f64 = 0.9;
+
+    fn new(
+        min_heap_pages: usize,
+        max_heap_pages: usize,
+        shrink_threshold: f64,
+        gc_threshold: f64,
+        pressure_file: PressureFile,
+    ) -> Self {
+        if pressure_file.read().is_none() {
+            warn!(
+                "Cannot read memory pressure from {}.  The heap is sized without memory pressure.",
+                pressure_file.path().display()
+            );
+        }
+        Self {
+            balancer: MemBalancerTrigger::new(min_heap_pages, max_heap_pages),
+            pressure_file,
+            shrink_threshold,
+            gc_threshold,
+            state: Mutex::new(MemoryPressureState {
+                last_check: None,
+                last_gc: None,
+                under_pressure: false,
+            }),
+        }
+    }
+
+    /// Read the pressure if it has not been read recently, and lower the heap limit if needed.
+    /// Return true if a GC should be triggered.
+    fn check_pressure<VM: VMBinding>(&self, plan: &dyn Plan<VM = VM>) -> bool {
+        // This is called in every poll.  Skip the check if another thread is doing it.
+        let Ok(mut state) = self.state.try_lock() else {
+            return false;
+        };
+        let now = Instant::now();
+        if state
+            .last_check
+            .is_some_and(|last| now - last < Self::CHECK_INTERVAL)
+        {
+            return false;
+        }
+        state.last_check = Some(now);
+
+        let Some(pressure) = self.pressure_file.read() else {
+            state.under_pressure = false;
+            return false;
+        };
+        trace!("MemoryPressure: {:?}", pressure);
+
+        state.under_pressure = pressure.some >= self.shrink_threshold;
+        if state.under_pressure {
+            // Keep the pages in use after the last GC, so we do not shrink the heap below what
+            // the program needs and thrash.
+            let floor = self.balancer.min_heap_pages.load(Ordering::Relaxed).max(
+                plan.base().global_state.get_used_pages_after_last_gc()
+                    + plan.get_collection_reserved_pages(),
+            );
+            let current = self.balancer.current_heap_pages.load(Ordering::Relaxed);
+            let new_heap = ((current as f64 * Self::SHRINK_FACTOR) as usize).max(floor);
+            if new_heap < current {
+                debug!(
+                    "MemoryPressure: some = {:.2}%, lower heap limit from {} to {} pages",
+                    pressure.some, current, new_heap
+                );
+                self.balancer
+                    .current_heap_pages
+                    .store(new_heap, Ordering::Relaxed);
+            }
+        }
+
+        if pressure.full >= self.gc_threshold
+            && state
+                .last_gc
+                .map_or(true, |last| now - last >= Self::GC_INTERVAL)
+        {
+            info!(
+                "MemoryPressure: full = {:.2}%, triggering a GC to give memory back",
+                pressure.full
+            );
+            state.last_gc = Some(now);
+            return true;
+        }
+        false
+    }
+}
@@ -8,9 +8,14 @@ pub(crate) mod freelistpageresource;
 pub(crate) mod gc_trigger;
 mod heap_meta;
 pub(crate) mod monotonepageresource;
+pub(crate) mod nursery_pause;
+pub(crate) mod oom_report;
 pub(crate) mod pageresource;
 pub(crate) mod regionpageresource;
 pub(crate) mod space_descriptor;
+pub(crate) mod trigger_replay;
+pub(crate) mod uncommit;
+pub(crate) mod usage_threshold;
 mod vmrequest;
 
 pub(crate) use self::accounting::PageAccounting;
@@ -21,6 +26,13 @@ pub use self::gc_trigger::SpaceStats;
 pub(crate) use self::heap_meta::HeapMeta;
 pub use self::layout::vm_layout;
 pub(crate) use self::monotonepageresource::MonotonePageResource;
+pub use self::oom_report::{GCRecord, OutOfMemoryReport, SpaceFragmentation, SpaceReport};
 pub(crate) use self::pageresource::PageResource;
 pub(crate) use self::regionpageresource::RegionPageResource;
+pub use self::trigger_replay::{
+    GCSchedule, RecordingTrigger, ReplayTrigger, ScheduledGC, ScheduledGCKind,
+};
+pub use self::usage_threshold::{
+    HeapUsageLimit, HeapUsageThreshold, HeapUsageThresholdEvent, HeapUsageThresholdId,
+};
 pub(crate) use self::vmrequest::VMRequest;
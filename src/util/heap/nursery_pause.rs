@@ -0,0 +1,256 @@
+//! Nursery sizing for a pause time target.
+//!
+//! With the nursery size `PauseTarget`, the nursery size is adjusted at the end of each nursery GC
+//! so that the next nursery GC takes about the target pause time.  The pause time of a nursery GC
+//! is modelled as a fixed cost (e.g. scanning roots and the remembered set) plus a cost for each
+//! byte that survives, and the surviving bytes are the survival rate times the nursery size.  We
+//! fit the model to the recent nursery GCs, and choose the nursery size whose survivors can be
+//! collected in the target pause time.
+
+use crate::plan::Plan;
+use crate::util::constants::{BYTES_IN_PAGE, LOG_BYTES_IN_PAGE};
+use crate::util::conversions;
+use crate::vm::VMBinding;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A nursery GC, used to fit the pause time model.
+#[derive(Copy, Clone, Debug)]
+struct NurseryGCSample {
+    /// The bytes that survived the GC.
+    survived_bytes: f64,
+    /// The pause time in seconds.
+    pause: f64,
+}
+
+/// Adjusts the nursery size to meet a target pause time.
+pub(crate) struct PauseTargetNursery {
+    /// The nursery size in bytes for the next GC, or 0 if it has not been decided yet.  It is read
+    /// whenever the plan checks if the nursery is full, so it is kept out of the mutex.
+    nursery_bytes: AtomicUsize,
+    state: Mutex<PauseTargetNurseryState>,
+}
+
+struct PauseTargetNurseryState {
+    /// The used pages at the end of the last GC.
+    used_pages_after_last_gc: usize,
+    /// The pages allocated since the last GC, measured when the current GC started.
+    young_pages: usize,
+    /// The smoothed survival rate of nursery GCs.
+    survival_rate: Option<f64>,
+    /// The recent nursery GCs, from the oldest to the latest.
+    samples: VecDeque<NurseryGCSample>,
+}
+
+impl PauseTargetNursery {
+    /// The number of nursery GCs used to fit the pause time model.
+    const SAMPLES: usize = 16;
+    /// The weight of the previous survival rate when it is smoothed.
+    const SMOOTH_FACTOR: f64 = 0.5;
+    /// The nursery size changes by at most this factor in each GC, as the estimates are noisy.
+    const MAX_STEP: f64 = 2.0;
+
+    pub fn new() -> Self {
+        Self {
+            nursery_bytes: AtomicUsize::new(0),
+            state: Mutex::new(PauseTargetNurseryState {
+                used_pages_after_last_gc: 0,
+                young_pages: 0,
+                survival_rate: None,
+                samples: VecDeque::with_capacity(Self::SAMPLES),
+            }),
+        }
+    }
+
+    /// Return the nursery size in bytes for the next GC.  Before the first nursery GC, the nursery
+    /// starts with the lower bound.
+    pub fn nursery_bytes(&self, min: usize, max: usize) -> usize {
+        match self.nursery_bytes.load(Ordering::Relaxed) {
+            0 => min,
+            bytes => bytes.clamp(min, max),
+        }
+    }
+
+    /// Forget the measured GCs, e.g. when the nursery size is changed at run time.
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.survival_rate = None;
+        state.samples.clear();
+        self.nursery_bytes.store(0, Ordering::Relaxed);
+    }
+
+    /// Called when a GC starts.
+    pub fn on_gc_start<VM: VMBinding>(&self, plan: &dyn Plan<VM = VM>) {
+        let mut state = self.state.lock().unwrap();
+        state.young_pages = plan
+            .get_used_pages()
+            .saturating_sub(state.used_pages_after_last_gc);
+    }
+
+    /// Called at the end of a GC.  If it is a nursery GC, measure its survival rate and pause
+    /// time, and decide the nursery size for the next GC.
+    ///
+    /// Arguments:
+    /// * `plan`: The plan in use.
+    /// * `elapsed`: The pause time of the GC.
+    /// * `min`, `max`: The bounds of the nursery size in bytes.
+    /// * `pause_ms`: The target pause time in milliseconds.
+    pub fn on_gc_end<VM: VMBinding>(
+        &self,
+        plan: &dyn Plan<VM = VM>,
+        elapsed: Duration,
+        min: usize,
+        max: usize,
+        pause_ms: f64,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        let used_pages = plan.get_used_pages();
+        let survived_pages = used_pages.saturating_sub(state.used_pages_after_last_gc);
+        state.used_pages_after_last_gc = used_pages;
+
+        let is_nursery_gc = plan
+            .generational()
+            .map_or(false, |gen| !gen.last_collection_full_heap());
+        if !is_nursery_gc || state.young_pages == 0 {
+            return;
+        }
+
+        let survival_rate = (survived_pages as f64 / state.young_pages as f64).min(1.0);
+        state.survival_rate = Some(state.survival_rate.map_or(survival_rate, |prev| {
+            prev * Self::SMOOTH_FACTOR + survival_rate * (1.0 - Self::SMOOTH_FACTOR)
+        }));
+        if state.samples.len() == Self::SAMPLES {
+            state.samples.pop_front();
+        }
+        state.samples.push_back(NurseryGCSample {
+            survived_bytes: (survived_pages << LOG_BYTES_IN_PAGE) as f64,
+            pause: elapsed.as_secs_f64(),
+        });
+
+        let current = self.nursery_bytes(min, max);
+        let next = Self::next_nursery_bytes(
+            &state.samples,
+            state.survival_rate.unwrap(),
+            pause_ms / 1000.0,
+            current,
+            min,
+            max,
+        );
+        debug!(
+            "Nursery pause {} ms (target {} ms), survival rate {:.3}: nursery size {} -> {} bytes",
+            elapsed.as_secs_f64() * 1000.0,
+            pause_ms,
+            survival_rate,
+            current,
+            next
+        );
+        self.nursery_bytes.store(next, Ordering::Relaxed);
+    }
+
+    /// Fit `pause = fixed + cost * survived_bytes` to the samples with least squares, and return
+    /// `(fixed, cost)`.  If the samples do not tell the two apart (e.g. the survived bytes are all
+    /// the same), the whole pause is attributed to the survived bytes.
+    fn fit_pause_model(samples: &VecDeque<NurseryGCSample>) -> (f64, f64) {
+        let n = samples.len() as f64;
+        let mean_x = samples.iter().map(|s| s.survived_bytes).sum::<f64>() / n;
+        let mean_y = samples.iter().map(|s| s.pause).sum::<f64>() / n;
+        let (sxx, sxy) = samples.iter().fold((0f64, 0f64), |(sxx, sxy), s| {
+            let dx = s.survived_bytes - mean_x;
+            (sxx + dx * dx, sxy + dx * (s.pause - mean_y))
+        });
+        if sxx > 0.0 && sxy > 0.0 {
+            let cost = sxy / sxx;
+            let fixed = mean_y - cost * mean_x;
+            if fixed >= 0.0 {
+                return (fixed, cost);
+            }
+        }
+        if mean_x > 0.0 {
+            (0.0, mean_y / mean_x)
+        } else {
+            (mean_y, 0.0)
+        }
+    }
+
+    /// Return the nursery size whose survivors can be collected in the target pause time (in
+    /// seconds), changing the current size by at most `MAX_STEP` times.
+    fn next_nursery_bytes(
+        samples: &VecDeque<NurseryGCSample>,
+        survival_rate: f64,
+        target: f64,
+        current: usize,
+        min: usize,
+        max: usize,
+    ) -> usize {
+        let (fixed, cost) = Self::fit_pause_model(samples);
+        let cost_per_nursery_byte = cost * survival_rate;
+        let ideal = if fixed >= target {
+            0.0
+        } else if cost_per_nursery_byte > 0.0 {
+            (target - fixed) / cost_per_nursery_byte
+        } else {
+            f64::INFINITY
+        };
+        let next = ideal.clamp(
+            current as f64 / Self::MAX_STEP,
+            current as f64 * Self::MAX_STEP,
+        );
+        let next = conversions::raw_align_up(next.min(max as f64) as usize, BYTES_IN_PAGE);
+        next.clamp(min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: usize = 1 << 20;
+
+    fn samples(samples: &[(usize, f64)]) -> VecDeque<NurseryGCSample> {
+        samples
+            .iter()
+            .map(|&(survived_bytes, pause)| NurseryGCSample {
+                survived_bytes: survived_bytes as f64,
+                pause,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fit_pause_model() {
+        // 1 ms fixed, and 1 ms per MB.
+        let (fixed, cost) =
+            PauseTargetNursery::fit_pause_model(&samples(&[(MB, 0.002), (3 * MB, 0.004)]));
+        assert!((fixed - 0.001).abs() < 1e-9);
+        assert!((cost * MB as f64 - 0.001).abs() < 1e-9);
+
+        // The same survived bytes: all the pause is attributed to them.
+        let (fixed, cost) =
+            PauseTargetNursery::fit_pause_model(&samples(&[(MB, 0.002), (MB, 0.002)]));
+        assert_eq!(fixed, 0.0);
+        assert!((cost * MB as f64 - 0.002).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_next_nursery_bytes() {
+        // 1 ms fixed and 1 ms per surviving MB.  At a 10% survival rate, a 5 ms target allows
+        // 40 MB of nursery.
+        let s = samples(&[(MB, 0.002), (3 * MB, 0.004)]);
+        let next = PauseTargetNursery::next_nursery_bytes(&s, 0.1, 0.005, 32 * MB, MB, 64 * MB);
+        assert_eq!(next, 40 * MB);
+
+        // The nursery changes by at most 2x in one GC.
+        let next = PauseTargetNursery::next_nursery_bytes(&s, 0.1, 0.005, 8 * MB, MB, 64 * MB);
+        assert_eq!(next, 16 * MB);
+        let next = PauseTargetNursery::next_nursery_bytes(&s, 1.0, 0.001, 8 * MB, MB, 64 * MB);
+        assert_eq!(next, 4 * MB);
+
+        // The nursery stays within the bounds.
+        let next = PauseTargetNursery::next_nursery_bytes(&s, 0.1, 0.010, 48 * MB, MB, 64 * MB);
+        assert_eq!(next, 64 * MB);
+        let next = PauseTargetNursery::next_nursery_bytes(&s, 1.0, 0.001, 2 * MB, 2 * MB, 64 * MB);
+        assert_eq!(next, 2 * MB);
+    }
+}
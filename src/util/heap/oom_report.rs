@@ -0,0 +1,348 @@
+//! Out-of-memory reports.
+//!
+//! When an allocation fails because the heap is exhausted, MMTk describes the state of the heap
+//! in an [`OutOfMemoryReport`], and passes it to
+//! [`crate::vm::Collection::out_of_memory_report`] before calling
+//! [`crate::vm::Collection::out_of_memory`].  The report includes the failed request, the usage
+//! and the fragmentation of each space, the live bytes from the last GC, and the recent GCs.
+
+use crate::global_state::GlobalState;
+use crate::plan::Plan;
+use crate::policy::space::Space;
+use crate::util::alloc::AllocationError;
+use crate::util::constants::LOG_BYTES_IN_PAGE;
+use crate::util::heap::GCTriggerPolicy;
+use crate::vm::VMBinding;
+use crate::LiveBytesStats;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A summary of a GC, kept in the GC history.
+#[derive(Copy, Clone, Debug)]
+pub struct GCRecord {
+    /// How long the GC took.
+    pub elapsed: Duration,
+    /// The reserved pages (including the collection reserve) when the GC started.
+    pub reserved_pages_before: usize,
+    /// The reserved pages (including the collection reserve) when the GC ended.
+    pub reserved_pages_after: usize,
+    /// The heap size (in pages) decided by the GC trigger at the end of the GC.
+    pub heap_pages: usize,
+    /// Was it an emergency GC?
+    pub emergency: bool,
+    /// Was it triggered by the user?
+    pub user_triggered: bool,
+    /// Did it collect the whole heap?
+    pub exhaustive: bool,
+}
+
+/// The recent GCs.
+pub(crate) struct GCHistory {
+    state: Mutex<GCHistoryState>,
+}
+
+struct GCHistoryState {
+    /// The recent GCs, from the oldest to the latest.
+    records: VecDeque<GCRecord>,
+    /// The reserved pages when the current GC started.
+    reserved_pages_at_start: usize,
+}
+
+impl GCHistory {
+    /// The number of GCs to keep.
+    const LENGTH: usize = 16;
+
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(GCHistoryState {
+                records: VecDeque::with_capacity(Self::LENGTH),
+                reserved_pages_at_start: 0,
+            }),
+        }
+    }
+
+    /// Called when a GC starts.
+    pub fn on_gc_start<VM: VMBinding>(&self, plan: &dyn Plan<VM = VM>) {
+        self.state.lock().unwrap().reserved_pages_at_start = plan.get_reserved_pages();
+    }
+
+    /// Called at the end of a GC, before the collection state is reset.
+    pub fn on_gc_end<VM: VMBinding>(
+        &self,
+        plan: &dyn Plan<VM = VM>,
+        state: &GlobalState,
+        heap_pages: usize,
+        elapsed: Duration,
+    ) {
+        let mut history = self.state.lock().unwrap();
+        if history.records.len() == Self::LENGTH {
+            history.records.pop_front();
+        }
+        let record = GCRecord {
+            elapsed,
+            reserved_pages_before: history.reserved_pages_at_start,
+            reserved_pages_after: plan.get_reserved_pages(),
+            heap_pages,
+            emergency: state.is_emergency_collection(),
+            user_triggered: state.is_user_triggered_collection(),
+            exhaustive: plan.last_collection_was_exhaustive(),
+        };
+        history.records.push_back(record);
+    }
+
+    /// Return the recent GCs, from the oldest to the latest.
+    pub fn records(&self) -> Vec<GCRecord> {
+        self.state.lock().unwrap().records.iter().copied().collect()
+    }
+}
+
+/// How free memory is scattered in a space.  Each policy reports what describes its
+/// fragmentation best.
+#[derive(Copy, Clone, Debug)]
+pub enum SpaceFragmentation {
+    /// An Immix space.  Free lines in reusable blocks can only be used by objects that fit in the
+    /// holes between live lines.
+    Immix {
+        /// The number of allocated blocks.
+        blocks: usize,
+        /// The number of blocks that have free lines.
+        reusable_blocks: usize,
+        /// The number of free lines in reusable blocks.
+        free_lines: usize,
+        /// The size of a line in bytes.
+        line_bytes: usize,
+    },
+    /// A native mark sweep space.  Free cells can only be used by objects of their size class.
+    MarkSweep {
+        /// The number of allocated blocks.
+        blocks: usize,
+        /// The number of free cells in blocks that are not owned by any mutator.
+        free_cells: usize,
+        /// The total size of those free cells in bytes.
+        free_cell_bytes: usize,
+    },
+    /// A large object space.  Each object occupies whole pages.
+    LargeObject {
+        /// The number of pages that hold large objects.
+        pages: usize,
+    },
+}
+
+/// The memory usage of a space.
+#[derive(Clone, Debug)]
+pub struct SpaceReport {
+    /// The name of the space.
+    pub name: &'static str,
+    /// The pages reserved by the space, including its side metadata.
+    pub reserved_pages: usize,
+    /// The pages committed by the space.
+    pub committed_pages: usize,
+    /// The pages committed by the space that are still resident (i.e. not returned to the OS).
+    pub resident_pages: usize,
+    /// The live bytes found in the last GC, if the option `count_live_bytes_in_gc` is enabled.
+    pub live_bytes: Option<LiveBytesStats>,
+    /// The fragmentation of the space, if its policy reports it.
+    pub fragmentation: Option<SpaceFragmentation>,
+}
+
+/// Describes the state of the heap when an allocation ran out of memory.
+#[derive(Clone, Debug)]
+pub struct OutOfMemoryReport {
+    /// The kind of the error.
+    pub error: AllocationError,
+    /// The size of the failed allocation in bytes.
+    pub request_bytes: usize,
+    /// The alignment of the failed allocation in bytes, if it is known.
+    pub request_align: Option<usize>,
+    /// The space that the failed allocation was made to.  The space is decided by the allocation
+    /// semantics.
+    pub space: &'static str,
+    /// The reserved pages of the heap, including the collection reserve.
+    pub reserved_pages: usize,
+    /// The used pages of the heap.
+    pub used_pages: usize,
+    /// The current heap size in pages.
+    pub heap_pages: usize,
+    /// The max heap size in pages.
+    pub max_heap_pages: usize,
+    /// The memory usage of each space.
+    pub spaces: Vec<SpaceReport>,
+    /// The recent GCs, from the oldest to the latest.
+    pub gc_history: Vec<GCRecord>,
+}
+
+impl OutOfMemoryReport {
+    /// Describe the current state of the heap.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new<VM: VMBinding>(
+        error: AllocationError,
+        request_bytes: usize,
+        request_align: Option<usize>,
+        space: &dyn Space<VM>,
+        plan: &dyn Plan<VM = VM>,
+        policy: &dyn GCTriggerPolicy<VM>,
+        state: &GlobalState,
+        history: &GCHistory,
+    ) -> Self {
+        // The live bytes may be updated by a GC running at the same time.  Skip them if so.
+        let live_bytes = state
+            .live_bytes_in_last_gc
+            .try_borrow()
+            .map(|live_bytes| live_bytes.clone())
+            .unwrap_or_default();
+        let mut spaces = vec![];
+        plan.for_each_space(&mut |space| {
+            let pr = space.get_page_resource();
+            spaces.push(SpaceReport {
+                name: space.get_name(),
+                reserved_pages: space.reserved_pages(),
+                committed_pages: pr.committed_pages(),
+                resident_pages: pr.resident_pages(),
+                live_bytes: live_bytes.get(space.get_name()).copied(),
+                fragmentation: space.fragmentation(),
+            });
+        });
+        Self {
+            error,
+            request_bytes,
+            request_align,
+            space: space.get_name(),
+            reserved_pages: plan.get_reserved_pages(),
+            used_pages: plan.get_used_pages(),
+            heap_pages: policy.get_current_heap_size_in_pages(),
+            max_heap_pages: policy.get_max_heap_size_in_pages(),
+            spaces,
+            gc_history: history.records(),
+        }
+    }
+}
+
+fn pages_to_mb(pages: usize) -> f64 {
+    (pages << LOG_BYTES_IN_PAGE) as f64 / (1024 * 1024) as f64
+}
+
+impl fmt::Display for OutOfMemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:?}: failed to allocate {} bytes{} in {}",
+            self.error,
+            self.request_bytes,
+            self.request_align
+                .map_or(String::new(), |align| format!(" (align {})", align)),
+            self.space
+        )?;
+        writeln!(
+            f,
+            "Heap: {} reserved / {} used / {} current / {} max pages ({:.1} MB max)",
+            self.reserved_pages,
+            self.used_pages,
+            self.heap_pages,
+            self.max_heap_pages,
+            pages_to_mb(self.max_heap_pages)
+        )?;
+        for space in self.spaces.iter() {
+            write!(
+                f,
+                "  {}: {} reserved / {} committed / {} resident pages",
+                space.name, space.reserved_pages, space.committed_pages, space.resident_pages
+            )?;
+            if let Some(live) = space.live_bytes {
+                write!(f, ", {} live bytes", live.live_bytes)?;
+            }
+            match space.fragmentation {
+                Some(SpaceFragmentation::Immix {
+                    blocks,
+                    reusable_blocks,
+                    free_lines,
+                    line_bytes,
+                }) => write!(
+                    f,
+                    ", {} blocks, {} reusable blocks, {} free lines ({} bytes)",
+                    blocks,
+                    reusable_blocks,
+                    free_lines,
+                    free_lines * line_bytes
+                )?,
+                Some(SpaceFragmentation::MarkSweep {
+                    blocks,
+                    free_cells,
+                    free_cell_bytes,
+                }) => write!(
+                    f,
+                    ", {} blocks, {} free cells ({} bytes)",
+                    blocks, free_cells, free_cell_bytes
+                )?,
+                Some(SpaceFragmentation::LargeObject { pages }) => {
+                    write!(f, ", {} pages of large objects", pages)?
+                }
+                None => {}
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "Recent GCs (oldest first):")?;
+        for gc in self.gc_history.iter() {
+            writeln!(
+                f,
+                "  {} -> {} pages (heap {} pages) in {} ms{}{}{}",
+                gc.reserved_pages_before,
+                gc.reserved_pages_after,
+                gc.heap_pages,
+                gc.elapsed.as_millis(),
+                if gc.exhaustive { ", full heap" } else { "" },
+                if gc.emergency { ", emergency" } else { "" },
+                if gc.user_triggered { ", user" } else { "" },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let report = OutOfMemoryReport {
+            error: AllocationError::HeapOutOfMemory,
+            request_bytes: 4096,
+            request_align: Some(8),
+            space: "immix",
+            reserved_pages: 256,
+            used_pages: 250,
+            heap_pages: 256,
+            max_heap_pages: 256,
+            spaces: vec![SpaceReport {
+                name: "immix",
+                reserved_pages: 200,
+                committed_pages: 200,
+                resident_pages: 180,
+                live_bytes: None,
+                fragmentation: Some(SpaceFragmentation::Immix {
+                    blocks: 20,
+                    reusable_blocks: 3,
+                    free_lines: 10,
+                    line_bytes: 256,
+                }),
+            }],
+            gc_history: vec![GCRecord {
+                elapsed: Duration::from_millis(5),
+                reserved_pages_before: 256,
+                reserved_pages_after: 250,
+                heap_pages: 256,
+                emergency: true,
+                user_triggered: false,
+                exhaustive: true,
+            }],
+        };
+        let text = report.to_string();
+        assert!(
+            text.starts_with("HeapOutOfMemory: failed to allocate 4096 bytes (align 8) in immix\n")
+        );
+        assert!(text.contains("immix: 200 reserved / 200 committed / 180 resident pages, 20 blocks, 3 reusable blocks, 10 free lines (2560 bytes)\n"));
+        assert!(text.contains("256 -> 250 pages (heap 256 pages) in 5 ms, full heap, emergency\n"));
+    }
+}
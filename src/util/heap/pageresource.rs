@@ -1,3 +1,4 @@
+use crate::mmtk::MMAPPER;
 use crate::util::address::Address;
 use crate::util::conversions;
 use crate::util::freelist::FreeList;
@@ -83,6 +84,22 @@ pub trait PageResource<VM: VMBinding>: 'static {
         self.common().accounting.get_committed_pages()
     }
 
+    /// Return the number of pages that may be resident in memory. See
+    /// [`PageAccounting::get_resident_pages`].
+    fn resident_pages(&self) -> usize {
+        self.common().accounting.get_resident_pages()
+    }
+
+    /// Return the physical memory of the free pages of this resource to the OS, and return the
+    /// number of pages returned. This is only called when mutators are stopped. By default, this
+    /// does nothing.
+    ///
+    /// Arguments:
+    /// * `lazy`: Let the OS reclaim the memory lazily. See [`crate::util::memory::uncommit`].
+    fn uncommit_free_pages(&self, _lazy: bool) -> usize {
+        0
+    }
+
     /// Return the number of available physical pages by this resource. This includes all pages
     /// currently unused by this resource. If the resource is using a discontiguous space, it also
     /// includes the currently unassigned discontiguous space.
@@ -111,6 +128,52 @@ pub trait PageResource<VM: VMBinding>: 'static {
     }
 }
 
+/// Return the physical memory of the pages in the given range that are mapped by MMTk to the OS,
+/// and return the number of pages returned. Unmapped parts of the range are skipped, as they may
+/// belong to other mappings. `limit` is the highest address that has ever been allocated in the
+/// range. Above it, we stop at the first unmapped part, as nothing above it was ever mapped.
+pub(crate) fn uncommit_mapped_pages(
+    start: Address,
+    end: Address,
+    limit: Address,
+    lazy: bool,
+) -> usize {
+    let granularity = MMAPPER.granularity();
+    let mut uncommitted = 0;
+    let mut run_start = None;
+    let mut cursor = start;
+    while cursor < end {
+        let next = (cursor + granularity).align_down(granularity).min(end);
+        if MMAPPER.is_mapped_address(cursor) {
+            run_start.get_or_insert(cursor);
+        } else {
+            if let Some(run_start) = run_start.take() {
+                uncommitted += uncommit_pages(run_start, cursor, lazy);
+            }
+            if cursor >= limit {
+                break;
+            }
+        }
+        cursor = next;
+    }
+    if let Some(run_start) = run_start {
+        uncommitted += uncommit_pages(run_start, cursor, lazy);
+    }
+    uncommitted
+}
+
+/// Return the physical memory of the pages in `[start, end)` to the OS, and return the number of
+/// pages returned.
+pub(crate) fn uncommit_pages(start: Address, end: Address, lazy: bool) -> usize {
+    match crate::util::memory::uncommit(start, end - start, lazy) {
+        Ok(()) => conversions::bytes_to_pages_up(end - start),
+        Err(e) => {
+            warn!("Failed to uncommit memory [{}, {}): {}", start, end, e);
+            0
+        }
+    }
+}
+
 pub struct PRAllocResult {
     pub start: Address,
     pub pages: usize,
@@ -164,6 +164,16 @@ impl<VM: VMBinding, R: Region + 'static> RegionPageResource<VM, R> {
         alloc.set_cursor(new);
     }
 
+    /// Retire one region, so that the allocator will not allocate in the region
+    /// until its cursor is reset. The pages after the cursor are accounted as
+    /// reserved, and [`Self::reset_cursor`] releases them again.
+    pub fn retire_region(&self, alloc: &AllocatedRegion<R>) {
+        let end = alloc.region.end();
+        let pages = (end - alloc.cursor()) / BYTES_IN_PAGE;
+        self.common().accounting.reserve_and_commit(pages);
+        alloc.set_cursor(end);
+    }
+
     /// Reset the allocator state after a collection, so that the allocator will
     /// revisit regions which the garbage collector has compacted.
     pub fn reset_allocator(&self) {
@@ -0,0 +1,504 @@
+//! Recording and replaying the GCs of a run, for debugging.
+//!
+//! [`RecordingTrigger`] wraps a GC trigger policy, and writes each GC to a file: the bytes that
+//! had been allocated when the GC started, and whether it was a nursery or a full heap GC.
+//! [`ReplayTrigger`] reads such a file, and triggers GCs at exactly the recorded allocated bytes,
+//! so that a bug that depends on the timing of GCs can be reproduced.  The allocated bytes are
+//! counted in pages acquired by spaces, so the replay is exact if the mutators allocate
+//! deterministically.
+//!
+//! The triggers are used if the option `gc_trigger_record` or `gc_trigger_replay` is set.  A
+//! binding that uses the `Delegated` GC trigger may also wrap its own policy in a
+//! [`RecordingTrigger`], or return a [`ReplayTrigger`], in
+//! [`crate::vm::Collection::create_gc_trigger`].
+//!
+//! The file is a text file with the heap size of the recorded run, followed by one line for each
+//! GC:
+//!
+//! ```text
+//! heap_pages 65536
+//! 8388608 nursery
+//! 16777216 full
+//! 20971520 full user
+//! ```
+
+use crate::plan::Plan;
+use crate::util::constants::LOG_BYTES_IN_PAGE;
+use crate::util::heap::{GCTriggerPolicy, SpaceStats};
+use crate::vm::VMBinding;
+use crate::MMTK;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The kind of a recorded GC.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScheduledGCKind {
+    /// A nursery GC of a generational plan.
+    Nursery,
+    /// A full heap GC.  All the GCs of non-generational plans are full heap GCs.
+    Full,
+}
+
+/// A recorded GC.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScheduledGC {
+    /// The bytes allocated since MMTk started when the GC started.
+    pub allocated_bytes: usize,
+    /// The kind of the GC.
+    pub kind: ScheduledGCKind,
+    /// Was the GC requested by the user (e.g. `System.gc()` in Java)?  User GCs are not triggered
+    /// by the replay, as the binding requests them again.
+    pub user_triggered: bool,
+}
+
+/// The GCs recorded in a run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GCSchedule {
+    /// The max heap size (in pages) of the recorded run.
+    pub heap_pages: usize,
+    /// The GCs, in the order they happened.
+    pub gcs: Vec<ScheduledGC>,
+}
+
+impl GCSchedule {
+    /// Read a schedule from a file written by [`RecordingTrigger`].
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        std::fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl fmt::Display for ScheduledGC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}{}",
+            self.allocated_bytes,
+            match self.kind {
+                ScheduledGCKind::Nursery => "nursery",
+                ScheduledGCKind::Full => "full",
+            },
+            if self.user_triggered { " user" } else { "" }
+        )
+    }
+}
+
+impl fmt::Display for GCSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "heap_pages {}", self.heap_pages)?;
+        for gc in self.gcs.iter() {
+            writeln!(f, "{}", gc)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ScheduledGC {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let (bytes, kind, user_triggered) = match fields[..] {
+            [bytes, kind] => (bytes, kind, false),
+            [bytes, kind, "user"] => (bytes, kind, true),
+            _ => return Err(format!("Invalid GC: {:?}", s)),
+        };
+        let allocated_bytes = bytes
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid allocated bytes: {:?}", bytes))?;
+        let kind = match kind {
+            "nursery" => ScheduledGCKind::Nursery,
+            "full" => ScheduledGCKind::Full,
+            _ => return Err(format!("Invalid GC kind: {:?}", kind)),
+        };
+        Ok(ScheduledGC {
+            allocated_bytes,
+            kind,
+            user_triggered,
+        })
+    }
+}
+
+impl FromStr for GCSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().filter(|line| !line.trim().is_empty());
+        let heap_pages = lines
+            .next()
+            .and_then(|line| line.strip_prefix("heap_pages "))
+            .and_then(|pages| pages.trim().parse::<usize>().ok())
+            .ok_or_else(|| "The schedule does not start with the heap size".to_string())?;
+        let gcs = lines.map(str::parse).collect::<Result<Vec<_>, _>>()?;
+        Ok(GCSchedule { heap_pages, gcs })
+    }
+}
+
+/// Counts the bytes allocated since MMTk started, from the used pages of the plan.  The used pages
+/// only grow between GCs, so the allocated bytes are the used pages that grew between GCs.
+struct AllocationCounter {
+    /// The pages allocated before the current (or the last) GC started.
+    allocated_pages: AtomicUsize,
+    /// The used pages at the end of the last GC.
+    used_pages_after_last_gc: AtomicUsize,
+}
+
+impl AllocationCounter {
+    fn new() -> Self {
+        Self {
+            allocated_pages: AtomicUsize::new(0),
+            used_pages_after_last_gc: AtomicUsize::new(0),
+        }
+    }
+
+    /// Return the bytes allocated so far.
+    fn allocated_bytes<VM: VMBinding>(&self, plan: &dyn Plan<VM = VM>) -> usize {
+        let allocated_since_last_gc = plan
+            .get_used_pages()
+            .saturating_sub(self.used_pages_after_last_gc.load(Ordering::Relaxed));
+        (self.allocated_pages.load(Ordering::Relaxed) + allocated_since_last_gc)
+            << LOG_BYTES_IN_PAGE
+    }
+
+    /// Called when a GC starts.  Return the bytes allocated before the GC.
+    fn on_gc_start<VM: VMBinding>(&self, plan: &dyn Plan<VM = VM>) -> usize {
+        let allocated_bytes = self.allocated_bytes(plan);
+        self.allocated_pages
+            .store(allocated_bytes >> LOG_BYTES_IN_PAGE, Ordering::Relaxed);
+        allocated_bytes
+    }
+
+    /// Called when a GC ends.
+    fn on_gc_end<VM: VMBinding>(&self, plan: &dyn Plan<VM = VM>) {
+        self.used_pages_after_last_gc
+            .store(plan.get_used_pages(), Ordering::Relaxed);
+    }
+
+    /// Return the bytes allocated before the current (or the last) GC started.
+    fn allocated_bytes_at_gc_start(&self) -> usize {
+        self.allocated_pages.load(Ordering::Relaxed) << LOG_BYTES_IN_PAGE
+    }
+}
+
+/// Return the current GC as a [`ScheduledGC`].  This is called at the end of the GC.
+fn current_gc<VM: VMBinding>(mmtk: &'static MMTK<VM>, allocated_bytes: usize) -> ScheduledGC {
+    let full_heap = mmtk
+        .get_plan()
+        .generational()
+        .map_or(true, |gen| gen.last_collection_full_heap());
+    ScheduledGC {
+        allocated_bytes,
+        kind: if full_heap {
+            ScheduledGCKind::Full
+        } else {
+            ScheduledGCKind::Nursery
+        },
+        user_triggered: mmtk.state.is_user_triggered_collection(),
+    }
+}
+
+/// A GC trigger policy that records each GC to a file, and otherwise behaves like the policy it
+/// wraps.
+pub struct RecordingTrigger<VM: VMBinding> {
+    inner: Box<dyn GCTriggerPolicy<VM>>,
+    counter: AllocationCounter,
+    /// Each GC is written to the file as soon as it ends, so the schedule is kept if the run
+    /// crashes.
+    file: Mutex<File>,
+}
+
+impl<VM: VMBinding> RecordingTrigger<VM> {
+    /// Create a policy that records the GCs triggered by `inner` to the file at `path`.
+    pub fn new(inner: Box<dyn GCTriggerPolicy<VM>>, path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        writeln!(file, "heap_pages {}", inner.get_max_heap_size_in_pages())?;
+        Ok(Self {
+            inner,
+            counter: AllocationCounter::new(),
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl<VM: VMBinding> GCTriggerPolicy<VM> for RecordingTrigger<VM> {
+    fn on_pending_allocation(&self, pages: usize) {
+        self.inner.on_pending_allocation(pages)
+    }
+
+    fn on_gc_start(&self, mmtk: &'static MMTK<VM>) {
+        self.counter.on_gc_start(mmtk.get_plan());
+        self.inner.on_gc_start(mmtk)
+    }
+
+    fn on_gc_release(&self, mmtk: &'static MMTK<VM>) {
+        self.inner.on_gc_release(mmtk)
+    }
+
+    fn on_gc_end(&self, mmtk: &'static MMTK<VM>) {
+        self.inner.on_gc_end(mmtk);
+        let gc = current_gc(mmtk, self.counter.allocated_bytes_at_gc_start());
+        if let Err(e) = writeln!(self.file.lock().unwrap(), "{}", gc) {
+            warn!("Failed to record GC {}: {}", gc, e);
+        }
+        self.counter.on_gc_end(mmtk.get_plan());
+    }
+
+    fn is_gc_required(
+        &self,
+        space_full: bool,
+        space: Option<SpaceStats<VM>>,
+        plan: &dyn Plan<VM = VM>,
+    ) -> bool {
+        self.inner.is_gc_required(space_full, space, plan)
+    }
+
+    fn is_heap_full(&self, plan: &dyn Plan<VM = VM>) -> bool {
+        self.inner.is_heap_full(plan)
+    }
+
+    fn get_current_heap_size_in_pages(&self) -> usize {
+        self.inner.get_current_heap_size_in_pages()
+    }
+
+    fn get_max_heap_size_in_pages(&self) -> usize {
+        self.inner.get_max_heap_size_in_pages()
+    }
+
+    fn can_heap_size_grow(&self) -> bool {
+        self.inner.can_heap_size_grow()
+    }
+
+    fn set_heap_size_bounds(&self, min_pages: usize, max_pages: usize) -> bool {
+        self.inner.set_heap_size_bounds(min_pages, max_pages)
+    }
+}
+
+/// A GC trigger policy that triggers GCs at the allocated bytes recorded by [`RecordingTrigger`].
+/// The heap size is fixed to the max heap size of the recorded run.
+///
+/// A GC is still triggered if a space is full before the next recorded GC.  Such a GC is reported
+/// as an extra GC, and the next recorded GC is still expected.  The plan may also decide to do a
+/// full heap GC when a nursery GC is recorded.  Such a GC is reported as diverging from the
+/// schedule.  After all the recorded GCs, GCs are triggered by the plan as with a fixed heap size.
+pub struct ReplayTrigger {
+    schedule: GCSchedule,
+    /// The index of the next GC in the schedule.
+    next: AtomicUsize,
+    counter: AllocationCounter,
+}
+
+impl ReplayTrigger {
+    /// Create a policy that replays the given schedule.
+    pub fn new(schedule: GCSchedule) -> Self {
+        Self {
+            schedule,
+            next: AtomicUsize::new(0),
+            counter: AllocationCounter::new(),
+        }
+    }
+
+    /// Create a policy that replays the schedule in the file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        GCSchedule::read(path).map(Self::new)
+    }
+
+    /// Return the schedule being replayed.
+    pub fn schedule(&self) -> &GCSchedule {
+        &self.schedule
+    }
+
+    fn next_gc(&self) -> Option<&ScheduledGC> {
+        self.schedule.gcs.get(self.next.load(Ordering::Relaxed))
+    }
+
+    /// Check a GC that just ended against the schedule, and move to the next scheduled GC if the
+    /// GC replayed the current one.  A GC is the replay of a scheduled user GC if it was requested
+    /// by the user, and the replay of any other scheduled GC if it was triggered by the schedule,
+    /// i.e. when the recorded bytes had been allocated.  Other GCs, such as a GC triggered by a
+    /// full space before the scheduled point, are extra GCs that do not consume the schedule.
+    fn on_replayed_gc(&self, gc: ScheduledGC) {
+        let index = self.next.load(Ordering::Relaxed);
+        let Some(expected) = self.schedule.gcs.get(index) else {
+            return;
+        };
+        let replayed = if expected.user_triggered {
+            gc.user_triggered
+        } else {
+            !gc.user_triggered && gc.allocated_bytes >= expected.allocated_bytes
+        };
+        if !replayed {
+            warn!(
+                "Extra GC {} is not in the schedule: expected {} next",
+                gc, expected
+            );
+            return;
+        }
+        if *expected == gc {
+            debug!("Replayed GC {}: {}", index, gc);
+        } else {
+            warn!(
+                "GC {} diverged from the schedule: expected {}, got {}",
+                index, expected, gc
+            );
+        }
+        self.next.store(index + 1, Ordering::Relaxed);
+        if index + 1 == self.schedule.gcs.len() {
+            info!("All the {} recorded GCs have been replayed", index + 1);
+        }
+    }
+}
+
+impl<VM: VMBinding> GCTriggerPolicy<VM> for ReplayTrigger {
+    fn on_gc_start(&self, mmtk: &'static MMTK<VM>) {
+        self.counter.on_gc_start(mmtk.get_plan());
+    }
+
+    fn on_gc_end(&self, mmtk: &'static MMTK<VM>) {
+        self.on_replayed_gc(current_gc(mmtk, self.counter.allocated_bytes_at_gc_start()));
+        self.counter.on_gc_end(mmtk.get_plan());
+    }
+
+    fn is_gc_required(
+        &self,
+        space_full: bool,
+        space: Option<SpaceStats<VM>>,
+        plan: &dyn Plan<VM = VM>,
+    ) -> bool {
+        match self.next_gc() {
+            Some(gc) if gc.user_triggered => {
+                // The binding will request the next GC.
+                space_full
+            }
+            Some(gc) => {
+                if space_full || self.counter.allocated_bytes(plan) >= gc.allocated_bytes {
+                    if gc.kind == ScheduledGCKind::Full {
+                        if let Some(gen) = plan.generational() {
+                            gen.force_full_heap_collection();
+                        }
+                    }
+                    true
+                } else {
+                    false
+                }
+            }
+            None => plan.collection_required(space_full, space),
+        }
+    }
+
+    fn is_heap_full(&self, plan: &dyn Plan<VM = VM>) -> bool {
+        plan.get_reserved_pages() > self.schedule.heap_pages
+    }
+
+    fn get_current_heap_size_in_pages(&self) -> usize {
+        self.schedule.heap_pages
+    }
+
+    fn get_max_heap_size_in_pages(&self) -> usize {
+        self.schedule.heap_pages
+    }
+
+    fn can_heap_size_grow(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_round_trip() {
+        let schedule = GCSchedule {
+            heap_pages: 65536,
+            gcs: vec![
+                ScheduledGC {
+                    allocated_bytes: 8388608,
+                    kind: ScheduledGCKind::Nursery,
+                    user_triggered: false,
+                },
+                ScheduledGC {
+                    allocated_bytes: 20971520,
+                    kind: ScheduledGCKind::Full,
+                    user_triggered: true,
+                },
+            ],
+        };
+        let text = schedule.to_string();
+        assert_eq!(
+            text,
+            "heap_pages 65536\n8388608 nursery\n20971520 full user\n"
+        );
+        assert_eq!(text.parse::<GCSchedule>(), Ok(schedule));
+    }
+
+    #[test]
+    fn test_schedule_invalid() {
+        assert!("".parse::<GCSchedule>().is_err());
+        assert!("8388608 nursery\n".parse::<GCSchedule>().is_err());
+        assert!("heap_pages 1\n8388608 minor\n"
+            .parse::<GCSchedule>()
+            .is_err());
+        assert!("heap_pages 1\n8388608 full system\n"
+            .parse::<GCSchedule>()
+            .is_err());
+        assert!("heap_pages 1\nabc full\n".parse::<GCSchedule>().is_err());
+    }
+
+    #[test]
+    fn test_replay_extra_gc() {
+        let gc = |allocated_bytes, kind, user_triggered| ScheduledGC {
+            allocated_bytes,
+            kind,
+            user_triggered,
+        };
+        let trigger = ReplayTrigger::new(GCSchedule {
+            heap_pages: 16,
+            gcs: vec![
+                gc(8192, ScheduledGCKind::Nursery, false),
+                gc(16384, ScheduledGCKind::Full, true),
+                gc(24576, ScheduledGCKind::Nursery, false),
+            ],
+        });
+        let next = || trigger.next.load(Ordering::Relaxed);
+
+        // A GC forced by a full space before the scheduled point is an extra GC.
+        trigger.on_replayed_gc(gc(4096, ScheduledGCKind::Full, false));
+        assert_eq!(next(), 0);
+        trigger.on_replayed_gc(gc(8192, ScheduledGCKind::Nursery, false));
+        assert_eq!(next(), 1);
+        // Only a user GC replays a scheduled user GC.
+        trigger.on_replayed_gc(gc(16384, ScheduledGCKind::Full, false));
+        assert_eq!(next(), 1);
+        trigger.on_replayed_gc(gc(12288, ScheduledGCKind::Full, true));
+        assert_eq!(next(), 2);
+        // An unexpected user GC is an extra GC.
+        trigger.on_replayed_gc(gc(28672, ScheduledGCKind::Full, true));
+        assert_eq!(next(), 2);
+        // A scheduled GC that diverged from the schedule still consumes it.
+        trigger.on_replayed_gc(gc(28672, ScheduledGCKind::Full, false));
+        assert_eq!(next(), 3);
+        trigger.on_replayed_gc(gc(32768, ScheduledGCKind::Full, false));
+        assert_eq!(next(), 3);
+    }
+
+    #[test]
+    fn test_read_file() {
+        let path = std::env::temp_dir().join(format!("mmtk-gc-schedule-{}", std::process::id()));
+        std::fs::write(&path, "heap_pages 16\n4096 full\n").unwrap();
+        let schedule = GCSchedule::read(&path).unwrap();
+        assert_eq!(schedule.heap_pages, 16);
+        assert_eq!(schedule.gcs.len(), 1);
+        std::fs::write(&path, "4096 full\n").unwrap();
+        assert_eq!(
+            GCSchedule::read(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+//! Returning the free memory in the heap to the OS.
+//!
+//! Page resources keep the memory of free pages mapped, so the memory stays resident after the
+//! heap shrinks, for example, after an allocation spike.  If the option `heap_uncommit` is
+//! enabled, the free pages of each page resource are returned to the OS with `madvise` at the end
+//! of a GC, while mutators are still stopped.
+
+use crate::plan::Plan;
+use crate::util::options::{HeapUncommit, Options};
+use crate::vm::VMBinding;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Decides when to return the free memory in the heap to the OS.
+pub(crate) struct HeapUncommitter {
+    /// How free memory is returned.
+    mode: HeapUncommit,
+    /// The delay before free memory is returned again if the heap has not shrunk.
+    delay: Duration,
+    state: Mutex<HeapUncommitterState>,
+}
+
+struct HeapUncommitterState {
+    /// The heap size (in pages) at the end of the last GC.
+    heap_pages: usize,
+    /// The time when free memory was last returned, or when MMTk started.
+    last_uncommit: Instant,
+}
+
+impl HeapUncommitter {
+    pub fn new(options: &Options) -> Self {
+        Self {
+            mode: *options.heap_uncommit,
+            delay: Duration::from_millis(*options.heap_uncommit_delay_ms as u64),
+            state: Mutex::new(HeapUncommitterState {
+                heap_pages: 0,
+                last_uncommit: Instant::now(),
+            }),
+        }
+    }
+
+    /// Called at the end of a GC, before mutators are resumed.  Return the free memory to the OS
+    /// if the heap has shrunk since the last GC, or if the delay has passed since free memory was
+    /// last returned.
+    ///
+    /// Arguments:
+    /// * `plan`: The plan in use.
+    /// * `heap_pages`: The heap size (in pages) decided by the GC trigger for the next GC.
+    pub fn on_gc_end<VM: VMBinding>(&self, plan: &dyn Plan<VM = VM>, heap_pages: usize) {
+        if self.mode == HeapUncommit::Disabled {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let shrunk = heap_pages < state.heap_pages;
+        state.heap_pages = heap_pages;
+        if !shrunk && state.last_uncommit.elapsed() < self.delay {
+            return;
+        }
+
+        let lazy = self.mode == HeapUncommit::Free;
+        let mut uncommitted = 0;
+        plan.for_each_space(&mut |space| {
+            uncommitted += space.get_page_resource().uncommit_free_pages(lazy);
+        });
+        state.last_uncommit = Instant::now();
+        info!(
+            "Returned {} free pages to the OS ({})",
+            uncommitted,
+            if shrunk {
+                "heap shrunk"
+            } else {
+                "uncommit delay passed"
+            }
+        );
+    }
+}
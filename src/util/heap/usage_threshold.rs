@@ -0,0 +1,189 @@
+//! Heap usage thresholds.
+//!
+//! The binding may register thresholds of the memory used by the heap, or by a single space, after
+//! a GC.  At the end of each GC, MMTk compares the used memory against each threshold, and calls
+//! [`crate::vm::Collection::heap_usage_threshold_crossed`] for each threshold that the used
+//! memory has crossed (in either direction) since the previous GC.  This is what Java's
+//! `MemoryPoolMXBean` calls a collection usage threshold.
+
+use crate::plan::Plan;
+use crate::util::constants::LOG_BYTES_IN_PAGE;
+use crate::util::VMWorkerThread;
+use crate::vm::{Collection, VMBinding};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The identifier of a registered heap usage threshold.  It is returned by
+/// [`crate::memory_manager::add_heap_usage_threshold`], and can be used to remove the threshold.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HeapUsageThresholdId(usize);
+
+/// The amount of used memory at which a heap usage threshold is reached.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HeapUsageLimit {
+    /// A number of bytes.
+    Bytes(usize),
+    /// A fraction of the max heap size, e.g. 0.8 for 80% of the max heap size.  The max heap size
+    /// at the end of each GC is used, so the limit follows changes of the heap size bounds.
+    MaxHeapFraction(f64),
+}
+
+impl HeapUsageLimit {
+    /// Is the limit valid?  A fraction must be positive.
+    pub(crate) fn validate(&self) -> bool {
+        match *self {
+            HeapUsageLimit::Bytes(_) => true,
+            HeapUsageLimit::MaxHeapFraction(fraction) => fraction > 0f64 && fraction.is_finite(),
+        }
+    }
+
+    /// Return the limit in bytes, given the max heap size in bytes.
+    fn to_bytes(self, max_heap_bytes: usize) -> usize {
+        match self {
+            HeapUsageLimit::Bytes(bytes) => bytes,
+            HeapUsageLimit::MaxHeapFraction(fraction) => {
+                (max_heap_bytes as f64 * fraction) as usize
+            }
+        }
+    }
+}
+
+/// A threshold of the memory used after a GC.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeapUsageThreshold {
+    /// The name of the space to watch, or `None` to watch the whole heap.
+    pub space: Option<&'static str>,
+    /// The threshold is exceeded if the used memory is no smaller than the limit.
+    pub limit: HeapUsageLimit,
+}
+
+/// Describes a heap usage threshold that was crossed in a GC.  It is passed to
+/// [`crate::vm::Collection::heap_usage_threshold_crossed`].
+#[derive(Clone, Debug)]
+pub struct HeapUsageThresholdEvent {
+    /// The identifier of the threshold.
+    pub id: HeapUsageThresholdId,
+    /// The threshold that was crossed.
+    pub threshold: HeapUsageThreshold,
+    /// True if the used memory went from below the limit to at or above the limit.  False if it
+    /// went from at or above the limit to below the limit.
+    pub exceeded: bool,
+    /// The bytes used by the watched space (or the whole heap) after the GC.
+    pub used_bytes: usize,
+    /// The limit of the threshold in bytes at the end of the GC.
+    pub limit_bytes: usize,
+    /// The bytes used by each space after the GC, keyed by the space name.
+    pub space_used_bytes: HashMap<&'static str, usize>,
+}
+
+struct RegisteredThreshold {
+    id: HeapUsageThresholdId,
+    threshold: HeapUsageThreshold,
+    /// Was the threshold exceeded at the end of the last GC?
+    exceeded: bool,
+}
+
+/// The heap usage thresholds registered by the binding.
+pub(crate) struct HeapUsageThresholds {
+    next_id: AtomicUsize,
+    thresholds: Mutex<Vec<RegisteredThreshold>>,
+}
+
+impl HeapUsageThresholds {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicUsize::new(0),
+            thresholds: Mutex::new(vec![]),
+        }
+    }
+
+    /// Register a threshold.  It is not exceeded until it is checked at the end of a GC.
+    pub fn add(&self, threshold: HeapUsageThreshold) -> HeapUsageThresholdId {
+        let id = HeapUsageThresholdId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.thresholds.lock().unwrap().push(RegisteredThreshold {
+            id,
+            threshold,
+            exceeded: false,
+        });
+        id
+    }
+
+    /// Remove a threshold.  Return false if there is no such threshold.
+    pub fn remove(&self, id: HeapUsageThresholdId) -> bool {
+        let mut thresholds = self.thresholds.lock().unwrap();
+        let len = thresholds.len();
+        thresholds.retain(|t| t.id != id);
+        thresholds.len() != len
+    }
+
+    /// Called at the end of a GC, before mutators are resumed.  Check all the thresholds, and
+    /// inform the binding of those that have been crossed since the last GC.
+    ///
+    /// Arguments:
+    /// * `tls`: The thread pointer for the GC worker.
+    /// * `plan`: The plan in use.
+    /// * `max_heap_pages`: The max heap size (in pages) decided by the GC trigger.
+    pub fn on_gc_end<VM: VMBinding>(
+        &self,
+        tls: VMWorkerThread,
+        plan: &dyn Plan<VM = VM>,
+        max_heap_pages: usize,
+    ) {
+        let events = {
+            let mut thresholds = self.thresholds.lock().unwrap();
+            if thresholds.is_empty() {
+                return;
+            }
+
+            let mut space_used_bytes = HashMap::new();
+            plan.for_each_space(&mut |space| {
+                space_used_bytes.insert(
+                    space.get_name(),
+                    space.reserved_pages() << LOG_BYTES_IN_PAGE,
+                );
+            });
+            let heap_used_bytes = plan.get_used_pages() << LOG_BYTES_IN_PAGE;
+            let max_heap_bytes = max_heap_pages << LOG_BYTES_IN_PAGE;
+
+            let mut events = vec![];
+            for t in thresholds.iter_mut() {
+                let used_bytes = match t.threshold.space {
+                    Some(name) => space_used_bytes.get(name).copied().unwrap_or(0),
+                    None => heap_used_bytes,
+                };
+                let limit_bytes = t.threshold.limit.to_bytes(max_heap_bytes);
+                let exceeded = used_bytes >= limit_bytes;
+                if exceeded != t.exceeded {
+                    t.exceeded = exceeded;
+                    events.push(HeapUsageThresholdEvent {
+                        id: t.id,
+                        threshold: t.threshold.clone(),
+                        exceeded,
+                        used_bytes,
+                        limit_bytes,
+                        space_used_bytes: space_used_bytes.clone(),
+                    });
+                }
+            }
+            events
+        };
+
+        // Do not hold the lock while calling into the binding, as the binding may add or remove
+        // thresholds in the hook.
+        for event in events.iter() {
+            debug!(
+                "Heap usage threshold {:?} {}: {} / {} bytes",
+                event.threshold,
+                if event.exceeded {
+                    "exceeded"
+                } else {
+                    "cleared"
+                },
+                event.used_bytes,
+                event.limit_bytes
+            );
+            VM::VMCollection::heap_usage_threshold_crossed(tls, event);
+        }
+    }
+}
@@ -330,6 +330,21 @@ mod tests {
         assert_eq!(coalesced_size, 2);
     }
 
+    #[test]
+    fn iterate_free() {
+        let mut l = IntArrayFreeList::new(LIST_SIZE, 2, 1);
+        let res1 = l.alloc(2);
+        assert_eq!(res1, 0);
+        let res2 = l.alloc(2);
+        assert_eq!(res2, 2);
+        l.free(res1, true);
+
+        let mut free = vec![];
+        l.iterate_free(&mut |unit, units| free.push((unit, units)));
+        free.sort_unstable();
+        assert_eq!(free, vec![(0, 2), (4, 1)]);
+    }
+
     #[test]
     fn free_realloc() {
         let mut l = IntArrayFreeList::new(LIST_SIZE, 2, 1);
@@ -61,7 +61,8 @@ impl<VM: VMBinding, S: LinearScanObjectSize, const ATOMIC_LOAD_VO_BIT: bool> std
                 self.cursor += S::size(object);
                 return Some(object);
             } else {
-                self.cursor += VM::MIN_ALIGNMENT;
+                // Object references are word-aligned, even if `VM::MIN_ALIGNMENT` is smaller.
+                self.cursor += ObjectReference::ALIGNMENT;
             }
         }
 
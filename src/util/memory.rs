@@ -395,6 +395,100 @@ pub fn mprotect(start: Address, size: usize) -> Result<()> {
     )
 }
 
+/// Return the physical memory of the given range (in page granularity) to the OS, while keeping
+/// the range mapped and accessible. With `lazy == false` (`MADV_DONTNEED`), the memory is released
+/// immediately, and reads as zero afterwards. With `lazy == true` (`MADV_FREE`), the OS reclaims
+/// the memory when it is under memory pressure, and the memory may keep its contents until then.
+/// `MADV_FREE` is only supported on Linux and macOS.
+pub fn uncommit(start: Address, size: usize, lazy: bool) -> Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+    let advice = if lazy {
+        libc::MADV_FREE
+    } else {
+        libc::MADV_DONTNEED
+    };
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+    let advice = {
+        // The option validation rejects lazy uncommit on other OSes.
+        debug_assert!(!lazy);
+        libc::MADV_DONTNEED
+    };
+    wrap_libc_call(
+        &|| unsafe { libc::madvise(start.to_mut_ptr(), size, advice) },
+        0,
+    )
+}
+
+/// Demand-zero mmap at an address chosen by the OS, and return the start of the mapping. The
+/// memory is readable and writable. Unlike the other mmap functions, this is used for MMTk's
+/// temporary internal memory which is not part of the heap.
+pub fn dzmmap_anywhere(size: usize) -> Result<Address> {
+    let ret = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            size,
+            PROT_READ | PROT_WRITE,
+            libc::MAP_ANON | libc::MAP_PRIVATE,
+            -1,
+            0,
+        )
+    };
+    if ret == libc::MAP_FAILED {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(Address::from_mut_ptr(ret))
+    }
+}
+
+/// Move the pages of the given memory (in page granularity) to `to`, replacing any existing
+/// mapping at `to`. The original address range stays mapped with the same protection, but its
+/// pages become demand-zero pages. This requires Linux 5.7 or later.
+pub fn mremap_dontunmap_fixed(from: Address, size: usize, to: Address) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        mremap_fixed_with_flags(from, size, to, libc::MREMAP_DONTUNMAP)
+    }
+    // Concurrent compaction, the only user of this function, will not pass the option validation
+    // on non-Linux OSes.
+    #[cfg(not(target_os = "linux"))]
+    unreachable!()
+}
+
+/// Move the pages of the given memory (in page granularity) to `to`, replacing any existing
+/// mapping at `to`. The replacement is atomic, i.e. other threads accessing `to` see either the
+/// old pages or the moved pages. The moved pages keep their protection.
+pub fn mremap_fixed(from: Address, size: usize, to: Address) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        mremap_fixed_with_flags(from, size, to, 0)
+    }
+    // Concurrent compaction, the only user of this function, will not pass the option validation
+    // on non-Linux OSes.
+    #[cfg(not(target_os = "linux"))]
+    unreachable!()
+}
+
+#[cfg(target_os = "linux")]
+fn mremap_fixed_with_flags(
+    from: Address,
+    size: usize,
+    to: Address,
+    flags: libc::c_int,
+) -> Result<()> {
+    wrap_libc_call(
+        &|| unsafe {
+            libc::mremap(
+                from.to_mut_ptr(),
+                size,
+                size,
+                libc::MREMAP_MAYMOVE | libc::MREMAP_FIXED | flags,
+                to.to_mut_ptr::<libc::c_void>(),
+            )
+        },
+        to.to_mut_ptr(),
+    )
+}
+
 fn wrap_libc_call<T: PartialEq>(f: &dyn Fn() -> T, expect: T) -> Result<()> {
     let ret = f();
     if ret == expect {
@@ -470,6 +564,16 @@ pub(crate) fn get_system_total_memory() -> u64 {
     sys.total_memory()
 }
 
+/// Returns the memory limit of the process in bytes.  It is the total physical memory of the
+/// system, or the memory limit of the cgroup of the process (e.g. in a container) if it is
+/// smaller.
+pub(crate) fn get_memory_limit() -> u64 {
+    let total = get_system_total_memory();
+    crate::util::cgroup::CgroupFs::default()
+        .memory_limit()
+        .map_or(total, |limit| limit.min(total))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -502,6 +606,27 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_uncommit() {
+        serial_test(|| {
+            with_cleanup(
+                || {
+                    let res = unsafe {
+                        dzmmap(START, BYTES_IN_PAGE, MmapStrategy::TEST, mmap_anno_test!())
+                    };
+                    assert!(res.is_ok());
+                    unsafe { START.store(42usize) };
+                    assert!(uncommit(START, BYTES_IN_PAGE, false).is_ok());
+                    // The memory is still accessible, and reads as zero.
+                    assert_eq!(unsafe { START.load::<usize>() }, 0);
+                },
+                || {
+                    assert!(munmap(START, BYTES_IN_PAGE).is_ok());
+                },
+            )
+        })
+    }
+
     #[test]
     fn test_munmap() {
         serial_test(|| {
@@ -572,6 +697,37 @@ mod tests {
         })
     }
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_mremap() {
+        serial_test(|| {
+            let buffer = dzmmap_anywhere(BYTES_IN_PAGE).unwrap();
+            with_cleanup(
+                || {
+                    assert!(dzmmap_noreplace(
+                        START,
+                        BYTES_IN_PAGE,
+                        MmapStrategy::TEST,
+                        mmap_anno_test!()
+                    )
+                    .is_ok());
+                    unsafe { START.store(42usize) };
+                    // Move the page away. The original page is still mapped, but zeroed.
+                    assert!(mremap_dontunmap_fixed(START, BYTES_IN_PAGE, buffer).is_ok());
+                    assert_eq!(unsafe { buffer.load::<usize>() }, 42);
+                    assert_eq!(unsafe { START.load::<usize>() }, 0);
+                    // Move it back, replacing the zeroed page.
+                    assert!(mremap_fixed(buffer, BYTES_IN_PAGE, START).is_ok());
+                    assert_eq!(unsafe { START.load::<usize>() }, 42);
+                },
+                || {
+                    assert!(munmap(START, BYTES_IN_PAGE).is_ok());
+                    assert!(munmap(buffer, BYTES_IN_PAGE).is_ok());
+                },
+            )
+        })
+    }
+
     #[cfg(target_os = "linux")]
     #[test]
     #[should_panic]
@@ -669,4 +825,11 @@ mod tests {
         let total = get_system_total_memory();
         println!("Total memory: {:?}", total);
     }
+
+    #[test]
+    fn test_get_memory_limit() {
+        let limit = get_memory_limit();
+        println!("Memory limit: {:?}", limit);
+        assert!(limit <= get_system_total_memory());
+    }
 }
@@ -228,5 +228,6 @@ pub use metadata_val_traits::*;
 pub(crate) mod log_bit;
 pub(crate) mod mark_bit;
 pub(crate) mod pin_bit;
+pub(crate) mod ref_count;
 
 pub use global::*;
@@ -0,0 +1,96 @@
+//! Reference counts of objects
+//!
+//! The reference count of an object is kept in the global side metadata [`RC_COUNT`], at the
+//! address of the `ObjectReference` of the object.  A count only has a few bits.  Once it
+//! reaches [`MAX_COUNT`], it is stuck, and it will never be incremented or decremented again.
+//! Such objects can only be reclaimed by tracing.
+//!
+//! [`RC_COUNT`]: crate::util::metadata::side_metadata::spec_defs::RC_COUNT
+
+use crate::util::constants::LOG_MIN_OBJECT_SIZE;
+use crate::util::metadata::side_metadata::address_to_meta_address;
+use crate::util::metadata::side_metadata::spec_defs::RC_COUNT;
+use crate::util::Address;
+use crate::util::ObjectReference;
+use std::sync::atomic::Ordering;
+
+/// The maximum reference count. A count that reaches this value is stuck.
+pub(crate) const MAX_COUNT: u8 = (1 << (1 << RC_COUNT.log_num_of_bits)) - 1;
+
+/// Get the reference count of an object.
+pub(crate) fn count(object: ObjectReference) -> u8 {
+    RC_COUNT.load_atomic::<u8>(object.to_raw_address(), Ordering::SeqCst)
+}
+
+/// Increment the reference count of an object, unless the count is stuck.
+/// Return the count before the increment.
+pub(crate) fn inc(object: ObjectReference) -> u8 {
+    match RC_COUNT.fetch_update_atomic::<u8, _>(
+        object.to_raw_address(),
+        Ordering::SeqCst,
+        Ordering::SeqCst,
+        |c| if c == MAX_COUNT { None } else { Some(c + 1) },
+    ) {
+        Ok(c) | Err(c) => c,
+    }
+}
+
+/// Decrement the reference count of an object, unless the count is zero or stuck.
+/// Return `true` if the count drops to zero, i.e. the object is dead.
+pub(crate) fn dec(object: ObjectReference) -> bool {
+    RC_COUNT
+        .fetch_update_atomic::<u8, _>(
+            object.to_raw_address(),
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+            |c| {
+                if c == 0 || c == MAX_COUNT {
+                    None
+                } else {
+                    Some(c - 1)
+                }
+            },
+        )
+        .is_ok_and(|c| c == 1)
+}
+
+/// Set the reference count of an object to zero.
+pub(crate) fn clear(object: ObjectReference) {
+    RC_COUNT.store_atomic::<u8>(object.to_raw_address(), 0, Ordering::SeqCst)
+}
+
+/// Set the reference counts of all the objects in `[start, start + size)` to zero.
+pub(crate) fn bzero(start: Address, size: usize) {
+    RC_COUNT.bzero_metadata(start, size)
+}
+
+/// Visit all the objects in `[start, end)` that have a non-zero reference count.  `start` and
+/// `end` must be aligned to the region covered by a byte of the metadata.
+pub(crate) fn for_each_counted_object(
+    start: Address,
+    end: Address,
+    mut visit: impl FnMut(ObjectReference),
+) {
+    const LOG_BYTES_PER_COUNT: usize = LOG_MIN_OBJECT_SIZE as usize;
+    const BITS_PER_COUNT: usize = 1 << RC_COUNT.log_num_of_bits;
+    const COUNTS_PER_BYTE: usize = 8 / BITS_PER_COUNT;
+    const BYTES_PER_META_BYTE: usize = COUNTS_PER_BYTE << LOG_BYTES_PER_COUNT;
+    debug_assert!(start.is_aligned_to(BYTES_PER_META_BYTE));
+    debug_assert!(end.is_aligned_to(BYTES_PER_META_BYTE));
+
+    let mut cursor = start;
+    while cursor < end {
+        // Skip metadata bytes without any counted object.
+        let meta = unsafe { address_to_meta_address(&RC_COUNT, cursor).load::<u8>() };
+        if meta != 0 {
+            for i in 0..COUNTS_PER_BYTE {
+                if (meta >> (i * BITS_PER_COUNT)) & MAX_COUNT != 0 {
+                    let addr = cursor + (i << LOG_BYTES_PER_COUNT);
+                    // Only object references have their counts set.
+                    visit(unsafe { ObjectReference::from_raw_address_unchecked(addr) });
+                }
+            }
+        }
+        cursor += BYTES_PER_META_BYTE;
+    }
+}
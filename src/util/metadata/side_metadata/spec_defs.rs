@@ -60,6 +60,10 @@ define_side_metadata_specs!(
     SFT_DENSE_CHUNK_MAP_INDEX   = (global: true, log_num_of_bits: 3, log_bytes_in_region: LOG_BYTES_IN_CHUNK),
     // Mark chunks (any plan that uses the chunk map should include this spec in their global sidemetadata specs)
     CHUNK_MARK   = (global: true, log_num_of_bits: 3, log_bytes_in_region: crate::util::heap::chunk_map::Chunk::LOG_BYTES),
+    // Reference counts of objects (only used by reference counting plans)
+    RC_COUNT     = (global: true, log_num_of_bits: 1, log_bytes_in_region: LOG_MIN_OBJECT_SIZE as usize),
+    // The number of GCs that an object has survived (only used by multi-generation plans)
+    OBJECT_AGE   = (global: true, log_num_of_bits: 2, log_bytes_in_region: LOG_MIN_OBJECT_SIZE as usize),
 );
 
 // This defines all LOCAL side metadata used by mmtk-core.
@@ -89,6 +93,8 @@ define_side_metadata_specs!(
     MS_BLOCK_TLS    = (global: false, log_num_of_bits: LOG_BITS_IN_ADDRESS, log_bytes_in_region: crate::policy::marksweepspace::native_ms::Block::LOG_BYTES),
     // First cell of free list in block for native mimalloc
     MS_FREE         = (global: false, log_num_of_bits: LOG_BITS_IN_ADDRESS, log_bytes_in_region: crate::policy::marksweepspace::native_ms::Block::LOG_BYTES),
+    // Record blocks that have been allocated into since the last GC for native mimalloc
+    MS_BLOCK_FRESH  = (global: false, log_num_of_bits: 3, log_bytes_in_region: crate::policy::marksweepspace::native_ms::Block::LOG_BYTES),
     // The following specs are only used for manual malloc/free
     // First cell of local free list in block for native mimalloc
     MS_LOCAL_FREE   = (global: false, log_num_of_bits: LOG_BITS_IN_ADDRESS, log_bytes_in_region: crate::policy::marksweepspace::native_ms::Block::LOG_BYTES),
@@ -98,6 +104,14 @@ define_side_metadata_specs!(
     COMPRESSOR_MARK = (global: false, log_num_of_bits: 0, log_bytes_in_region: LOG_BYTES_IN_WORD as usize),
     // Block offset vectors by Compressor
     COMPRESSOR_OFFSET_VECTOR = (global: false, log_num_of_bits: LOG_BITS_IN_ADDRESS, log_bytes_in_region: crate::policy::compressor::forwarding::Block::LOG_BYTES),
+    // Record pinned live objects by Compressor
+    COMPRESSOR_PINNED = (global: false, log_num_of_bits: 0, log_bytes_in_region: LOG_BYTES_IN_WORD as usize),
+    // Mark objects by the region space
+    REGION_MARK     = (global: false, log_num_of_bits: 0, log_bytes_in_region: LOG_MIN_OBJECT_SIZE as usize),
+    // Bytes of live objects in each region of the region space
+    REGION_LIVE_BYTES = (global: false, log_num_of_bits: LOG_BITS_IN_ADDRESS, log_bytes_in_region: crate::policy::region::HeapRegion::LOG_BYTES),
+    // Record whether a region of the region space is in the collection set
+    REGION_IN_CSET  = (global: false, log_num_of_bits: 3, log_bytes_in_region: crate::policy::region::HeapRegion::LOG_BYTES),
 );
 
 #[cfg(test)]
@@ -14,7 +14,9 @@
 //! but dead objects will not be visited.  Therefore we cannot clear the VO bits of individual
 //! dead objects.  We cannot clear all VO bits for the line in bulk because it contains live
 //! objects.  This module updates the VO bits for such regions (e.g. Immix lines, or Immix blocks
-//! if Immix is configured to be block-only).
+//! if Immix is configured to be block-only).  A reference counted ImmixSpace does not use the
+//! strategies below.  It rebuilds the VO bits of each block from the reference counts when the
+//! block is swept.
 //!
 //! We implement several strategies depending on whether mmtk-core or the VM binding also requires
 //! the VO bits to also be available during tracing.
@@ -45,8 +47,8 @@ use crate::{
 /// 1.  It was the strategy described in the original paper that described the algorithm for
 ///     filtering roots using VO bits for stack-conservative GC.  See: *Fast Conservative Garbage
 ///     Collection* published in OOPSLA'14 <https://dl.acm.org/doi/10.1145/2660193.2660198>
-/// 2.  It does not require mark bits to be on the side.  It will be needed if we implement
-///     in-header mark bits in the future.
+/// 2.  It does not require mark bits to be on the side.  It is needed if the mark bits are in the
+///     header.
 #[derive(Debug)]
 enum VOBitUpdateStrategy {
     /// Clear all VO bits after stacks are scanned, and reconstruct the VO bits during tracing.
@@ -86,9 +88,8 @@ const fn strategy<VM: VMBinding>() -> VOBitUpdateStrategy {
     // TODO: Revisit this choice in the future if non-trivial changes are made and the performance
     // characterestics may change for the strategies.
     match VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.as_spec() {
-        // Note that currently ImmixSpace doesn't support in-header mark bits,
-        // but the DummyVM for testing declares mark bits to be "in header" as a place holder
-        // because it never runs GC.
+        // In-header mark bits cannot be copied in bulk.  Note that the DummyVM for testing
+        // declares mark bits to be "in header" as a place holder because it never runs GC.
         MetadataSpec::InHeader(_) => VOBitUpdateStrategy::ClearAndReconstruct,
         MetadataSpec::OnSide(_) => VOBitUpdateStrategy::CopyFromMarkBits,
     }
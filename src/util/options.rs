@@ -50,8 +50,23 @@ pub enum PlanSelector {
     Compressor,
     /// An Immix collector that uses a sticky mark bit to allow generational behaviors without a copying nursery.
     StickyImmix,
+    /// A mark-sweep collector that uses a sticky mark bit to allow generational behaviors with the
+    /// native mark-sweep space.
+    StickyMarkSweep,
     /// Concurrent non-moving immix using SATB
     ConcurrentImmix,
+    /// Concurrent mark-sweep using SATB, with the native mark-sweep space and lazy sweeping
+    ConcurrentMarkSweep,
+    /// Generational immix with a copying nursery, whose mature space is marked concurrently using SATB
+    ConcurrentGenImmix,
+    /// Reference counting immix in the style of LXR, with a concurrent backup trace using SATB
+    LXR,
+    /// A region-based collector that evacuates young regions and the least live old regions within
+    /// a pause-time goal, in the style of G1
+    G1,
+    /// A generational copying collector with survivor spaces between the nursery and the mature
+    /// space. Objects are promoted after they survive a configurable number of nursery GCs.
+    MultiGenCopy,
 }
 
 /// MMTk option for perf events
@@ -124,6 +139,9 @@ pub const DEFAULT_PROPORTIONAL_MIN_NURSERY: f64 = 0.25;
 /// The default max nursery size proportional to the current heap size
 pub const DEFAULT_PROPORTIONAL_MAX_NURSERY: f64 = 1.0;
 
+/// The default target of nursery pause time in milliseconds
+pub const DEFAULT_NURSERY_PAUSE_TARGET_MS: f64 = 10.0;
+
 fn always_valid<T>(_: &T) -> bool {
     true
 }
@@ -210,9 +228,9 @@ macro_rules! options {
                 match s {
                     // Parse the given value from str (by env vars or by calling process()) to the right type
                     $(stringify!($name) => {
-                        let Ok(typed_val) = val.parse::<$type>() else {
-                            return Err(SetOptionByStringError::ValueParseError);
-                        };
+                        let typed_val = val
+                            .parse::<$type>()
+                            .map_err(|_| SetOptionByStringError::ValueParseError)?;
 
                         if !self.$name.set(typed_val) {
                             return Err(SetOptionByStringError::ValueValidationError);
@@ -357,6 +375,11 @@ pub enum AffinityKind {
     /// Assign all the cores specified in the set to all the GC threads. This allows to have core
     /// exclusivity for GC threads without us caring about which core it gets scheduled on.
     AllInSet(Vec<CoreId>),
+    /// Spread GC threads over the NUMA nodes in a round robin fashion, and assign all the cores of
+    /// a node to the threads on the node.  Workers prefer stealing work from workers on the same
+    /// node, and the work of scanning objects is routed to the node that holds the objects.  If the
+    /// NUMA topology cannot be discovered, this behaves like `OsDefault`.
+    NumaAware,
 }
 
 impl AffinityKind {
@@ -372,12 +395,16 @@ impl AffinityKind {
     ///  - "`0,5,8-11`" specifies that the cores 0,5,8,9,10,11 should be used for pinning threads.
     ///  - "`AllInSet:0,5`" specifies that the cores 0,5 should be used for pinning threads using the
     ///    [`AffinityKind::AllInSet`] method.
+    ///  - "`NumaAware`" specifies [`AffinityKind::NumaAware`], which takes no list of cores.
     fn parse_cpulist(cpulist: &str) -> Result<AffinityKind, String> {
         let mut cpuset = vec![];
 
         if cpulist.is_empty() {
             return Ok(AffinityKind::OsDefault);
         }
+        if cpulist == "NumaAware" {
+            return Ok(AffinityKind::NumaAware);
+        }
 
         // Trying to parse strings such as "RoundRobin:0,1-3"
         // First split on ":" to check if an affinity kind has been specified.
@@ -500,17 +527,32 @@ pub enum NurserySize {
     /// lower bounds. Note that this is considered less performant than a Bounded nursery since a
     /// Fixed nursery size can be too restrictive and cause more GCs.
     Fixed(usize),
+    /// A nursery whose size is adjusted between GCs so that nursery GCs take about the target
+    /// pause time.  The size is estimated from the survival rate and the pause time of recent
+    /// nursery GCs.  The size only controls the upper bound, and the lower bound is `min`.
+    PauseTarget {
+        /// The lower bound of the nursery size in bytes. Default to [`DEFAULT_MIN_NURSERY`].
+        min: usize,
+        /// The upper bound of the nursery size in bytes. Default to [`DEFAULT_MAX_NURSERY`].
+        max: usize,
+        /// The target pause time of nursery GCs in milliseconds. Default to
+        /// [`DEFAULT_NURSERY_PAUSE_TARGET_MS`].
+        pause_ms: f64,
+    },
 }
 
 impl NurserySize {
     /// Return true if the values are valid.
-    fn validate(&self) -> bool {
+    pub(crate) fn validate(&self) -> bool {
         match *self {
             NurserySize::Bounded { min, max } => min <= max,
             NurserySize::ProportionalBounded { min, max } => {
                 0.0f64 < min && min <= max && max <= 1.0f64
             }
             NurserySize::Fixed(_) => true,
+            NurserySize::PauseTarget { min, max, pause_ms } => {
+                min <= max && pause_ms > 0.0f64 && pause_ms.is_finite()
+            }
         }
     }
 }
@@ -565,6 +607,16 @@ impl FromStr for NurserySize {
                     Err("Fixed requires one value".to_string())
                 }
             }
+            "PauseTarget" => {
+                if values.len() == 3 {
+                    let min = default_or_parse(values[0], DEFAULT_MIN_NURSERY)?;
+                    let max = default_or_parse(values[1], DEFAULT_MAX_NURSERY)?;
+                    let pause_ms = default_or_parse(values[2], DEFAULT_NURSERY_PAUSE_TARGET_MS)?;
+                    Ok(NurserySize::PauseTarget { min, max, pause_ms })
+                } else {
+                    Err("PauseTarget requires three values".to_string())
+                }
+            }
             _ => Err("Unknown variant".to_string()),
         }
     }
@@ -653,6 +705,49 @@ mod nursery_size_parsing_tests {
             panic!("Failed: {:?}", result);
         }
     }
+
+    #[test]
+    fn test_pause_target() {
+        // Simple case
+        let result = "PauseTarget:1,2,5.5".parse::<NurserySize>().unwrap();
+        if let NurserySize::PauseTarget { min, max, pause_ms } = result {
+            assert_eq!(min, 1);
+            assert_eq!(max, 2);
+            assert_eq!(pause_ms, 5.5);
+        } else {
+            panic!("Failed: {:?}", result);
+        }
+
+        // Default all
+        let result = "PauseTarget:_,_,_".parse::<NurserySize>().unwrap();
+        if let NurserySize::PauseTarget { min, max, pause_ms } = result {
+            assert_eq!(min, DEFAULT_MIN_NURSERY);
+            assert_eq!(max, DEFAULT_MAX_NURSERY);
+            assert_eq!(pause_ms, DEFAULT_NURSERY_PAUSE_TARGET_MS);
+        } else {
+            panic!("Failed: {:?}", result);
+        }
+
+        // Missing the pause target
+        assert!("PauseTarget:1,2".parse::<NurserySize>().is_err());
+        // Invalid pause target
+        assert!(!"PauseTarget:1,2,0"
+            .parse::<NurserySize>()
+            .unwrap()
+            .validate());
+    }
+}
+
+/// How free memory in the heap is returned to the OS.  See the option `heap_uncommit`.
+#[derive(Copy, Clone, EnumString, Debug, PartialEq, Eq)]
+pub enum HeapUncommit {
+    /// Keep free memory committed.
+    Disabled,
+    /// Return free memory with `MADV_DONTNEED`.  The memory is released immediately.
+    DontNeed,
+    /// Return free memory with `MADV_FREE`.  The OS reclaims the memory lazily when it is under
+    /// memory pressure, which is cheaper if the memory is reused soon.
+    Free,
 }
 
 /// Select a GC trigger for MMTk.
@@ -663,6 +758,16 @@ pub enum GCTriggerSelector {
     /// GC is triggered by internal heuristics, and the heap size is varying between the two given values.
     /// The two values are the lower and the upper bound of the heap size.
     DynamicHeapSize(usize, usize),
+    /// GC is triggered when the heap is full, and the heap size is adjusted between the first two given
+    /// values so that the fraction of time spent in GC stays near `1 / (1 + ratio)`, where `ratio` is
+    /// the third value. This is similar to `-XX:GCTimeRatio` in HotSpot.
+    GCTimeRatio(usize, usize, usize),
+    /// Like `DynamicHeapSize`, but also monitors the memory pressure of the system reported by
+    /// Linux (`/proc/pressure/memory`). The heap limit is lowered when the pressure crosses
+    /// `memory_pressure_shrink_threshold`, and a GC is triggered when it crosses
+    /// `memory_pressure_gc_threshold`. The two values are the lower and the upper bound of the
+    /// heap size.
+    MemoryPressure(usize, usize),
     /// Delegate the GC triggering to the binding.
     Delegated,
 }
@@ -678,14 +783,28 @@ impl GCTriggerSelector {
         match self {
             Self::FixedHeapSize(s) => *s,
             Self::DynamicHeapSize(_, s) => *s,
+            Self::GCTimeRatio(_, s, _) => *s,
+            Self::MemoryPressure(_, s) => *s,
             _ => unreachable!("Cannot get max heap size"),
         }
     }
 
+    /// The pattern of a size in the option string.
+    const SIZE_PATTERN: &'static str = r"\d+(?:\.\d+)?%|\d+[kKmMgGtT]?";
+
     /// Parse a size representation, which could be a number to represents bytes,
-    /// or a number with the suffix K/k/M/m/G/g. Return the byte number if it can be
+    /// or a number with the suffix K/k/M/m/G/g, or a percentage of the memory limit of the
+    /// process (see [`crate::util::memory::get_memory_limit`]). Return the byte number if it can be
     /// parsed properly, otherwise return an error string.
     fn parse_size(s: &str) -> Result<usize, String> {
+        if let Some(percent) = s.strip_suffix('%') {
+            let percent = percent.parse::<f64>().map_err(|e| e.to_string())?;
+            if !(0f64..=100f64).contains(&percent) {
+                return Err(format!("percentage out of range: {}", s));
+            }
+            let limit = crate::util::memory::get_memory_limit() as f64;
+            return Ok((limit * percent / 100f64) as usize);
+        }
         let s = s.to_lowercase();
         if s.ends_with(char::is_alphabetic) {
             let num = s[0..s.len() - 1]
@@ -722,6 +841,8 @@ impl GCTriggerSelector {
         match self {
             Self::FixedHeapSize(size) => *size > 0,
             Self::DynamicHeapSize(min, max) => min <= max,
+            Self::GCTimeRatio(min, max, ratio) => min <= max && *ratio > 0,
+            Self::MemoryPressure(min, max) => min <= max,
             Self::Delegated => true,
         }
     }
@@ -733,11 +854,26 @@ impl FromStr for GCTriggerSelector {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use regex::Regex;
         lazy_static! {
-            static ref FIXED_HEAP_REGEX: Regex =
-                Regex::new(r"^FixedHeapSize:(?P<size>\d+[kKmMgGtT]?)$").unwrap();
-            static ref DYNAMIC_HEAP_REGEX: Regex =
-                Regex::new(r"^DynamicHeapSize:(?P<min>\d+[kKmMgGtT]?),(?P<max>\d+[kKmMgGtT]?)$")
-                    .unwrap();
+            static ref FIXED_HEAP_REGEX: Regex = Regex::new(&format!(
+                r"^FixedHeapSize:(?P<size>{size})$",
+                size = GCTriggerSelector::SIZE_PATTERN
+            ))
+            .unwrap();
+            static ref DYNAMIC_HEAP_REGEX: Regex = Regex::new(&format!(
+                r"^DynamicHeapSize:(?P<min>{size}),(?P<max>{size})$",
+                size = GCTriggerSelector::SIZE_PATTERN
+            ))
+            .unwrap();
+            static ref GC_TIME_RATIO_REGEX: Regex = Regex::new(&format!(
+                r"^GCTimeRatio:(?P<min>{size}),(?P<max>{size}),(?P<ratio>\d+)$",
+                size = GCTriggerSelector::SIZE_PATTERN
+            ))
+            .unwrap();
+            static ref MEMORY_PRESSURE_REGEX: Regex = Regex::new(&format!(
+                r"^MemoryPressure:(?P<min>{size}),(?P<max>{size})$",
+                size = GCTriggerSelector::SIZE_PATTERN
+            ))
+            .unwrap();
         }
 
         if s.is_empty() {
@@ -750,6 +886,17 @@ impl FromStr for GCTriggerSelector {
             let min = Self::parse_size(&captures["min"])?;
             let max = Self::parse_size(&captures["max"])?;
             return Ok(Self::DynamicHeapSize(min, max));
+        } else if let Some(captures) = GC_TIME_RATIO_REGEX.captures(s) {
+            let min = Self::parse_size(&captures["min"])?;
+            let max = Self::parse_size(&captures["max"])?;
+            let ratio = captures["ratio"]
+                .parse::<usize>()
+                .map_err(|e| e.to_string())?;
+            return Ok(Self::GCTimeRatio(min, max, ratio));
+        } else if let Some(captures) = MEMORY_PRESSURE_REGEX.captures(s) {
+            let min = Self::parse_size(&captures["min"])?;
+            let max = Self::parse_size(&captures["max"])?;
+            return Ok(Self::MemoryPressure(min, max));
         } else if s.starts_with("Delegated") {
             return Ok(Self::Delegated);
         }
@@ -795,6 +942,18 @@ mod gc_trigger_tests {
 
         // no number
         assert!(GCTriggerSelector::parse_size("k").is_err());
+
+        // percentage of the memory limit
+        let limit = crate::util::memory::get_memory_limit() as usize;
+        assert_eq!(GCTriggerSelector::parse_size("100%"), Ok(limit));
+        assert_eq!(GCTriggerSelector::parse_size("0%"), Ok(0));
+        assert_eq!(
+            GCTriggerSelector::parse_size("12.5%"),
+            Ok((limit as f64 * 0.125) as usize)
+        );
+        assert!(GCTriggerSelector::parse_size("101%").is_err());
+        assert!(GCTriggerSelector::parse_size("-1%").is_err());
+        assert!(GCTriggerSelector::parse_size("%").is_err());
     }
 
     #[test]
@@ -825,6 +984,13 @@ mod gc_trigger_tests {
             ))
         );
 
+        assert_eq!(
+            GCTriggerSelector::from_str("FixedHeapSize:100%"),
+            Ok(GCTriggerSelector::FixedHeapSize(
+                crate::util::memory::get_memory_limit() as usize
+            ))
+        );
+
         // incorrect
         assert!(GCTriggerSelector::from_str("FixedHeapSize").is_err());
         assert!(GCTriggerSelector::from_str("FixedHeapSize:").is_err());
@@ -849,8 +1015,58 @@ mod gc_trigger_tests {
             ))
         );
 
+        let limit = crate::util::memory::get_memory_limit() as usize;
+        assert_eq!(
+            GCTriggerSelector::from_str("DynamicHeapSize:1m,50%"),
+            Ok(GCTriggerSelector::DynamicHeapSize(
+                1024 * 1024,
+                (limit as f64 * 0.5) as usize
+            ))
+        );
+
         // incorrect
         assert!(GCTriggerSelector::from_str("DynamicHeapSize:1024,1024,").is_err());
+        assert!(GCTriggerSelector::from_str("DynamicHeapSize:1024,50k%").is_err());
+    }
+
+    #[test]
+    fn test_parse_gc_time_ratio() {
+        assert_eq!(
+            GCTriggerSelector::from_str("GCTimeRatio:1024,2048,12"),
+            Ok(GCTriggerSelector::GCTimeRatio(1024, 2048, 12))
+        );
+        assert_eq!(
+            GCTriggerSelector::from_str("GCTimeRatio:1m,2g,99"),
+            Ok(GCTriggerSelector::GCTimeRatio(
+                1024 * 1024,
+                2 * 1024 * 1024 * 1024,
+                99
+            ))
+        );
+
+        // incorrect
+        assert!(GCTriggerSelector::from_str("GCTimeRatio:1024,2048").is_err());
+        assert!(GCTriggerSelector::from_str("GCTimeRatio:1024,2048,1k").is_err());
+        assert!(GCTriggerSelector::from_str("GCTimeRatio:1024,2048,-1").is_err());
+    }
+
+    #[test]
+    fn test_parse_memory_pressure() {
+        assert_eq!(
+            GCTriggerSelector::from_str("MemoryPressure:1024,2048"),
+            Ok(GCTriggerSelector::MemoryPressure(1024, 2048))
+        );
+        assert_eq!(
+            GCTriggerSelector::from_str("MemoryPressure:1m,2g"),
+            Ok(GCTriggerSelector::MemoryPressure(
+                1024 * 1024,
+                2 * 1024 * 1024 * 1024
+            ))
+        );
+
+        // incorrect
+        assert!(GCTriggerSelector::from_str("MemoryPressure:1024").is_err());
+        assert!(GCTriggerSelector::from_str("MemoryPressure:1024,2048,10").is_err());
     }
 
     #[test]
@@ -858,9 +1074,14 @@ mod gc_trigger_tests {
         assert!(GCTriggerSelector::FixedHeapSize(1024).validate());
         assert!(GCTriggerSelector::DynamicHeapSize(1024, 2048).validate());
         assert!(GCTriggerSelector::DynamicHeapSize(1024, 1024).validate());
+        assert!(GCTriggerSelector::GCTimeRatio(1024, 2048, 12).validate());
+        assert!(GCTriggerSelector::MemoryPressure(1024, 2048).validate());
 
         assert!(!GCTriggerSelector::FixedHeapSize(0).validate());
         assert!(!GCTriggerSelector::DynamicHeapSize(2048, 1024).validate());
+        assert!(!GCTriggerSelector::GCTimeRatio(2048, 1024, 12).validate());
+        assert!(!GCTriggerSelector::GCTimeRatio(1024, 2048, 0).validate());
+        assert!(!GCTriggerSelector::MemoryPressure(2048, 1024).validate());
     }
 }
 
@@ -877,12 +1098,13 @@ options! {
     eager_complete_sweep:   bool                    [always_valid] = false,
     /// Should we ignore GCs requested by the user (e.g. java.lang.System.gc)?
     ignore_system_gc:       bool                    [always_valid] = false,
-    /// The nursery size for generational plans. It can be one of Bounded, ProportionalBounded or Fixed.
+    /// The nursery size for generational plans. It can be one of Bounded, ProportionalBounded, Fixed or PauseTarget.
     /// The nursery size can be set like 'Fixed:8192', for example,
     /// to have a Fixed nursery size of 8192 bytes, or 'ProportionalBounded:0.2,1.0' to have a nursery size
     /// between 20% and 100% of the heap size. You can omit lower bound and upper bound to use the default
     /// value for bounded nursery by using '_'. For example, 'ProportionalBounded:0.1,_' sets the min nursery
-    /// to 10% of the heap size while using the default value for max nursery.
+    /// to 10% of the heap size while using the default value for max nursery. 'PauseTarget:_,_,5' adjusts
+    /// the nursery size between the default bounds so that nursery GCs take about 5 milliseconds.
     nursery:                NurserySize             [|v: &NurserySize| v.validate()]
         = NurserySize::ProportionalBounded { min: DEFAULT_PROPORTIONAL_MIN_NURSERY, max: DEFAULT_PROPORTIONAL_MAX_NURSERY },
     /// Should a major GC be performed when a system GC is required?
@@ -946,11 +1168,16 @@ options! {
     /// be used to pin threads even though we specified the core IDs "0,1,2,3,4".
     /// `MMTK_THREAD_AFFINITY="12" taskset -c 6-12 <program>` will not work, on the other hand, as
     /// there is no core with (perceived) ID 12.
+    ///
+    /// `NumaAware` spreads the threads over the NUMA nodes listed in `/sys/devices/system/node`,
+    /// and binds each thread to the cores of its node.
     // XXX: This option is currently only supported on Linux.
     thread_affinity:        AffinityKind            [|v: &AffinityKind| v.validate()] = AffinityKind::OsDefault,
     /// Set the GC trigger. This defines the heap size and how MMTk triggers a GC.
-    /// Default to a fixed heap size of 0.5x physical memory.
-    gc_trigger:             GCTriggerSelector       [|v: &GCTriggerSelector| v.validate()] = GCTriggerSelector::FixedHeapSize((crate::util::memory::get_system_total_memory() as f64 * 0.5f64) as usize),
+    /// Default to a fixed heap size of 0.5x physical memory, or 0.5x the memory limit of the cgroup
+    /// of the process if it is smaller.  Sizes can also be given as a percentage of that memory,
+    /// e.g. `DynamicHeapSize:10%,75%`.
+    gc_trigger:             GCTriggerSelector       [|v: &GCTriggerSelector| v.validate()] = GCTriggerSelector::FixedHeapSize((crate::util::memory::get_memory_limit() as f64 * 0.5f64) as usize),
     /// Enable transparent hugepage support for MMTk spaces via madvise (only Linux is supported)
     /// This only affects the memory for MMTk spaces.
     transparent_hugepages:  bool                    [|v: &bool| !v || cfg!(target_os = "linux")] = false,
@@ -964,7 +1191,90 @@ options! {
     /// Percentage of heap size reserved for defragmentation.
     /// According to [this paper](https://doi.org/10.1145/1375581.1375586), Immix works well with
     /// headroom between 1% to 3% of the heap size.
-    immix_defrag_headroom_percent: usize            [|v: &usize| *v <= 50] = 2
+    immix_defrag_headroom_percent: usize            [|v: &usize| *v <= 50] = 2,
+    /// Let the Compressor move objects concurrently with mutators after the roots are updated.
+    /// The pages which objects are moved into are protected until the objects are moved, and the
+    /// binding must call [`crate::memory_manager::handle_protection_fault`] from its fault handler
+    /// in all threads, and [`crate::memory_manager::resolve_protection_fault`] after the faults
+    /// that MMTk causes. This is only supported on Linux 5.7 or later.
+    compressor_concurrent_compaction: bool          [|v: &bool| !*v || cfg!(target_os = "linux")] = false,
+    /// The mark/alloc ratio for mutator-assisted (incremental) concurrent marking: the number of
+    /// bytes of objects a mutator marks for each byte it allocates in the allocation slow path
+    /// while concurrent marking is in progress. Zero disables assists, and marking is only done
+    /// by GC threads. This only affects concurrent plans.
+    concurrent_mark_assist_ratio: f64               [|v: &f64| v.is_finite() && *v >= 0.0] = 0.0,
+    /// The pause-time goal in milliseconds for the region-based plan (G1). The plan sizes the young
+    /// generation and chooses the old regions to evacuate in each pause so that the predicted pause
+    /// time stays within the goal. Full-heap pauses are not bounded by the goal.
+    g1_pause_time_goal_ms: usize                    [|v: &usize| *v > 0] = 200,
+    /// The number of generations for the multi-generation copying plan (MultiGenCopy), including
+    /// the nursery and the mature generation. Objects that survive a nursery GC are copied
+    /// between the survivor spaces until they have survived `multigen_generations - 1` GCs, and
+    /// are then promoted to the mature space. With 2 generations, the plan behaves like GenCopy.
+    multigen_generations: usize                     [|v: &usize| (2..=16).contains(v)] = 3,
+    /// Return the free memory in the heap to the OS with `madvise` at the end of a GC, so that the
+    /// resident memory of the process drops after the heap shrinks.  Free memory is returned when
+    /// the GC trigger shrinks the heap, or when `heap_uncommit_delay_ms` has passed since free
+    /// memory was last returned.  `Free` is only supported on Linux and macOS.
+    heap_uncommit: HeapUncommit                     [|v: &HeapUncommit| *v != HeapUncommit::Free || cfg!(any(target_os = "linux", target_os = "android", target_os = "macos"))] = HeapUncommit::Disabled,
+    /// The delay in milliseconds before free memory is returned to the OS again if the heap has not
+    /// shrunk.  A program that allocates steadily reuses its free memory soon after each GC, so
+    /// returning it after every GC would only add page faults.
+    heap_uncommit_delay_ms: usize                   [always_valid] = 10000,
+    /// The memory pressure (the `some avg10` value in `/proc/pressure/memory`, i.e. the percentage
+    /// of time in the last 10 seconds in which some tasks stalled on memory) above which the
+    /// `MemoryPressure` GC trigger lowers the heap limit.
+    memory_pressure_shrink_threshold: f64           [|v: &f64| (0.0..=100.0).contains(v)] = 10.0,
+    /// The memory pressure (the `full avg10` value in `/proc/pressure/memory`, i.e. the percentage
+    /// of time in the last 10 seconds in which all non-idle tasks stalled on memory) above which
+    /// the `MemoryPressure` GC trigger triggers a GC.
+    memory_pressure_gc_threshold: f64               [|v: &f64| (0.0..=100.0).contains(v)] = 5.0,
+    /// Delay mutators that allocate fast in the allocation slow path when the heap is close to its
+    /// limit, or while concurrent work (e.g. concurrent marking) is in progress, so that a single
+    /// thread cannot trigger GCs for all other threads.  The limits can be changed for each
+    /// mutator with `memory_manager::set_allocation_pacing`.
+    allocation_pacing: bool                         [always_valid] = false,
+    /// The fraction of the heap in use above which allocation is paced.  The pressure grows from 0
+    /// at this fraction to 1 when the heap is full.
+    allocation_pacing_threshold: f64                [|v: &f64| (0.0..1.0).contains(v)] = 0.8,
+    /// The allocation rate in bytes per second that each mutator is held to when the heap is full.
+    /// At lower pressure, the allowed rate is higher in inverse proportion to the pressure.
+    allocation_pacing_rate: usize                   [|v: &usize| *v > 0] = 64 * 1024 * 1024,
+    /// The longest delay in microseconds of a single allocation.  A delayed mutator does not reach
+    /// a safepoint, so this also bounds how much pacing can delay a GC.
+    allocation_pacing_max_delay_us: usize           [always_valid] = 10000,
+    /// Record the GCs of this run to the given file: the allocated bytes at which each GC started,
+    /// and whether it was a nursery or a full heap GC.  The file can be replayed with
+    /// `gc_trigger_replay` to reproduce the GCs in a later run.  Empty for no recording.
+    gc_trigger_record: String                       [always_valid] = String::new(),
+    /// Replay the GCs recorded in the given file with `gc_trigger_record`.  GCs are triggered at
+    /// the recorded allocated bytes instead of by `gc_trigger`, and the heap size is fixed to the
+    /// max heap size of the recorded run.  Empty for no replay.
+    gc_trigger_replay: String                       [always_valid] = String::new(),
+    /// Activate only some of the GC workers in each GC, depending on the amount of work.  A GC
+    /// activates one worker for each `heap_bytes_per_gc_thread` bytes of work, which is the bytes
+    /// allocated since the last GC for a nursery GC, or the used bytes of the heap for a full heap
+    /// GC.  Inactive workers stay parked during the GC.  This is similar to
+    /// `-XX:+UseDynamicNumberOfGCThreads` in HotSpot.
+    dynamic_gc_threads: bool                        [always_valid] = false,
+    /// The bytes of work for each active GC worker if `dynamic_gc_threads` is enabled.
+    heap_bytes_per_gc_thread: usize                 [|v: &usize| *v > 0] = 32 * 1024 * 1024,
+    /// Trace GC work packets, work bucket openings, GCs and pauses in memory, and write the trace
+    /// to the given file when the benchmark harness ends (or with `memory_manager::write_gc_trace`).
+    /// The file is in the Chrome trace event format, and can be opened in Perfetto UI.  Empty for
+    /// no tracing.
+    gc_trace_file: String                           [always_valid] = String::new(),
+    /// The number of trace events kept for each GC worker if `gc_trace_file` is set.  Older events
+    /// are dropped when a worker has recorded more events.
+    gc_trace_buffer_events: usize                   [|v: &usize| *v > 0] = 65536,
+    /// Run all work packets on one GC worker (regardless of `threads`), in an order drawn from a
+    /// pseudo-random number generator, to reproduce GC bugs that depend on the order of work
+    /// packets.  The seed is printed when MMTk is initialized.  Mutators do not assist concurrent
+    /// work in this mode.  This is for debugging, and is slow.
+    deterministic_scheduler: bool                   [always_valid] = false,
+    /// The seed of the deterministic scheduler.  Set it to the seed printed by a previous run to
+    /// replay the order of work packets of that run.  0 for a seed chosen at random.
+    deterministic_scheduler_seed: u64               [always_valid] = 0
 }
 
 #[cfg(test)]
@@ -1078,6 +1388,91 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_concurrent_mark_assist_ratio() {
+        serial_test(|| {
+            let mut options = Options::default();
+            // A zero ratio disables assists.  Negative or non-finite ratios are rejected.
+            assert!(options.set_from_string("concurrent_mark_assist_ratio", "0"));
+            assert!(!options.set_from_string("concurrent_mark_assist_ratio", "-0.5"));
+            assert!(!options.set_from_string("concurrent_mark_assist_ratio", "inf"));
+            assert!(!options.set_from_string("concurrent_mark_assist_ratio", "NaN"));
+        })
+    }
+
+    #[test]
+    fn test_g1_pause_time_goal_ms() {
+        serial_test(|| {
+            let mut options = Options::default();
+            // The goal must be at least one millisecond.
+            assert!(options.set_from_string("g1_pause_time_goal_ms", "1"));
+            assert!(!options.set_from_string("g1_pause_time_goal_ms", "0"));
+        })
+    }
+
+    #[test]
+    fn test_multigen_generations() {
+        serial_test(|| {
+            let mut options = Options::default();
+            // The nursery and the mature generation are always present, and the age of an
+            // object must fit in its side metadata.
+            assert!(options.set_from_string("multigen_generations", "2"));
+            assert!(options.set_from_string("multigen_generations", "16"));
+            assert!(!options.set_from_string("multigen_generations", "1"));
+            assert!(!options.set_from_string("multigen_generations", "17"));
+        })
+    }
+
+    #[test]
+    fn test_heap_uncommit() {
+        serial_test(|| {
+            let mut options = Options::default();
+            // `MADV_FREE` is only accepted where it is supported.
+            assert_eq!(
+                options.set_from_string("heap_uncommit", "Free"),
+                cfg!(any(
+                    target_os = "linux",
+                    target_os = "android",
+                    target_os = "macos"
+                ))
+            );
+            assert!(options.set_from_string("heap_uncommit", "DontNeed"));
+        })
+    }
+
+    #[test]
+    fn test_allocation_pacing() {
+        serial_test(|| {
+            let mut options = Options::default();
+            // The threshold is a fraction of the heap below 1, and the rate must be positive.
+            assert!(options.set_from_string("allocation_pacing_threshold", "0"));
+            assert!(!options.set_from_string("allocation_pacing_threshold", "1.0"));
+            assert!(!options.set_from_string("allocation_pacing_threshold", "-0.1"));
+            assert!(options.set_from_string("allocation_pacing_rate", "1"));
+            assert!(!options.set_from_string("allocation_pacing_rate", "0"));
+        })
+    }
+
+    #[test]
+    fn test_gc_trace_buffer_events() {
+        serial_test(|| {
+            let mut options = Options::default();
+            // Each buffer must hold at least one event.
+            assert!(options.set_from_string("gc_trace_buffer_events", "1"));
+            assert!(!options.set_from_string("gc_trace_buffer_events", "0"));
+        })
+    }
+
+    #[test]
+    fn test_dynamic_gc_threads() {
+        serial_test(|| {
+            let mut options = Options::default();
+            // Each active GC thread must cover some of the heap.
+            assert!(options.set_from_string("heap_bytes_per_gc_thread", "1"));
+            assert!(!options.set_from_string("heap_bytes_per_gc_thread", "0"));
+        })
+    }
+
     #[test]
     fn test_str_option_default() {
         serial_test(|| {
@@ -1301,6 +1696,19 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_thread_affinity_numa_aware() {
+        serial_test(|| {
+            let affinity = "NumaAware".parse::<AffinityKind>();
+            assert_eq!(affinity, Ok(AffinityKind::NumaAware));
+            let affinity = "NumaAware:0,1".parse::<AffinityKind>();
+            assert_eq!(
+                affinity,
+                Err("Unknown affinity kind: NumaAware".to_string())
+            );
+        })
+    }
+
     #[test]
     fn test_thread_affinity_bad_affinity_kind() {
         serial_test(|| {
@@ -0,0 +1,133 @@
+//! Memory pressure of the system reported by Linux pressure stall information (PSI).
+//!
+//! The file `/proc/pressure/memory` reports the percentage of time in which tasks stalled waiting
+//! for memory, averaged over the last 10, 60 and 300 seconds:
+//!
+//! ```text
+//! some avg10=0.00 avg60=0.00 avg300=0.00 total=0
+//! full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+//! ```
+//!
+//! `some` is the time in which at least one task stalled, and `full` is the time in which all
+//! non-idle tasks stalled at the same time.  We use the 10-second averages, as we want to react
+//! before the kernel starts killing processes.
+
+use std::path::{Path, PathBuf};
+
+/// The memory pressure in percentages of time, averaged over the last 10 seconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct MemoryPressure {
+    /// The share of time in which some tasks stalled on memory.
+    pub some: f64,
+    /// The share of time in which all non-idle tasks stalled on memory.
+    pub full: f64,
+}
+
+/// The file that we read the memory pressure from.  The path can be changed for testing.
+pub(crate) struct PressureFile {
+    path: PathBuf,
+}
+
+impl Default for PressureFile {
+    fn default() -> Self {
+        Self::new("/proc/pressure/memory")
+    }
+}
+
+impl PressureFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return the current memory pressure, or `None` if the file cannot be read (e.g. PSI is not
+    /// supported or not enabled by the kernel).
+    pub fn read(&self) -> Option<MemoryPressure> {
+        let content = std::fs::read_to_string(&self.path).ok()?;
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> Option<MemoryPressure> {
+        let mut some = None;
+        let mut full = None;
+        for line in content.lines() {
+            let mut fields = line.split_whitespace();
+            let kind = fields.next();
+            let avg10 = fields
+                .find_map(|field| field.strip_prefix("avg10="))
+                .and_then(|value| value.parse::<f64>().ok());
+            match kind {
+                Some("some") => some = avg10,
+                Some("full") => full = avg10,
+                _ => {}
+            }
+        }
+        // `full` is missing in kernels before 5.13 for some resources.  Treat it as no stall.
+        Some(MemoryPressure {
+            some: some?,
+            full: full.unwrap_or(0f64),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let pressure = PressureFile::parse(
+            "some avg10=12.50 avg60=3.00 avg300=0.50 total=123456\n\
+             full avg10=4.25 avg60=1.00 avg300=0.10 total=23456\n",
+        );
+        assert_eq!(
+            pressure,
+            Some(MemoryPressure {
+                some: 12.5,
+                full: 4.25
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_no_full() {
+        let pressure = PressureFile::parse("some avg10=1.00 avg60=0.00 avg300=0.00 total=1\n");
+        assert_eq!(
+            pressure,
+            Some(MemoryPressure {
+                some: 1.0,
+                full: 0.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(PressureFile::parse(""), None);
+        assert_eq!(PressureFile::parse("some avg10=abc\n"), None);
+    }
+
+    #[test]
+    fn test_read_file() {
+        let path = std::env::temp_dir().join(format!("mmtk-psi-test-{}", std::process::id()));
+        std::fs::write(
+            &path,
+            "some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n\
+             full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n",
+        )
+        .unwrap();
+        let file = PressureFile::new(&path);
+        assert_eq!(
+            file.read(),
+            Some(MemoryPressure {
+                some: 0.0,
+                full: 0.0
+            })
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(file.read(), None);
+    }
+}
@@ -67,8 +67,10 @@ impl ReferenceProcessors {
     /// the forwarding addresses yet, thus we cannot do forwarding during scan refs. And for those
     /// plans, this separate step is required.
     pub fn forward_refs<E: ProcessEdgesWork>(&self, trace: &mut E, mmtk: &'static MMTK<E::VM>) {
+        // Reference counting plans also use this step to keep the referents alive until the next GC.
         debug_assert!(
-            mmtk.get_plan().constraints().needs_forward_after_liveness,
+            mmtk.get_plan().constraints().needs_forward_after_liveness
+                || mmtk.get_plan().constraints().needs_ref_count,
             "A plan with needs_forward_after_liveness=false does not need a separate forward step"
         );
         self.soft
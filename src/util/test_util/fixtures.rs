@@ -6,8 +6,9 @@ use std::sync::Mutex;
 use std::sync::Once;
 
 use crate::memory_manager;
-use crate::util::test_util::mock_vm::MockVM;
-use crate::util::{ObjectReference, VMMutatorThread, VMThread};
+use crate::util::test_util::mock_method::MockMethod;
+use crate::util::test_util::mock_vm::{write_mockvm, MockVM};
+use crate::util::{Address, ObjectReference, OpaquePointer, VMMutatorThread, VMThread};
 use crate::AllocationSemantics;
 use crate::MMTKBuilder;
 use crate::MMTK;
@@ -215,6 +216,80 @@ impl MutatorFixture {
 
 unsafe impl Send for MutatorFixture {}
 
+/// An MMTk instance with a mutator, which runs GCs on the mutator thread.  The collection is
+/// initialized with [`memory_manager::initialize_collection_inline`], so no GC thread is spawned.
+///
+/// This mocks `number_of_mutators`, `mutators` and `stop_all_mutators` of the current `MockVM`
+/// with the mutator, so it must be created in the test closure of `with_mockvm`, and the
+/// `MockVM` must not be replaced afterwards.
+pub struct InlineGCFixture {
+    mmtk: MMTKFixture,
+    /// The thread of the mutator.  The mutator thread becomes the GC worker thread, so it is not
+    /// `VMThread::UNINITIALIZED`.
+    pub tls: VMMutatorThread,
+    mutator: *mut Mutator<MockVM>,
+}
+
+impl InlineGCFixture {
+    pub fn create_with_builder<F>(with_builder: F) -> Self
+    where
+        F: FnOnce(&mut MMTKBuilder),
+    {
+        let mmtk = MMTKFixture::create_with_builder(with_builder, false);
+        memory_manager::initialize_collection_inline(mmtk.get_mmtk(), VMThread::UNINITIALIZED);
+
+        let tls = VMMutatorThread(VMThread(OpaquePointer::from_address(unsafe {
+            Address::from_usize(0x1000)
+        })));
+        // The mutator is leaked, as the mocked methods may use it until the end of the process.
+        let mutator = Box::leak(memory_manager::bind_mutator(mmtk.get_mmtk(), tls));
+        let get_mutator = Self::mutator_getter_of(mutator);
+        write_mockvm(|mock| {
+            mock.number_of_mutators = MockMethod::new_fixed(Box::new(|_| 1));
+            mock.mutators =
+                MockMethod::new_fixed(Box::new(move |_| Box::new(std::iter::once(get_mutator()))));
+            mock.stop_all_mutators =
+                MockMethod::new_fixed(Box::new(move |(_, mut mutator_visitor)| {
+                    mutator_visitor(get_mutator())
+                }));
+        });
+
+        Self { mmtk, tls, mutator }
+    }
+
+    pub fn mmtk(&self) -> &'static MMTK<MockVM> {
+        self.mmtk.get_mmtk()
+    }
+
+    /// Get the mutator.  Like a mutator of a binding, it is also reached by MMTk during GCs.
+    #[allow(clippy::mut_from_ref)]
+    pub fn mutator(&self) -> &'static mut Mutator<MockVM> {
+        unsafe { &mut *self.mutator }
+    }
+
+    /// Get a function that returns the mutator.  Unlike the fixture, the function can be moved
+    /// into work packets and mocked methods.
+    pub fn mutator_getter(&self) -> impl Fn() -> &'static mut Mutator<MockVM> + Copy + Send {
+        Self::mutator_getter_of(self.mutator)
+    }
+
+    fn mutator_getter_of(
+        mutator: *mut Mutator<MockVM>,
+    ) -> impl Fn() -> &'static mut Mutator<MockVM> + Copy + Send {
+        let mutator_addr = mutator as usize;
+        move || unsafe { &mut *(mutator_addr as *mut Mutator<MockVM>) }
+    }
+
+    /// Request a GC, and run it on the mutator thread until it finishes.
+    pub fn gc(&self) {
+        assert!(memory_manager::handle_user_collection_request(
+            self.mmtk(),
+            self.tls
+        ));
+        memory_manager::run_gc_inline(self.mmtk(), self.tls);
+    }
+}
+
 pub struct SingleObject {
     pub objref: ObjectReference,
     mutator: MutatorFixture,
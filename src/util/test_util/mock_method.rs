@@ -1,4 +1,5 @@
 use std::any::Any;
+use std::sync::Arc;
 
 /// `MockAny` hides any type information. It is useful when we want to create
 /// a mock method for methods with generic type parameters.
@@ -8,15 +9,51 @@ use std::any::Any;
 /// The user should check if their intended arguments match the default `MockMethod` type, and if not,
 /// they should create their own `MockMethod`s for those methods.
 pub trait MockAny {
-    fn call_any(&mut self, args: Box<dyn Any>) -> Box<dyn Any>;
+    fn call_any(&mut self, args: Box<dyn Any>) -> Box<dyn Any> {
+        self.prepare_call_any(&*args)(args)
+    }
+    /// Can the method be called with `args`?
+    fn accepts(&self, args: &dyn Any) -> bool;
+    /// Count a call with `args`, and return the closure for the call. The closure should be
+    /// called with `args`. See [`MockMethod::prepare_call`].
+    fn prepare_call_any(&mut self, args: &dyn Any) -> MockAnyClosure;
 }
 
+/// The closure returned by [`MockAny::prepare_call_any`].
+pub type MockAnyClosure = Box<dyn FnOnce(Box<dyn Any>) -> Box<dyn Any>>;
+
 impl<I: 'static, R: 'static> MockAny for MockMethod<I, R> {
-    fn call_any(&mut self, args: Box<dyn Any>) -> Box<dyn Any> {
-        let typed_args: Box<I> = args.downcast().unwrap();
-        let typed_args_inner: I = *typed_args;
-        let typed_ret = self.call(typed_args_inner);
-        Box::new(typed_ret)
+    fn accepts(&self, args: &dyn Any) -> bool {
+        args.is::<I>()
+    }
+
+    fn prepare_call_any(&mut self, _args: &dyn Any) -> MockAnyClosure {
+        let closure = self.prepare_call();
+        Box::new(move |args: Box<dyn Any>| {
+            let typed_args: Box<I> = args.downcast().unwrap();
+            let typed_args_inner: I = *typed_args;
+            let typed_ret = closure(typed_args_inner);
+            Box::new(typed_ret) as Box<dyn Any>
+        })
+    }
+}
+
+/// A `MockAny` that calls the first of its mock methods that accepts the arguments.  It is useful
+/// for methods that are called with different types in different GCs.  For example, the type of
+/// `RootsWorkFactory` is different for nursery GCs and full heap GCs of a generational plan.
+pub struct MockAnyOf(pub Vec<Box<dyn MockAny>>);
+
+impl MockAny for MockAnyOf {
+    fn accepts(&self, args: &dyn Any) -> bool {
+        self.0.iter().any(|method| method.accepts(args))
+    }
+
+    fn prepare_call_any(&mut self, args: &dyn Any) -> MockAnyClosure {
+        self.0
+            .iter_mut()
+            .find(|method| method.accepts(args))
+            .expect("No mock method accepts the arguments")
+            .prepare_call_any(args)
     }
 }
 
@@ -37,22 +74,25 @@ pub enum MockImpl<I, R> {
 /// The function pointer for the mock closure.
 pub type MockClosureSignature<I, R> = Box<dyn Fn(I) -> R + Send + Sync>;
 
+/// The closure returned by [`MockMethod::prepare_call`].
+pub type SharedMockClosure<I, R> = Arc<dyn Fn(I) -> R + Send + Sync>;
+
 /// The function pointer for the closure, and some metadata.
 pub struct MockClosure<I, R> {
-    closure: MockClosureSignature<I, R>,
+    closure: SharedMockClosure<I, R>,
     call_count: usize,
 }
 
 impl<I, R> MockClosure<I, R> {
     fn new(closure: MockClosureSignature<I, R>) -> Self {
         Self {
-            closure,
+            closure: Arc::from(closure),
             call_count: 0,
         }
     }
-    fn call(&mut self, args: I) -> R {
+    fn prepare_call(&mut self) -> SharedMockClosure<I, R> {
         self.call_count += 1;
-        (self.closure)(args)
+        self.closure.clone()
     }
 }
 
@@ -96,14 +136,21 @@ impl<I, R> MockMethod<I, R> {
 
     /// Call the mock method.
     pub fn call(&mut self, args: I) -> R {
+        self.prepare_call()(args)
+    }
+
+    /// Count a call to the mock method, and return the closure to call.  Unlike [`Self::call`],
+    /// the closure can be called without borrowing the mock method, so it may call other mock
+    /// methods, e.g. a mocked `copy` that allocates with the copy context.
+    pub fn prepare_call(&mut self) -> SharedMockClosure<I, R> {
         let cur_call = self.call_count();
 
         match &mut self.imp {
             MockImpl::Sequence(closures) => {
                 let len = closures.len();
-                closures[cur_call % len].call(args)
+                closures[cur_call % len].prepare_call()
             }
-            MockImpl::Fixed(closure) => closure.call(args),
+            MockImpl::Fixed(closure) => closure.prepare_call(),
         }
     }
 
@@ -0,0 +1,107 @@
+//! A simple object model for mock tests that run GCs with live objects.
+//!
+//! An object starts `DEFAULT_OBJECT_REF_OFFSET` bytes before its reference.  The word at the
+//! object reference is the header, where `MockVM` keeps its in-header metadata, and it is followed
+//! by `NUM_FIELDS` reference fields.  A test uses [`with_object_model`] to mock the methods that
+//! MMTk calls to get the size of objects, to scan them and to copy them.
+
+// Some tests are conditionally compiled. So not all the code in this module will be used. We simply allow dead code in this module.
+#![allow(dead_code)]
+
+use super::mock_method::MockMethod;
+use super::mock_vm::{MockVM, DEFAULT_OBJECT_REF_OFFSET};
+use crate::memory_manager;
+use crate::plan::BarrierSelector;
+use crate::util::constants::BYTES_IN_ADDRESS;
+use crate::util::{Address, ObjectReference};
+use crate::vm::slot::Slot;
+use crate::vm::ObjectModel;
+use crate::{AllocationSemantics, Mutator};
+
+/// The number of reference fields of each object.
+pub const NUM_FIELDS: usize = 2;
+/// The size of each object in bytes.
+pub const OBJECT_SIZE: usize = DEFAULT_OBJECT_REF_OFFSET + BYTES_IN_ADDRESS * (1 + NUM_FIELDS);
+
+/// Mock the object size, object scanning and object copying methods of `mock` for objects of
+/// this module.
+pub fn with_object_model(mock: MockVM) -> MockVM {
+    MockVM {
+        copy_object: MockMethod::new_fixed(Box::new(|(from, semantics, copy_context)| {
+            let to = copy_context.alloc_copy(from, OBJECT_SIZE, BYTES_IN_ADDRESS, 0, semantics);
+            copy_bytes(MockVM::ref_to_object_start(from), to);
+            let object = MockVM::object_start_to_ref(to);
+            copy_context.post_copy(object, OBJECT_SIZE, semantics);
+            object
+        })),
+        copy_object_to: MockMethod::new_fixed(Box::new(|(from, to, _)| {
+            let to_start = MockVM::ref_to_object_start(to);
+            if from != to {
+                copy_bytes(MockVM::ref_to_object_start(from), to_start);
+            }
+            to_start + OBJECT_SIZE
+        })),
+        get_object_reference_when_copied_to: MockMethod::new_fixed(Box::new(|(_, to)| {
+            MockVM::object_start_to_ref(to)
+        })),
+        get_object_size: MockMethod::new_fixed(Box::new(|_| OBJECT_SIZE)),
+        get_object_size_when_copied: MockMethod::new_fixed(Box::new(|_| OBJECT_SIZE)),
+        scan_object: MockMethod::new_fixed(Box::new(|(_, object, slot_visitor)| {
+            for i in 0..NUM_FIELDS {
+                slot_visitor.visit_slot(field(object, i));
+            }
+        })),
+        ..mock
+    }
+}
+
+/// Copy an object from `from` to `to`.  The regions may overlap (e.g. when compacting).
+fn copy_bytes(from: Address, to: Address) {
+    unsafe { std::ptr::copy(from.to_ptr::<u8>(), to.to_mut_ptr::<u8>(), OBJECT_SIZE) }
+}
+
+/// Return the slot of the `i`-th field of `object`.
+pub fn field(object: ObjectReference, i: usize) -> Address {
+    debug_assert!(i < NUM_FIELDS);
+    object.to_raw_address() + BYTES_IN_ADDRESS * (1 + i)
+}
+
+/// Load the `i`-th field of `object`.
+pub fn read_field(object: ObjectReference, i: usize) -> Option<ObjectReference> {
+    Slot::load(&field(object, i))
+}
+
+/// Store `target` to the `i`-th field of `object` with the write barrier of `mutator`.  Like a
+/// binding, it only calls the post-write hook if the barrier of the plan uses it.  The SATB
+/// barrier is a pre-write barrier only.
+pub fn write_field(
+    mutator: &mut Mutator<MockVM>,
+    object: ObjectReference,
+    i: usize,
+    target: ObjectReference,
+) {
+    let slot = field(object, i);
+    memory_manager::object_reference_write_pre(mutator, object, slot, Some(target));
+    Slot::store(&slot, target);
+    if !mutator
+        .plan
+        .constraints()
+        .barrier
+        .equals(BarrierSelector::SATBBarrier)
+    {
+        memory_manager::object_reference_write_post(mutator, object, slot, Some(target));
+    }
+}
+
+/// Allocate an object whose header and fields are zeroed.
+pub fn alloc_object(
+    mutator: &mut Mutator<MockVM>,
+    semantics: AllocationSemantics,
+) -> ObjectReference {
+    let start = memory_manager::alloc(mutator, OBJECT_SIZE, BYTES_IN_ADDRESS, 0, semantics);
+    assert!(!start.is_zero());
+    crate::util::memory::zero(start, OBJECT_SIZE);
+    let object = MockVM::object_start_to_ref(start);
+    memory_manager::post_alloc(mutator, object, OBJECT_SIZE, semantics);
+    object
+}
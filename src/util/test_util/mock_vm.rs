@@ -9,6 +9,7 @@ use crate::scheduler::*;
 use crate::util::alloc::AllocationError;
 use crate::util::copy::*;
 use crate::util::heap::gc_trigger::GCTriggerPolicy;
+use crate::util::heap::{HeapUsageThresholdEvent, OutOfMemoryReport};
 use crate::util::opaque_pointer::*;
 use crate::util::{Address, ObjectReference};
 use crate::vm::object_model::specs::*;
@@ -53,16 +54,20 @@ macro_rules! lifetime {
     };
 }
 
-/// Call `MockMethod`.
+/// Call `MockMethod`. The mock closure is called after the lock of the MockVM instance is
+/// released, so the closure can call other mocked methods.
 macro_rules! mock {
     ($fn: ident($($arg:expr),*)) => {
-        write_mockvm(|mock| mock.$fn.call(($($arg),*)))
+        write_mockvm(|mock| mock.$fn.prepare_call())(($($arg),*))
     };
 }
 /// Call `MockAny`.
 macro_rules! mock_any {
     ($fn: ident($($arg:expr),*)) => {
-        *write_mockvm(|mock| mock.$fn.call_any(Box::new(($($arg),*)))).downcast().unwrap()
+        {
+            let args: Box<dyn std::any::Any> = Box::new(($($arg),*));
+            *write_mockvm(|mock| mock.$fn.prepare_call_any(&*args))(args).downcast().unwrap()
+        }
     };
 }
 
@@ -206,8 +211,10 @@ pub struct MockVM {
     pub block_for_gc: MockMethod<VMMutatorThread, ()>,
     pub spawn_gc_thread: MockMethod<(VMThread, GCThreadContext<MockVM>), ()>,
     pub out_of_memory: MockMethod<(VMThread, AllocationError), ()>,
+    pub out_of_memory_report: MockMethod<(VMThread, OutOfMemoryReport), ()>,
     pub schedule_finalization: MockMethod<VMWorkerThread, ()>,
     pub post_forwarding: MockMethod<VMWorkerThread, ()>,
+    pub heap_usage_threshold_crossed: MockMethod<(VMWorkerThread, HeapUsageThresholdEvent), ()>,
     pub vm_live_bytes: MockMethod<(), usize>,
     pub is_collection_enabled: MockMethod<(), bool>,
     pub create_gc_trigger: MockMethod<(), Box<dyn GCTriggerPolicy<MockVM>>>,
@@ -216,7 +223,7 @@ pub struct MockVM {
         (
             ObjectReference,
             CopySemantics,
-            &'static GCWorkerCopyContext<MockVM>,
+            &'static mut GCWorkerCopyContext<MockVM>,
         ),
         ObjectReference,
     >,
@@ -281,8 +288,10 @@ impl Default for MockVM {
             out_of_memory: MockMethod::new_fixed(Box::new(|(_, err)| {
                 panic!("Out of memory with {:?}!", err)
             })),
+            out_of_memory_report: MockMethod::new_default(),
             schedule_finalization: MockMethod::new_default(),
             post_forwarding: MockMethod::new_default(),
+            heap_usage_threshold_crossed: MockMethod::new_default(),
             vm_live_bytes: MockMethod::new_default(),
             is_collection_enabled: MockMethod::new_fixed(Box::new(|_| true)),
             create_gc_trigger: MockMethod::new_unimplemented(),
@@ -441,6 +450,10 @@ impl crate::vm::Collection<MockVM> for MockVM {
         mock!(out_of_memory(tls, err_kind))
     }
 
+    fn out_of_memory_report(tls: VMThread, report: &OutOfMemoryReport) {
+        mock!(out_of_memory_report(tls, report.clone()))
+    }
+
     fn schedule_finalization(tls: VMWorkerThread) {
         mock!(schedule_finalization(tls))
     }
@@ -449,6 +462,10 @@ impl crate::vm::Collection<MockVM> for MockVM {
         mock!(post_forwarding(tls))
     }
 
+    fn heap_usage_threshold_crossed(tls: VMWorkerThread, event: &HeapUsageThresholdEvent) {
+        mock!(heap_usage_threshold_crossed(tls, event.clone()))
+    }
+
     fn is_collection_enabled() -> bool {
         mock!(is_collection_enabled())
     }
@@ -463,17 +480,23 @@ impl crate::vm::Collection<MockVM> for MockVM {
 }
 
 impl crate::vm::ObjectModel<MockVM> for MockVM {
-    const GLOBAL_LOG_BIT_SPEC: VMGlobalLogBitSpec = VMGlobalLogBitSpec::in_header(0);
+    // The log bit is on the side, as the plans with the SATB barrier bulk set and clear it in
+    // their side metadata (`extract_side_spec` panics for an in-header log bit), so their GCs
+    // could not run with MockVM.  In the header, it would also share bit 0 with the mark bits,
+    // so marking an object would log it.
+    const GLOBAL_LOG_BIT_SPEC: VMGlobalLogBitSpec = VMGlobalLogBitSpec::side_first();
     const LOCAL_FORWARDING_POINTER_SPEC: VMLocalForwardingPointerSpec =
         VMLocalForwardingPointerSpec::in_header(0);
     const LOCAL_FORWARDING_BITS_SPEC: VMLocalForwardingBitsSpec =
         VMLocalForwardingBitsSpec::in_header(0);
-    const LOCAL_MARK_BIT_SPEC: VMLocalMarkBitSpec = VMLocalMarkBitSpec::in_header(0);
+    // Immix spaces need two mark bits to run GCs (see `VMLocalMarkBitSpec::in_header_with_bits`).
+    const LOCAL_MARK_BIT_SPEC: VMLocalMarkBitSpec = VMLocalMarkBitSpec::in_header_with_bits(0, 2);
     const LOCAL_LOS_MARK_NURSERY_SPEC: VMLocalLOSMarkNurserySpec =
         VMLocalLOSMarkNurserySpec::in_header(0);
 
+    // The pinning bit follows the mark bits, so pinned objects are not considered marked.
     #[cfg(feature = "object_pinning")]
-    const LOCAL_PINNING_BIT_SPEC: VMLocalPinningBitSpec = VMLocalPinningBitSpec::in_header(0);
+    const LOCAL_PINNING_BIT_SPEC: VMLocalPinningBitSpec = VMLocalPinningBitSpec::in_header(2);
 
     const OBJECT_REF_OFFSET_LOWER_BOUND: isize = DEFAULT_OBJECT_REF_OFFSET as isize;
 
@@ -11,6 +11,8 @@ pub mod fixtures;
 #[cfg(feature = "mock_test")]
 pub mod mock_method;
 #[cfg(feature = "mock_test")]
+pub mod mock_objects;
+#[cfg(feature = "mock_test")]
 pub mod mock_vm;
 
 // Sometimes we need to mmap for tests. We want to ensure that the mmapped addresses do not overlap
@@ -1,5 +1,6 @@
 use crate::util::alloc::AllocationError;
 use crate::util::heap::gc_trigger::GCTriggerPolicy;
+use crate::util::heap::{HeapUsageThresholdEvent, OutOfMemoryReport};
 use crate::util::opaque_pointer::*;
 use crate::vm::VMBinding;
 use crate::{scheduler::*, Mutator};
@@ -39,6 +40,10 @@ pub trait Collection<VM: VMBinding> {
     /// is going to happen. Then MMTk starts a GC. For a stop-the-world GC, MMTk will then call `stop_all_mutators()`
     /// before the GC, and call `resume_mutators()` after the GC.
     ///
+    /// If the collection is initialized with [`crate::mmtk::MMTK::initialize_collection_inline`],
+    /// there are no GC threads, and the binding should call [`crate::memory_manager::run_gc_inline`]
+    /// here to run the GC on the current thread.
+    ///
     /// Arguments:
     /// * `tls`: The current thread pointer that should be blocked. The VM can optionally check if the current thread matches `tls`.
     fn block_for_gc(tls: VMMutatorThread);
@@ -47,7 +52,8 @@ pub trait Collection<VM: VMBinding> {
     /// have assumptions that those calls needs to be within VM internal threads.
     /// As a result, MMTk does not spawn GC threads itself to avoid breaking this kind of assumptions.
     /// MMTk calls this method to spawn GC threads during [`crate::mmtk::MMTK::initialize_collection`]
-    /// and [`crate::mmtk::MMTK::after_fork`].
+    /// and [`crate::mmtk::MMTK::after_fork`].  It is never called if the collection is initialized
+    /// with [`crate::mmtk::MMTK::initialize_collection_inline`].
     ///
     /// Arguments:
     /// * `tls`: The thread pointer for the parent thread that we spawn new threads from. This is the same `tls` when the VM
@@ -75,6 +81,22 @@ pub trait Collection<VM: VMBinding> {
         panic!("Out of memory with {:?}!", err_kind);
     }
 
+    /// Inform the VM of the state of the heap when an allocation runs out of heap memory.  MMTk
+    /// calls this method right before calling [`Collection::out_of_memory`] with
+    /// [`AllocationError::HeapOutOfMemory`], so the binding can log the report, or keep it to
+    /// include in the error that it raises.  The report describes the failed allocation, the memory
+    /// used by each space and its fragmentation, the live bytes from the last GC (if the option
+    /// `count_live_bytes_in_gc` is enabled), and the recent GCs.
+    ///
+    /// By default, the report is logged as a warning.
+    ///
+    /// Arguments:
+    /// * `tls`: The thread pointer for the mutator which failed the allocation.
+    /// * `report`: The out-of-memory report.
+    fn out_of_memory_report(_tls: VMThread, report: &OutOfMemoryReport) {
+        warn!("Out of memory:\n{}", report);
+    }
+
     /// Inform the VM to schedule finalization threads.
     ///
     /// Arguments:
@@ -104,6 +126,21 @@ pub trait Collection<VM: VMBinding> {
     /// * `tls_worker`: The thread pointer for the worker thread performing this call.
     fn post_forwarding(_tls: VMWorkerThread) {}
 
+    /// Inform the VM that the memory used by the heap (or by a space) after a GC has crossed a
+    /// heap usage threshold registered by [`crate::memory_manager::add_heap_usage_threshold`].
+    /// MMTk calls this at the end of a GC, before mutators are resumed, once for each threshold
+    /// that has been crossed since the previous GC.  A threshold is crossed when the used memory
+    /// reaches the limit (`event.exceeded` is true), or falls below the limit again
+    /// (`event.exceeded` is false).
+    ///
+    /// The binding must not allocate in the MMTk heap or trigger a GC in this method, but it may
+    /// add or remove heap usage thresholds.
+    ///
+    /// Arguments:
+    /// * `tls`: The thread pointer for the GC worker.
+    /// * `event`: The threshold that was crossed, and the memory used by each space after the GC.
+    fn heap_usage_threshold_crossed(_tls: VMWorkerThread, _event: &HeapUsageThresholdEvent) {}
+
     /// Return the amount of memory (in bytes) which the VM allocated outside the MMTk heap but
     /// wants to include into the current MMTk heap size.  MMTk core will consider the reported
     /// memory as part of MMTk heap for the purpose of heap size accounting.
@@ -108,6 +108,8 @@ pub trait ObjectModel<VM: VMBinding> {
     /// [forwarding bits](crate::vm::ObjectModel::LOCAL_FORWARDING_BITS_SPEC), you can often steal the last bit in
     /// the object header (due to alignment requirements) for the mark bit. Though some bindings such as the
     /// OpenJDK binding prefer to have the mark bits in side metadata to allow for bulk operations.
+    /// The Immix space needs at least 2 bits if the mark bit is in the header, which can be declared with
+    /// [`VMLocalMarkBitSpec::in_header_with_bits`].
     const LOCAL_MARK_BIT_SPEC: VMLocalMarkBitSpec;
 
     #[cfg(feature = "object_pinning")]
@@ -617,6 +619,25 @@ pub mod specs {
         0,
         LOG_MIN_OBJECT_SIZE
     );
+    impl VMLocalMarkBitSpec {
+        /// Declare that the VM uses `num_of_bits` bits in the header for the mark bit, starting
+        /// from `bit_offset`.  The bits must be within one byte.
+        ///
+        /// Spaces that cannot clear in-header mark bits in bulk, such as the Immix space, use the
+        /// extra bits to cycle through different mark states in successive GCs instead.  Such
+        /// spaces set the mark bits of new objects in
+        /// [`post_alloc`](crate::memory_manager::post_alloc), so the binding must call
+        /// `post_alloc` for every object allocated in them, or make sure the bits are zero for
+        /// newly allocated objects.
+        pub const fn in_header_with_bits(bit_offset: isize, num_of_bits: usize) -> Self {
+            assert!(num_of_bits > 0 && num_of_bits < 8);
+            assert!((bit_offset >> 3) == ((bit_offset + num_of_bits as isize - 1) >> 3));
+            Self(MetadataSpec::InHeader(HeaderMetadataSpec {
+                bit_offset,
+                num_of_bits,
+            }))
+        }
+    }
     // Pinning bit: 1 bit per object, local
     define_vm_metadata_spec!(
         /// 1-bit local metadata for spaces that support pinning.
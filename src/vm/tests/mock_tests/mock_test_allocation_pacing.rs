@@ -0,0 +1,79 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+
+use crate::util::alloc::AllocationPacing;
+use crate::AllocationSemantics;
+use std::time::{Duration, Instant};
+
+/// This test enables allocation pacing with a very low allocation rate, so that every allocation
+/// in the slow path (except the first) is delayed by the max delay.
+#[test]
+pub fn allocation_pacing() {
+    with_mockvm(
+        default_setup,
+        || {
+            const MB: usize = 1024 * 1024;
+            const OBJECT_SIZE: usize = 64 * 1024;
+            const OBJECTS: usize = 16;
+            const MAX_DELAY_US: usize = 1000;
+            let mut fixture = MutatorFixture::create_with_builder(|builder| {
+                builder.options.gc_trigger.set(
+                    crate::util::options::GCTriggerSelector::FixedHeapSize(8 * MB),
+                );
+                builder.options.allocation_pacing.set(true);
+                // Pace the allocation as soon as any memory is used.
+                builder.options.allocation_pacing_threshold.set(0.0);
+                builder.options.allocation_pacing_rate.set(1);
+                builder
+                    .options
+                    .allocation_pacing_max_delay_us
+                    .set(MAX_DELAY_US);
+            });
+
+            let start = Instant::now();
+            for _ in 0..OBJECTS {
+                let addr = memory_manager::alloc(
+                    &mut fixture.mutator,
+                    OBJECT_SIZE,
+                    8,
+                    0,
+                    AllocationSemantics::Los,
+                );
+                assert!(!addr.is_zero());
+            }
+            assert!(
+                start.elapsed() >= Duration::from_micros((MAX_DELAY_US * (OBJECTS - 1)) as u64)
+            );
+
+            // The limits can be changed for each mutator.
+            assert!(!memory_manager::set_allocation_pacing(
+                &mut fixture.mutator,
+                Some(AllocationPacing {
+                    rate: 0,
+                    max_delay: Duration::ZERO,
+                })
+            ));
+            assert!(memory_manager::set_allocation_pacing(
+                &mut fixture.mutator,
+                Some(AllocationPacing {
+                    rate: usize::MAX,
+                    max_delay: Duration::ZERO,
+                })
+            ));
+            let addr = memory_manager::alloc(
+                &mut fixture.mutator,
+                OBJECT_SIZE,
+                8,
+                0,
+                AllocationSemantics::Los,
+            );
+            assert!(!addr.is_zero());
+            assert!(memory_manager::set_allocation_pacing(
+                &mut fixture.mutator,
+                None
+            ));
+        },
+        no_cleanup,
+    )
+}
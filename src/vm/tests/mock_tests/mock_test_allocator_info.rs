@@ -30,6 +30,10 @@ pub fn test_allocator_info() {
                 | PlanSelector::MarkCompact
                 | PlanSelector::Compressor
                 | PlanSelector::ConcurrentImmix
+                | PlanSelector::ConcurrentGenImmix
+                | PlanSelector::LXR
+                | PlanSelector::G1
+                | PlanSelector::MultiGenCopy
                 | PlanSelector::StickyImmix => {
                     // These plans all use bump pointer allocator.
                     let AllocatorInfo::BumpPointer {
@@ -44,6 +48,10 @@ pub fn test_allocator_info() {
                         bump_pointer_offset
                     );
                 }
+                PlanSelector::ConcurrentMarkSweep | PlanSelector::StickyMarkSweep => {
+                    // We haven't implemented for a free list allocator
+                    assert!(matches!(allocator_info, AllocatorInfo::Unimplemented))
+                }
                 PlanSelector::MarkSweep => {
                     if cfg!(feature = "malloc_mark_sweep") {
                         // We provide no info for a malloc allocator
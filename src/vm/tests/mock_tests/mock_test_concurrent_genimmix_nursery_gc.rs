@@ -0,0 +1,254 @@
+// GITHUB-CI: MMTK_PLAN=ConcurrentGenImmix
+
+use super::mock_test_prelude::*;
+
+use crate::plan::{ConcurrentGenImmix, GenNurseryProcessEdges, InitialMarkProcessEdges};
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::policy::immix::TRACE_KIND_FAST;
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext,
+    UnsupportedProcessEdges,
+};
+use crate::scheduler::{GCWork, GCWorker, ProcessEdgesWork, WorkBucketStage};
+use crate::util::alloc::allocator::AllocationOptions;
+use crate::util::constants::BYTES_IN_ADDRESS;
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::test_util::mock_objects::*;
+use crate::util::{Address, ObjectReference, VMThread, VMWorkerThread};
+use crate::vm::RootsWorkFactory;
+use crate::{AllocationSemantics, Mutator, MMTK};
+use std::sync::Mutex;
+
+type NurseryProcessEdges =
+    GenNurseryProcessEdges<MockVM, ConcurrentGenImmix<MockVM>, DEFAULT_TRACE>;
+type MatureProcessEdges = PlanProcessEdges<MockVM, ConcurrentGenImmix<MockVM>, TRACE_KIND_FAST>;
+type RootsWorkFactoryOf<E> =
+    ProcessEdgesWorkRootsWorkFactory<MockVM, E, UnsupportedProcessEdges<MockVM>>;
+
+/// A work packet that runs mutator code during concurrent marking.
+struct MutatorStep(Option<Box<dyn FnOnce() + Send>>);
+
+impl GCWork<MockVM> for MutatorStep {
+    fn do_work(&mut self, _worker: &mut GCWorker<MockVM>, _mmtk: &'static MMTK<MockVM>) {
+        (self.0.take().unwrap())()
+    }
+}
+
+/// Mock `scan_roots_in_mutator_thread` for GCs that use the process edges type `E`.  Roots are
+/// reported with `report`.
+fn scan_roots<E: ProcessEdgesWork<VM = MockVM>>(
+    report: impl Fn(&mut RootsWorkFactoryOf<E>) + Send + Sync + 'static,
+) -> Box<dyn MockAny> {
+    Box::new(MockMethod::<
+        (
+            VMWorkerThread,
+            &'static mut Mutator<MockVM>,
+            Box<RootsWorkFactoryOf<E>>,
+        ),
+        (),
+    >::new_fixed(Box::new(move |(_, _, mut factory)| {
+        report(&mut factory)
+    })))
+}
+
+fn scan_vm_specific_roots<E: ProcessEdgesWork<VM = MockVM>>() -> Box<dyn MockAny> {
+    Box::new(MockMethod::<(VMWorkerThread, Box<RootsWorkFactoryOf<E>>), ()>::new_default())
+}
+
+fn process_weak_refs<E: ProcessEdgesWork<VM = MockVM>>() -> Box<dyn MockAny> {
+    Box::new(MockMethod::<
+        (
+            &'static mut GCWorker<MockVM>,
+            ProcessEdgesWorkTracerContext<E>,
+        ),
+        bool,
+    >::new_default())
+}
+
+/// This test runs a nursery GC of concurrent generational immix in the middle of concurrent
+/// marking.  The nursery GC promotes young objects as marked objects, and keeps the SATB barrier
+/// and the remembered set working, so the final mark pause keeps all the reachable objects.
+#[test]
+pub fn concurrent_genimmix_nursery_gc() {
+    with_mockvm(
+        || -> MockVM {
+            with_object_model(MockVM {
+                // GC workers copy objects with the uninitialized `tls`.
+                is_mutator: MockMethod::new_fixed(Box::new(|tls: VMThread| {
+                    !tls.0.to_address().is_zero()
+                })),
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                scan_vm_specific_roots: Box::new(MockAnyOf(vec![
+                    scan_vm_specific_roots::<NurseryProcessEdges>(),
+                    scan_vm_specific_roots::<InitialMarkProcessEdges<MockVM>>(),
+                    scan_vm_specific_roots::<MatureProcessEdges>(),
+                ])),
+                process_weak_refs: Box::new(MockAnyOf(vec![
+                    process_weak_refs::<NurseryProcessEdges>(),
+                    process_weak_refs::<InitialMarkProcessEdges<MockVM>>(),
+                    process_weak_refs::<MatureProcessEdges>(),
+                ])),
+                ..MockVM::default()
+            })
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            // The plan is fixed, as the types of the mocked methods depend on it.
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::ConcurrentGenImmix);
+                builder.options.threads.set(1);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+            });
+            let mmtk = fixture.mmtk();
+            let plan = mmtk.get_plan();
+            let mutator = fixture.mutator();
+            let get_mutator = fixture.mutator_getter();
+
+            // root -> a -> b -> e.  They are promoted by the initial mark pause.
+            let a = alloc_object(mutator, AllocationSemantics::Default);
+            let b = alloc_object(mutator, AllocationSemantics::Default);
+            let e = alloc_object(mutator, AllocationSemantics::Default);
+            write_field(mutator, a, 0, b);
+            write_field(mutator, b, 0, e);
+            let root: Address = Address::from_ref(Box::leak(Box::new(a)));
+            let load_root = move || -> ObjectReference { unsafe { root.load() } };
+
+            // Dead large objects that fill more than half of the heap, so that the next GC starts
+            // concurrent marking.
+            for _ in 0..9 {
+                let large = memory_manager::alloc(
+                    mutator,
+                    MB,
+                    BYTES_IN_ADDRESS,
+                    0,
+                    AllocationSemantics::Los,
+                );
+                assert!(!large.is_zero());
+                memory_manager::post_alloc(
+                    mutator,
+                    MockVM::object_start_to_ref(large),
+                    MB,
+                    AllocationSemantics::Los,
+                );
+            }
+
+            // The address of `c` before the nursery GC, and the object `e`.
+            let c_addr = Address::from_mut_ptr(Box::leak(Box::new(Address::ZERO)));
+            let e_addr = Address::from_mut_ptr(Box::leak(Box::new(Address::ZERO)));
+
+            // Runs during concurrent marking, after the nursery GC.
+            let step2 = move || {
+                let mutator = get_mutator();
+                let plan = mmtk.get_plan();
+                assert!(!plan.generational().unwrap().last_collection_full_heap());
+                assert!(plan.concurrent().unwrap().concurrent_work_in_progress());
+
+                // `c` has been promoted as a marked object.
+                let a = load_root();
+                let c = read_field(a, 0).unwrap();
+                assert_ne!(c.to_raw_address(), unsafe { c_addr.load::<Address>() });
+                assert!(c.is_live());
+
+                // `b` and `e` have not been marked.  After `b.0` is overwritten, only the SATB
+                // barrier keeps `e` alive.  `d` is only referenced by the promoted object `c`, and
+                // is found through the remembered set.
+                let b = read_field(c, 0).unwrap();
+                let e = read_field(b, 0).unwrap();
+                assert!(!b.is_live());
+                assert!(!e.is_live());
+                let d = alloc_object(mutator, AllocationSemantics::Default);
+                write_field(mutator, c, 1, d);
+                write_field(mutator, b, 0, a);
+                unsafe { e_addr.store(e) };
+            };
+
+            // Runs during concurrent marking, before the marking work from the roots.
+            let step1 = move || {
+                let mutator = get_mutator();
+                let a = load_root();
+                let b = read_field(a, 0).unwrap();
+                assert!(!b.is_live());
+
+                // root -> a -> c -> b.  Only the SATB barrier keeps `b` alive.
+                let c = alloc_object(mutator, AllocationSemantics::Default);
+                write_field(mutator, c, 0, b);
+                write_field(mutator, a, 0, c);
+                unsafe { c_addr.store(c) };
+
+                // Fill the nursery until a GC is requested.
+                let options = AllocationOptions {
+                    at_safepoint: false,
+                    ..AllocationOptions::default()
+                };
+                while !memory_manager::alloc_with_options(
+                    mutator,
+                    OBJECT_SIZE,
+                    BYTES_IN_ADDRESS,
+                    0,
+                    AllocationSemantics::Default,
+                    options,
+                )
+                .is_zero()
+                {}
+                // Concurrent marking is suspended so that the nursery GC can start.
+                assert!(!mmtk.scheduler.work_buckets[WorkBucketStage::Concurrent].is_enabled());
+                memory_manager::add_work_packet(
+                    mmtk,
+                    WorkBucketStage::Concurrent,
+                    MutatorStep(Some(Box::new(step2))),
+                );
+            };
+            let step1 = Mutex::new(Some(step1));
+
+            write_mockvm(|mock| {
+                mock.scan_roots_in_mutator_thread = Box::new(MockAnyOf(vec![
+                    scan_roots::<NurseryProcessEdges>(move |factory| {
+                        factory.create_process_roots_work(vec![root])
+                    }),
+                    scan_roots::<InitialMarkProcessEdges<MockVM>>(move |factory| {
+                        // Add `step1` to the `Concurrent` bucket before the marking work from the
+                        // roots, so it runs first when concurrent marking starts.
+                        if let Some(step1) = step1.lock().unwrap().take() {
+                            memory_manager::add_work_packet(
+                                mmtk,
+                                WorkBucketStage::Concurrent,
+                                MutatorStep(Some(Box::new(step1))),
+                            );
+                        }
+                        factory.create_process_roots_work(vec![root])
+                    }),
+                    scan_roots::<MatureProcessEdges>(move |factory| {
+                        factory.create_process_roots_work(vec![root])
+                    }),
+                ]));
+            });
+
+            // Initial mark.  Concurrent marking, the nursery GC and the rest of concurrent marking
+            // run in this call.
+            fixture.gc();
+            let e: ObjectReference = unsafe { e_addr.load() };
+            assert!(!e.to_raw_address().is_zero(), "The nursery GC did not run");
+            assert!(plan.concurrent().unwrap().concurrent_work_in_progress());
+
+            // Final mark.
+            fixture.gc();
+            assert!(!plan.concurrent().unwrap().concurrent_work_in_progress());
+            assert!(!mmtk.gc_in_progress());
+
+            let a = load_root();
+            let c = read_field(a, 0).unwrap();
+            let b = read_field(c, 0).unwrap();
+            let d = read_field(c, 1).unwrap();
+            assert_eq!(read_field(b, 0), Some(a));
+            for object in [a, b, c, d, e] {
+                assert!(object.is_live(), "{} is not live", object);
+            }
+        },
+        no_cleanup,
+    )
+}
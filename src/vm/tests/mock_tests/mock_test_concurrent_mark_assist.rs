@@ -0,0 +1,187 @@
+// GITHUB-CI: MMTK_PLAN=ConcurrentMarkSweep
+
+use super::mock_test_prelude::*;
+
+use crate::plan::{ConcurrentMarkSweep, ProcessRootSlots};
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext,
+    UnsupportedProcessEdges,
+};
+use crate::scheduler::{GCWork, GCWorker, WorkBucketStage};
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::test_util::mock_objects::*;
+use crate::util::{Address, ObjectReference, VMWorkerThread};
+use crate::{AllocationSemantics, Mutator, MMTK};
+use std::sync::{Arc, Mutex};
+
+type CMSProcessEdges = PlanProcessEdges<MockVM, ConcurrentMarkSweep<MockVM>, DEFAULT_TRACE>;
+type CMSRootsWorkFactory = ProcessEdgesWorkRootsWorkFactory<
+    MockVM,
+    ProcessRootSlots<MockVM, ConcurrentMarkSweep<MockVM>, DEFAULT_TRACE>,
+    UnsupportedProcessEdges<MockVM>,
+>;
+
+/// A work packet that runs mutator code during concurrent marking.  It is added to the
+/// `Concurrent` bucket before the initial mark pause, so it runs before the marking packets.
+struct MutatorStep(Option<Box<dyn FnOnce() + Send>>);
+
+impl GCWork<MockVM> for MutatorStep {
+    fn do_work(&mut self, _worker: &mut GCWorker<MockVM>, _mmtk: &'static MMTK<MockVM>) {
+        (self.0.take().unwrap())()
+    }
+}
+
+/// A work packet that records whether the `Concurrent` bucket is drained, and the thread of the
+/// worker, when it is executed.
+struct AssistProbe(Arc<Mutex<Option<(bool, VMWorkerThread)>>>);
+
+impl GCWork<MockVM> for AssistProbe {
+    fn do_work(&mut self, worker: &mut GCWorker<MockVM>, mmtk: &'static MMTK<MockVM>) {
+        let drained = mmtk.scheduler.work_buckets[WorkBucketStage::Concurrent].is_drained();
+        *self.0.lock().unwrap() = Some((drained, worker.tls));
+    }
+}
+
+/// This test lets a mutator mark a linked list while it allocates during concurrent marking.
+/// Each allocation slow path only marks a few objects, and the unfinished marking work is always
+/// visible in the `Concurrent` bucket, so the final mark pause is not triggered before the list
+/// is marked, not even while the mutator executes the last packet.  The GC keeps the whole list
+/// alive.
+#[test]
+pub fn concurrent_mark_assist() {
+    with_mockvm(
+        || -> MockVM {
+            with_object_model(MockVM {
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                scan_vm_specific_roots: Box::new(MockMethod::<
+                    (VMWorkerThread, Box<CMSRootsWorkFactory>),
+                    (),
+                >::new_default()),
+                process_weak_refs: Box::new(MockMethod::<
+                    (
+                        &'static mut GCWorker<MockVM>,
+                        ProcessEdgesWorkTracerContext<CMSProcessEdges>,
+                    ),
+                    bool,
+                >::new_default()),
+                ..MockVM::default()
+            })
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            const LIST_LENGTH: usize = 512;
+            // The plan is fixed, as the types of the mocked methods depend on it.
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::ConcurrentMarkSweep);
+                builder.options.threads.set(1);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+                // Mark about 20 objects for each block the mutator allocates into.
+                builder.options.concurrent_mark_assist_ratio.set(0.01);
+            });
+            let mmtk = fixture.mmtk();
+            let tls = fixture.tls;
+            let mutator = fixture.mutator();
+            let get_mutator = fixture.mutator_getter();
+
+            // root -> list[0] -> list[1] -> ... -> list[LIST_LENGTH - 1]
+            let list: &'static [ObjectReference] = (0..LIST_LENGTH)
+                .map(|_| alloc_object(mutator, AllocationSemantics::Default))
+                .collect::<Vec<_>>()
+                .leak();
+            for pair in list.windows(2) {
+                write_field(mutator, pair[0], 0, pair[1]);
+            }
+            let root: Address = Address::from_ref(Box::leak(Box::new(list[0])));
+            let count_live = move || list.iter().filter(|object| object.is_live()).count();
+
+            write_mockvm(|mock| {
+                mock.scan_roots_in_mutator_thread =
+                    Box::new(MockMethod::<
+                        (
+                            VMWorkerThread,
+                            &'static mut Mutator<MockVM>,
+                            Box<CMSRootsWorkFactory>,
+                        ),
+                        (),
+                    >::new_fixed(Box::new(
+                        move |(_, _, mut factory)| factory.create_process_roots_work(vec![root]),
+                    )));
+            });
+
+            // During concurrent marking, the mutator allocates until it has marked the list.
+            memory_manager::add_work_packet(
+                mmtk,
+                WorkBucketStage::Concurrent,
+                MutatorStep(Some(Box::new(move || {
+                    let mutator = get_mutator();
+                    let bucket = &mmtk.scheduler.work_buckets[WorkBucketStage::Concurrent];
+                    assert_eq!(count_live(), 0);
+                    let mut partially_marked = false;
+                    for i in 0.. {
+                        alloc_object(mutator, AllocationSemantics::Default);
+                        if i % 256 != 0 {
+                            continue;
+                        }
+                        let live = count_live();
+                        if live == LIST_LENGTH {
+                            break;
+                        }
+                        partially_marked |= live > 0;
+                        // The rest of the list is still to be marked by packets in the bucket.
+                        assert!(!bucket.is_drained());
+                        assert!(
+                            i < 4 * MB / OBJECT_SIZE,
+                            "The mutator did not mark the list"
+                        );
+                    }
+                    // The work is paced by the allocation of the mutator.
+                    assert!(partially_marked);
+
+                    // The bucket is not drained while the mutator executes the last packet in
+                    // it, and the packet runs with the thread of the mutator.
+                    while !bucket.is_empty() {
+                        mmtk.scheduler.assist_concurrent_work(tls, usize::MAX);
+                    }
+                    let probed = Arc::new(Mutex::new(None));
+                    bucket.add(AssistProbe(probed.clone()));
+                    mmtk.scheduler.assist_concurrent_work(tls, usize::MAX);
+                    assert_eq!(
+                        *probed.lock().unwrap(),
+                        Some((false, VMWorkerThread(tls.0)))
+                    );
+                    assert!(bucket.is_drained());
+                }))),
+            );
+
+            // Initial mark.  Concurrent marking also runs in this call.
+            fixture.gc();
+            assert_eq!(count_live(), LIST_LENGTH);
+            assert!(mmtk
+                .get_plan()
+                .concurrent()
+                .unwrap()
+                .concurrent_work_in_progress());
+
+            // Final mark.
+            fixture.gc();
+            assert!(!mmtk
+                .get_plan()
+                .concurrent()
+                .unwrap()
+                .concurrent_work_in_progress());
+            assert!(!mmtk.gc_in_progress());
+
+            assert_eq!(count_live(), LIST_LENGTH);
+            for pair in list.windows(2) {
+                assert_eq!(read_field(pair[0], 0), Some(pair[1]));
+            }
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,159 @@
+// GITHUB-CI: MMTK_PLAN=ConcurrentMarkSweep
+
+use super::mock_test_prelude::*;
+
+use crate::plan::{ConcurrentMarkSweep, ProcessRootSlots};
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext,
+    UnsupportedProcessEdges,
+};
+use crate::scheduler::{GCWork, GCWorker, WorkBucketStage};
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::test_util::mock_objects::*;
+use crate::util::{Address, ObjectReference, VMWorkerThread};
+use crate::{AllocationSemantics, Mutator, MMTK};
+use std::collections::HashSet;
+
+type CMSProcessEdges = PlanProcessEdges<MockVM, ConcurrentMarkSweep<MockVM>, DEFAULT_TRACE>;
+type CMSRootsWorkFactory = ProcessEdgesWorkRootsWorkFactory<
+    MockVM,
+    ProcessRootSlots<MockVM, ConcurrentMarkSweep<MockVM>, DEFAULT_TRACE>,
+    UnsupportedProcessEdges<MockVM>,
+>;
+
+/// A work packet that runs mutator code during concurrent marking.  Concurrent marking runs in
+/// `run_gc_inline` right after the initial mark pause.  The packet is added to the `Concurrent`
+/// bucket before the pause, so it runs before the marking packets added by root scanning.
+struct MutatorStep(Option<Box<dyn FnOnce() + Send>>);
+
+impl GCWork<MockVM> for MutatorStep {
+    fn do_work(&mut self, _worker: &mut GCWorker<MockVM>, _mmtk: &'static MMTK<MockVM>) {
+        (self.0.take().unwrap())()
+    }
+}
+
+/// This test runs a concurrent mark-sweep GC on the mutator thread.  During concurrent marking,
+/// the mutator allocates an object and moves an unmarked object behind it, so the object is only
+/// kept alive by the SATB barrier.  After the GC, lazy sweeping reuses the memory of dead objects.
+#[test]
+pub fn concurrent_marksweep() {
+    with_mockvm(
+        || -> MockVM {
+            with_object_model(MockVM {
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                scan_vm_specific_roots: Box::new(MockMethod::<
+                    (VMWorkerThread, Box<CMSRootsWorkFactory>),
+                    (),
+                >::new_default()),
+                process_weak_refs: Box::new(MockMethod::<
+                    (
+                        &'static mut GCWorker<MockVM>,
+                        ProcessEdgesWorkTracerContext<CMSProcessEdges>,
+                    ),
+                    bool,
+                >::new_default()),
+                ..MockVM::default()
+            })
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            const NUM_GARBAGE: usize = 1024;
+            // The plan is fixed, as the types of the mocked methods depend on it.
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::ConcurrentMarkSweep);
+                builder.options.threads.set(1);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+            });
+            let mmtk = fixture.mmtk();
+            let mutator = fixture.mutator();
+            let get_mutator = fixture.mutator_getter();
+
+            // root -> a -> b, and some garbage.
+            let a = alloc_object(mutator, AllocationSemantics::Default);
+            let b = alloc_object(mutator, AllocationSemantics::Default);
+            write_field(mutator, a, 0, b);
+            let garbage: HashSet<ObjectReference> = (0..NUM_GARBAGE)
+                .map(|_| alloc_object(mutator, AllocationSemantics::Default))
+                .collect();
+            let root: Address = Address::from_ref(Box::leak(Box::new(a)));
+
+            write_mockvm(|mock| {
+                mock.scan_roots_in_mutator_thread =
+                    Box::new(MockMethod::<
+                        (
+                            VMWorkerThread,
+                            &'static mut Mutator<MockVM>,
+                            Box<CMSRootsWorkFactory>,
+                        ),
+                        (),
+                    >::new_fixed(Box::new(
+                        move |(_, _, mut factory)| factory.create_process_roots_work(vec![root]),
+                    )));
+            });
+
+            // During concurrent marking: a -> c -> b.  `c` is allocated during marking, so it is
+            // live but never scanned.  `b` is not marked yet, and only the SATB barrier (which
+            // remembers the old value of `a.0`) keeps it alive.
+            let c_cell = Box::leak(Box::new(Address::ZERO));
+            let c_addr = Address::from_mut_ptr(c_cell);
+            memory_manager::add_work_packet(
+                mmtk,
+                WorkBucketStage::Concurrent,
+                MutatorStep(Some(Box::new(move || {
+                    let mutator = get_mutator();
+                    assert!(!b.is_live());
+                    let c = alloc_object(mutator, AllocationSemantics::Default);
+                    write_field(mutator, c, 0, b);
+                    write_field(mutator, a, 0, c);
+                    unsafe { c_addr.store(c) };
+                }))),
+            );
+
+            // Initial mark.  Concurrent marking also runs in this call.
+            fixture.gc();
+            let c: ObjectReference = unsafe { c_addr.load() };
+            assert!(!c.to_raw_address().is_zero());
+            assert!(mmtk
+                .get_plan()
+                .concurrent()
+                .unwrap()
+                .concurrent_work_in_progress());
+
+            // Final mark.
+            fixture.gc();
+            assert!(!mmtk
+                .get_plan()
+                .concurrent()
+                .unwrap()
+                .concurrent_work_in_progress());
+            assert!(!mmtk.gc_in_progress());
+
+            assert_eq!(read_field(a, 0), Some(c));
+            assert_eq!(read_field(c, 0), Some(b));
+            for object in [a, b, c] {
+                assert!(object.is_live(), "{} is not live", object);
+            }
+            assert!(garbage.iter().all(|object| !object.is_live()));
+
+            // The blocks are swept lazily when the mutator allocates into them.  The new objects
+            // reuse the cells of the garbage without taking more pages.
+            let used_pages = mmtk.get_plan().get_used_pages();
+            let reused = (0..NUM_GARBAGE)
+                .map(|_| alloc_object(mutator, AllocationSemantics::Default))
+                .filter(|object| garbage.contains(object))
+                .count();
+            assert!(reused > 0);
+            assert_eq!(mmtk.get_plan().get_used_pages(), used_pages);
+            for object in [a, b, c] {
+                assert!(object.is_live(), "{} is not live", object);
+            }
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,91 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+
+use crate::plan::SemiSpace;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext,
+    UnsupportedProcessEdges,
+};
+use crate::scheduler::GCWorker;
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::VMWorkerThread;
+use crate::{AllocationSemantics, Mutator};
+
+type SSProcessEdges = PlanProcessEdges<MockVM, SemiSpace<MockVM>, DEFAULT_TRACE>;
+type SSRootsWorkFactory =
+    ProcessEdgesWorkRootsWorkFactory<MockVM, SSProcessEdges, UnsupportedProcessEdges<MockVM>>;
+
+/// This test runs GCs with the deterministic scheduler and a given seed.  The GCs run on the mutator
+/// thread, so no GC thread is spawned.  There are no roots, so each GC frees all the allocated
+/// memory.
+#[test]
+pub fn deterministic_scheduler() {
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                scan_roots_in_mutator_thread: Box::new(MockMethod::<
+                    (
+                        VMWorkerThread,
+                        &'static mut Mutator<MockVM>,
+                        Box<SSRootsWorkFactory>,
+                    ),
+                    (),
+                >::new_default()),
+                scan_vm_specific_roots: Box::new(MockMethod::<
+                    (VMWorkerThread, Box<SSRootsWorkFactory>),
+                    (),
+                >::new_default()),
+                process_weak_refs: Box::new(MockMethod::<
+                    (
+                        &'static mut GCWorker<MockVM>,
+                        ProcessEdgesWorkTracerContext<SSProcessEdges>,
+                    ),
+                    bool,
+                >::new_default()),
+                ..MockVM::default()
+            }
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            // The plan is fixed, as the types of the mocked methods depend on it.
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+                // The deterministic scheduler uses one worker regardless of this.
+                builder.options.threads.set(4);
+                builder.options.deterministic_scheduler.set(true);
+                builder.options.deterministic_scheduler_seed.set(42);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+            });
+            let mmtk = fixture.mmtk();
+            assert_eq!(memory_manager::num_of_workers(mmtk), 1);
+            assert_eq!(memory_manager::deterministic_scheduler_seed(mmtk), Some(42));
+            let mutator = fixture.mutator();
+            for _ in 0..64 {
+                let addr = memory_manager::alloc(mutator, 1024, 8, 0, AllocationSemantics::Default);
+                assert!(!addr.is_zero());
+            }
+            let used_pages = mmtk.get_plan().get_used_pages();
+
+            for _ in 0..4 {
+                fixture.gc();
+                assert!(mmtk.get_plan().get_used_pages() < used_pages);
+                assert!(!mmtk.gc_in_progress());
+
+                for _ in 0..64 {
+                    let addr =
+                        memory_manager::alloc(mutator, 1024, 8, 0, AllocationSemantics::Default);
+                    assert!(!addr.is_zero());
+                }
+            }
+        },
+        no_cleanup,
+    )
+}
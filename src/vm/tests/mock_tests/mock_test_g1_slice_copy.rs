@@ -0,0 +1,127 @@
+// GITHUB-CI: MMTK_PLAN=G1
+
+use super::mock_test_prelude::*;
+
+use crate::plan::G1;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext,
+    UnsupportedProcessEdges,
+};
+use crate::scheduler::GCWorker;
+use crate::util::constants::BYTES_IN_ADDRESS;
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::test_util::mock_objects::*;
+use crate::util::{Address, ObjectReference, VMThread, VMWorkerThread};
+use crate::{AllocationSemantics, Mutator};
+
+type G1ProcessEdges = PlanProcessEdges<MockVM, G1<MockVM>, DEFAULT_TRACE>;
+type G1RootsWorkFactory =
+    ProcessEdgesWorkRootsWorkFactory<MockVM, G1ProcessEdges, UnsupportedProcessEdges<MockVM>>;
+
+/// This test copies a reference into a memory slice that is not in an object.  The barrier of
+/// G1 records the slot of the slice, so that the slot is updated when a young pause evacuates
+/// the young object it points to, and when a mixed pause evacuates the old region it points
+/// into.
+#[test]
+pub fn g1_slice_copy() {
+    with_mockvm(
+        || -> MockVM {
+            with_object_model(MockVM {
+                // GC workers copy objects with the uninitialized `tls`.
+                is_mutator: MockMethod::new_fixed(Box::new(|tls: VMThread| {
+                    !tls.0.to_address().is_zero()
+                })),
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                scan_vm_specific_roots: Box::new(MockMethod::<
+                    (VMWorkerThread, Box<G1RootsWorkFactory>),
+                    (),
+                >::new_default()),
+                process_weak_refs: Box::new(MockMethod::<
+                    (
+                        &'static mut GCWorker<MockVM>,
+                        ProcessEdgesWorkTracerContext<G1ProcessEdges>,
+                    ),
+                    bool,
+                >::new_default()),
+                ..MockVM::default()
+            })
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            // The plan is fixed, as the types of the mocked methods depend on it.
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::G1);
+                builder.options.threads.set(1);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+            });
+            let mmtk = fixture.mmtk();
+            let plan = mmtk.get_plan();
+            let mutator = fixture.mutator();
+
+            // root -> a
+            let a = alloc_object(mutator, AllocationSemantics::Default);
+            let root: Address = Address::from_ref(Box::leak(Box::new(a)));
+            let load_root = move || -> ObjectReference { unsafe { root.load() } };
+
+            write_mockvm(|mock| {
+                mock.scan_roots_in_mutator_thread =
+                    Box::new(MockMethod::<
+                        (
+                            VMWorkerThread,
+                            &'static mut Mutator<MockVM>,
+                            Box<G1RootsWorkFactory>,
+                        ),
+                        (),
+                    >::new_fixed(Box::new(
+                        move |(_, _, mut factory)| factory.create_process_roots_work(vec![root]),
+                    )));
+            });
+
+            // A slot outside objects, which is not a root.
+            let slot = Address::from_mut_ptr(Box::leak(Box::new(Address::ZERO)));
+            let buffer = Address::from_mut_ptr(Box::leak(Box::new(Address::ZERO)));
+            let load_slot = move || -> ObjectReference { unsafe { slot.load() } };
+
+            // Copy the young object `b` to the slot.  `a.0` keeps `b` alive.
+            let b = alloc_object(mutator, AllocationSemantics::Default);
+            write_field(mutator, a, 0, b);
+            unsafe { buffer.store(b) };
+            memory_manager::memory_region_copy(
+                mutator,
+                buffer..buffer + BYTES_IN_ADDRESS,
+                slot..slot + BYTES_IN_ADDRESS,
+            );
+            assert_eq!(load_slot(), b);
+
+            // A young pause evacuates `b`, and updates the slot.
+            fixture.gc();
+            let a = load_root();
+            let b = read_field(a, 0).unwrap();
+            assert_eq!(load_slot(), b);
+            assert!(b.is_live());
+
+            // A full pause marks the old regions.  The region of `a` and `b` is mostly free, and
+            // is chosen to be evacuated.
+            plan.notify_emergency_collection();
+            fixture.gc();
+            assert!(plan.last_collection_was_exhaustive());
+
+            // A mixed pause evacuates the region of `b`, and updates the slot from the
+            // remembered set of the region.
+            fixture.gc();
+            assert!(!plan.last_collection_was_exhaustive());
+            let a = load_root();
+            let new_b = read_field(a, 0).unwrap();
+            assert_ne!(new_b, b, "The region of b was not evacuated");
+            assert_eq!(load_slot(), new_b);
+            assert!(new_b.is_live());
+        },
+        no_cleanup,
+    )
+}
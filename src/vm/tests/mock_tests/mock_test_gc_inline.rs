@@ -0,0 +1,94 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+
+use crate::plan::SemiSpace;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext,
+    UnsupportedProcessEdges,
+};
+use crate::scheduler::GCWorker;
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::VMWorkerThread;
+use crate::{AllocationSemantics, Mutator};
+
+type SSProcessEdges = PlanProcessEdges<MockVM, SemiSpace<MockVM>, DEFAULT_TRACE>;
+type SSRootsWorkFactory =
+    ProcessEdgesWorkRootsWorkFactory<MockVM, SSProcessEdges, UnsupportedProcessEdges<MockVM>>;
+
+/// This test runs a SemiSpace GC on the mutator thread, without spawning GC threads. There are no
+/// roots, so the GC frees all the allocated memory.
+#[test]
+pub fn gc_inline() {
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                scan_roots_in_mutator_thread: Box::new(MockMethod::<
+                    (
+                        VMWorkerThread,
+                        &'static mut Mutator<MockVM>,
+                        Box<SSRootsWorkFactory>,
+                    ),
+                    (),
+                >::new_default()),
+                scan_vm_specific_roots: Box::new(MockMethod::<
+                    (VMWorkerThread, Box<SSRootsWorkFactory>),
+                    (),
+                >::new_default()),
+                process_weak_refs: Box::new(MockMethod::<
+                    (
+                        &'static mut GCWorker<MockVM>,
+                        ProcessEdgesWorkTracerContext<SSProcessEdges>,
+                    ),
+                    bool,
+                >::new_default()),
+                ..MockVM::default()
+            }
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            // The plan is fixed, as the types of the mocked methods depend on it.
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+                builder.options.threads.set(1);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+            });
+            let mmtk = fixture.mmtk();
+            let tls = fixture.tls;
+            let mutator = fixture.mutator();
+            for _ in 0..64 {
+                let addr = memory_manager::alloc(mutator, 1024, 8, 0, AllocationSemantics::Default);
+                assert!(!addr.is_zero());
+            }
+            let used_pages = mmtk.get_plan().get_used_pages();
+
+            // No GC is requested.
+            memory_manager::run_gc_inline(mmtk, tls);
+            read_mockvm(|mock| assert!(!mock.stop_all_mutators.is_called()));
+
+            assert!(memory_manager::handle_user_collection_request(mmtk, tls));
+            read_mockvm(|mock| assert!(mock.block_for_gc.is_called()));
+            memory_manager::run_gc_inline(mmtk, tls);
+
+            read_mockvm(|mock| {
+                assert!(!mock.spawn_gc_thread.is_called());
+                assert!(mock.stop_all_mutators.is_called());
+                assert!(mock.resume_mutators.is_called());
+            });
+            assert!(mmtk.get_plan().get_used_pages() < used_pages);
+            assert!(!mmtk.gc_in_progress());
+
+            // The mutator can allocate after the GC.
+            let addr = memory_manager::alloc(mutator, 1024, 8, 0, AllocationSemantics::Default);
+            assert!(!addr.is_zero());
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,100 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+
+use crate::plan::SemiSpace;
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext,
+    UnsupportedProcessEdges,
+};
+use crate::scheduler::GCWorker;
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::VMWorkerThread;
+use crate::Mutator;
+
+type SSProcessEdges = PlanProcessEdges<MockVM, SemiSpace<MockVM>, DEFAULT_TRACE>;
+type SSRootsWorkFactory =
+    ProcessEdgesWorkRootsWorkFactory<MockVM, SSProcessEdges, UnsupportedProcessEdges<MockVM>>;
+
+/// This test runs a GC with the option `gc_trace_file`, and checks that the trace has the GC, the
+/// pause, the work packets and the bucket openings.  The GC runs on the mutator thread, so no GC
+/// thread is spawned.
+#[test]
+pub fn gc_trace() {
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                scan_roots_in_mutator_thread: Box::new(MockMethod::<
+                    (
+                        VMWorkerThread,
+                        &'static mut Mutator<MockVM>,
+                        Box<SSRootsWorkFactory>,
+                    ),
+                    (),
+                >::new_default()),
+                scan_vm_specific_roots: Box::new(MockMethod::<
+                    (VMWorkerThread, Box<SSRootsWorkFactory>),
+                    (),
+                >::new_default()),
+                process_weak_refs: Box::new(MockMethod::<
+                    (
+                        &'static mut GCWorker<MockVM>,
+                        ProcessEdgesWorkTracerContext<SSProcessEdges>,
+                    ),
+                    bool,
+                >::new_default()),
+                ..MockVM::default()
+            }
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            let path =
+                std::env::temp_dir().join(format!("mmtk-gc-trace-{}.json", std::process::id()));
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+                builder.options.threads.set(1);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+                assert!(builder
+                    .options
+                    .set_from_string("gc_trace_file", path.to_str().unwrap()));
+            });
+            let mmtk = fixture.mmtk();
+
+            fixture.gc();
+
+            memory_manager::write_gc_trace(mmtk).unwrap();
+            let trace = std::fs::read_to_string(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert!(trace.starts_with("{\"traceEvents\":["));
+            for event in [
+                "\"name\":\"gc_requested\",\"ph\":\"i\"",
+                "\"name\":\"GC\",\"ph\":\"B\"",
+                "\"name\":\"GC\",\"ph\":\"E\"",
+                "\"name\":\"Pause\",\"ph\":\"B\"",
+                "\"name\":\"Pause\",\"ph\":\"E\"",
+                "\"args\":{\"stage\":\"Closure\"}",
+                "\"args\":{\"name\":\"GC worker 0\"}",
+            ] {
+                assert!(trace.contains(event), "{} is not in the trace", event);
+            }
+            // Every work packet that begins also ends.
+            let packets = |phase: &str| {
+                trace
+                    .lines()
+                    .filter(|line| line.contains("\"name\":\"mmtk::") && line.contains(phase))
+                    .count()
+            };
+            assert!(packets("\"ph\":\"B\"") > 0);
+            assert_eq!(packets("\"ph\":\"B\""), packets("\"ph\":\"E\""));
+        },
+        no_cleanup,
+    )
+}
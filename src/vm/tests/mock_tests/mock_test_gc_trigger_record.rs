@@ -0,0 +1,51 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+
+use crate::util::heap::GCSchedule;
+use crate::AllocationSemantics;
+
+/// This test records a GC to a file. The GC is simulated by calling the GC trigger policy
+/// directly.
+#[test]
+pub fn gc_trigger_record() {
+    with_mockvm(
+        default_setup,
+        || {
+            const MB: usize = 1024 * 1024;
+            let path =
+                std::env::temp_dir().join(format!("mmtk-mock-gc-record-{}", std::process::id()));
+            let mut fixture = MutatorFixture::create_with_builder(|builder| {
+                builder.options.gc_trigger.set(
+                    crate::util::options::GCTriggerSelector::FixedHeapSize(16 * MB),
+                );
+                builder
+                    .options
+                    .gc_trigger_record
+                    .set(path.to_str().unwrap().to_string());
+            });
+            let addr = memory_manager::alloc(
+                &mut fixture.mutator,
+                16 * 1024,
+                8,
+                0,
+                AllocationSemantics::Default,
+            );
+            assert!(!addr.is_zero());
+            let mmtk = fixture.mmtk();
+            mmtk.gc_trigger.policy.on_gc_start(mmtk);
+            mmtk.gc_trigger.policy.on_gc_end(mmtk);
+
+            let schedule = GCSchedule::read(&path).unwrap();
+            assert_eq!(
+                schedule.heap_pages,
+                mmtk.gc_trigger.policy.get_max_heap_size_in_pages()
+            );
+            assert_eq!(schedule.gcs.len(), 1);
+            assert!(schedule.gcs[0].allocated_bytes > 0);
+            assert!(!schedule.gcs[0].user_triggered);
+            std::fs::remove_file(&path).unwrap();
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,59 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+
+use crate::AllocationSemantics;
+
+/// This test replays a GC schedule, and checks that a GC is required once the recorded bytes are
+/// allocated. Collection is disabled so that allocation does not trigger the GC.
+#[test]
+pub fn gc_trigger_replay() {
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                is_collection_enabled: MockMethod::new_fixed(Box::new(|_| false)),
+                ..MockVM::default()
+            }
+        },
+        || {
+            const OBJECT_SIZE: usize = 16 * 1024;
+            const HEAP_PAGES: usize = 4096;
+            let path =
+                std::env::temp_dir().join(format!("mmtk-mock-gc-replay-{}", std::process::id()));
+            std::fs::write(
+                &path,
+                format!("heap_pages {}\n{} full\n", HEAP_PAGES, OBJECT_SIZE * 8),
+            )
+            .unwrap();
+            let mut fixture = MutatorFixture::create_with_builder(|builder| {
+                builder
+                    .options
+                    .gc_trigger_replay
+                    .set(path.to_str().unwrap().to_string());
+            });
+            std::fs::remove_file(&path).unwrap();
+
+            let mmtk = fixture.mmtk();
+            let plan = mmtk.get_plan();
+            assert_eq!(
+                mmtk.gc_trigger.policy.get_max_heap_size_in_pages(),
+                HEAP_PAGES
+            );
+            assert!(!mmtk.gc_trigger.policy.can_heap_size_grow());
+            assert!(!mmtk.gc_trigger.policy.is_gc_required(false, None, plan));
+
+            for _ in 0..16 {
+                let addr = memory_manager::alloc(
+                    &mut fixture.mutator,
+                    OBJECT_SIZE,
+                    8,
+                    0,
+                    AllocationSemantics::Default,
+                );
+                assert!(!addr.is_zero());
+            }
+            assert!(mmtk.gc_trigger.policy.is_gc_required(false, None, plan));
+        },
+        no_cleanup,
+    )
+}
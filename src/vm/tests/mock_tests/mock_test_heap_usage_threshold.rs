@@ -0,0 +1,93 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+
+use crate::util::heap::{HeapUsageLimit, HeapUsageThreshold, HeapUsageThresholdEvent};
+use crate::util::{VMThread, VMWorkerThread};
+use crate::AllocationSemantics;
+use std::sync::Mutex;
+
+static EVENTS: Mutex<Vec<HeapUsageThresholdEvent>> = Mutex::new(vec![]);
+
+/// This test registers heap usage thresholds, and checks that the binding is informed when a
+/// threshold is crossed. The thresholds are checked at the end of a GC, which we simulate by
+/// checking them directly.
+#[test]
+pub fn heap_usage_threshold() {
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                heap_usage_threshold_crossed: MockMethod::new_fixed(Box::new(|(_, event)| {
+                    EVENTS.lock().unwrap().push(event);
+                })),
+                ..MockVM::default()
+            }
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            let mut fixture = MutatorFixture::create_with_heapsize(MB);
+            let addr =
+                memory_manager::alloc(&mut fixture.mutator, 16, 8, 0, AllocationSemantics::Default);
+            assert!(!addr.is_zero());
+            let mmtk = fixture.mmtk();
+            let tls = VMWorkerThread(VMThread::UNINITIALIZED);
+
+            // Invalid thresholds are rejected.
+            assert!(memory_manager::add_heap_usage_threshold(
+                mmtk,
+                HeapUsageThreshold {
+                    space: Some("no_such_space"),
+                    limit: HeapUsageLimit::Bytes(1),
+                }
+            )
+            .is_none());
+            assert!(memory_manager::add_heap_usage_threshold(
+                mmtk,
+                HeapUsageThreshold {
+                    space: None,
+                    limit: HeapUsageLimit::MaxHeapFraction(-1.0),
+                }
+            )
+            .is_none());
+
+            // The heap is in use, but it is not full.
+            let low = memory_manager::add_heap_usage_threshold(
+                mmtk,
+                HeapUsageThreshold {
+                    space: None,
+                    limit: HeapUsageLimit::Bytes(1),
+                },
+            )
+            .unwrap();
+            let high = memory_manager::add_heap_usage_threshold(
+                mmtk,
+                HeapUsageThreshold {
+                    space: None,
+                    limit: HeapUsageLimit::MaxHeapFraction(1.0),
+                },
+            )
+            .unwrap();
+            assert_ne!(low, high);
+
+            // Only the low threshold is crossed.
+            mmtk.gc_trigger.check_usage_thresholds(tls);
+            {
+                let events = EVENTS.lock().unwrap();
+                assert_eq!(events.len(), 1);
+                assert_eq!(events[0].id, low);
+                assert!(events[0].exceeded);
+                assert!(events[0].used_bytes >= events[0].limit_bytes);
+                assert!(!events[0].space_used_bytes.is_empty());
+            }
+
+            // The binding is not informed again until a threshold is crossed again.
+            mmtk.gc_trigger.check_usage_thresholds(tls);
+            assert_eq!(EVENTS.lock().unwrap().len(), 1);
+
+            assert!(memory_manager::remove_heap_usage_threshold(mmtk, low));
+            assert!(!memory_manager::remove_heap_usage_threshold(mmtk, low));
+            assert!(memory_manager::remove_heap_usage_threshold(mmtk, high));
+        },
+        no_cleanup,
+    )
+}
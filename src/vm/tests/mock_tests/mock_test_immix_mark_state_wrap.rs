@@ -0,0 +1,162 @@
+// GITHUB-CI: MMTK_PLAN=Immix
+// GITHUB-CI: FEATURES=vo_bit
+
+use super::mock_test_prelude::*;
+
+use crate::plan::Immix;
+use crate::policy::gc_work::TRACE_KIND_TRANSITIVE_PIN;
+use crate::policy::immix::block::Block;
+use crate::policy::immix::line::Line;
+use crate::policy::immix::{TRACE_KIND_DEFRAG, TRACE_KIND_FAST};
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext,
+};
+use crate::scheduler::{GCWorker, ProcessEdgesWork};
+use crate::util::linear_scan::Region;
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::test_util::mock_objects::*;
+use crate::util::{Address, ObjectReference, VMThread, VMWorkerThread};
+use crate::vm::ObjectModel;
+use crate::{AllocationSemantics, Mutator};
+use std::sync::atomic::Ordering;
+
+type RootsWorkFactoryOf<E> = ProcessEdgesWorkRootsWorkFactory<
+    MockVM,
+    E,
+    PlanProcessEdges<MockVM, Immix<MockVM>, TRACE_KIND_TRANSITIVE_PIN>,
+>;
+
+/// Mock `scan_roots_in_mutator_thread` for GCs that use the process edges type `E`.
+fn scan_roots<E: ProcessEdgesWork<VM = MockVM>>(root: Address) -> Box<dyn MockAny> {
+    Box::new(MockMethod::<
+        (
+            VMWorkerThread,
+            &'static mut Mutator<MockVM>,
+            Box<RootsWorkFactoryOf<E>>,
+        ),
+        (),
+    >::new_fixed(Box::new(move |(_, _, mut factory)| {
+        factory.create_process_roots_work(vec![root])
+    })))
+}
+
+fn scan_vm_specific_roots<E: ProcessEdgesWork<VM = MockVM>>() -> Box<dyn MockAny> {
+    Box::new(MockMethod::<(VMWorkerThread, Box<RootsWorkFactoryOf<E>>), ()>::new_default())
+}
+
+fn process_weak_refs<E: ProcessEdgesWork<VM = MockVM>>() -> Box<dyn MockAny> {
+    Box::new(MockMethod::<
+        (
+            &'static mut GCWorker<MockVM>,
+            ProcessEdgesWorkTracerContext<E>,
+        ),
+        bool,
+    >::new_default())
+}
+
+type FastProcessEdges = PlanProcessEdges<MockVM, Immix<MockVM>, TRACE_KIND_FAST>;
+type DefragProcessEdges = PlanProcessEdges<MockVM, Immix<MockVM>, TRACE_KIND_DEFRAG>;
+
+/// This test runs more full-heap GCs of Immix than there are values of the in-header mark bits,
+/// so the mark state wraps around.  An object that died in an earlier GC stays in a live line,
+/// and it is not considered marked when its mark state is used again.
+#[test]
+pub fn immix_mark_state_wrap() {
+    with_mockvm(
+        || -> MockVM {
+            with_object_model(MockVM {
+                // GC workers copy objects with the uninitialized `tls`.
+                is_mutator: MockMethod::new_fixed(Box::new(|tls: VMThread| {
+                    !tls.0.to_address().is_zero()
+                })),
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                scan_vm_specific_roots: Box::new(MockAnyOf(vec![
+                    scan_vm_specific_roots::<FastProcessEdges>(),
+                    scan_vm_specific_roots::<DefragProcessEdges>(),
+                ])),
+                process_weak_refs: Box::new(MockAnyOf(vec![
+                    process_weak_refs::<FastProcessEdges>(),
+                    process_weak_refs::<DefragProcessEdges>(),
+                ])),
+                ..MockVM::default()
+            })
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            // The plan is fixed, as the types of the mocked methods depend on it.
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::Immix);
+                builder.options.threads.set(1);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+                // Without headroom, defrag GCs do not move objects.
+                builder.options.immix_defrag_headroom_percent.set(0);
+            });
+            let mutator = fixture.mutator();
+
+            // root -> a -> x.  `a` and `x` share a line.
+            let a = alloc_object(mutator, AllocationSemantics::Default);
+            let x = alloc_object(mutator, AllocationSemantics::Default);
+            write_field(mutator, a, 0, x);
+            // a -> list[0] -> ... -> y.  The list fills more than a block, so the block of `y` only
+            // has objects of the list.
+            let mut y = a;
+            for i in 0..(2 * Block::BYTES / OBJECT_SIZE) {
+                let object = alloc_object(mutator, AllocationSemantics::Default);
+                write_field(mutator, y, if i == 0 { 1 } else { 0 }, object);
+                y = object;
+            }
+            let y_block = Block::from_unaligned_address(y.to_raw_address());
+            let root: Address = Address::from_ref(Box::leak(Box::new(a)));
+
+            write_mockvm(|mock| {
+                mock.scan_roots_in_mutator_thread = Box::new(MockAnyOf(vec![
+                    scan_roots::<FastProcessEdges>(root),
+                    scan_roots::<DefragProcessEdges>(root),
+                ]));
+            });
+            let gc = || {
+                fixture.gc();
+                // The objects are not moved, so `x` stays in the line of `a`.
+                assert_eq!(unsafe { root.load::<ObjectReference>() }, a);
+            };
+            let line_mark =
+                |line: Line| Line::MARK_TABLE.load_atomic::<u8>(line.start(), Ordering::SeqCst);
+            let mark_bits =
+                |object: ObjectReference| {
+                    <MockVM as VMBinding>::VMObjectModel::LOCAL_MARK_BIT_SPEC
+                        .load_atomic::<MockVM, u8>(object, None, Ordering::SeqCst)
+                };
+
+            // `x` and `y` are marked by the first GC, and die in the second GC.
+            gc();
+            assert!(x.is_live());
+            assert!(y.is_live());
+            assert!(y_block.lines().any(|line| line_mark(line) != 0));
+            unsafe { field(a, 0).store(Address::ZERO) };
+            unsafe { field(a, 1).store(Address::ZERO) };
+
+            // The block of `y` is released, and its line marks are cleared.
+            gc();
+            assert!(!y.is_live());
+            assert!(y_block.lines().all(|line| line_mark(line) == 0));
+
+            let crate::util::metadata::MetadataSpec::InHeader(spec) =
+                *<MockVM as VMBinding>::VMObjectModel::LOCAL_MARK_BIT_SPEC
+            else {
+                panic!("The mark bits of MockVM are expected to be in the header");
+            };
+            let num_mark_states = (1 << spec.num_of_bits) - 1;
+            for _ in 0..2 * num_mark_states {
+                gc();
+                assert!(a.is_live());
+                assert!(!x.is_live(), "{} has the mark bits {}", x, mark_bits(x));
+            }
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,165 @@
+// GITHUB-CI: MMTK_PLAN=LXR
+// GITHUB-CI: FEATURES=vo_bit
+
+use super::mock_test_prelude::*;
+
+use crate::plan::{RCIncEdges, RCRetainEdges};
+use crate::policy::gc_work::{DEFAULT_TRACE, TRACE_KIND_TRANSITIVE_PIN};
+use crate::scheduler::gc_work::{ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext};
+use crate::scheduler::{GCWork, GCWorker, WorkBucketStage};
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::test_util::mock_objects::*;
+use crate::util::{Address, ObjectReference, VMThread, VMWorkerThread};
+use crate::{AllocationSemantics, Mutator, MMTK};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+type LXRRootsWorkFactory = ProcessEdgesWorkRootsWorkFactory<
+    MockVM,
+    RCIncEdges<MockVM, DEFAULT_TRACE>,
+    RCIncEdges<MockVM, TRACE_KIND_TRANSITIVE_PIN>,
+>;
+type WeakRefsTracerContext = ProcessEdgesWorkTracerContext<RCRetainEdges<MockVM>>;
+
+type Step = Box<dyn FnOnce(&mut GCWorker<MockVM>) + Send>;
+
+/// A work packet that runs mutator code after a GC, before the decrements of the GC.
+struct MutatorStep(Option<Step>);
+
+impl GCWork<MockVM> for MutatorStep {
+    fn do_work(&mut self, worker: &mut GCWorker<MockVM>, _mmtk: &'static MMTK<MockVM>) {
+        (self.0.take().unwrap())(worker)
+    }
+}
+
+/// This test requests a GC of LXR while the decrements of the last GC are pending, because
+/// concurrent work is suspended.  The GC processes the decrements before it increments any
+/// count, so an object that is dead after the last GC is not found live by the root scanning of
+/// the next GC, and it is reclaimed in that GC.
+#[test]
+pub fn lxr_pending_decs() {
+    with_mockvm(
+        || -> MockVM {
+            with_object_model(MockVM {
+                // GC workers copy objects with the uninitialized `tls`.
+                is_mutator: MockMethod::new_fixed(Box::new(|tls: VMThread| {
+                    !tls.0.to_address().is_zero()
+                })),
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                scan_vm_specific_roots: Box::new(MockMethod::<
+                    (VMWorkerThread, Box<LXRRootsWorkFactory>),
+                    (),
+                >::new_default()),
+                process_weak_refs: Box::new(MockMethod::<
+                    (&'static mut GCWorker<MockVM>, WeakRefsTracerContext),
+                    bool,
+                >::new_default()),
+                forward_weak_refs: Box::new(MockMethod::<
+                    (&'static mut GCWorker<MockVM>, WeakRefsTracerContext),
+                    (),
+                >::new_default()),
+                ..MockVM::default()
+            })
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            // The plan is fixed, as the types of the mocked methods depend on it.
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::LXR);
+                builder.options.threads.set(1);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+            });
+            let mmtk = fixture.mmtk();
+            let mutator = fixture.mutator();
+
+            // root -> a -> b
+            let a = alloc_object(mutator, AllocationSemantics::Default);
+            let b = alloc_object(mutator, AllocationSemantics::Default);
+            write_field(mutator, a, 0, b);
+            let root: Address = Address::from_ref(Box::leak(Box::new(a)));
+            let load_root = move || -> ObjectReference { unsafe { root.load() } };
+
+            // The object `b` after it is promoted, and whether it was live when the roots were
+            // scanned in the last GC.
+            let b_addr = Address::from_mut_ptr(Box::leak(Box::new(Address::ZERO)));
+            let b_live_at_root_scanning: &'static AtomicBool = Box::leak(Box::default());
+
+            write_mockvm(|mock| {
+                mock.scan_roots_in_mutator_thread = Box::new(MockMethod::<
+                    (
+                        VMWorkerThread,
+                        &'static mut Mutator<MockVM>,
+                        Box<LXRRootsWorkFactory>,
+                    ),
+                    (),
+                >::new_fixed(
+                    Box::new(move |(_, _, mut factory)| {
+                        let b: Address = unsafe { b_addr.load() };
+                        if let Some(b) = ObjectReference::from_raw_address(b) {
+                            b_live_at_root_scanning.store(b.is_live(), Ordering::SeqCst);
+                        }
+                        factory.create_process_roots_work(vec![root])
+                    }),
+                ));
+            });
+
+            // The first GC promotes `a` and `b`.
+            fixture.gc();
+            let a = load_root();
+            let b = read_field(a, 0).unwrap();
+            assert!(a.is_live());
+            assert!(b.is_live());
+            unsafe { b_addr.store(b) };
+
+            // root -> a -> c.  The old value `b` is decremented after the next GC.
+            let c = alloc_object(mutator, AllocationSemantics::Default);
+            write_field(mutator, a, 0, c);
+
+            // Runs after the second GC, before its decrements.
+            memory_manager::add_work_packet(
+                mmtk,
+                WorkBucketStage::Concurrent,
+                MutatorStep(Some(Box::new(move |worker| {
+                    let bucket = &mmtk.scheduler.work_buckets[WorkBucketStage::Concurrent];
+                    assert!(b.is_live());
+                    // Suspend concurrent work, so the decrements are still pending when the next
+                    // GC is requested.  The decrements taken from the bucket together with this
+                    // packet are returned to it.
+                    bucket.set_enabled(false);
+                    let mut pending = false;
+                    while let Some(work) = worker.local_work_buffer.pop() {
+                        bucket.add_boxed(work);
+                        pending = true;
+                    }
+                    assert!(pending || !bucket.is_empty());
+                }))),
+            );
+
+            // The second GC.
+            fixture.gc();
+            assert!(b.is_live());
+            assert!(b_live_at_root_scanning.load(Ordering::SeqCst));
+
+            // The third GC processes the decrements first.
+            fixture.gc();
+            assert!(!b_live_at_root_scanning.load(Ordering::SeqCst));
+            assert!(!b.is_live());
+            // The object is reclaimed by the third GC.
+            #[cfg(feature = "vo_bit")]
+            assert!(!crate::util::metadata::vo_bit::is_vo_bit_set(b));
+
+            let a = load_root();
+            let c = read_field(a, 0).unwrap();
+            for object in [a, c] {
+                assert!(object.is_live(), "{} is not live", object);
+                #[cfg(feature = "vo_bit")]
+                assert!(crate::util::metadata::vo_bit::is_vo_bit_set(object));
+            }
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,149 @@
+// GITHUB-CI: MMTK_PLAN=MarkCompact
+// GITHUB-CI: FEATURES=object_pinning
+
+use super::mock_test_prelude::*;
+
+use crate::plan::MarkCompact;
+use crate::policy::markcompactspace::{TRACE_KIND_FORWARD, TRACE_KIND_MARK};
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext,
+    UnsupportedProcessEdges,
+};
+use crate::scheduler::{GCWorker, ProcessEdgesWork};
+use crate::util::metadata::vo_bit;
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::test_util::mock_objects::*;
+use crate::util::{Address, ObjectReference, VMThread, VMWorkerThread};
+use crate::{AllocationSemantics, Mutator};
+
+type RootsWorkFactoryOf<E> =
+    ProcessEdgesWorkRootsWorkFactory<MockVM, E, UnsupportedProcessEdges<MockVM>>;
+
+/// Mock `scan_roots_in_mutator_thread` for the trace that uses the process edges type `E`.
+fn scan_roots<E: ProcessEdgesWork<VM = MockVM>>(root: Address) -> Box<dyn MockAny> {
+    Box::new(MockMethod::<
+        (
+            VMWorkerThread,
+            &'static mut Mutator<MockVM>,
+            Box<RootsWorkFactoryOf<E>>,
+        ),
+        (),
+    >::new_fixed(Box::new(move |(_, _, mut factory)| {
+        factory.create_process_roots_work(vec![root])
+    })))
+}
+
+fn scan_vm_specific_roots<E: ProcessEdgesWork<VM = MockVM>>() -> Box<dyn MockAny> {
+    Box::new(MockMethod::<(VMWorkerThread, Box<RootsWorkFactoryOf<E>>), ()>::new_default())
+}
+
+fn process_weak_refs<E: ProcessEdgesWork<VM = MockVM>>() -> Box<dyn MockAny> {
+    Box::new(MockMethod::<
+        (
+            &'static mut GCWorker<MockVM>,
+            ProcessEdgesWorkTracerContext<E>,
+        ),
+        bool,
+    >::new_default())
+}
+
+fn forward_weak_refs<E: ProcessEdgesWork<VM = MockVM>>() -> Box<dyn MockAny> {
+    Box::new(MockMethod::<
+        (
+            &'static mut GCWorker<MockVM>,
+            ProcessEdgesWorkTracerContext<E>,
+        ),
+        (),
+    >::new_default())
+}
+
+type MarkingProcessEdges = PlanProcessEdges<MockVM, MarkCompact<MockVM>, TRACE_KIND_MARK>;
+type ForwardingProcessEdges = PlanProcessEdges<MockVM, MarkCompact<MockVM>, TRACE_KIND_FORWARD>;
+
+/// This test pins an object of MarkCompact between dead objects, and runs a GC.  The pinned
+/// object stays at its address, and the live objects before and after it are compacted around
+/// it.
+#[test]
+pub fn markcompact_pinning() {
+    with_mockvm(
+        || -> MockVM {
+            with_object_model(MockVM {
+                is_mutator: MockMethod::new_fixed(Box::new(|tls: VMThread| {
+                    !tls.0.to_address().is_zero()
+                })),
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                prepare_for_roots_re_scanning: MockMethod::new_default(),
+                scan_vm_specific_roots: Box::new(MockAnyOf(vec![
+                    scan_vm_specific_roots::<MarkingProcessEdges>(),
+                    scan_vm_specific_roots::<ForwardingProcessEdges>(),
+                ])),
+                process_weak_refs: Box::new(MockAnyOf(vec![
+                    process_weak_refs::<MarkingProcessEdges>(),
+                    process_weak_refs::<ForwardingProcessEdges>(),
+                ])),
+                forward_weak_refs: Box::new(MockAnyOf(vec![
+                    forward_weak_refs::<MarkingProcessEdges>(),
+                    forward_weak_refs::<ForwardingProcessEdges>(),
+                ])),
+                ..MockVM::default()
+            })
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            // The plan is fixed, as the types of the mocked methods depend on it.
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::MarkCompact);
+                builder.options.threads.set(1);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+            });
+            let mutator = fixture.mutator();
+
+            // Each of `a`, `pinned` and `b` follows four dead objects.  root -> a, a.0 -> pinned,
+            // a.1 -> b.
+            let objects: Vec<ObjectReference> = (0..15)
+                .map(|_| alloc_object(mutator, AllocationSemantics::Default))
+                .collect();
+            let (a, pinned, b) = (objects[4], objects[9], objects[14]);
+            write_field(mutator, a, 0, pinned);
+            write_field(mutator, a, 1, b);
+            assert!(memory_manager::pin_object(pinned));
+            let root: Address = Address::from_ref(Box::leak(Box::new(a)));
+
+            write_mockvm(|mock| {
+                mock.scan_roots_in_mutator_thread = Box::new(MockAnyOf(vec![
+                    scan_roots::<MarkingProcessEdges>(root),
+                    scan_roots::<ForwardingProcessEdges>(root),
+                ]));
+            });
+
+            fixture.gc();
+
+            // The pinned object is not moved.  The mark bits are cleared by the GC, so the objects
+            // at the new addresses are found by their VO bits.
+            let new_a: ObjectReference = unsafe { root.load() };
+            assert_eq!(read_field(new_a, 0), Some(pinned));
+            assert!(vo_bit::is_vo_bit_set(pinned));
+            assert!(memory_manager::is_pinned(pinned));
+            // `a` is moved to the dead objects before it, and `b` is moved to the dead objects
+            // between `pinned` and `b`.
+            assert!(new_a < a, "{} is not moved down from {}", new_a, a);
+            let new_b = read_field(new_a, 1).unwrap();
+            assert!(
+                pinned < new_b && new_b < b,
+                "{} is not moved down from {} to after {}",
+                new_b,
+                b,
+                pinned
+            );
+            assert!(vo_bit::is_vo_bit_set(new_a));
+            assert!(vo_bit::is_vo_bit_set(new_b));
+            assert!(!vo_bit::is_vo_bit_set(b));
+        },
+        no_cleanup,
+    )
+}
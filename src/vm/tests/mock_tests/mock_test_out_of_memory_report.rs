@@ -0,0 +1,59 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+
+use crate::util::alloc::allocator::AllocationOptions;
+use crate::util::alloc::AllocationError;
+use crate::util::heap::OutOfMemoryReport;
+use crate::AllocationSemantics;
+use std::sync::Mutex;
+
+static REPORT: Mutex<Option<OutOfMemoryReport>> = Mutex::new(None);
+
+/// This test will allocate a large object that is larger than the heap size. Before calling
+/// `Collection::out_of_memory`, MMTk should pass a report that describes the failed allocation and
+/// the heap to the binding.
+#[test]
+pub fn out_of_memory_report() {
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                out_of_memory: MockMethod::new_default(),
+                out_of_memory_report: MockMethod::new_fixed(Box::new(|(_, report)| {
+                    *REPORT.lock().unwrap() = Some(report);
+                })),
+                ..MockVM::default()
+            }
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            let mut fixture = MutatorFixture::create_with_heapsize(MB);
+
+            // Attempt to allocate an object that is larger than the heap size.
+            let addr = memory_manager::alloc_with_options(
+                &mut fixture.mutator,
+                MB * 10,
+                8,
+                0,
+                AllocationSemantics::Los,
+                AllocationOptions {
+                    at_safepoint: false,
+                    ..Default::default()
+                },
+            );
+            assert!(addr.is_zero());
+
+            read_mockvm(|mock| {
+                assert!(mock.out_of_memory_report.is_called());
+                assert!(mock.out_of_memory.is_called());
+            });
+            let report = REPORT.lock().unwrap().take().unwrap();
+            assert_eq!(report.error, AllocationError::HeapOutOfMemory);
+            assert!(report.request_bytes >= MB * 10);
+            assert!(report.spaces.iter().any(|space| space.name == report.space));
+            assert!(report.gc_history.is_empty());
+            assert!(report.to_string().contains(report.space));
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,37 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+
+/// This test changes the heap size bounds at run time. The new bounds only take effect when the
+/// next GC starts, which we simulate by applying the requested bounds directly.
+#[test]
+pub fn set_heap_size_bounds() {
+    with_mockvm(
+        default_setup,
+        || {
+            const MB: usize = 1024 * 1024;
+            let fixture = MutatorFixture::create_with_heapsize(MB);
+            let mmtk = fixture.mmtk();
+            assert_eq!(memory_manager::total_bytes(mmtk), MB);
+
+            // Invalid bounds are rejected.
+            assert!(!memory_manager::set_heap_size_bounds(mmtk, 2 * MB, MB));
+            assert!(!memory_manager::set_heap_size_bounds(mmtk, 0, 0));
+            // The min cannot be larger than the memory limit of the process.
+            assert!(!memory_manager::set_heap_size_bounds(
+                mmtk,
+                usize::MAX,
+                usize::MAX
+            ));
+
+            // Valid bounds do not take effect until the next GC.
+            assert!(memory_manager::set_heap_size_bounds(mmtk, MB, 4 * MB));
+            assert_eq!(memory_manager::total_bytes(mmtk), MB);
+
+            // The fixed heap size trigger uses the max as its heap size.
+            mmtk.gc_trigger.apply_requested_bounds();
+            assert_eq!(memory_manager::total_bytes(mmtk), 4 * MB);
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,130 @@
+// GITHUB-CI: MMTK_PLAN=StickyMarkSweep
+// GITHUB-CI: FEATURES=vo_bit
+
+use super::mock_test_prelude::*;
+
+use crate::plan::{GenNurseryProcessEdges, StickyMarkSweep};
+use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::scheduler::gc_work::{
+    PlanProcessEdges, ProcessEdgesWorkRootsWorkFactory, ProcessEdgesWorkTracerContext,
+};
+use crate::scheduler::{GCWorker, ProcessEdgesWork};
+use crate::util::metadata::vo_bit;
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::test_util::mock_objects::*;
+use crate::util::{Address, VMWorkerThread};
+use crate::{AllocationSemantics, Mutator};
+
+type NurseryProcessEdges = GenNurseryProcessEdges<MockVM, StickyMarkSweep<MockVM>, DEFAULT_TRACE>;
+type MatureProcessEdges = PlanProcessEdges<MockVM, StickyMarkSweep<MockVM>, DEFAULT_TRACE>;
+// Sticky MarkSweep never moves objects, so it pins roots with the same process edges type.
+type RootsWorkFactoryOf<E> = ProcessEdgesWorkRootsWorkFactory<MockVM, E, E>;
+
+/// Mock `scan_roots_in_mutator_thread` for GCs that use the process edges type `E`.
+fn scan_roots<E: ProcessEdgesWork<VM = MockVM>>(root: Address) -> Box<dyn MockAny> {
+    Box::new(MockMethod::<
+        (
+            VMWorkerThread,
+            &'static mut Mutator<MockVM>,
+            Box<RootsWorkFactoryOf<E>>,
+        ),
+        (),
+    >::new_fixed(Box::new(move |(_, _, mut factory)| {
+        factory.create_process_roots_work(vec![root])
+    })))
+}
+
+fn scan_vm_specific_roots<E: ProcessEdgesWork<VM = MockVM>>() -> Box<dyn MockAny> {
+    Box::new(MockMethod::<(VMWorkerThread, Box<RootsWorkFactoryOf<E>>), ()>::new_default())
+}
+
+fn process_weak_refs<E: ProcessEdgesWork<VM = MockVM>>() -> Box<dyn MockAny> {
+    Box::new(MockMethod::<
+        (
+            &'static mut GCWorker<MockVM>,
+            ProcessEdgesWorkTracerContext<E>,
+        ),
+        bool,
+    >::new_default())
+}
+
+/// This test runs a nursery GC of sticky MarkSweep.  The GC keeps the old objects, including an
+/// old object that is no longer reachable, keeps the young object that is only reachable from an
+/// old object, and reclaims the young object that is not reachable.
+#[test]
+pub fn sticky_marksweep_nursery_gc() {
+    with_mockvm(
+        || -> MockVM {
+            with_object_model(MockVM {
+                resume_mutators: MockMethod::new_default(),
+                block_for_gc: MockMethod::new_default(),
+                notify_initial_thread_scan_complete: MockMethod::new_default(),
+                scan_vm_specific_roots: Box::new(MockAnyOf(vec![
+                    scan_vm_specific_roots::<NurseryProcessEdges>(),
+                    scan_vm_specific_roots::<MatureProcessEdges>(),
+                ])),
+                process_weak_refs: Box::new(MockAnyOf(vec![
+                    process_weak_refs::<NurseryProcessEdges>(),
+                    process_weak_refs::<MatureProcessEdges>(),
+                ])),
+                ..MockVM::default()
+            })
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            // The plan is fixed, as the types of the mocked methods depend on it.
+            let fixture = InlineGCFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::StickyMarkSweep);
+                builder.options.threads.set(1);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(16 * MB));
+            });
+            let mmtk = fixture.mmtk();
+            let plan = mmtk.get_plan();
+            let mutator = fixture.mutator();
+
+            // root -> old -> old_dead
+            let old = alloc_object(mutator, AllocationSemantics::Default);
+            let old_dead = alloc_object(mutator, AllocationSemantics::Default);
+            write_field(mutator, old, 0, old_dead);
+            let root: Address = Address::from_ref(Box::leak(Box::new(old)));
+
+            write_mockvm(|mock| {
+                mock.scan_roots_in_mutator_thread = Box::new(MockAnyOf(vec![
+                    scan_roots::<NurseryProcessEdges>(root),
+                    scan_roots::<MatureProcessEdges>(root),
+                ]));
+            });
+            let gc = || {
+                fixture.gc();
+                assert!(!plan.last_collection_was_exhaustive());
+            };
+
+            // The first GC makes `old` and `old_dead` old.
+            gc();
+            assert!(old.is_live());
+            assert!(old_dead.is_live());
+
+            // root -> old -> young_live.  `young_dead` is not reachable, and neither is
+            // `old_dead` any more.
+            let young_live = alloc_object(mutator, AllocationSemantics::Default);
+            let young_dead = alloc_object(mutator, AllocationSemantics::Default);
+            write_field(mutator, old, 0, young_live);
+            assert!(vo_bit::is_vo_bit_set(young_dead));
+
+            // The nursery GC only traces from the roots and the old objects modified since the
+            // last GC.
+            gc();
+            for object in [old, old_dead, young_live] {
+                assert!(object.is_live(), "{} is not live", object);
+                assert!(vo_bit::is_vo_bit_set(object), "{} is reclaimed", object);
+            }
+            assert_eq!(read_field(old, 0), Some(young_live));
+            assert!(!young_dead.is_live());
+            assert!(!vo_bit::is_vo_bit_set(young_dead));
+        },
+        no_cleanup,
+    )
+}
@@ -33,16 +33,29 @@ mod mock_test_allocate_with_disable_collection;
 mod mock_test_allocate_with_initialize_collection;
 mod mock_test_allocate_with_re_enable_collection;
 mod mock_test_allocate_without_initialize_collection;
+mod mock_test_allocation_pacing;
 mod mock_test_allocator_info;
 mod mock_test_barrier_slow_path_assertion;
+mod mock_test_concurrent_genimmix_nursery_gc;
+mod mock_test_concurrent_mark_assist;
+mod mock_test_concurrent_marksweep;
 #[cfg(feature = "is_mmtk_object")]
 mod mock_test_conservatism;
 mod mock_test_debug_get_object_info;
+mod mock_test_deterministic_scheduler;
+mod mock_test_g1_slice_copy;
+mod mock_test_gc_inline;
+mod mock_test_gc_trace;
+mod mock_test_gc_trigger_record;
+mod mock_test_gc_trigger_replay;
 #[cfg(target_os = "linux")]
 mod mock_test_handle_mmap_conflict;
 mod mock_test_handle_mmap_oom;
 #[cfg(feature = "vo_bit")]
 mod mock_test_heap_traversal;
+mod mock_test_heap_usage_threshold;
+#[cfg(feature = "vo_bit")]
+mod mock_test_immix_mark_state_wrap;
 mod mock_test_init_fork;
 #[cfg(feature = "is_mmtk_object")]
 mod mock_test_internal_ptr_before_object_ref;
@@ -57,14 +70,21 @@ mod mock_test_internal_ptr_normal_object;
 mod mock_test_is_in_mmtk_spaces;
 mod mock_test_issue139_allocate_non_multiple_of_min_alignment;
 mod mock_test_issue867_allocate_unrealistically_large_object;
+mod mock_test_lxr_pending_decs;
 #[cfg(feature = "malloc_counted_size")]
 mod mock_test_malloc_counted;
 mod mock_test_malloc_ms;
+#[cfg(feature = "object_pinning")]
+mod mock_test_markcompact_pinning;
 #[cfg(all(target_pointer_width = "64", feature = "vm_space"))]
 mod mock_test_mmtk_julia_pr_143;
 #[cfg(feature = "nogc_lock_free")]
 mod mock_test_nogc_lock_free;
+mod mock_test_out_of_memory_report;
+mod mock_test_set_heap_size_bounds;
 mod mock_test_slots;
+#[cfg(feature = "vo_bit")]
+mod mock_test_sticky_marksweep_nursery_gc;
 #[cfg(target_pointer_width = "64")]
 mod mock_test_vm_layout_compressed_pointer;
 mod mock_test_vm_layout_default;